@@ -12,12 +12,6 @@ use std::mem;
 use std::ptr;
 use std::str;
 
-macro_rules! c_str {
-    ($s:expr) => {
-        concat!($s, "\0").as_ptr() as *const i8
-    };
-}
-
 macro_rules! offset_of {
     ($ty:ty, $field:ident) => {
         unsafe {
@@ -31,8 +25,6 @@ macro_rules! offset_of {
 }
 
 const TEXT_MAX: usize = 256;
-const DOUBLE_CLICK_LO: f64 = 0.02;
-const DOUBLE_CLICK_HI: f64 = 0.2;
 
 // settings
 const WINDOW_WIDTH: u32 = 1200;
@@ -204,15 +196,12 @@ struct GlfwContext {
     display_width: i32,
     display_height: i32,
     device: Device,
-    context: Context,
+    context: OwnedContext,
     atlas: FontAtlas,
     fb_scale: Point,
     text: [u32; TEXT_MAX],
     text_len: usize,
     scroll: Point,
-    last_button_click: f64,
-    is_double_click_down: i32,
-    double_click_pos: Point,
 }
 
 fn pressed(action: Action) -> i32 {
@@ -225,8 +214,8 @@ fn pressed(action: Action) -> i32 {
 
 impl GlfwContext {
     unsafe fn new(window: glfw::Window) -> Self {
-        let mut context: Context = mem::zeroed();
-        nk_init_default(&mut context, ptr::null());
+        let mut context = OwnedContext::new();
+        nk_track_redraws(&mut *context, 1);
         // if (init_state == NK_GLFW3_INSTALL_CALLBACKS) {
         //     glfwSetScrollCallback(win, nk_gflw3_scroll_callback);
         //     glfwSetCharCallback(win, nk_glfw3_char_callback);
@@ -248,7 +237,7 @@ impl GlfwContext {
             &mut device.null,
         );
         if atlas.default_font != ptr::null_mut() {
-            nk_style_set_font(&mut context, &(*atlas.default_font).handle);
+            nk_style_set_font(&mut *context, &(*atlas.default_font).handle);
         }
         Self {
             window,
@@ -263,9 +252,6 @@ impl GlfwContext {
             text: [0; TEXT_MAX],
             text_len: 0,
             scroll: Point::new(0.0, 0.0),
-            last_button_click: 0.0,
-            is_double_click_down: 0,
-            double_click_pos: Point::new(0.0, 0.0),
         }
     }
 
@@ -278,7 +264,7 @@ impl GlfwContext {
         self.display_height = fh;
         self.fb_scale.x = fw as f32 / w as f32;
         self.fb_scale.y = fh as f32 / h as f32;
-        let ctx = &mut self.context;
+        let ctx = &mut *self.context;
         nk_input_begin(ctx);
         for i in 0..self.text_len {
             nk_input_unicode(ctx, self.text[i]);
@@ -377,12 +363,13 @@ impl GlfwContext {
         //         ctx->input.mouse.pos.y = ctx->input.mouse.prev.y;
         //     }
         // #endif
-        nk_input_button(
+        nk_input_button_timed(
             ctx,
             NK_BUTTON_LEFT,
             x,
             y,
             pressed(self.window.get_mouse_button(glfw::MouseButtonLeft)),
+            self.window.glfw.get_time(),
         );
         nk_input_button(
             ctx,
@@ -398,13 +385,6 @@ impl GlfwContext {
             y,
             pressed(self.window.get_mouse_button(glfw::MouseButtonRight)),
         );
-        nk_input_button(
-            ctx,
-            NK_BUTTON_DOUBLE,
-            self.double_click_pos.x as i32,
-            self.double_click_pos.y as i32,
-            self.is_double_click_down,
-        );
         nk_input_scroll(ctx, self.scroll);
         nk_input_end(ctx);
         self.text_len = 0;
@@ -508,7 +488,7 @@ impl GlfwContext {
                 nk_buffer_init_fixed(&mut vbuf, vertices as _, MAX_VERTEX_BUFFER as _);
                 nk_buffer_init_fixed(&mut ebuf, elements as _, MAX_ELEMENT_BUFFER as _);
                 nk_convert(
-                    &mut self.context,
+                    &mut *self.context,
                     &mut self.device.cmds,
                     &mut vbuf,
                     &mut ebuf,
@@ -519,7 +499,7 @@ impl GlfwContext {
             gl::UnmapBuffer(gl::ELEMENT_ARRAY_BUFFER);
             let mut offset = ptr::null();
             /* iterate over and execute each draw command */
-            let mut cmd = nk__draw_begin(&mut self.context, &mut self.device.cmds);
+            let mut cmd = nk__draw_begin(&mut *self.context, &mut self.device.cmds);
             while cmd != ptr::null() {
                 if (*cmd).elem_count != 0 {
                     gl::BindTexture(gl::TEXTURE_2D, (*cmd).texture.id as GLuint);
@@ -538,9 +518,9 @@ impl GlfwContext {
                     );
                     offset = offset.offset((*cmd).elem_count as isize);
                 }
-                cmd = nk__draw_next(cmd, &mut self.device.cmds, &mut self.context);
+                cmd = nk__draw_next(cmd, &mut self.device.cmds, &mut *self.context);
             }
-            nk_clear(&mut self.context);
+            nk_clear(&mut *self.context);
         }
 
         /* default OpenGL state */
@@ -551,29 +531,6 @@ impl GlfwContext {
         gl::Disable(gl::BLEND);
         gl::Disable(gl::SCISSOR_TEST);
     }
-
-    unsafe fn on_mouse_button(
-        &mut self,
-        button: glfw::MouseButton,
-        action: Action,
-        modifiers: glfw::Modifiers,
-        time: f64,
-    ) {
-        // if button != glfw::MouseButtonLeft {
-        //     return;
-        // }
-        // let (x, y) = self.window.get_cursor_pos();
-        // if action == Action::Press {
-        //     let dt = time - self.last_button_click;
-        //     if dt > DOUBLE_CLICK_LO && dt < DOUBLE_CLICK_HI {
-        //         self.is_double_click_down = 1;
-        //         self.double_click_pos = Point(x as f32, y as f32);
-        //     }
-        //     self.last_button_click = time;
-        // } else {
-        //     self.is_double_click_down = 0;
-        // }
-    }
 }
 
 #[repr(C)]
@@ -635,62 +592,52 @@ pub fn main() {
                     glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
                         context.window.set_should_close(true)
                     }
-                    glfw::WindowEvent::MouseButton(button, action, modifiers) => {
-                        context.on_mouse_button(button, action, modifiers, glfw.get_time());
-                    }
                     _ => {}
                 }
             }
             context.new_frame();
             {
-                let ctx = &mut context.context;
-                if nk_begin(
-                    ctx,
-                    c_str!("Demo"),
+                let mut ui = Ui::new(&mut context.context);
+                ui.window(
+                    "Demo",
                     Rect::new(50.0, 50.0, 230.0, 250.0),
                     NK_WINDOW_BORDER
                         | NK_WINDOW_MOVABLE
                         | NK_WINDOW_SCALABLE
                         | NK_WINDOW_MINIMIZABLE
                         | NK_WINDOW_TITLE,
-                ) != 0
-                {
-                    nk_layout_row_static(ctx, 30.0, 80, 1);
-                    if nk_button_label(ctx, c_str!("button")) != 0 {
-                        println!("button pressed");
-                    }
-                    nk_layout_row_dynamic(ctx, 30.0, 2);
-                    if nk_option_label(ctx, c_str!("easy"), if op == Op::EASY { 1 } else { 0 }) != 0
-                    {
-                        op = Op::EASY;
-                    }
-                    if nk_option_label(ctx, c_str!("hard"), if op == Op::HARD { 1 } else { 0 }) != 0
-                    {
-                        op = Op::HARD;
-                    }
-                    nk_layout_row_dynamic(ctx, 25.0, 1);
-                    nk_property_int(ctx, c_str!("Compression:"), 0, &mut property, 100, 10, 1.0);
-
-                    nk_layout_row_dynamic(ctx, 20.0, 1);
-                    nk_label(ctx, c_str!("background:"), NK_TEXT_LEFT);
-                    nk_layout_row_dynamic(ctx, 25.0, 1);
-                    if nk_combo_begin_color(
-                        ctx,
-                        nk_rgb_cf(bg),
-                        Point::new(nk_widget_width(ctx), 400.0),
-                    ) != 0
-                    {
-                        nk_layout_row_dynamic(ctx, 120.0, 1);
-                        bg = nk_color_picker(ctx, bg, NK_RGBA);
-                        nk_layout_row_dynamic(ctx, 25.0, 1);
-                        bg.r = nk_propertyf(ctx, c_str!("#R:"), 0.0, bg.r, 1.0, 0.01, 0.005);
-                        bg.g = nk_propertyf(ctx, c_str!("#G:"), 0.0, bg.g, 1.0, 0.01, 0.005);
-                        bg.b = nk_propertyf(ctx, c_str!("#B:"), 0.0, bg.b, 1.0, 0.01, 0.005);
-                        bg.a = nk_propertyf(ctx, c_str!("#A:"), 0.0, bg.a, 1.0, 0.01, 0.005);
-                        nk_combo_end(ctx);
-                    }
-                }
-                nk_end(ctx);
+                    |ui| {
+                        ui.layout_row_static(30.0, 80, 1);
+                        if ui.button("button") {
+                            println!("button pressed");
+                        }
+                        ui.layout_row_dynamic(30.0, 2);
+                        if ui.option("easy", op == Op::EASY) {
+                            op = Op::EASY;
+                        }
+                        if ui.option("hard", op == Op::HARD) {
+                            op = Op::HARD;
+                        }
+                        ui.layout_row_dynamic(25.0, 1);
+                        ui.property_i32("Compression:", 0, &mut property, 100, 10, 1.0);
+
+                        ui.layout_row_dynamic(20.0, 1);
+                        ui.label("background:", NK_TEXT_LEFT);
+                        ui.layout_row_dynamic(25.0, 1);
+                        let width = ui.widget_bounds().w;
+                        let size = Point::new(width, 400.0);
+                        let mut combo = ui.begin_combo_color(nk_rgb_cf(bg), size);
+                        if combo.is_open() {
+                            combo.layout_row_dynamic(120.0, 1);
+                            bg = combo.color_picker(bg, NK_RGBA);
+                            combo.layout_row_dynamic(25.0, 1);
+                            combo.property_f32("#R:", 0.0, &mut bg.r, 1.0, 0.01, 0.005);
+                            combo.property_f32("#G:", 0.0, &mut bg.g, 1.0, 0.01, 0.005);
+                            combo.property_f32("#B:", 0.0, &mut bg.b, 1.0, 0.01, 0.005);
+                            combo.property_f32("#A:", 0.0, &mut bg.a, 1.0, 0.01, 0.005);
+                        }
+                    },
+                );
             }
             let (w, h) = context.window.get_size();
             gl::Viewport(0, 0, w, h);
@@ -698,7 +645,15 @@ pub fn main() {
             gl::ClearColor(bg.r, bg.g, bg.b, bg.a);
             context.render(NK_ANTI_ALIASING_ON);
             context.window.swap_buffers();
-            glfw.poll_events();
+            /* sleep until input arrives or the UI asks for a frame */
+            let timeout = nk_redraw_timeout(&*context.context);
+            if timeout < 0.0 {
+                glfw.wait_events();
+            } else if timeout > 0.0 {
+                glfw.wait_events_timeout(timeout as f64);
+            } else {
+                glfw.poll_events();
+            }
         }
     }
 }
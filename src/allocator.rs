@@ -0,0 +1,444 @@
+//! `Allocator`s backed by Rust allocation, an owned `Context` and typed
+//! out-of-memory errors.
+//!
+//! Nuklear frees memory without passing its size back, so every block
+//! handed out here carries a small header with the size of its layout.
+
+use nuklear::*;
+use std::alloc::{self, GlobalAlloc, Layout};
+use std::ops::{Deref, DerefMut};
+use std::{error, fmt, mem, ptr};
+
+/* room for the stored size that keeps the returned block 16 byte aligned */
+const HEADER: usize = 16;
+
+/// The Rust global allocator as a `GlobalAlloc` value.
+pub struct RustAlloc;
+
+unsafe impl GlobalAlloc for RustAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        alloc::alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        alloc::dealloc(ptr, layout)
+    }
+}
+
+static RUST_ALLOC: RustAlloc = RustAlloc;
+
+/// `Allocator` on top of the Rust global allocator. This is what
+/// `nk_init_default` and the other `*_default` initializers use.
+pub fn rust_allocator() -> Allocator {
+    allocator_from(&RUST_ALLOC)
+}
+
+/// `Allocator` on top of any `GlobalAlloc`, e.g. `std::alloc::System` or an
+/// arena living as long as the program.
+pub fn allocator_from<A: GlobalAlloc>(a: &'static A) -> Allocator {
+    Allocator {
+        userdata: Handle {
+            ptr: a as *const A as *mut Void,
+        },
+        alloc: Some(plugin_alloc::<A>),
+        free: Some(plugin_free::<A>),
+    }
+}
+
+/// Allocates `size` bytes from `a`, or returns null. Blocks have to be
+/// released with `free_with` on the same allocator.
+///
+/// # Safety
+///
+/// `a` must be a working `GlobalAlloc`; the block is uninitialized.
+pub unsafe fn alloc_with<A: GlobalAlloc>(a: &A, size: Size) -> *mut Void {
+    if size > (usize::MAX - HEADER) as Size {
+        return ptr::null_mut();
+    }
+    let total = size as usize + HEADER;
+    let layout = match Layout::from_size_align(total, HEADER) {
+        Ok(layout) => layout,
+        Err(_) => return ptr::null_mut(),
+    };
+    let base = a.alloc(layout);
+    if base.is_null() {
+        return ptr::null_mut();
+    }
+    *(base as *mut usize) = total;
+    base.add(HEADER) as *mut Void
+}
+
+/// Releases a block of `alloc_with`; null is ignored.
+///
+/// # Safety
+///
+/// `block` must be null or come from `alloc_with` on `a` and not have been
+/// freed yet.
+pub unsafe fn free_with<A: GlobalAlloc>(a: &A, block: *mut Void) {
+    if block.is_null() {
+        return;
+    }
+    let base = (block as *mut u8).sub(HEADER);
+    let total = *(base as *const usize);
+    a.dealloc(base, Layout::from_size_align_unchecked(total, HEADER));
+}
+
+/* `old` is ignored like with malloc: nk_buffer_realloc copies and frees it */
+unsafe fn plugin_alloc<A: GlobalAlloc>(handle: Handle, _old: *mut Void, size: Size) -> *mut Void {
+    alloc_with(&*(handle.ptr as *const A), size)
+}
+
+unsafe fn plugin_free<A: GlobalAlloc>(handle: Handle, block: *mut Void) {
+    free_with(&*(handle.ptr as *const A), block)
+}
+
+/// Heap allocated `Context` that is released with `nk_free` on drop.
+///
+/// The context stays at the same address for its whole life, which window
+/// command buffers rely on, and derefs to `Context` for the `nk_*` API.
+pub struct OwnedContext {
+    ctx: Box<Context>,
+}
+
+impl OwnedContext {
+    /// Context on the Rust global allocator without a font; set one with
+    /// `nk_style_set_font` before drawing text.
+    pub fn new() -> Self {
+        unsafe { Self::with_allocator(&rust_allocator(), ptr::null()) }
+    }
+
+    /// Context on the Rust global allocator drawing text with `font`.
+    ///
+    /// # Safety
+    ///
+    /// `font` has to outlive the context.
+    pub unsafe fn with_font(font: &UserFont) -> Self {
+        Self::with_allocator(&rust_allocator(), font)
+    }
+
+    /// Context allocating through `alloc`.
+    ///
+    /// # Safety
+    ///
+    /// `alloc` has to stay usable for the life of the context. `font` may
+    /// be null and has to outlive the context otherwise.
+    pub unsafe fn with_allocator(alloc: &Allocator, font: *const UserFont) -> Self {
+        let mut ctx: Box<Context> = Box::new(mem::zeroed());
+        let mut alloc = *alloc;
+        nk_init(&mut *ctx, &mut alloc, font);
+        OwnedContext { ctx }
+    }
+
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut Context {
+        &mut *self.ctx
+    }
+}
+
+impl Default for OwnedContext {
+    fn default() -> Self {
+        OwnedContext::new()
+    }
+}
+
+impl Deref for OwnedContext {
+    type Target = Context;
+    fn deref(&self) -> &Context {
+        &self.ctx
+    }
+}
+
+impl DerefMut for OwnedContext {
+    fn deref_mut(&mut self) -> &mut Context {
+        &mut self.ctx
+    }
+}
+
+impl Drop for OwnedContext {
+    fn drop(&mut self) {
+        unsafe { nk_free(&mut *self.ctx) }
+    }
+}
+
+/// A memory region that ran out during a frame or a conversion.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OutOfMemory {
+    /// One of the `NK_MEMORY_*` regions.
+    pub region: MemoryRegion,
+    /// Bytes the region was asked for, including what did not fit.
+    pub needed: Size,
+    pub capacity: Size,
+    /// Allocations that did not fit.
+    pub failed: Size,
+    /// Windows left out under `NK_OOM_TRUNCATE_WINDOWS`.
+    pub truncated_windows: Uint,
+}
+
+impl OutOfMemory {
+    fn new(region: MemoryRegion, usage: &MemoryUsage, truncated_windows: Uint) -> Self {
+        OutOfMemory {
+            region,
+            needed: usage.needed,
+            capacity: usage.capacity,
+            failed: usage.failed,
+            truncated_windows,
+        }
+    }
+}
+
+impl fmt::Display for OutOfMemory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let region = match self.region {
+            NK_MEMORY_COMMANDS => "command buffer",
+            NK_MEMORY_POOL => "window pool",
+            NK_MEMORY_TEXT_EDIT => "text edit",
+            NK_MEMORY_DRAW_COMMANDS => "draw command buffer",
+            NK_MEMORY_VERTICES => "vertex buffer",
+            NK_MEMORY_ELEMENTS => "element buffer",
+            _ => "font atlas",
+        };
+        write!(
+            f,
+            "out of {} memory: {} bytes needed, {} available, {} allocations failed",
+            region, self.needed, self.capacity, self.failed
+        )?;
+        if self.truncated_windows > 0 {
+            write!(f, ", {} windows left out", self.truncated_windows)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for OutOfMemory {}
+
+/// Ends the frame with `nk_clear` and reports the first region of `ctx`
+/// that ran out of memory while it was built.
+pub fn end_frame(ctx: &mut Context) -> Result<(), OutOfMemory> {
+    unsafe { nk_clear(ctx) };
+    let report = &ctx.memory_report;
+    let regions = [
+        (NK_MEMORY_COMMANDS, &report.commands),
+        (NK_MEMORY_POOL, &report.pool),
+        (NK_MEMORY_TEXT_EDIT, &report.text_edit),
+    ];
+    for &(region, usage) in regions.iter() {
+        if 0 != report.overflow & region {
+            return Err(OutOfMemory::new(region, usage, report.truncated_windows));
+        }
+    }
+    Ok(())
+}
+
+/// Why `convert` failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConvertError {
+    /// `ConvertConfig::vertex_layout` is missing.
+    InvalidParam,
+    OutOfMemory(OutOfMemory),
+}
+
+impl From<OutOfMemory> for ConvertError {
+    fn from(err: OutOfMemory) -> Self {
+        ConvertError::OutOfMemory(err)
+    }
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConvertError::InvalidParam => write!(f, "nk_convert: no vertex layout"),
+            ConvertError::OutOfMemory(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for ConvertError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ConvertError::InvalidParam => None,
+            ConvertError::OutOfMemory(ref err) => Some(err),
+        }
+    }
+}
+
+/// `nk_convert` reporting a missing vertex layout or the first buffer that
+/// ran out of memory.
+///
+/// # Safety
+///
+/// The buffers have to be initialized and `config` has to describe the
+/// vertex layout it points to, terminated by an `NK_VERTEX_ATTRIBUTE_COUNT`
+/// element.
+pub unsafe fn convert(
+    ctx: &mut Context,
+    cmds: &mut Buffer,
+    vertices: &mut Buffer,
+    elements: &mut Buffer,
+    config: &ConvertConfig,
+) -> Result<(), ConvertError> {
+    let res = nk_convert(ctx, cmds, vertices, elements, config);
+    if 0 != res & NK_CONVERT_INVALID_PARAM {
+        return Err(ConvertError::InvalidParam);
+    }
+    let report = &ctx.memory_report;
+    let buffers = [
+        (NK_CONVERT_COMMAND_BUFFER_FULL, NK_MEMORY_DRAW_COMMANDS, &report.draw_commands),
+        (NK_CONVERT_VERTEX_BUFFER_FULL, NK_MEMORY_VERTICES, &report.vertices),
+        (NK_CONVERT_ELEMENT_BUFFER_FULL, NK_MEMORY_ELEMENTS, &report.elements),
+    ];
+    for &(full, region, usage) in buffers.iter() {
+        if 0 != res & full {
+            return Err(OutOfMemory::new(region, usage, 0).into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use math::Rect;
+
+    unsafe fn no_width(_: Handle, _: f32, _: *const Char, _: Int) -> f32 {
+        0.0
+    }
+
+    /* windows measure the font even without a title */
+    fn font() -> UserFont {
+        UserFont {
+            userdata: Handle { id: 0 },
+            height: 13.0,
+            width: Some(no_width),
+            query: None,
+            texture: Handle { id: 0 },
+        }
+    }
+
+    /* windows with a background, so there is something to convert */
+    fn frame(ctx: &mut Context, windows: usize) {
+        for i in 0..windows {
+            let name = format!("w{}\0", i);
+            let bounds = Rect::new(10.0 * i as f32, 0.0, 100.0, 100.0);
+            unsafe {
+                nk_begin(ctx, name.as_ptr() as _, bounds, NK_WINDOW_BORDER as Flags);
+                nk_end(ctx);
+            }
+        }
+    }
+
+    fn buffer(size: usize) -> (Vec<u8>, Buffer) {
+        let mut memory = vec![0u8; size];
+        let mut b: Buffer = unsafe { mem::zeroed() };
+        unsafe { nk_buffer_init_fixed(&mut b, memory.as_mut_ptr() as _, size as Size) };
+        (memory, b)
+    }
+
+    #[test]
+    fn blocks_round_trip() {
+        unsafe {
+            let block = alloc_with(&RUST_ALLOC, 100) as *mut u8;
+            assert!(!block.is_null());
+            assert_eq!(block as usize % HEADER, 0);
+            ptr::write_bytes(block, 0xab, 100);
+            free_with(&RUST_ALLOC, block as _);
+            assert!(alloc_with(&RUST_ALLOC, Size::MAX).is_null());
+            free_with(&RUST_ALLOC, ptr::null_mut());
+        }
+    }
+
+    /* one window filled with `rects` rectangles */
+    fn fills(ctx: &mut Context, rects: usize) {
+        unsafe {
+            let bounds = Rect::new(0.0, 0.0, 100.0, 100.0);
+            nk_begin(ctx, b"w\0".as_ptr() as _, bounds, NK_WINDOW_BORDER as Flags);
+            let canvas = nk_window_get_canvas(ctx);
+            for i in 0..rects {
+                let r = Rect::new((i % 50) as f32, 20.0, 10.0, 10.0);
+                nk_fill_rect(canvas, r, 0.0, nk_rgb(255, 0, 0));
+            }
+            nk_end(ctx);
+        }
+    }
+
+    #[test]
+    fn fixed_context_reports_the_region_that_ran_out() {
+        let font = font();
+        let run = |size: usize| {
+            let mut memory = vec![0u8; size];
+            let mut ctx: Box<Context> = Box::new(unsafe { mem::zeroed() });
+            unsafe { nk_init_fixed(&mut *ctx, memory.as_mut_ptr() as _, size as Size, &font) };
+            fills(&mut ctx, 1000);
+            let res = end_frame(&mut ctx);
+            let report = ctx.memory_report;
+            unsafe { nk_free(&mut *ctx) };
+            (res, report)
+        };
+        /* the same frame in memory it fits into tells what it needs */
+        let (res, fits) = run(64 * 1024);
+        assert_eq!(res, Ok(()));
+        let needed = fits.commands.needed;
+        /* room for the window and its panel in the back, not the fills */
+        let (res, report) = run(8 * 1024);
+        assert!(needed > 8 * 1024 && fits.pool.needed < 2 * 1024);
+        let err = res.unwrap_err();
+        assert_eq!(err.region, NK_MEMORY_COMMANDS);
+        assert_eq!(err.needed, needed);
+        assert_eq!(err.capacity, 8 * 1024);
+        assert!(err.failed > 0);
+        assert_eq!(report.overflow, NK_MEMORY_COMMANDS);
+        assert_eq!(report.pool.failed, 0);
+    }
+
+    #[test]
+    fn pool_pages_hold_every_window() {
+        let (font, alloc) = (font(), rust_allocator());
+        let mut memory = vec![0u8; 16 * 1024];
+        let mut ctx: Box<Context> = Box::new(unsafe { mem::zeroed() });
+        let size = memory.len() as Size;
+        unsafe {
+            nk_init_fixed(&mut *ctx, memory.as_mut_ptr() as _, size, &font);
+            nk_set_oom_policy(&mut *ctx, NK_OOM_FALLBACK as Flags, &alloc);
+        }
+        for _ in 0..3 {
+            frame(&mut ctx, 40);
+            end_frame(&mut ctx).unwrap();
+        }
+        assert_eq!(ctx.count, 40);
+        assert!(!ctx.pool.pages.is_null());
+        unsafe { nk_free(&mut *ctx) };
+    }
+
+    #[test]
+    fn convert_reports_errors() {
+        let font = font();
+        let mut ctx = unsafe { OwnedContext::with_font(&font) };
+        frame(&mut ctx, 1);
+        let layout = [
+            DrawVertexLayoutElement {
+                attribute: NK_VERTEX_POSITION,
+                format: NK_FORMAT_FLOAT,
+                offset: 0,
+            },
+            DrawVertexLayoutElement {
+                attribute: NK_VERTEX_ATTRIBUTE_COUNT,
+                format: NK_FORMAT_COUNT,
+                offset: 0,
+            },
+        ];
+        let mut config: ConvertConfig = unsafe { mem::zeroed() };
+        config.vertex_size = 8;
+        config.vertex_alignment = 4;
+        let (_c, mut cmds) = buffer(64 * 1024);
+        let (_v, mut vertices) = buffer(16);
+        let (_e, mut elements) = buffer(64 * 1024);
+        let mut run = |config: &ConvertConfig| unsafe {
+            convert(&mut ctx, &mut cmds, &mut vertices, &mut elements, config)
+        };
+        assert_eq!(run(&config), Err(ConvertError::InvalidParam));
+        config.vertex_layout = layout.as_ptr();
+        match run(&config) {
+            Err(ConvertError::OutOfMemory(err)) => assert_eq!(err.region, NK_MEMORY_VERTICES),
+            _ => panic!("vertices should not fit into 16 bytes"),
+        }
+    }
+}
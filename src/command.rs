@@ -0,0 +1,929 @@
+//! Typed view over the command queue of a `Context`.
+//!
+//! `Commands` walks `nk__begin`/`nk__next` and yields a `Command` for every
+//! queued draw command, so backends can pattern-match instead of casting
+//! `nuklear::Command` pointers by `CommandType`. The crate root exports
+//! that raw header every queued command starts with as `CommandHeader`.
+
+use custom::{custom_draw_id, is_custom_draw, CustomDrawId};
+use math::{Point, Rect};
+use nuklear::{self, *};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::{ptr, slice, str};
+
+#[derive(Copy, Clone)]
+pub enum Command<'a> {
+    Nop,
+    Scissor {
+        rect: Rect,
+    },
+    Line {
+        begin: Point,
+        end: Point,
+        thickness: f32,
+        color: Color,
+    },
+    Curve {
+        begin: Point,
+        ctrl: [Point; 2],
+        end: Point,
+        thickness: f32,
+        color: Color,
+    },
+    LineStyled {
+        begin: Point,
+        end: Point,
+        thickness: f32,
+        color: Color,
+        style: StrokeStyle,
+    },
+    CurveStyled {
+        begin: Point,
+        ctrl: [Point; 2],
+        end: Point,
+        thickness: f32,
+        color: Color,
+        style: StrokeStyle,
+    },
+    Rect {
+        rect: Rect,
+        rounding: f32,
+        thickness: f32,
+        color: Color,
+    },
+    RectFilled {
+        rect: Rect,
+        rounding: f32,
+        color: Color,
+    },
+    /// Alpha fades out across a band `feather` wide centered on the
+    /// outline.
+    RectShadow {
+        rect: Rect,
+        rounding: f32,
+        feather: f32,
+        color: Color,
+    },
+    RectMultiColor {
+        rect: Rect,
+        left: Color,
+        top: Color,
+        right: Color,
+        bottom: Color,
+    },
+    /// Corner radii are ordered top-left, top-right, bottom-right,
+    /// bottom-left.
+    RectCorners {
+        rect: Rect,
+        rounding: [f32; 4],
+        thickness: f32,
+        color: Color,
+    },
+    RectCornersFilled {
+        rect: Rect,
+        rounding: [f32; 4],
+        color: Color,
+    },
+    RectGradient {
+        rect: Rect,
+        rounding: [f32; 4],
+        gradient: Gradient,
+    },
+    Circle {
+        rect: Rect,
+        thickness: f32,
+        color: Color,
+    },
+    CircleFilled {
+        rect: Rect,
+        color: Color,
+    },
+    CircleGradient {
+        rect: Rect,
+        gradient: Gradient,
+    },
+    Arc {
+        center: Point,
+        radius: f32,
+        angles: [f32; 2],
+        thickness: f32,
+        color: Color,
+    },
+    ArcFilled {
+        center: Point,
+        radius: f32,
+        angles: [f32; 2],
+        color: Color,
+    },
+    Triangle {
+        points: [Point; 3],
+        thickness: f32,
+        color: Color,
+    },
+    TriangleFilled {
+        points: [Point; 3],
+        color: Color,
+    },
+    Polygon {
+        points: &'a [PointI],
+        thickness: f32,
+        color: Color,
+    },
+    PolygonFilled {
+        points: &'a [PointI],
+        color: Color,
+    },
+    PolygonGradient {
+        points: &'a [PointI],
+        gradient: Gradient,
+    },
+    Polyline {
+        points: &'a [PointI],
+        thickness: f32,
+        color: Color,
+    },
+    PolygonStyled {
+        points: &'a [PointI],
+        thickness: f32,
+        color: Color,
+        style: StrokeStyle,
+    },
+    PolylineStyled {
+        points: &'a [PointI],
+        thickness: f32,
+        color: Color,
+        style: StrokeStyle,
+    },
+    Text {
+        rect: Rect,
+        font: &'a UserFont,
+        height: f32,
+        string: &'a str,
+        background: Color,
+        foreground: Color,
+    },
+    Image {
+        rect: Rect,
+        image: Image,
+        color: Color,
+    },
+    Custom {
+        rect: Rect,
+        callback: CommandCustomCallback,
+        data: Handle,
+    },
+    /// Closure queued with `CustomDraws::push`.
+    CustomDraw {
+        rect: Rect,
+        id: CustomDrawId,
+    },
+}
+
+#[inline]
+fn rect(x: Short, y: Short, w: Ushort, h: Ushort) -> Rect {
+    Rect::new(x as f32, y as f32, w as f32, h as f32)
+}
+
+#[inline]
+fn point(p: PointI) -> Point {
+    Point::new(p.x as f32, p.y as f32)
+}
+
+unsafe fn points<'a>(first: *const PointI, count: Ushort) -> &'a [PointI] {
+    slice::from_raw_parts(first, count as usize)
+}
+
+#[inline]
+fn radii(r: [Ushort; 4]) -> [f32; 4] {
+    [r[0] as f32, r[1] as f32, r[2] as f32, r[3] as f32]
+}
+
+impl<'a> Command<'a> {
+    /// Decodes a raw command header.
+    ///
+    /// # Safety
+    ///
+    /// `cmd` must point to a command written by one of the `nk_stroke_*`,
+    /// `nk_fill_*`, `nk_draw_*` or `nk_push_*` functions and stay valid
+    /// for `'a`.
+    pub unsafe fn from_raw(cmd: *const nuklear::Command) -> Command<'a> {
+        match (*cmd).type_0 {
+            NK_COMMAND_SCISSOR => {
+                let c = &*(cmd as *const CommandScissor);
+                Command::Scissor {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                }
+            }
+            NK_COMMAND_LINE => {
+                let c = &*(cmd as *const CommandLine);
+                Command::Line {
+                    begin: point(c.begin),
+                    end: point(c.end),
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_CURVE => {
+                let c = &*(cmd as *const CommandCurve);
+                Command::Curve {
+                    begin: point(c.begin),
+                    ctrl: [point(c.ctrl[0]), point(c.ctrl[1])],
+                    end: point(c.end),
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_LINE_STYLED => {
+                let c = &*(cmd as *const CommandLineStyled);
+                Command::LineStyled {
+                    begin: point(c.begin),
+                    end: point(c.end),
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                    style: c.style,
+                }
+            }
+            NK_COMMAND_CURVE_STYLED => {
+                let c = &*(cmd as *const CommandCurveStyled);
+                Command::CurveStyled {
+                    begin: point(c.begin),
+                    ctrl: [point(c.ctrl[0]), point(c.ctrl[1])],
+                    end: point(c.end),
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                    style: c.style,
+                }
+            }
+            NK_COMMAND_RECT => {
+                let c = &*(cmd as *const CommandRect);
+                Command::Rect {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    rounding: c.rounding as f32,
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_RECT_FILLED => {
+                let c = &*(cmd as *const CommandRectFilled);
+                Command::RectFilled {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    rounding: c.rounding as f32,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_RECT_SHADOW => {
+                let c = &*(cmd as *const CommandRectShadow);
+                Command::RectShadow {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    rounding: c.rounding as f32,
+                    feather: c.feather as f32,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_RECT_MULTI_COLOR => {
+                let c = &*(cmd as *const CommandRectMultiColor);
+                Command::RectMultiColor {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    left: c.left,
+                    top: c.top,
+                    right: c.right,
+                    bottom: c.bottom,
+                }
+            }
+            NK_COMMAND_RECT_CORNERS => {
+                let c = &*(cmd as *const CommandRectCorners);
+                Command::RectCorners {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    rounding: radii(c.rounding),
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_RECT_CORNERS_FILLED => {
+                let c = &*(cmd as *const CommandRectCornersFilled);
+                Command::RectCornersFilled {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    rounding: radii(c.rounding),
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_RECT_GRADIENT => {
+                let c = &*(cmd as *const CommandRectGradient);
+                Command::RectGradient {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    rounding: radii(c.rounding),
+                    gradient: c.gradient,
+                }
+            }
+            NK_COMMAND_CIRCLE => {
+                let c = &*(cmd as *const CommandCircle);
+                Command::Circle {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_CIRCLE_FILLED => {
+                let c = &*(cmd as *const CommandCircleFilled);
+                Command::CircleFilled {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_CIRCLE_GRADIENT => {
+                let c = &*(cmd as *const CommandCircleGradient);
+                Command::CircleGradient {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    gradient: c.gradient,
+                }
+            }
+            NK_COMMAND_ARC => {
+                let c = &*(cmd as *const CommandArc);
+                Command::Arc {
+                    center: Point::new(c.cx as f32, c.cy as f32),
+                    radius: c.r as f32,
+                    angles: c.a,
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_ARC_FILLED => {
+                let c = &*(cmd as *const CommandArcFilled);
+                Command::ArcFilled {
+                    center: Point::new(c.cx as f32, c.cy as f32),
+                    radius: c.r as f32,
+                    angles: c.a,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_TRIANGLE => {
+                let c = &*(cmd as *const CommandTriangle);
+                Command::Triangle {
+                    points: [point(c.a), point(c.b), point(c.c)],
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_TRIANGLE_FILLED => {
+                let c = &*(cmd as *const CommandTriangleFilled);
+                Command::TriangleFilled {
+                    points: [point(c.a), point(c.b), point(c.c)],
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_POLYGON => {
+                let c = &*(cmd as *const CommandPolygon);
+                Command::Polygon {
+                    points: points(c.points.as_ptr(), c.point_count),
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_POLYGON_FILLED => {
+                let c = &*(cmd as *const CommandPolygonFilled);
+                Command::PolygonFilled {
+                    points: points(c.points.as_ptr(), c.point_count),
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_POLYGON_GRADIENT => {
+                let c = &*(cmd as *const CommandPolygonGradient);
+                Command::PolygonGradient {
+                    points: points(c.points.as_ptr(), c.point_count),
+                    gradient: c.gradient,
+                }
+            }
+            NK_COMMAND_POLYLINE => {
+                let c = &*(cmd as *const CommandPolyline);
+                Command::Polyline {
+                    points: points(c.points.as_ptr(), c.point_count),
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                }
+            }
+            NK_COMMAND_POLYGON_STYLED => {
+                let c = &*(cmd as *const CommandPolygonStyled);
+                Command::PolygonStyled {
+                    points: points(c.points.as_ptr(), c.point_count),
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                    style: c.style,
+                }
+            }
+            NK_COMMAND_POLYLINE_STYLED => {
+                let c = &*(cmd as *const CommandPolylineStyled);
+                Command::PolylineStyled {
+                    points: points(c.points.as_ptr(), c.point_count),
+                    thickness: c.line_thickness as f32,
+                    color: c.color,
+                    style: c.style,
+                }
+            }
+            NK_COMMAND_TEXT => {
+                let c = &*(cmd as *const CommandText);
+                let bytes =
+                    slice::from_raw_parts(c.string.as_ptr() as *const u8, c.length as usize);
+                let string = match str::from_utf8(bytes) {
+                    Ok(s) => s,
+                    Err(e) => str::from_utf8_unchecked(&bytes[..e.valid_up_to()]),
+                };
+                Command::Text {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    font: &*c.font,
+                    height: c.height,
+                    string,
+                    background: c.background,
+                    foreground: c.foreground,
+                }
+            }
+            NK_COMMAND_IMAGE => {
+                let c = &*(cmd as *const CommandImage);
+                Command::Image {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    image: c.img,
+                    color: c.col,
+                }
+            }
+            NK_COMMAND_CUSTOM => {
+                let c = &*(cmd as *const CommandCustom);
+                if is_custom_draw(c.callback) {
+                    return Command::CustomDraw {
+                        rect: rect(c.x, c.y, c.w, c.h),
+                        id: custom_draw_id(c.callback_data),
+                    };
+                }
+                Command::Custom {
+                    rect: rect(c.x, c.y, c.w, c.h),
+                    callback: c.callback,
+                    data: c.callback_data,
+                }
+            }
+            _ => Command::Nop,
+        }
+    }
+}
+
+fn hash_f32<H: Hasher>(v: f32, state: &mut H) {
+    v.to_bits().hash(state)
+}
+
+fn hash_point<H: Hasher>(p: Point, state: &mut H) {
+    hash_f32(p.x, state);
+    hash_f32(p.y, state);
+}
+
+fn hash_rect<H: Hasher>(r: Rect, state: &mut H) {
+    hash_f32(r.x, state);
+    hash_f32(r.y, state);
+    hash_f32(r.w, state);
+    hash_f32(r.h, state);
+}
+
+fn hash_color<H: Hasher>(c: Color, state: &mut H) {
+    [c.r, c.g, c.b, c.a].hash(state)
+}
+
+fn hash_gradient<H: Hasher>(g: &Gradient, state: &mut H) {
+    g.type_0.hash(state);
+    hash_point(g.start, state);
+    hash_point(g.end, state);
+    hash_color(g.start_color, state);
+    hash_color(g.end_color, state);
+}
+
+fn hash_stroke_style<H: Hasher>(s: &StrokeStyle, state: &mut H) {
+    (s.cap, s.join, s.dash_count).hash(state);
+    hash_f32(s.miter_limit, state);
+    hash_f32(s.dash_offset, state);
+    for &d in s.dash.iter().take(s.dash_count as usize) {
+        hash_f32(d, state);
+    }
+}
+
+/// Hashes everything that affects the pixels a command draws; fonts,
+/// images and callbacks by identity. Closures of `CustomDraw` are opaque,
+/// so only their rectangle is hashed.
+impl<'a> Hash for Command<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            Command::Nop => NK_COMMAND_NOP.hash(state),
+            Command::Scissor { rect } => {
+                NK_COMMAND_SCISSOR.hash(state);
+                hash_rect(rect, state);
+            }
+            Command::Line {
+                begin,
+                end,
+                thickness,
+                color,
+            } => {
+                NK_COMMAND_LINE.hash(state);
+                hash_point(begin, state);
+                hash_point(end, state);
+                hash_f32(thickness, state);
+                hash_color(color, state);
+            }
+            Command::Curve {
+                begin,
+                ctrl,
+                end,
+                thickness,
+                color,
+            } => {
+                NK_COMMAND_CURVE.hash(state);
+                hash_point(begin, state);
+                hash_point(ctrl[0], state);
+                hash_point(ctrl[1], state);
+                hash_point(end, state);
+                hash_f32(thickness, state);
+                hash_color(color, state);
+            }
+            Command::LineStyled {
+                begin,
+                end,
+                thickness,
+                color,
+                ref style,
+            } => {
+                NK_COMMAND_LINE_STYLED.hash(state);
+                hash_point(begin, state);
+                hash_point(end, state);
+                hash_f32(thickness, state);
+                hash_color(color, state);
+                hash_stroke_style(style, state);
+            }
+            Command::CurveStyled {
+                begin,
+                ctrl,
+                end,
+                thickness,
+                color,
+                ref style,
+            } => {
+                NK_COMMAND_CURVE_STYLED.hash(state);
+                hash_point(begin, state);
+                hash_point(ctrl[0], state);
+                hash_point(ctrl[1], state);
+                hash_point(end, state);
+                hash_f32(thickness, state);
+                hash_color(color, state);
+                hash_stroke_style(style, state);
+            }
+            Command::Rect {
+                rect,
+                rounding,
+                thickness,
+                color,
+            } => {
+                NK_COMMAND_RECT.hash(state);
+                hash_rect(rect, state);
+                hash_f32(rounding, state);
+                hash_f32(thickness, state);
+                hash_color(color, state);
+            }
+            Command::RectFilled {
+                rect,
+                rounding,
+                color,
+            } => {
+                NK_COMMAND_RECT_FILLED.hash(state);
+                hash_rect(rect, state);
+                hash_f32(rounding, state);
+                hash_color(color, state);
+            }
+            Command::RectShadow {
+                rect,
+                rounding,
+                feather,
+                color,
+            } => {
+                NK_COMMAND_RECT_SHADOW.hash(state);
+                hash_rect(rect, state);
+                hash_f32(rounding, state);
+                hash_f32(feather, state);
+                hash_color(color, state);
+            }
+            Command::RectMultiColor {
+                rect,
+                left,
+                top,
+                right,
+                bottom,
+            } => {
+                NK_COMMAND_RECT_MULTI_COLOR.hash(state);
+                hash_rect(rect, state);
+                for &c in &[left, top, right, bottom] {
+                    hash_color(c, state);
+                }
+            }
+            Command::RectCorners {
+                rect,
+                rounding,
+                thickness,
+                color,
+            } => {
+                NK_COMMAND_RECT_CORNERS.hash(state);
+                hash_rect(rect, state);
+                for &r in &rounding {
+                    hash_f32(r, state);
+                }
+                hash_f32(thickness, state);
+                hash_color(color, state);
+            }
+            Command::RectCornersFilled {
+                rect,
+                rounding,
+                color,
+            } => {
+                NK_COMMAND_RECT_CORNERS_FILLED.hash(state);
+                hash_rect(rect, state);
+                for &r in &rounding {
+                    hash_f32(r, state);
+                }
+                hash_color(color, state);
+            }
+            Command::RectGradient {
+                rect,
+                rounding,
+                ref gradient,
+            } => {
+                NK_COMMAND_RECT_GRADIENT.hash(state);
+                hash_rect(rect, state);
+                for &r in &rounding {
+                    hash_f32(r, state);
+                }
+                hash_gradient(gradient, state);
+            }
+            Command::Circle {
+                rect,
+                thickness,
+                color,
+            } => {
+                NK_COMMAND_CIRCLE.hash(state);
+                hash_rect(rect, state);
+                hash_f32(thickness, state);
+                hash_color(color, state);
+            }
+            Command::CircleFilled { rect, color } => {
+                NK_COMMAND_CIRCLE_FILLED.hash(state);
+                hash_rect(rect, state);
+                hash_color(color, state);
+            }
+            Command::CircleGradient { rect, ref gradient } => {
+                NK_COMMAND_CIRCLE_GRADIENT.hash(state);
+                hash_rect(rect, state);
+                hash_gradient(gradient, state);
+            }
+            Command::Arc {
+                center,
+                radius,
+                angles,
+                thickness,
+                color,
+            } => {
+                NK_COMMAND_ARC.hash(state);
+                hash_point(center, state);
+                hash_f32(radius, state);
+                hash_f32(angles[0], state);
+                hash_f32(angles[1], state);
+                hash_f32(thickness, state);
+                hash_color(color, state);
+            }
+            Command::ArcFilled {
+                center,
+                radius,
+                angles,
+                color,
+            } => {
+                NK_COMMAND_ARC_FILLED.hash(state);
+                hash_point(center, state);
+                hash_f32(radius, state);
+                hash_f32(angles[0], state);
+                hash_f32(angles[1], state);
+                hash_color(color, state);
+            }
+            Command::Triangle {
+                points,
+                thickness,
+                color,
+            } => {
+                NK_COMMAND_TRIANGLE.hash(state);
+                for &p in &points {
+                    hash_point(p, state);
+                }
+                hash_f32(thickness, state);
+                hash_color(color, state);
+            }
+            Command::TriangleFilled { points, color } => {
+                NK_COMMAND_TRIANGLE_FILLED.hash(state);
+                for &p in &points {
+                    hash_point(p, state);
+                }
+                hash_color(color, state);
+            }
+            Command::Polygon {
+                points,
+                thickness,
+                color,
+            }
+            | Command::Polyline {
+                points,
+                thickness,
+                color,
+            } => {
+                match *self {
+                    Command::Polygon { .. } => NK_COMMAND_POLYGON.hash(state),
+                    _ => NK_COMMAND_POLYLINE.hash(state),
+                }
+                points.len().hash(state);
+                for p in points {
+                    (p.x, p.y).hash(state);
+                }
+                hash_f32(thickness, state);
+                hash_color(color, state);
+            }
+            Command::PolygonStyled {
+                points,
+                thickness,
+                color,
+                ref style,
+            }
+            | Command::PolylineStyled {
+                points,
+                thickness,
+                color,
+                ref style,
+            } => {
+                match *self {
+                    Command::PolygonStyled { .. } => NK_COMMAND_POLYGON_STYLED.hash(state),
+                    _ => NK_COMMAND_POLYLINE_STYLED.hash(state),
+                }
+                points.len().hash(state);
+                for p in points {
+                    (p.x, p.y).hash(state);
+                }
+                hash_f32(thickness, state);
+                hash_color(color, state);
+                hash_stroke_style(style, state);
+            }
+            Command::PolygonFilled { points, color } => {
+                NK_COMMAND_POLYGON_FILLED.hash(state);
+                points.len().hash(state);
+                for p in points {
+                    (p.x, p.y).hash(state);
+                }
+                hash_color(color, state);
+            }
+            Command::PolygonGradient {
+                points,
+                ref gradient,
+            } => {
+                NK_COMMAND_POLYGON_GRADIENT.hash(state);
+                points.len().hash(state);
+                for p in points {
+                    (p.x, p.y).hash(state);
+                }
+                hash_gradient(gradient, state);
+            }
+            Command::Text {
+                rect,
+                font,
+                height,
+                string,
+                background,
+                foreground,
+            } => {
+                NK_COMMAND_TEXT.hash(state);
+                hash_rect(rect, state);
+                (font as *const UserFont as usize).hash(state);
+                hash_f32(height, state);
+                string.hash(state);
+                hash_color(background, state);
+                hash_color(foreground, state);
+            }
+            Command::Image { rect, image, color } => {
+                NK_COMMAND_IMAGE.hash(state);
+                hash_rect(rect, state);
+                (unsafe { image.handle.ptr } as usize).hash(state);
+                (image.w, image.h, image.region).hash(state);
+                hash_color(color, state);
+            }
+            Command::Custom {
+                rect,
+                callback,
+                data,
+            } => {
+                NK_COMMAND_CUSTOM.hash(state);
+                hash_rect(rect, state);
+                callback.map(|f| f as usize).hash(state);
+                (unsafe { data.ptr } as usize).hash(state);
+            }
+            Command::CustomDraw { rect, .. } => {
+                NK_COMMAND_CUSTOM.hash(state);
+                hash_rect(rect, state);
+            }
+        }
+    }
+}
+
+/// Iterator over the command queue of a `Context`.
+///
+/// Borrows the context mutably since the first call to `nk__begin` links
+/// all window, popup and overlay buffers into one list.
+pub struct Commands<'a> {
+    ctx: *mut Context,
+    cmd: *const nuklear::Command,
+    _marker: PhantomData<&'a mut Context>,
+}
+
+impl<'a> Iterator for Commands<'a> {
+    type Item = Command<'a>;
+
+    fn next(&mut self) -> Option<Command<'a>> {
+        if self.cmd.is_null() {
+            return None;
+        }
+        unsafe {
+            let cmd = Command::from_raw(self.cmd);
+            self.cmd = nk__next(self.ctx, self.cmd);
+            Some(cmd)
+        }
+    }
+}
+
+impl Context {
+    /// Iterates over all commands queued during the current frame.
+    pub fn commands(&mut self) -> Commands<'_> {
+        let cmd = unsafe { nk__begin(self) };
+        Commands {
+            ctx: self,
+            cmd,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Hash of the commands queued during the current frame; equal hashes
+    /// mean the frames draw the same. With `nk_track_redraws` on,
+    /// `nk_clear` keeps the hash of every finished frame in `redraw`.
+    pub fn frame_hash(&mut self) -> u64 {
+        let mut state = DefaultHasher::new();
+        for cmd in self.commands() {
+            cmd.hash(&mut state);
+        }
+        state.finish()
+    }
+}
+
+/// Iterator over the commands of a single `CommandBuffer`, e.g. one window.
+pub struct BufferCommands<'a> {
+    base: *const u8,
+    offset: Size,
+    end: Size,
+    _marker: PhantomData<&'a CommandBuffer>,
+}
+
+impl<'a> BufferCommands<'a> {
+    /// # Safety
+    ///
+    /// `buffer` must be a command buffer of the current frame whose base
+    /// `Buffer` outlives `'a`.
+    pub unsafe fn new(buffer: &'a CommandBuffer) -> Self {
+        let base = if buffer.base.is_null() {
+            ptr::null()
+        } else {
+            (*buffer.base).memory.ptr as *const u8
+        };
+        BufferCommands {
+            base,
+            offset: buffer.begin,
+            end: if base.is_null() {
+                buffer.begin
+            } else {
+                buffer.end
+            },
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a> Iterator for BufferCommands<'a> {
+    type Item = Command<'a>;
+
+    fn next(&mut self) -> Option<Command<'a>> {
+        if self.offset >= self.end {
+            return None;
+        }
+        unsafe {
+            let cmd = self.base.offset(self.offset as isize) as *const nuklear::Command;
+            /* the last command of a window is linked to the next buffer */
+            self.offset = if (*cmd).next > self.offset && (*cmd).next <= self.end {
+                (*cmd).next
+            } else {
+                self.end
+            };
+            Some(Command::from_raw(cmd))
+        }
+    }
+}
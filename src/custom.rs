@@ -0,0 +1,203 @@
+//! Closure-based custom draw commands.
+//!
+//! `nk_push_custom` only carries a C callback and a `Handle`. `CustomDraws`
+//! keeps boxed closures for the frame instead and queues a custom command
+//! per closure, so backends can run arbitrary drawing (3D viewports,
+//! video) at the right place in the command stream without casting
+//! pointers:
+//!
+//! * backends walking `Context::commands` receive `Command::CustomDraw`
+//!   and call `CustomDraws::draw` with its id;
+//! * backends drawing the output of `nk_convert` find an empty
+//!   `DrawCommand` at the same position and call
+//!   `CustomDraws::draw_command` with it.
+//!
+//! `T` is whatever the backend hands to the closures, e.g. the `Pixmap`
+//! of the `Rasterizer` or a GL context wrapper. Closures only run through
+//! the registry that queued them and are dropped by `clear`, which the host
+//! calls once the frame has been drawn.
+
+use math::Rect;
+use nuklear::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/* ids are unique within the process, so a marker never matches a closure
+ * of another registry or frame */
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// Identifies a closure queued in a `CustomDraws`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CustomDrawId(usize);
+
+/// Where a custom draw command paints.
+#[derive(Copy, Clone)]
+pub struct CustomArea {
+    pub rect: Rect,
+    /// `rect` clipped to the window, backends should not draw outside it.
+    pub clip: Rect,
+}
+
+type DrawFn<'a, T> = Box<dyn FnMut(&mut T, CustomArea) + 'a>;
+
+struct Entry<'a, T: ?Sized> {
+    id: CustomDrawId,
+    area: CustomArea,
+    draw: DrawFn<'a, T>,
+}
+
+/// Closures queued as custom draw commands during one frame.
+pub struct CustomDraws<'a, T: ?Sized> {
+    entries: Vec<Entry<'a, T>>,
+}
+
+/* callback of every command queued by `CustomDraws::push`; `nk_convert`
+ * calls it with the draw list, where it leaves an empty draw command that
+ * carries the id as userdata */
+pub(crate) unsafe fn custom_draw_callback(
+    canvas: *mut Void,
+    x: Short,
+    y: Short,
+    w: Ushort,
+    h: Ushort,
+    data: Handle,
+) {
+    let list = canvas as *mut DrawList;
+    if list.is_null() {
+        return;
+    }
+    let clip = if 0 == (*list).cmd_count {
+        Rect::new(x as f32, y as f32, w as f32, h as f32)
+    } else {
+        (*list).clip_rect
+    };
+    let texture = (*list).config.null.texture;
+    let userdata = (*list).userdata;
+    nk_draw_list_push_userdata(list, data);
+    nk_draw_list_push_command(list, clip, texture);
+    /* later geometry must not reuse the marker */
+    nk_draw_list_push_userdata(list, userdata);
+    nk_draw_list_push_command(list, clip, texture);
+}
+
+pub(crate) fn is_custom_draw(callback: CommandCustomCallback) -> bool {
+    match callback {
+        Some(f) => f as usize == custom_draw_callback as unsafe fn(_, _, _, _, _, _) as usize,
+        None => false,
+    }
+}
+
+pub(crate) fn custom_draw_id(data: Handle) -> CustomDrawId {
+    CustomDrawId(unsafe { data.ptr } as usize)
+}
+
+fn intersect(a: Rect, b: Rect) -> Rect {
+    let x0 = a.x.max(b.x);
+    let y0 = a.y.max(b.y);
+    let x1 = (a.x + a.w).min(b.x + b.w);
+    let y1 = (a.y + a.h).min(b.y + b.h);
+    Rect::new(x0, y0, (x1 - x0).max(0.0), (y1 - y0).max(0.0))
+}
+
+impl<'a, T: ?Sized> Default for CustomDraws<'a, T> {
+    fn default() -> Self {
+        CustomDraws::new()
+    }
+}
+
+impl<'a, T: ?Sized> CustomDraws<'a, T> {
+    pub fn new() -> Self {
+        CustomDraws {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Queues `draw` as a custom command covering `rect` in the window
+    /// currently being built in `ctx`.
+    ///
+    /// Returns `None` outside of a window or if the command was clipped
+    /// away or did not fit; the closure is dropped then.
+    pub fn push<F>(&mut self, ctx: &mut Context, rect: Rect, draw: F) -> Option<CustomDrawId>
+    where
+        F: FnMut(&mut T, CustomArea) + 'a,
+    {
+        unsafe {
+            let buffer = nk_window_get_canvas(ctx);
+            if buffer.is_null() {
+                return None;
+            }
+            let clip = if 0 != (*buffer).use_clipping {
+                intersect(rect, (*buffer).clip)
+            } else {
+                rect
+            };
+            let id = CustomDrawId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+            let end = (*buffer).end;
+            nk_push_custom(
+                buffer,
+                rect,
+                Some(custom_draw_callback),
+                nk_handle_ptr(id.0 as *mut Void),
+            );
+            if (*buffer).end == end {
+                return None;
+            }
+            self.entries.push(Entry {
+                id,
+                area: CustomArea { rect, clip },
+                draw: Box::new(draw),
+            });
+            Some(id)
+        }
+    }
+
+    fn find(&mut self, id: CustomDrawId) -> Option<&mut Entry<'a, T>> {
+        self.entries.iter_mut().find(|e| e.id == id)
+    }
+
+    /// Runs the closure of `id` on `target`. Returns `false` if `id` was
+    /// not queued here.
+    pub fn draw(&mut self, id: CustomDrawId, target: &mut T) -> bool {
+        match self.find(id) {
+            Some(entry) => {
+                (entry.draw)(target, entry.area);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Id of the closure `cmd` of the `nk_convert` output stands for.
+    pub fn id_of(&self, cmd: &DrawCommand) -> Option<CustomDrawId> {
+        if cmd.elem_count != 0 {
+            return None;
+        }
+        let id = custom_draw_id(cmd.userdata);
+        if self.entries.iter().any(|e| e.id == id) {
+            Some(id)
+        } else {
+            None
+        }
+    }
+
+    /// Runs the closure `cmd` of the `nk_convert` output stands for, if
+    /// any, and returns whether it did.
+    pub fn draw_command(&mut self, cmd: &DrawCommand, target: &mut T) -> bool {
+        match self.id_of(cmd) {
+            Some(id) => self.draw(id, target),
+            None => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drops all closures; call after the frame was drawn.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
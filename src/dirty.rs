@@ -0,0 +1,694 @@
+//! Dirty-rectangle tracking for partial redraws.
+//!
+//! `DirtyRects::update` compares the command queue of a frame with the one
+//! of the previous update and returns the screen regions whose pixels may
+//! differ. Commands are grouped into layers by the window buffer they were
+//! written to, in drawing order. A layer that appeared, disappeared, moved
+//! in the stacking order or whose `Window::bounds` changed is dirty as a
+//! whole; otherwise only the commands between its longest unchanged prefix
+//! and suffix are.
+//!
+//! Backends clear every returned rectangle to the background and draw the
+//! whole frame again with the rectangle as scissor, see
+//! `Rasterizer::render_regions`.
+
+use command::Command;
+use math::Rect;
+use nuklear::*;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash as StdHash, Hasher};
+
+/* a changed range longer than this is marked as one rectangle */
+const MAX_ITEM_RECTS: usize = 64;
+
+/* pixel box, end exclusive */
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Area {
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+}
+
+impl Area {
+    /* smallest pixel box covering `x0..x1` x `y0..y1` grown by `pad` */
+    fn cover(x0: f32, y0: f32, x1: f32, y1: f32, pad: f32) -> Option<Area> {
+        let (x0, y0) = ((x0 - pad).floor(), (y0 - pad).floor());
+        let (x1, y1) = ((x1 + pad).ceil(), (y1 + pad).ceil());
+        if !(x0 < x1 && y0 < y1) {
+            return None;
+        }
+        /* keep far-away geometry from overflowing */
+        let clamp = |v: f32| v.max(-(1 << 24) as f32).min((1 << 24) as f32) as i32;
+        Some(Area {
+            x0: clamp(x0),
+            y0: clamp(y0),
+            x1: clamp(x1),
+            y1: clamp(y1),
+        })
+    }
+
+    fn from_rect(r: Rect) -> Option<Area> {
+        Area::cover(r.x, r.y, r.x + r.w, r.y + r.h, 0.0)
+    }
+
+    fn intersect(self, o: Area) -> Option<Area> {
+        let a = Area {
+            x0: self.x0.max(o.x0),
+            y0: self.y0.max(o.y0),
+            x1: self.x1.min(o.x1),
+            y1: self.y1.min(o.y1),
+        };
+        if a.x0 < a.x1 && a.y0 < a.y1 {
+            Some(a)
+        } else {
+            None
+        }
+    }
+
+    fn union(self, o: Area) -> Area {
+        Area {
+            x0: self.x0.min(o.x0),
+            y0: self.y0.min(o.y0),
+            x1: self.x1.max(o.x1),
+            y1: self.y1.max(o.y1),
+        }
+    }
+
+    fn touches(self, o: Area) -> bool {
+        self.x0 <= o.x1 && o.x0 <= self.x1 && self.y0 <= o.y1 && o.y0 <= self.y1
+    }
+
+    fn size(self) -> i64 {
+        (self.x1 - self.x0) as i64 * (self.y1 - self.y0) as i64
+    }
+
+    fn to_rect(self) -> Rect {
+        Rect::new(
+            self.x0 as f32,
+            self.y0 as f32,
+            (self.x1 - self.x0) as f32,
+            (self.y1 - self.y0) as f32,
+        )
+    }
+}
+
+/* stroke width including how far miters and square caps reach past it */
+fn styled_thickness(thickness: f32, style: &StrokeStyle) -> f32 {
+    let mut scale: f32 = 1.0;
+    if style.join == NK_LINE_JOIN_MITER {
+        scale = scale.max(style.miter_limit);
+    }
+    if style.cap == NK_LINE_CAP_SQUARE {
+        scale = scale.max(2.0f32.sqrt());
+    }
+    thickness * scale
+}
+
+fn union_all<I: IntoIterator<Item = Area>>(areas: I) -> Option<Area> {
+    areas.into_iter().fold(None, |acc, a| match acc {
+        Some(acc) => Some(a.union(acc)),
+        None => Some(a),
+    })
+}
+
+/* pixels `cmd` may touch, anti-aliasing fringe included */
+fn extent(cmd: &Command) -> Option<Area> {
+    let points = |pts: &[(f32, f32)], thickness: f32| {
+        let x0 = pts.iter().fold(f32::INFINITY, |m, p| m.min(p.0));
+        let y0 = pts.iter().fold(f32::INFINITY, |m, p| m.min(p.1));
+        let x1 = pts.iter().fold(f32::NEG_INFINITY, |m, p| m.max(p.0));
+        let y1 = pts.iter().fold(f32::NEG_INFINITY, |m, p| m.max(p.1));
+        /* zero-area outlines still cover their stroke */
+        Area::cover(x0, y0, x1.max(x0 + 0.5), y1.max(y0 + 0.5), thickness / 2.0 + 2.0)
+    };
+    let rect = |r: Rect, thickness: f32| {
+        Area::cover(r.x, r.y, r.x + r.w, r.y + r.h, thickness / 2.0 + 2.0)
+    };
+    match *cmd {
+        Command::Nop | Command::Scissor { .. } => None,
+        Command::Line {
+            begin,
+            end,
+            thickness,
+            ..
+        } => points(&[(begin.x, begin.y), (end.x, end.y)], thickness),
+        Command::Curve {
+            begin,
+            ctrl,
+            end,
+            thickness,
+            ..
+        } => points(
+            &[
+                (begin.x, begin.y),
+                (ctrl[0].x, ctrl[0].y),
+                (ctrl[1].x, ctrl[1].y),
+                (end.x, end.y),
+            ],
+            thickness,
+        ),
+        Command::LineStyled {
+            begin,
+            end,
+            thickness,
+            ref style,
+            ..
+        } => points(
+            &[(begin.x, begin.y), (end.x, end.y)],
+            styled_thickness(thickness, style),
+        ),
+        Command::CurveStyled {
+            begin,
+            ctrl,
+            end,
+            thickness,
+            ref style,
+            ..
+        } => points(
+            &[
+                (begin.x, begin.y),
+                (ctrl[0].x, ctrl[0].y),
+                (ctrl[1].x, ctrl[1].y),
+                (end.x, end.y),
+            ],
+            styled_thickness(thickness, style),
+        ),
+        /* the fade reaches half its width past the outline */
+        Command::RectShadow {
+            rect: r, feather, ..
+        } => rect(r, feather),
+        Command::Rect {
+            rect: r, thickness, ..
+        }
+        | Command::RectCorners {
+            rect: r, thickness, ..
+        }
+        | Command::Circle {
+            rect: r, thickness, ..
+        } => rect(r, thickness),
+        Command::RectFilled { rect: r, .. }
+        | Command::RectMultiColor { rect: r, .. }
+        | Command::RectCornersFilled { rect: r, .. }
+        | Command::RectGradient { rect: r, .. }
+        | Command::CircleFilled { rect: r, .. }
+        | Command::CircleGradient { rect: r, .. }
+        | Command::Image { rect: r, .. }
+        | Command::Custom { rect: r, .. }
+        | Command::CustomDraw { rect: r, .. } => rect(r, 0.0),
+        Command::Arc {
+            center,
+            radius,
+            thickness,
+            ..
+        } => rect(
+            Rect::new(center.x - radius, center.y - radius, radius * 2.0, radius * 2.0),
+            thickness,
+        ),
+        Command::ArcFilled { center, radius, .. } => rect(
+            Rect::new(center.x - radius, center.y - radius, radius * 2.0, radius * 2.0),
+            0.0,
+        ),
+        Command::Triangle {
+            points: p,
+            thickness,
+            ..
+        } => points(&[(p[0].x, p[0].y), (p[1].x, p[1].y), (p[2].x, p[2].y)], thickness),
+        Command::TriangleFilled { points: p, .. } => {
+            points(&[(p[0].x, p[0].y), (p[1].x, p[1].y), (p[2].x, p[2].y)], 0.0)
+        }
+        Command::Polygon {
+            points: p,
+            thickness,
+            ..
+        }
+        | Command::Polyline {
+            points: p,
+            thickness,
+            ..
+        } => {
+            let p: Vec<(f32, f32)> = p.iter().map(|p| (p.x as f32, p.y as f32)).collect();
+            points(&p, thickness)
+        }
+        Command::PolygonStyled {
+            points: p,
+            thickness,
+            ref style,
+            ..
+        }
+        | Command::PolylineStyled {
+            points: p,
+            thickness,
+            ref style,
+            ..
+        } => {
+            let p: Vec<(f32, f32)> = p.iter().map(|p| (p.x as f32, p.y as f32)).collect();
+            points(&p, styled_thickness(thickness, style))
+        }
+        Command::PolygonFilled { points: p, .. }
+        | Command::PolygonGradient { points: p, .. } => {
+            let p: Vec<(f32, f32)> = p.iter().map(|p| (p.x as f32, p.y as f32)).collect();
+            points(&p, 0.0)
+        }
+        /* glyphs may reach a little past the measured text */
+        Command::Text { rect: r, height, .. } => rect(r, height / 2.0),
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+struct Item {
+    hash: u64,
+    /* clipped to the scissor in effect */
+    area: Option<Area>,
+    /* closures may paint differently every frame */
+    always_dirty: bool,
+}
+
+struct Layer {
+    /* window name and the number of earlier layers of the same window */
+    key: (Hash, u32),
+    bounds: Option<Rect>,
+    items: Vec<Item>,
+}
+
+impl Layer {
+    fn area(&self) -> Option<Area> {
+        union_all(self.items.iter().filter_map(|i| i.area))
+    }
+}
+
+fn same_rect(a: Rect, b: Rect) -> bool {
+    a.x == b.x && a.y == b.y && a.w == b.w && a.h == b.h
+}
+
+/* buffer ranges of the windows drawn this frame, as `nk_build` links them */
+unsafe fn window_ranges(ctx: &Context) -> Vec<(Size, Size, *const Window)> {
+    let mut ranges = Vec::new();
+    let mut it = ctx.begin as *const Window;
+    while !it.is_null() {
+        if (*it).buffer.last != (*it).buffer.begin
+            && 0 == (*it).flags & NK_WINDOW_HIDDEN as Flags
+            && (*it).seq == ctx.seq
+        {
+            ranges.push(((*it).buffer.begin, (*it).buffer.end, it));
+        }
+        it = (*it).next;
+    }
+    ranges
+}
+
+/* splits the command queue of `ctx` into layers */
+unsafe fn collect_layers(ctx: &mut Context) -> Vec<Layer> {
+    let ranges = window_ranges(ctx);
+    let base = ctx.memory.memory.ptr as usize;
+    let mut layers: Vec<Layer> = Vec::new();
+    let mut owner: Option<*const Window> = None;
+    let mut clip: Option<Area> = None;
+    let mut cmd = nk__begin(ctx);
+    while !cmd.is_null() {
+        let offset = (cmd as usize).wrapping_sub(base) as Size;
+        let window = ranges
+            .iter()
+            .find(|&&(begin, end, _)| offset >= begin && offset < end)
+            .map(|&(_, _, w)| w);
+        if layers.is_empty() || window != owner {
+            owner = window;
+            let name = window.map_or(0, |w| (*w).name);
+            let count = layers.iter().filter(|l| l.key.0 == name).count() as u32;
+            layers.push(Layer {
+                key: (name, count),
+                /* moves of popups and the overlay show in their commands */
+                bounds: match window {
+                    Some(w) if count == 0 => Some((*w).bounds),
+                    _ => None,
+                },
+                items: Vec::new(),
+            });
+        }
+        let command = Command::from_raw(cmd);
+        if let Command::Scissor { rect } = command {
+            clip = Area::from_rect(rect).or(Some(Area {
+                x0: 0,
+                y0: 0,
+                x1: 0,
+                y1: 0,
+            }));
+        }
+        let mut state = DefaultHasher::new();
+        command.hash(&mut state);
+        let area = match (extent(&command), clip) {
+            (Some(a), Some(c)) => a.intersect(c),
+            (a, None) => a,
+            (None, _) => None,
+        };
+        if let Some(layer) = layers.last_mut() {
+            layer.items.push(Item {
+                hash: state.finish(),
+                area,
+                always_dirty: matches!(command, Command::CustomDraw { .. }),
+            });
+        }
+        cmd = nk__next(ctx, cmd);
+    }
+    layers
+}
+
+/// Screen regions that changed since the last frame.
+pub struct DirtyRects {
+    /// Upper bound for the number of rectangles; beyond it the cheapest
+    /// pairs are merged.
+    pub max_rects: usize,
+    layers: Vec<Layer>,
+    viewport: Option<Area>,
+    full: bool,
+    rects: Vec<Rect>,
+}
+
+impl Default for DirtyRects {
+    fn default() -> Self {
+        DirtyRects::new()
+    }
+}
+
+impl DirtyRects {
+    pub fn new() -> Self {
+        DirtyRects {
+            max_rects: 8,
+            layers: Vec::new(),
+            viewport: None,
+            full: true,
+            rects: Vec::new(),
+        }
+    }
+
+    /// Marks the whole viewport dirty on the next update, e.g. after the
+    /// backend lost its pixels.
+    pub fn invalidate(&mut self) {
+        self.full = true;
+    }
+
+    /// Compares the commands queued in `ctx` with those of the previous
+    /// update and returns the changed regions within `viewport`, as
+    /// disjoint rectangles on whole pixels. Call after building the frame
+    /// and before `nk_clear`.
+    ///
+    /// The first update and every update with a new viewport return the
+    /// whole viewport.
+    pub fn update(&mut self, ctx: &mut Context, viewport: Rect) -> &[Rect] {
+        let layers = unsafe { collect_layers(ctx) };
+        let view = Area::from_rect(viewport);
+        let mut dirty = Vec::new();
+        if self.full || view != self.viewport {
+            dirty.extend(view);
+        } else {
+            diff(&self.layers, &layers, &mut dirty);
+        }
+        self.layers = layers;
+        self.viewport = view;
+        self.full = false;
+
+        let mut areas: Vec<Area> = match view {
+            Some(view) => dirty.iter().filter_map(|a| a.intersect(view)).collect(),
+            None => Vec::new(),
+        };
+        merge(&mut areas, self.max_rects.max(1));
+        self.rects.clear();
+        self.rects.extend(areas.iter().map(|a| a.to_rect()));
+        &self.rects
+    }
+
+    /// Result of the last `update`.
+    pub fn rects(&self) -> &[Rect] {
+        &self.rects
+    }
+}
+
+fn diff(old: &[Layer], new: &[Layer], dirty: &mut Vec<Area>) {
+    /* stacking order of the layers present in both frames */
+    let old_common: Vec<(Hash, u32)> = old
+        .iter()
+        .map(|l| l.key)
+        .filter(|k| new.iter().any(|l| l.key == *k))
+        .collect();
+    let new_common: Vec<(Hash, u32)> = new
+        .iter()
+        .map(|l| l.key)
+        .filter(|k| old.iter().any(|l| l.key == *k))
+        .collect();
+    for layer in old {
+        if !new.iter().any(|l| l.key == layer.key) {
+            dirty.extend(layer.area());
+        }
+    }
+    for layer in new {
+        let prev = match old.iter().find(|l| l.key == layer.key) {
+            Some(prev) => prev,
+            None => {
+                dirty.extend(layer.area());
+                continue;
+            }
+        };
+        let moved = match (prev.bounds, layer.bounds) {
+            (Some(a), Some(b)) => !same_rect(a, b),
+            (None, None) => false,
+            _ => true,
+        };
+        let reordered = old_common.iter().position(|k| *k == layer.key)
+            != new_common.iter().position(|k| *k == layer.key);
+        if moved || reordered {
+            dirty.extend(prev.area());
+            dirty.extend(layer.area());
+            continue;
+        }
+        diff_items(&prev.items, &layer.items, dirty);
+    }
+}
+
+fn diff_items(old: &[Item], new: &[Item], dirty: &mut Vec<Area>) {
+    dirty.extend(new.iter().filter(|i| i.always_dirty).filter_map(|i| i.area));
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|&(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|&(a, b)| a == b)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+    let changed = old.iter().chain(new.iter()).filter_map(|i| i.area);
+    if old.len() + new.len() > MAX_ITEM_RECTS {
+        dirty.extend(union_all(changed));
+    } else {
+        dirty.extend(changed);
+    }
+}
+
+fn merge_touching(areas: &mut Vec<Area>) {
+    let mut merged = true;
+    while merged {
+        merged = false;
+        let mut i = 0;
+        while i < areas.len() {
+            let mut j = i + 1;
+            while j < areas.len() {
+                if areas[i].touches(areas[j]) {
+                    areas[i] = areas[i].union(areas[j]);
+                    areas.swap_remove(j);
+                    merged = true;
+                } else {
+                    j += 1;
+                }
+            }
+            i += 1;
+        }
+    }
+}
+
+/* merges touching rectangles, then the cheapest pairs down to `max`;
+ * every pass ends with `merge_touching`, so the result never overlaps */
+fn merge(areas: &mut Vec<Area>, max: usize) {
+    merge_touching(areas);
+    while areas.len() > max {
+        let mut best = (0, 1, i64::MAX);
+        for i in 0..areas.len() {
+            for j in i + 1..areas.len() {
+                let waste = areas[i].union(areas[j]).size() - areas[i].size() - areas[j].size();
+                if waste < best.2 {
+                    best = (i, j, waste);
+                }
+            }
+        }
+        let (i, j, _) = best;
+        areas[i] = areas[i].union(areas[j]);
+        areas.swap_remove(j);
+        /* the union may reach rectangles it did not touch before */
+        merge_touching(areas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use custom::CustomDraws;
+    use harness::Harness;
+
+    const VIEWPORT: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        w: 400.0,
+        h: 300.0,
+    };
+
+    type Rects = Vec<(f32, f32, f32, f32)>;
+
+    /* a window at `x` with a 10x10 square of `color` 30 pixels into it */
+    fn frame(harness: &mut Harness, dirty: &mut DirtyRects, x: f32, color: Color) -> Rects {
+        let ctx = harness.context_mut();
+        unsafe {
+            nk_begin(ctx, b"w\0".as_ptr() as _, Rect::new(x, 10.0, 100.0, 100.0), 0);
+            let canvas = nk_window_get_canvas(ctx);
+            nk_fill_rect(canvas, Rect::new(x + 30.0, 40.0, 10.0, 10.0), 0.0, color);
+            nk_end(ctx);
+        }
+        let rects = dirty.update(ctx, VIEWPORT).iter().map(|r| (r.x, r.y, r.w, r.h)).collect();
+        unsafe { nk_clear(ctx) };
+        rects
+    }
+
+    fn contains(outer: &Rects, (x, y, w, h): (f32, f32, f32, f32)) -> bool {
+        outer
+            .iter()
+            .any(|o| o.0 <= x && o.1 <= y && x + w <= o.0 + o.2 && y + h <= o.1 + o.3)
+    }
+
+    const RED: Color = Color {
+        r: 255,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+    const BLUE: Color = Color {
+        r: 0,
+        g: 0,
+        b: 255,
+        a: 255,
+    };
+
+    #[test]
+    fn first_and_invalidated_updates_are_full() {
+        let mut harness = Harness::new(400, 300);
+        let mut dirty = DirtyRects::new();
+        let full = vec![(0.0, 0.0, 400.0, 300.0)];
+        assert_eq!(frame(&mut harness, &mut dirty, 0.0, RED), full);
+        assert!(frame(&mut harness, &mut dirty, 0.0, RED).is_empty());
+        dirty.invalidate();
+        assert_eq!(frame(&mut harness, &mut dirty, 0.0, RED), full);
+    }
+
+    #[test]
+    fn a_changed_command_dirties_only_its_pixels() {
+        let mut harness = Harness::new(400, 300);
+        let mut dirty = DirtyRects::new();
+        frame(&mut harness, &mut dirty, 0.0, RED);
+        let rects = frame(&mut harness, &mut dirty, 0.0, BLUE);
+        assert!(contains(&rects, (30.0, 40.0, 10.0, 10.0)));
+        let area: f32 = rects.iter().map(|r| r.2 * r.3).sum();
+        assert!(area < 20.0 * 20.0, "{:?}", rects);
+    }
+
+    #[test]
+    fn a_moved_window_dirties_both_places() {
+        let mut harness = Harness::new(400, 300);
+        let mut dirty = DirtyRects::new();
+        frame(&mut harness, &mut dirty, 0.0, RED);
+        let rects = frame(&mut harness, &mut dirty, 200.0, RED);
+        assert!(contains(&rects, (0.0, 10.0, 100.0, 100.0)));
+        assert!(contains(&rects, (200.0, 10.0, 100.0, 100.0)));
+        assert!(!contains(&rects, (120.0, 50.0, 60.0, 10.0)));
+    }
+
+    #[test]
+    fn custom_draws_are_dirty_every_frame() {
+        let mut harness = Harness::new(400, 300);
+        let mut dirty = DirtyRects::new();
+        let mut draws: CustomDraws<()> = CustomDraws::new();
+        for i in 0..3 {
+            let ctx = harness.context_mut();
+            unsafe { nk_begin(ctx, b"w\0".as_ptr() as _, Rect::new(0.0, 10.0, 100.0, 100.0), 0) };
+            draws.push(ctx, Rect::new(50.0, 60.0, 20.0, 20.0), |_, _| {});
+            unsafe { nk_end(ctx) };
+            let rects: Rects =
+                dirty.update(ctx, VIEWPORT).iter().map(|r| (r.x, r.y, r.w, r.h)).collect();
+            draws.clear();
+            unsafe { nk_clear(ctx) };
+            assert!(contains(&rects, (50.0, 60.0, 20.0, 20.0)), "{:?}", rects);
+            if i > 0 {
+                let area: f32 = rects.iter().map(|r| r.2 * r.3).sum();
+                assert!(area < 30.0 * 30.0, "{:?}", rects);
+            }
+        }
+    }
+
+    #[test]
+    fn merging_keeps_to_max_rects() {
+        let mut areas: Vec<Area> = (0..20)
+            .map(|i| Area {
+                x0: i * 20,
+                y0: 0,
+                x1: i * 20 + 5,
+                y1: 5,
+            })
+            .collect();
+        let covered = union_all(areas.iter().cloned()).unwrap();
+        merge(&mut areas, 3);
+        assert!(areas.len() <= 3);
+        assert_eq!(union_all(areas.iter().cloned()), Some(covered));
+        for i in 0..20 {
+            let a = Area {
+                x0: i * 20,
+                y0: 0,
+                x1: i * 20 + 5,
+                y1: 5,
+            };
+            assert!(areas.iter().any(|m| m.intersect(a) == Some(a)));
+        }
+    }
+
+    #[test]
+    fn merged_rects_are_disjoint() {
+        /* overlapping boxes scattered by a small LCG */
+        let mut seed: u32 = 7;
+        let mut next = |n: i32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as i32 % n
+        };
+        let input: Vec<Area> = (0..40)
+            .map(|_| {
+                let (x, y) = (next(300), next(200));
+                Area {
+                    x0: x,
+                    y0: y,
+                    x1: x + 1 + next(60),
+                    y1: y + 1 + next(60),
+                }
+            })
+            .collect();
+        for max in 1..8 {
+            let mut areas = input.clone();
+            merge(&mut areas, max);
+            assert!(areas.len() <= max);
+            for (i, a) in areas.iter().enumerate() {
+                for b in &areas[i + 1..] {
+                    assert_eq!(a.intersect(*b), None, "{:?} overlaps {:?}", a, b);
+                }
+            }
+            for a in &input {
+                assert!(areas.iter().any(|m| m.intersect(*a) == Some(*a)));
+            }
+        }
+    }
+}
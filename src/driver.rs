@@ -0,0 +1,399 @@
+//! Scripted input driver on top of `Harness`.
+//!
+//! A `Driver` rebuilds the same UI every frame and turns high level steps
+//! ("click `Apply`", "type `42`", "press Enter") into the raw `nk_input_*`
+//! calls of one or more frames. Widgets are found by label in the bounds
+//! recorded during the previous frame, so tests never compute coordinates.
+
+use harness::{Harness, InputOp, Snapshot};
+use input::{Buttons, Keys, NK_BUTTON_LEFT};
+use math::Rect;
+use std::{error, fmt};
+use ui::Ui;
+
+/* seconds between the clicks of `multi_click` and after the last one */
+const CLICK_INTERVAL: f64 = 0.1;
+const SETTLE_INTERVAL: f64 = 1.0;
+
+/* intermediate mouse positions of `drag` */
+const DRAG_STEPS: i32 = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DriverError {
+    /// No widget with this label was built in the previous frame.
+    WidgetNotFound(String),
+}
+
+impl fmt::Display for DriverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DriverError::WidgetNotFound(ref label) => {
+                write!(f, "no widget labelled {:?} in the previous frame", label)
+            }
+        }
+    }
+}
+
+impl error::Error for DriverError {}
+
+pub struct Driver<S, F>
+where
+    F: FnMut(&mut Ui, &mut S),
+{
+    pub harness: Harness,
+    pub state: S,
+    build: F,
+    mouse: (i32, i32),
+    time: f64,
+    last: Option<Snapshot>,
+}
+
+impl<S, F> Driver<S, F>
+where
+    F: FnMut(&mut Ui, &mut S),
+{
+    /// `build` is called once per frame with the UI and the test state.
+    pub fn new(harness: Harness, state: S, build: F) -> Self {
+        Driver {
+            harness,
+            state,
+            build,
+            mouse: (0, 0),
+            time: 0.0,
+            last: None,
+        }
+    }
+
+    /// Snapshot of the most recent frame.
+    pub fn last(&self) -> Option<&Snapshot> {
+        self.last.as_ref()
+    }
+
+    /// Runs one frame with the given raw input.
+    pub fn frame(&mut self, input: &[InputOp]) -> &Snapshot {
+        for op in input {
+            match *op {
+                InputOp::Motion(x, y)
+                | InputOp::Button(_, x, y, _)
+                | InputOp::TimedButton(_, x, y, _, _)
+                | InputOp::Touch(_, x, y, _) => self.mouse = (x, y),
+                _ => {}
+            }
+        }
+        let build = &mut self.build;
+        let state = &mut self.state;
+        let snapshot = self.harness.frame(input, |ui| build(ui, state));
+        self.last = Some(snapshot);
+        self.last.as_ref().unwrap()
+    }
+
+    /// Bounds of the widget labelled `label` in the previous frame. Runs an
+    /// input-less frame first if there is none yet.
+    pub fn find(&mut self, label: &str) -> Result<Rect, DriverError> {
+        if self.last.is_none() {
+            self.frame(&[]);
+        }
+        self.last
+            .as_ref()
+            .and_then(|s| s.widget(label))
+            .ok_or_else(|| DriverError::WidgetNotFound(label.to_owned()))
+    }
+
+    fn center(&mut self, label: &str) -> Result<(i32, i32), DriverError> {
+        let r = self.find(label)?;
+        Ok(((r.x + r.w / 2.0) as i32, (r.y + r.h / 2.0) as i32))
+    }
+
+    /// Moves the mouse over the center of the widget labelled `label`.
+    pub fn move_to(&mut self, label: &str) -> Result<&Snapshot, DriverError> {
+        let (x, y) = self.center(label)?;
+        Ok(self.frame(&[InputOp::Motion(x, y)]))
+    }
+
+    /// Presses `button` in one frame and releases it in the next at the
+    /// current mouse position.
+    pub fn click_button(&mut self, button: Buttons) -> &Snapshot {
+        let (x, y) = self.mouse;
+        self.frame(&[InputOp::Button(button, x, y, true)]);
+        self.frame(&[InputOp::Button(button, x, y, false)])
+    }
+
+    /// Moves to the widget labelled `label` and left-clicks it.
+    pub fn click(&mut self, label: &str) -> Result<&Snapshot, DriverError> {
+        self.move_to(label)?;
+        Ok(self.click_button(NK_BUTTON_LEFT))
+    }
+
+    /// Moves to the widget labelled `label` and clicks it `count` times in
+    /// quick succession, so it sees a double (2) or triple (3) click.
+    pub fn multi_click(&mut self, label: &str, count: u32) -> Result<&Snapshot, DriverError> {
+        self.move_to(label)?;
+        let (x, y) = self.mouse;
+        for _ in 0..count {
+            self.frame(&[InputOp::TimedButton(NK_BUTTON_LEFT, x, y, true, self.time)]);
+            self.frame(&[InputOp::TimedButton(NK_BUTTON_LEFT, x, y, false, self.time)]);
+            self.time += CLICK_INTERVAL;
+        }
+        /* keep the next click from continuing this sequence */
+        self.time += SETTLE_INTERVAL;
+        Ok(self.last.as_ref().unwrap())
+    }
+
+    /// Presses the left button on the widget labelled `from`, moves to the
+    /// one labelled `to` over a few frames and releases it there.
+    pub fn drag(&mut self, from: &str, to: &str) -> Result<&Snapshot, DriverError> {
+        /* an extra frame first, in case the last one changed the layout */
+        self.move_to(from)?;
+        let (x0, y0) = self.center(from)?;
+        self.frame(&[
+            InputOp::Motion(x0, y0),
+            InputOp::Button(NK_BUTTON_LEFT, x0, y0, true),
+        ]);
+        let (x1, y1) = self.center(to)?;
+        for i in 1..=DRAG_STEPS {
+            let x = x0 + (x1 - x0) * i / DRAG_STEPS;
+            let y = y0 + (y1 - y0) * i / DRAG_STEPS;
+            self.frame(&[InputOp::Motion(x, y)]);
+        }
+        Ok(self.frame(&[InputOp::Button(NK_BUTTON_LEFT, x1, y1, false)]))
+    }
+
+    /// Enters `text` in a single frame.
+    pub fn type_text(&mut self, text: &str) -> &Snapshot {
+        let ops: Vec<InputOp> = text.chars().map(InputOp::Char).collect();
+        self.frame(&ops)
+    }
+
+    /// Holds `key` down for one frame and releases it in the next.
+    pub fn press_key(&mut self, key: Keys) -> &Snapshot {
+        self.frame(&[InputOp::Key(key, true)]);
+        self.frame(&[InputOp::Key(key, false)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harness::InputOp;
+    use input::*;
+    use nuklear::*;
+
+    type State = (String, Rect);
+
+    fn edit_driver() -> Driver<State, impl FnMut(&mut Ui, &mut State)> {
+        let state = (String::new(), Rect::new(0.0, 0.0, 0.0, 0.0));
+        let mut driver = Driver::new(Harness::new(240, 80), state, |ui, state: &mut State| {
+            ui.window("edit", Rect::new(0.0, 0.0, 240.0, 80.0), 0, |ui| {
+                ui.layout_row_dynamic(30.0, 1);
+                ui.edit_string(NK_EDIT_FIELD, &mut state.0, 64);
+                state.1 = ui.context().last_widget_bounds;
+            });
+        });
+        driver.frame(&[]);
+        let r = driver.state.1;
+        let (x, y) = ((r.x + r.w / 2.0) as i32, (r.y + r.h / 2.0) as i32);
+        driver.frame(&[
+            InputOp::Motion(x, y),
+            InputOp::Button(NK_BUTTON_LEFT, x, y, true),
+        ]);
+        driver.frame(&[InputOp::Button(NK_BUTTON_LEFT, x, y, false)]);
+        driver
+    }
+
+    #[test]
+    fn type_text_delivers_every_byte() {
+        let mut driver = edit_driver();
+        let text = "0123456789abcdefghij\u{e9}\u{20ac}";
+        driver.type_text(text);
+        assert_eq!(driver.state.0, text);
+    }
+
+    #[test]
+    fn drag_carries_large_payloads() {
+        let tag = "a type tag well over thirty-one bytes long";
+        let payload: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let sent = payload.clone();
+        let mut driver = Driver::new(Harness::new(240, 80), None, move |ui, got| {
+            ui.window("drag", Rect::new(0.0, 0.0, 240.0, 80.0), 0, |ui| {
+                ui.layout_row_dynamic(30.0, 2);
+                ui.button("from");
+                ui.drag_source(tag, &sent);
+                ui.button("to");
+                if let Some(data) = ui.drop_target(tag) {
+                    *got = Some(data.to_vec());
+                }
+            });
+        });
+        driver.drag("from", "to").unwrap();
+        assert_eq!(driver.state, Some(payload));
+    }
+
+    /* what the navigation tests observe of one frame */
+    #[derive(Default)]
+    struct Nav {
+        focused: Vec<&'static str>,
+        pressed: Vec<&'static str>,
+        slider: f32,
+        property: f32,
+    }
+
+    fn nav_driver(flags: Flags) -> Driver<Nav, impl FnMut(&mut Ui, &mut Nav)> {
+        let nav = Nav {
+            slider: 5.0,
+            property: 5.0,
+            ..Nav::default()
+        };
+        let mut driver = Driver::new(Harness::new(240, 200), nav, move |ui, nav: &mut Nav| {
+            nav.focused.clear();
+            nav.pressed.clear();
+            ui.window("nav", Rect::new(0.0, 0.0, 240.0, 160.0), flags, |ui| {
+                ui.layout_row_dynamic(30.0, 1);
+                if ui.button("one") {
+                    nav.pressed.push("one");
+                }
+                if ui.is_widget_focused() {
+                    nav.focused.push("one");
+                }
+                ui.slider_f32(0.0, &mut nav.slider, 10.0, 1.0);
+                if ui.is_widget_focused() {
+                    nav.focused.push("slider");
+                }
+                ui.property_f32("#value", 0.0, &mut nav.property, 10.0, 1.0, 1.0);
+                if ui.is_widget_focused() {
+                    nav.focused.push("property");
+                }
+                if ui.button("two") {
+                    nav.pressed.push("two");
+                }
+                if ui.is_widget_focused() {
+                    nav.focused.push("two");
+                }
+            });
+            ui.window("other", Rect::new(0.0, 160.0, 240.0, 40.0), 0, |ui| {
+                ui.layout_row_dynamic(30.0, 1);
+                ui.button("elsewhere");
+            });
+        });
+        /* the window built last ends up on top; activate the one under test */
+        driver.frame(&[]);
+        activate_nav(&mut driver);
+        driver
+    }
+
+    /* clicks the empty space below the widgets of the "nav" window */
+    fn activate_nav<S, F: FnMut(&mut Ui, &mut S)>(driver: &mut Driver<S, F>) {
+        let (x, y) = (120, 150);
+        driver.frame(&[
+            InputOp::Motion(x, y),
+            InputOp::Button(NK_BUTTON_LEFT, x, y, true),
+        ]);
+        driver.frame(&[InputOp::Button(NK_BUTTON_LEFT, x, y, false)]);
+    }
+
+    fn shift_tab<S, F: FnMut(&mut Ui, &mut S)>(driver: &mut Driver<S, F>) {
+        driver.frame(&[
+            InputOp::Key(NK_KEY_SHIFT, true),
+            InputOp::Key(NK_KEY_TAB, true),
+        ]);
+        driver.frame(&[
+            InputOp::Key(NK_KEY_TAB, false),
+            InputOp::Key(NK_KEY_SHIFT, false),
+        ]);
+    }
+
+    #[test]
+    fn tab_walks_the_widgets_and_wraps_around() {
+        let mut driver = nav_driver(0);
+        assert!(driver.state.focused.is_empty());
+        for &label in &["one", "slider", "property", "two", "one"] {
+            driver.press_key(NK_KEY_TAB);
+            assert_eq!(driver.state.focused, [label]);
+        }
+        shift_tab(&mut driver);
+        assert_eq!(driver.state.focused, ["two"]);
+        shift_tab(&mut driver);
+        assert_eq!(driver.state.focused, ["property"]);
+    }
+
+    #[test]
+    fn focused_widgets_act_on_navigation_keys() {
+        let mut driver = nav_driver(0);
+        driver.press_key(NK_KEY_TAB);
+        driver.frame(&[InputOp::Key(NK_KEY_ENTER, true)]);
+        assert_eq!(driver.state.pressed, ["one"]);
+        driver.frame(&[InputOp::Key(NK_KEY_ENTER, false)]);
+        assert!(driver.state.pressed.is_empty());
+
+        driver.press_key(NK_KEY_TAB);
+        driver.press_key(NK_KEY_RIGHT);
+        assert_eq!(driver.state.slider, 6.0);
+        driver.press_key(NK_KEY_TAB);
+        driver.press_key(NK_KEY_LEFT);
+        assert_eq!(driver.state.property, 4.0);
+        assert_eq!(driver.state.slider, 6.0);
+    }
+
+    #[test]
+    fn mouse_press_hides_the_ring_and_its_actions() {
+        let mut driver = nav_driver(0);
+        driver.press_key(NK_KEY_TAB);
+        assert!(driver.harness.context().nav.visible != 0);
+        activate_nav(&mut driver);
+        assert_eq!(driver.harness.context().nav.visible, 0);
+        assert_eq!(driver.state.focused, ["one"]);
+        driver.press_key(NK_KEY_ENTER);
+        assert!(driver.state.pressed.is_empty());
+    }
+
+    #[test]
+    fn focus_follows_the_active_window() {
+        let mut driver = nav_driver(0);
+        driver.press_key(NK_KEY_TAB);
+        driver.press_key(NK_KEY_TAB);
+        assert_eq!(driver.state.focused, ["slider"]);
+        driver.click("elsewhere").unwrap();
+        assert!(driver.state.focused.is_empty());
+        /* coming back starts over */
+        activate_nav(&mut driver);
+        assert!(driver.state.focused.is_empty());
+        driver.press_key(NK_KEY_TAB);
+        assert_eq!(driver.state.focused, ["one"]);
+        /* clicking a widget of an inactive window focuses it right away */
+        driver.click("elsewhere").unwrap();
+        driver.click("two").unwrap();
+        assert_eq!(driver.state.focused, ["two"]);
+    }
+
+    #[test]
+    fn editing_a_property_keeps_navigation_keys() {
+        let mut driver = nav_driver(0);
+        /* the number left of the increment arrow switches to text input */
+        let r = driver.find("#value").unwrap();
+        let (x, y) = ((r.x + r.w) as i32 - 25, (r.y + r.h / 2.0) as i32);
+        driver.frame(&[
+            InputOp::Motion(x, y),
+            InputOp::Button(NK_BUTTON_LEFT, x, y, true),
+        ]);
+        driver.frame(&[InputOp::Button(NK_BUTTON_LEFT, x, y, false)]);
+        assert_eq!(driver.state.focused, ["property"]);
+        let win = driver.harness.context().active;
+        assert!(unsafe { (*win).property.active } != 0);
+        driver.press_key(NK_KEY_TAB);
+        assert_eq!(driver.state.focused, ["property"]);
+        driver.press_key(NK_KEY_RIGHT);
+        assert_eq!(driver.state.property, 5.0);
+        assert!(unsafe { (*win).property.active } != 0);
+    }
+
+    #[test]
+    fn read_only_windows_focus_without_acting() {
+        let mut driver = nav_driver(NK_WINDOW_ROM);
+        driver.press_key(NK_KEY_TAB);
+        assert_eq!(driver.state.focused, ["one"]);
+        driver.press_key(NK_KEY_ENTER);
+        assert!(driver.state.pressed.is_empty());
+        driver.press_key(NK_KEY_TAB);
+        driver.press_key(NK_KEY_RIGHT);
+        assert_eq!(driver.state.slider, 5.0);
+    }
+}
@@ -0,0 +1,519 @@
+//! Event-queue front-end for `nk_input_*`.
+//!
+//! Instead of calling `nk_input_key`, `nk_input_button` and friends between
+//! `nk_input_begin`/`nk_input_end`, backends push `InputEvent`s into an
+//! `InputQueue` as they arrive and call `InputQueue::process` once per
+//! frame. Events are applied in order. Whatever `Input` cannot represent in
+//! a single frame is left queued for the next one instead of being lost:
+//!
+//! * a second transition of the same key or button (e.g. press and release
+//!   within one frame) ends the frame, so widgets see both states;
+//! * mouse motion after a button transition ends the frame, so the click
+//!   lands where it was made;
+//! * text a fixed context has no room for continues next frame;
+//! * a touch that lifts and lands again waits for the next frame.
+
+use input::*;
+use math::Point;
+use nuklear::*;
+use std::collections::VecDeque;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    Key {
+        key: Keys,
+        down: bool,
+        modifiers: Modifiers,
+    },
+    /// Key by physical position, see `nk_input_physical_key`.
+    PhysicalKey {
+        key: PhysicalKey,
+        down: bool,
+        modifiers: Modifiers,
+    },
+    Text(String),
+    /// IME composition with caret and converted clause in chars; an empty
+    /// `text` ends it. See `nk_input_preedit`.
+    Preedit {
+        text: String,
+        cursor: i32,
+        selection: (i32, i32),
+    },
+    MouseMove {
+        x: i32,
+        y: i32,
+    },
+    /// `time` in seconds enables double and triple click detection, see
+    /// `nk_input_button_timed`.
+    MouseButton {
+        button: Buttons,
+        x: i32,
+        y: i32,
+        down: bool,
+        time: Option<f64>,
+    },
+    Wheel {
+        x: f32,
+        y: f32,
+    },
+    /// Finger `id` touching (`down`) or leaving the screen, see
+    /// `nk_input_touch`.
+    Touch {
+        id: u64,
+        x: i32,
+        y: i32,
+        down: bool,
+    },
+    /// The system took over all touches.
+    TouchCancel,
+    /// Releases all held keys and buttons, e.g. when the window loses focus.
+    FocusLost,
+}
+
+/* per-frame bookkeeping of which keys and buttons already changed state */
+struct FrameState {
+    keys: u32,
+    physical: u128,
+    buttons: u32,
+    moved_button: bool,
+    touches: Vec<u64>,
+}
+
+impl FrameState {
+    fn key(&mut self, input: &Input, key: Keys, down: bool) -> bool {
+        let bit = 1 << key;
+        if (input.keyboard.keys[key as usize].down != 0) == down {
+            return true;
+        }
+        if self.keys & bit != 0 {
+            return false;
+        }
+        self.keys |= bit;
+        true
+    }
+
+    fn physical(&mut self, input: &Input, key: PhysicalKey, down: bool) -> bool {
+        let bit = 1 << key;
+        if (input.keyboard.physical[key as usize].down != 0) == down {
+            return true;
+        }
+        if self.physical & bit != 0 {
+            return false;
+        }
+        self.physical |= bit;
+        true
+    }
+
+    fn button(&mut self, input: &Input, button: Buttons, down: bool) -> bool {
+        let bit = 1 << button;
+        if (input.mouse.buttons[button as usize].down != 0) == down {
+            return true;
+        }
+        if self.buttons & bit != 0 {
+            return false;
+        }
+        self.buttons |= bit;
+        self.moved_button = true;
+        true
+    }
+
+    fn touch(&mut self, input: &Input, id: u64, down: bool) -> bool {
+        let is_down = input.touch.points.iter().any(|p| {
+            p.id == id && p.phase >= NK_TOUCH_BEGAN && p.phase <= NK_TOUCH_STATIONARY
+        });
+        if is_down == down {
+            return true;
+        }
+        if self.touches.contains(&id) {
+            return false;
+        }
+        self.touches.push(id);
+        true
+    }
+}
+
+fn modifier_keys(modifiers: Modifiers) -> [(Keys, bool); 2] {
+    [
+        (NK_KEY_SHIFT, modifiers & NK_MOD_SHIFT != 0),
+        (NK_KEY_CTRL, modifiers & NK_MOD_CTRL != 0),
+    ]
+}
+
+fn own_modifier(modifiers: Modifiers, own: Modifiers, down: bool) -> Modifiers {
+    if down {
+        modifiers | own
+    } else {
+        modifiers & !own
+    }
+}
+
+/// Ordered, unbounded stream of input events.
+#[derive(Clone, Default)]
+pub struct InputQueue {
+    events: VecDeque<InputEvent>,
+}
+
+impl InputQueue {
+    pub fn new() -> Self {
+        InputQueue {
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, event: InputEvent) {
+        self.events.push_back(event);
+    }
+
+    /// Number of events still waiting to be processed.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Runs `nk_input_begin`, applies as many queued events as one frame
+    /// can hold and runs `nk_input_end`.
+    ///
+    /// Returns `true` if events are left for the next frame, in which case
+    /// the caller should schedule another frame even without new input.
+    pub fn process(&mut self, ctx: &mut Context) -> bool {
+        let mut frame = FrameState {
+            keys: 0,
+            physical: 0,
+            buttons: 0,
+            moved_button: false,
+            touches: Vec::new(),
+        };
+        unsafe {
+            nk_input_begin(ctx);
+            while let Some(event) = self.events.pop_front() {
+                if let Some(rest) = Self::apply(ctx, &mut frame, event) {
+                    self.events.push_front(rest);
+                    break;
+                }
+            }
+            nk_input_end(ctx);
+        }
+        !self.events.is_empty()
+    }
+
+    /* applies `event`, or returns the part of it that has to wait */
+    unsafe fn apply(
+        ctx: &mut Context,
+        frame: &mut FrameState,
+        event: InputEvent,
+    ) -> Option<InputEvent> {
+        match event {
+            InputEvent::Key {
+                key,
+                down,
+                modifiers,
+            } => {
+                if key >= NK_KEY_MAX {
+                    return None;
+                }
+                /* the key's own modifier follows the key, whatever was reported */
+                let modifiers = match key {
+                    NK_KEY_SHIFT => own_modifier(modifiers, NK_MOD_SHIFT, down),
+                    NK_KEY_CTRL => own_modifier(modifiers, NK_MOD_CTRL, down),
+                    _ => modifiers,
+                };
+                let fits = modifier_keys(modifiers)
+                    .iter()
+                    .filter(|&&(k, _)| k != key)
+                    .chain(Some(&(key, down)))
+                    .all(|&(k, d)| k == NK_KEY_NONE || frame.key(&ctx.input, k, d));
+                if !fits {
+                    return Some(event);
+                }
+                nk_input_modifiers(ctx, modifiers);
+                if key != NK_KEY_NONE {
+                    nk_input_key(ctx, key, down as Int);
+                }
+                None
+            }
+            InputEvent::PhysicalKey {
+                key,
+                down,
+                modifiers,
+            } => {
+                if key == NK_PKEY_NONE || key >= NK_PKEY_MAX {
+                    return None;
+                }
+                /* the key's own modifier follows the key, unless the other
+                 * side's key still holds it */
+                let own = nk_physical_key_modifier(key);
+                let held = (0..NK_PKEY_MAX).any(|k| {
+                    k != key
+                        && ctx.input.keyboard.physical[k as usize].down != 0
+                        && nk_physical_key_modifier(k) == own
+                });
+                let modifiers = own_modifier(modifiers, own, down || held);
+                let fits = frame.physical(&ctx.input, key, down)
+                    && modifier_keys(modifiers)
+                        .iter()
+                        .all(|&(k, d)| frame.key(&ctx.input, k, d));
+                if !fits {
+                    return Some(event);
+                }
+                nk_input_physical_key(ctx, key, down as Int);
+                nk_input_modifiers(ctx, modifiers);
+                None
+            }
+            InputEvent::Text(text) => {
+                for (i, c) in text.char_indices() {
+                    let len = ctx.input.keyboard.text_len;
+                    nk_input_unicode(ctx, c as Rune);
+                    /* only a fixed text buffer without fallback fills up; what
+                     * does not even fit into an empty one is dropped */
+                    if ctx.input.keyboard.text_len == len && len > 0 {
+                        return Some(InputEvent::Text(text[i..].to_owned()));
+                    }
+                }
+                None
+            }
+            InputEvent::Preedit {
+                text,
+                cursor,
+                selection,
+            } => {
+                if text.is_empty() {
+                    nk_input_preedit_clear(ctx);
+                } else {
+                    nk_input_preedit(
+                        ctx,
+                        text.as_ptr() as *const Char,
+                        text.len() as Int,
+                        cursor,
+                        selection.0,
+                        selection.1,
+                    );
+                }
+                None
+            }
+            InputEvent::MouseMove { x, y } => {
+                let pos = ctx.input.mouse.pos;
+                if frame.moved_button && (pos.x != x as f32 || pos.y != y as f32) {
+                    return Some(event);
+                }
+                nk_input_motion(ctx, x, y);
+                None
+            }
+            InputEvent::MouseButton {
+                button,
+                x,
+                y,
+                down,
+                time,
+            } => {
+                if button >= NK_BUTTON_MAX {
+                    return None;
+                }
+                let pos = ctx.input.mouse.pos;
+                let moved = pos.x != x as f32 || pos.y != y as f32;
+                if (moved && frame.moved_button) || !frame.button(&ctx.input, button, down) {
+                    return Some(event);
+                }
+                if moved {
+                    nk_input_motion(ctx, x, y);
+                }
+                match time {
+                    Some(time) => nk_input_button_timed(ctx, button, x, y, down as Int, time),
+                    None => nk_input_button(ctx, button, x, y, down as Int),
+                }
+                None
+            }
+            InputEvent::Wheel { x, y } => {
+                nk_input_scroll(ctx, Point::new(x, y));
+                None
+            }
+            InputEvent::Touch { id, x, y, down } => {
+                if !frame.touch(&ctx.input, id, down) {
+                    return Some(event);
+                }
+                nk_input_touch(ctx, id, x, y, down as Int);
+                None
+            }
+            InputEvent::TouchCancel => {
+                nk_input_touch_cancel(ctx);
+                None
+            }
+            InputEvent::FocusLost => {
+                for key in 0..NK_KEY_MAX {
+                    if !frame.key(&ctx.input, key, false) {
+                        return Some(event);
+                    }
+                }
+                for key in 0..NK_PKEY_MAX {
+                    if !frame.physical(&ctx.input, key, false) {
+                        return Some(event);
+                    }
+                }
+                for button in 0..NK_BUTTON_MAX {
+                    if !frame.button(&ctx.input, button, false) {
+                        return Some(event);
+                    }
+                }
+                for key in 0..NK_PKEY_MAX {
+                    nk_input_physical_key(ctx, key, 0);
+                }
+                for key in 0..NK_KEY_MAX {
+                    nk_input_key(ctx, key, 0);
+                }
+                nk_input_modifiers(ctx, NK_MOD_NONE);
+                let pos = ctx.input.mouse.pos;
+                for button in 0..NK_BUTTON_MAX {
+                    nk_input_button(ctx, button, pos.x as Int, pos.y as Int, 0);
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use allocator::OwnedContext;
+    use std::{mem, ptr, slice};
+
+    fn text(ctx: &Context) -> String {
+        let k = &ctx.input.keyboard;
+        let len = k.text_len as usize;
+        let bytes = unsafe { slice::from_raw_parts(k.text.memory.ptr as *const u8, len) };
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn key(key: Keys, down: bool) -> InputEvent {
+        InputEvent::Key {
+            key,
+            down,
+            modifiers: NK_MOD_NONE,
+        }
+    }
+
+    #[test]
+    fn long_text_arrives_in_one_frame() {
+        let mut ctx = OwnedContext::new();
+        let long: String = "n\u{fc}k\u{2603}".chars().cycle().take(400).collect();
+        let mut queue = InputQueue::new();
+        queue.push(InputEvent::Text(long.clone()));
+        assert!(!queue.process(&mut ctx));
+        assert_eq!(text(&ctx), long);
+        assert!(!queue.process(&mut ctx));
+        assert_eq!(text(&ctx), "");
+    }
+
+    #[test]
+    fn fixed_context_defers_text_without_losing_it() {
+        let mut memory = vec![0u8; 64 * 1024];
+        let mut ctx: Box<Context> = Box::new(unsafe { mem::zeroed() });
+        let size = memory.len() as Size;
+        unsafe { nk_init_fixed(&mut *ctx, memory.as_mut_ptr() as _, size, ptr::null()) };
+        let long: String = "0123456789\u{e9}".chars().cycle().take(60).collect();
+        let mut queue = InputQueue::new();
+        queue.push(InputEvent::Text(long.clone()));
+        let mut typed = String::new();
+        let mut frames = 0;
+        loop {
+            let more = queue.process(&mut ctx);
+            assert!(ctx.input.keyboard.text_len as usize <= 16);
+            typed.push_str(&text(&ctx));
+            frames += 1;
+            if !more {
+                break;
+            }
+        }
+        assert_eq!(typed, long);
+        assert!(frames > 1);
+        unsafe { nk_free(&mut *ctx) };
+    }
+
+    #[test]
+    fn repeated_transitions_split_frames() {
+        let mut ctx = OwnedContext::new();
+        let mut queue = InputQueue::new();
+        queue.push(key(NK_KEY_ENTER, true));
+        queue.push(key(NK_KEY_ENTER, false));
+        assert!(queue.process(&mut ctx));
+        assert!(ctx.input.keyboard.keys[NK_KEY_ENTER as usize].down != 0);
+        assert!(!queue.process(&mut ctx));
+        assert!(ctx.input.keyboard.keys[NK_KEY_ENTER as usize].down == 0);
+        assert_eq!(ctx.input.keyboard.keys[NK_KEY_ENTER as usize].clicked, 1);
+    }
+
+    #[test]
+    fn motion_after_a_click_waits() {
+        let mut ctx = OwnedContext::new();
+        let mut queue = InputQueue::new();
+        queue.push(InputEvent::MouseButton {
+            button: NK_BUTTON_LEFT,
+            x: 5,
+            y: 6,
+            down: true,
+            time: None,
+        });
+        queue.push(InputEvent::MouseMove { x: 50, y: 60 });
+        assert!(queue.process(&mut ctx));
+        assert_eq!(ctx.input.mouse.buttons[NK_BUTTON_LEFT as usize].clicked_pos.x, 5.0);
+        assert_eq!(ctx.input.mouse.pos.x, 5.0);
+        assert!(!queue.process(&mut ctx));
+        assert_eq!(ctx.input.mouse.pos.x, 50.0);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn out_of_range_keys_and_buttons_are_ignored() {
+        let mut ctx = OwnedContext::new();
+        let mut queue = InputQueue::new();
+        queue.push(key(NK_KEY_MAX, true));
+        queue.push(key(Keys::MAX, true));
+        for &button in &[NK_BUTTON_MAX, Buttons::MAX] {
+            queue.push(InputEvent::MouseButton {
+                button,
+                x: 5,
+                y: 6,
+                down: true,
+                time: Some(0.0),
+            });
+        }
+        queue.push(InputEvent::PhysicalKey {
+            key: NK_PKEY_MAX,
+            down: true,
+            modifiers: NK_MOD_NONE,
+        });
+        queue.push(key(NK_KEY_ENTER, true));
+        assert!(!queue.process(&mut ctx));
+        assert!(ctx.input.keyboard.keys[NK_KEY_ENTER as usize].down != 0);
+        assert_eq!(ctx.input.mouse.pos.x, 0.0);
+        assert!(ctx.input.mouse.buttons.iter().all(|b| b.down == 0));
+    }
+
+    #[test]
+    fn physical_release_clears_its_modifier() {
+        let mut ctx = OwnedContext::new();
+        let mut queue = InputQueue::new();
+        let shift = |key, down| InputEvent::PhysicalKey {
+            key,
+            down,
+            /* backends report the state from before the event */
+            modifiers: if down { NK_MOD_NONE } else { NK_MOD_SHIFT },
+        };
+        queue.push(shift(NK_PKEY_LEFT_SHIFT, true));
+        queue.push(shift(NK_PKEY_RIGHT_SHIFT, true));
+        queue.push(shift(NK_PKEY_LEFT_SHIFT, false));
+        while queue.process(&mut ctx) {}
+        assert_eq!(ctx.input.keyboard.modifiers, NK_MOD_SHIFT);
+        assert!(ctx.input.keyboard.keys[NK_KEY_SHIFT as usize].down != 0);
+        queue.push(shift(NK_PKEY_RIGHT_SHIFT, false));
+        while queue.process(&mut ctx) {}
+        assert_eq!(ctx.input.keyboard.modifiers, NK_MOD_NONE);
+        assert!(ctx.input.keyboard.keys[NK_KEY_SHIFT as usize].down == 0);
+    }
+}
@@ -0,0 +1,961 @@
+//! Headless snapshot harness for regression-testing UIs built on `Ui`.
+//!
+//! `Harness` owns a context with the default font, feeds scripted input,
+//! runs a closure to build the frame and renders it with the `Rasterizer`.
+//! The returned `Snapshot` can be compared against reference images (PAM
+//! files) within a tolerance and against a textual dump of the widget
+//! bounds and emitted commands.
+//!
+//! A missing reference is an error. Set `NUKE_BLESS=1` to write new
+//! references and to overwrite existing ones after an intended change; on
+//! mismatch or a missing reference the actual output is written next to it
+//! with an `.actual` suffix.
+//!
+//! Outside the crate's own tests the harness needs the `testing` feature.
+
+use allocator::OwnedContext;
+use command::Command;
+use input::{Buttons, Keys};
+use math::{Point, Rect};
+use nuklear::*;
+use raster::{Pixmap, Rasterizer};
+use std::fmt::{self, Write as FmtWrite};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::{env, error, fs, mem, ptr};
+use ui::{Ui, WidgetRecord};
+
+/// Environment variable that makes comparisons overwrite the references.
+pub const BLESS_VAR: &str = "NUKE_BLESS";
+
+/// One scripted input event, applied between `nk_input_begin` and
+/// `nk_input_end` of a frame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputOp {
+    Motion(i32, i32),
+    Button(Buttons, i32, i32, bool),
+    /// Button transition with a timestamp in seconds, for multi-clicks.
+    TimedButton(Buttons, i32, i32, bool, f64),
+    Key(Keys, bool),
+    Char(char),
+    Scroll(f32, f32),
+    /// Touch id at x, y, down or lifted.
+    Touch(u64, i32, i32, bool),
+}
+
+impl InputOp {
+    /// # Safety
+    ///
+    /// `ctx` must point to an initialized `Context` between
+    /// `nk_input_begin` and `nk_input_end`.
+    pub unsafe fn apply(&self, ctx: *mut Context) {
+        match *self {
+            InputOp::Motion(x, y) => nk_input_motion(ctx, x, y),
+            InputOp::Button(id, x, y, down) => nk_input_button(ctx, id, x, y, down as Int),
+            InputOp::TimedButton(id, x, y, down, time) => {
+                nk_input_button_timed(ctx, id, x, y, down as Int, time)
+            }
+            InputOp::Key(key, down) => nk_input_key(ctx, key, down as Int),
+            InputOp::Char(c) => nk_input_unicode(ctx, c as Rune),
+            InputOp::Scroll(x, y) => nk_input_scroll(ctx, Point::new(x, y)),
+            InputOp::Touch(id, x, y, down) => nk_input_touch(ctx, id, x, y, down as Int),
+        }
+    }
+}
+
+/// Maximum difference a comparison accepts before failing.
+#[derive(Copy, Clone, Debug)]
+pub struct Tolerance {
+    /// Per-channel difference below or equal to which pixels count as equal.
+    pub channel: u8,
+    /// Number of pixels allowed to exceed `channel`.
+    pub pixels: usize,
+}
+
+impl Tolerance {
+    pub fn exact() -> Self {
+        Tolerance {
+            channel: 0,
+            pixels: 0,
+        }
+    }
+}
+
+impl Default for Tolerance {
+    /* absorbs float rounding differences between compilers and targets */
+    fn default() -> Self {
+        Tolerance {
+            channel: 2,
+            pixels: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(PathBuf, io::Error),
+    Format(PathBuf),
+    /// No reference exists and blessing is off.
+    MissingReference(PathBuf),
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    ImageMismatch {
+        differing: usize,
+        max_delta: u8,
+    },
+    TextMismatch {
+        line: usize,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SnapshotError::Io(ref path, ref err) => write!(f, "{}: {}", path.display(), err),
+            SnapshotError::Format(ref path) => {
+                write!(f, "{}: not a RGB_ALPHA PAM file", path.display())
+            }
+            SnapshotError::MissingReference(ref path) => write!(
+                f,
+                "{}: no reference, set {}=1 to write it",
+                path.display(),
+                BLESS_VAR
+            ),
+            SnapshotError::SizeMismatch { expected, actual } => write!(
+                f,
+                "image size {}x{} differs from reference {}x{}",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+            SnapshotError::ImageMismatch {
+                differing,
+                max_delta,
+            } => write!(
+                f,
+                "{} pixels differ from reference (max channel delta {})",
+                differing, max_delta
+            ),
+            SnapshotError::TextMismatch {
+                line,
+                ref expected,
+                ref actual,
+            } => write!(
+                f,
+                "line {} differs from reference\n  expected: {}\n  actual:   {}",
+                line, expected, actual
+            ),
+        }
+    }
+}
+
+impl error::Error for SnapshotError {}
+
+fn blessing() -> bool {
+    match env::var_os(BLESS_VAR) {
+        Some(v) => !v.is_empty() && v != "0",
+        None => false,
+    }
+}
+
+fn actual_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".actual");
+    PathBuf::from(name)
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), SnapshotError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| SnapshotError::Io(dir.to_owned(), e))?;
+    }
+    fs::File::create(path)
+        .and_then(|mut f| f.write_all(bytes))
+        .map_err(|e| SnapshotError::Io(path.to_owned(), e))
+}
+
+fn read_file(path: &Path) -> Result<Option<Vec<u8>>, SnapshotError> {
+    let mut bytes = Vec::new();
+    match fs::File::open(path) {
+        Ok(mut f) => f
+            .read_to_end(&mut bytes)
+            .map(|_| Some(bytes))
+            .map_err(|e| SnapshotError::Io(path.to_owned(), e)),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(SnapshotError::Io(path.to_owned(), e)),
+    }
+}
+
+impl Pixmap {
+    /// Encodes the pixmap as a PAM (`P7`, `RGB_ALPHA`) file.
+    pub fn to_pam(&self) -> Vec<u8> {
+        let mut out = format!(
+            "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+            self.width, self.height
+        )
+        .into_bytes();
+        out.extend_from_slice(&self.pixels);
+        out
+    }
+
+    /// Decodes a PAM file written by `to_pam`.
+    pub fn from_pam(bytes: &[u8]) -> Option<Pixmap> {
+        const END: &[u8] = b"ENDHDR\n";
+        let end = bytes.windows(END.len()).position(|w| w == END)?;
+        let header = ::std::str::from_utf8(&bytes[..end]).ok()?;
+        let mut lines = header.lines();
+        if lines.next()? != "P7" {
+            return None;
+        }
+        let (mut width, mut height, mut depth, mut maxval) = (0, 0, 0, 0);
+        for line in lines {
+            let mut it = line.split_whitespace();
+            let key = it.next();
+            let value = it.next().and_then(|v| v.parse::<u32>().ok());
+            match (key, value) {
+                (Some("WIDTH"), Some(v)) => width = v,
+                (Some("HEIGHT"), Some(v)) => height = v,
+                (Some("DEPTH"), Some(v)) => depth = v,
+                (Some("MAXVAL"), Some(v)) => maxval = v,
+                _ => {}
+            }
+        }
+        let pixels = &bytes[end + END.len()..];
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|n| n.checked_mul(4));
+        if depth != 4 || maxval != 255 || len != Some(pixels.len()) {
+            return None;
+        }
+        Some(Pixmap::from_rgba(width, height, pixels.to_vec()))
+    }
+}
+
+/// Result of one harness frame.
+pub struct Snapshot {
+    pub image: Pixmap,
+    pub widgets: Vec<WidgetRecord>,
+    /// One line per recorded widget followed by one line per command.
+    pub dump: String,
+}
+
+impl Snapshot {
+    /// Looks up the bounds of the first widget with the given label.
+    pub fn widget(&self, label: &str) -> Option<Rect> {
+        self.widgets
+            .iter()
+            .find(|w| w.label == label)
+            .map(|w| w.bounds)
+    }
+
+    /// Compares the rendered image with the PAM reference at `path`.
+    pub fn compare_image<P: AsRef<Path>>(
+        &self,
+        path: P,
+        tolerance: Tolerance,
+    ) -> Result<(), SnapshotError> {
+        let path = path.as_ref();
+        let reference = match (read_file(path)?, blessing()) {
+            (_, true) => return write_file(path, &self.image.to_pam()),
+            (Some(ref bytes), false) => {
+                Pixmap::from_pam(bytes).ok_or_else(|| SnapshotError::Format(path.to_owned()))?
+            }
+            (None, false) => {
+                write_file(&actual_path(path), &self.image.to_pam())?;
+                return Err(SnapshotError::MissingReference(path.to_owned()));
+            }
+        };
+        let result = compare_pixmaps(&reference, &self.image, tolerance);
+        if result.is_err() {
+            write_file(&actual_path(path), &self.image.to_pam())?;
+        }
+        result
+    }
+
+    /// Compares the widget and command dump with the text reference at `path`.
+    pub fn compare_text<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        let path = path.as_ref();
+        let reference = match (read_file(path)?, blessing()) {
+            (_, true) => return write_file(path, self.dump.as_bytes()),
+            (Some(bytes), false) => String::from_utf8_lossy(&bytes).into_owned(),
+            (None, false) => {
+                write_file(&actual_path(path), self.dump.as_bytes())?;
+                return Err(SnapshotError::MissingReference(path.to_owned()));
+            }
+        };
+        let result = compare_lines(&reference, &self.dump);
+        if result.is_err() {
+            write_file(&actual_path(path), self.dump.as_bytes())?;
+        }
+        result
+    }
+
+    pub fn assert_image<P: AsRef<Path>>(&self, path: P, tolerance: Tolerance) {
+        let path = path.as_ref();
+        if let Err(err) = self.compare_image(path, tolerance) {
+            panic!("image snapshot {} failed: {}", path.display(), err);
+        }
+    }
+
+    pub fn assert_text<P: AsRef<Path>>(&self, path: P) {
+        let path = path.as_ref();
+        if let Err(err) = self.compare_text(path) {
+            panic!("text snapshot {} failed: {}", path.display(), err);
+        }
+    }
+}
+
+pub fn compare_pixmaps(
+    expected: &Pixmap,
+    actual: &Pixmap,
+    tolerance: Tolerance,
+) -> Result<(), SnapshotError> {
+    if expected.width != actual.width || expected.height != actual.height {
+        return Err(SnapshotError::SizeMismatch {
+            expected: (expected.width, expected.height),
+            actual: (actual.width, actual.height),
+        });
+    }
+    let mut differing = 0;
+    let mut max_delta = 0;
+    for (a, b) in expected.pixels.chunks(4).zip(actual.pixels.chunks(4)) {
+        let delta = a
+            .iter()
+            .zip(b)
+            .map(|(&x, &y)| x.abs_diff(y))
+            .max()
+            .unwrap_or(0);
+        max_delta = max_delta.max(delta);
+        if delta > tolerance.channel {
+            differing += 1;
+        }
+    }
+    if differing > tolerance.pixels {
+        Err(SnapshotError::ImageMismatch {
+            differing,
+            max_delta,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn compare_lines(expected: &str, actual: &str) -> Result<(), SnapshotError> {
+    let mut e = expected.lines();
+    let mut a = actual.lines();
+    let mut line = 1;
+    loop {
+        match (e.next(), a.next()) {
+            (None, None) => return Ok(()),
+            (x, y) if x == y => line += 1,
+            (x, y) => {
+                return Err(SnapshotError::TextMismatch {
+                    line,
+                    expected: x.unwrap_or("<end of file>").to_owned(),
+                    actual: y.unwrap_or("<end of file>").to_owned(),
+                })
+            }
+        }
+    }
+}
+
+fn color(c: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}{:02x}", c.r, c.g, c.b, c.a)
+}
+
+fn rect(r: Rect) -> String {
+    format!("{} {} {} {}", r.x, r.y, r.w, r.h)
+}
+
+fn points(p: &[PointI]) -> String {
+    let v: Vec<String> = p.iter().map(|p| format!("{},{}", p.x, p.y)).collect();
+    v.join(" ")
+}
+
+fn radii(r: [f32; 4]) -> String {
+    format!("{},{},{},{}", r[0], r[1], r[2], r[3])
+}
+
+fn gradient(g: &Gradient) -> String {
+    format!(
+        "{} {},{} {},{} {} {}",
+        if g.type_0 == NK_GRADIENT_RADIAL {
+            "radial"
+        } else {
+            "linear"
+        },
+        g.start.x,
+        g.start.y,
+        g.end.x,
+        g.end.y,
+        color(g.start_color),
+        color(g.end_color)
+    )
+}
+
+fn stroke_style(s: &StrokeStyle) -> String {
+    let cap = match s.cap {
+        NK_LINE_CAP_ROUND => "round",
+        NK_LINE_CAP_SQUARE => "square",
+        _ => "butt",
+    };
+    let join = match s.join {
+        NK_LINE_JOIN_ROUND => "round".to_owned(),
+        NK_LINE_JOIN_BEVEL => "bevel".to_owned(),
+        _ => format!("miter:{}", s.miter_limit),
+    };
+    let dash: Vec<String> = s.dash[..s.dash_count.min(NK_STROKE_MAX_DASHES) as usize]
+        .iter()
+        .map(|d| d.to_string())
+        .collect();
+    format!(
+        "cap={} join={} dash={}@{}",
+        cap,
+        join,
+        if dash.is_empty() {
+            "none".to_owned()
+        } else {
+            dash.join(",")
+        },
+        s.dash_offset
+    )
+}
+
+/// Formats a command as one line of the textual dump.
+pub fn describe_command(cmd: &Command) -> String {
+    match *cmd {
+        Command::Nop => "nop".to_owned(),
+        Command::Scissor { rect: r } => format!("scissor {}", rect(r)),
+        Command::Line {
+            begin,
+            end,
+            thickness,
+            color: c,
+        } => format!(
+            "line {},{} {},{} t={} {}",
+            begin.x,
+            begin.y,
+            end.x,
+            end.y,
+            thickness,
+            color(c)
+        ),
+        Command::Curve {
+            begin,
+            ctrl,
+            end,
+            thickness,
+            color: c,
+        } => format!(
+            "curve {},{} {},{} {},{} {},{} t={} {}",
+            begin.x,
+            begin.y,
+            ctrl[0].x,
+            ctrl[0].y,
+            ctrl[1].x,
+            ctrl[1].y,
+            end.x,
+            end.y,
+            thickness,
+            color(c)
+        ),
+        Command::LineStyled {
+            begin,
+            end,
+            thickness,
+            color: c,
+            ref style,
+        } => format!(
+            "line_styled {},{} {},{} t={} {} {}",
+            begin.x,
+            begin.y,
+            end.x,
+            end.y,
+            thickness,
+            color(c),
+            stroke_style(style)
+        ),
+        Command::CurveStyled {
+            begin,
+            ctrl,
+            end,
+            thickness,
+            color: c,
+            ref style,
+        } => format!(
+            "curve_styled {},{} {},{} {},{} {},{} t={} {} {}",
+            begin.x,
+            begin.y,
+            ctrl[0].x,
+            ctrl[0].y,
+            ctrl[1].x,
+            ctrl[1].y,
+            end.x,
+            end.y,
+            thickness,
+            color(c),
+            stroke_style(style)
+        ),
+        Command::Rect {
+            rect: r,
+            rounding,
+            thickness,
+            color: c,
+        } => format!(
+            "rect {} r={} t={} {}",
+            rect(r),
+            rounding,
+            thickness,
+            color(c)
+        ),
+        Command::RectFilled {
+            rect: r,
+            rounding,
+            color: c,
+        } => format!("rect_filled {} r={} {}", rect(r), rounding, color(c)),
+        Command::RectShadow {
+            rect: r,
+            rounding,
+            feather,
+            color: c,
+        } => format!(
+            "rect_shadow {} r={} f={} {}",
+            rect(r),
+            rounding,
+            feather,
+            color(c)
+        ),
+        Command::RectMultiColor {
+            rect: r,
+            left,
+            top,
+            right,
+            bottom,
+        } => format!(
+            "rect_multi_color {} {} {} {} {}",
+            rect(r),
+            color(left),
+            color(top),
+            color(right),
+            color(bottom)
+        ),
+        Command::RectCorners {
+            rect: r,
+            rounding,
+            thickness,
+            color: c,
+        } => format!(
+            "rect_corners {} r={} t={} {}",
+            rect(r),
+            radii(rounding),
+            thickness,
+            color(c)
+        ),
+        Command::RectCornersFilled {
+            rect: r,
+            rounding,
+            color: c,
+        } => format!(
+            "rect_corners_filled {} r={} {}",
+            rect(r),
+            radii(rounding),
+            color(c)
+        ),
+        Command::RectGradient {
+            rect: r,
+            rounding,
+            gradient: ref g,
+        } => format!(
+            "rect_gradient {} r={} {}",
+            rect(r),
+            radii(rounding),
+            gradient(g)
+        ),
+        Command::Circle {
+            rect: r,
+            thickness,
+            color: c,
+        } => format!("circle {} t={} {}", rect(r), thickness, color(c)),
+        Command::CircleFilled { rect: r, color: c } => {
+            format!("circle_filled {} {}", rect(r), color(c))
+        }
+        Command::CircleGradient {
+            rect: r,
+            gradient: ref g,
+        } => format!("circle_gradient {} {}", rect(r), gradient(g)),
+        Command::Arc {
+            center,
+            radius,
+            angles,
+            thickness,
+            color: c,
+        } => format!(
+            "arc {},{} r={} a={},{} t={} {}",
+            center.x,
+            center.y,
+            radius,
+            angles[0],
+            angles[1],
+            thickness,
+            color(c)
+        ),
+        Command::ArcFilled {
+            center,
+            radius,
+            angles,
+            color: c,
+        } => format!(
+            "arc_filled {},{} r={} a={},{} {}",
+            center.x,
+            center.y,
+            radius,
+            angles[0],
+            angles[1],
+            color(c)
+        ),
+        Command::Triangle {
+            points: p,
+            thickness,
+            color: c,
+        } => format!(
+            "triangle {},{} {},{} {},{} t={} {}",
+            p[0].x,
+            p[0].y,
+            p[1].x,
+            p[1].y,
+            p[2].x,
+            p[2].y,
+            thickness,
+            color(c)
+        ),
+        Command::TriangleFilled {
+            points: p,
+            color: c,
+        } => format!(
+            "triangle_filled {},{} {},{} {},{} {}",
+            p[0].x,
+            p[0].y,
+            p[1].x,
+            p[1].y,
+            p[2].x,
+            p[2].y,
+            color(c)
+        ),
+        Command::Polygon {
+            points: p,
+            thickness,
+            color: c,
+        } => format!("polygon {} t={} {}", points(p), thickness, color(c)),
+        Command::PolygonFilled {
+            points: p,
+            color: c,
+        } => {
+            format!("polygon_filled {} {}", points(p), color(c))
+        }
+        Command::PolygonGradient {
+            points: p,
+            gradient: ref g,
+        } => format!("polygon_gradient {} {}", points(p), gradient(g)),
+        Command::Polyline {
+            points: p,
+            thickness,
+            color: c,
+        } => format!("polyline {} t={} {}", points(p), thickness, color(c)),
+        Command::PolygonStyled {
+            points: p,
+            thickness,
+            color: c,
+            ref style,
+        } => format!(
+            "polygon_styled {} t={} {} {}",
+            points(p),
+            thickness,
+            color(c),
+            stroke_style(style)
+        ),
+        Command::PolylineStyled {
+            points: p,
+            thickness,
+            color: c,
+            ref style,
+        } => format!(
+            "polyline_styled {} t={} {} {}",
+            points(p),
+            thickness,
+            color(c),
+            stroke_style(style)
+        ),
+        Command::Text {
+            rect: r,
+            height,
+            string,
+            background,
+            foreground,
+            ..
+        } => format!(
+            "text {} h={} {} {} {:?}",
+            rect(r),
+            height,
+            color(background),
+            color(foreground),
+            string
+        ),
+        Command::Image {
+            rect: r,
+            image,
+            color: c,
+        } => format!("image {} {}x{} {}", rect(r), image.w, image.h, color(c)),
+        Command::Custom { rect: r, .. } => format!("custom {}", rect(r)),
+        Command::CustomDraw { rect: r, .. } => format!("custom_draw {}", rect(r)),
+    }
+}
+
+/* texture id of the baked font atlas */
+const FONT_TEXTURE: Int = 1;
+
+/// Headless context, default font and rasterizer for snapshot tests.
+pub struct Harness {
+    pub width: u32,
+    pub height: u32,
+    pub background: Color,
+    pub rasterizer: Rasterizer,
+    ctx: OwnedContext,
+    atlas: Box<FontAtlas>,
+}
+
+impl Harness {
+    /// Creates a `width` x `height` harness using the built-in ProggyClean
+    /// font at 13 pixels.
+    pub fn new(width: u32, height: u32) -> Self {
+        unsafe {
+            let mut atlas: Box<FontAtlas> = Box::new(mem::zeroed());
+            nk_font_atlas_init_default(&mut *atlas);
+            nk_font_atlas_begin(&mut *atlas);
+            let font = nk_font_atlas_add_default(&mut *atlas, 13.0, ptr::null());
+            let (mut w, mut h) = (0, 0);
+            let image = nk_font_atlas_bake(&mut *atlas, &mut w, &mut h, NK_FONT_ATLAS_RGBA32);
+            let pixels = Pixmap::from_raw_rgba(w as u32, h as u32, image);
+            let mut null: DrawNullTexture = mem::zeroed();
+            nk_font_atlas_end(&mut *atlas, nk_handle_id(FONT_TEXTURE), &mut null);
+
+            let ctx = OwnedContext::with_font(&(*font).handle);
+            let mut rasterizer = Rasterizer::new(null);
+            rasterizer.set_texture(nk_handle_id(FONT_TEXTURE), pixels);
+            Harness {
+                width,
+                height,
+                background: nk_rgb(0, 0, 0),
+                rasterizer,
+                ctx,
+                atlas,
+            }
+        }
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.ctx
+    }
+
+    pub fn context_mut(&mut self) -> &mut Context {
+        &mut self.ctx
+    }
+
+    /// Runs one frame: applies `input`, builds the UI with `f`, renders it
+    /// and clears the context for the next frame.
+    pub fn frame<F>(&mut self, input: &[InputOp], f: F) -> Snapshot
+    where
+        F: FnOnce(&mut Ui),
+    {
+        let ctx: *mut Context = &mut *self.ctx;
+        let mut widgets = Vec::new();
+        unsafe {
+            nk_input_begin(ctx);
+            for op in input {
+                op.apply(ctx);
+            }
+            nk_input_end(ctx);
+        }
+        f(&mut Ui::with_records(&mut self.ctx, &mut widgets));
+
+        let mut dump = String::new();
+        for w in &widgets {
+            let _ = writeln!(dump, "widget {:?} {}", w.label, rect(w.bounds));
+        }
+        for cmd in self.ctx.commands() {
+            dump.push_str(&describe_command(&cmd));
+            dump.push('\n');
+        }
+
+        let mut image = Pixmap::new(self.width, self.height);
+        image.clear(self.background);
+        self.rasterizer.render(&mut self.ctx, &mut image);
+        unsafe { nk_clear(ctx) };
+        Snapshot {
+            image,
+            widgets,
+            dump,
+        }
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        unsafe { nk_font_atlas_clear(&mut *self.atlas) }
+    }
+}
+
+/* a window with one command of every kind that crosses process borders,
+ * for the tests of command consumers */
+#[cfg(test)]
+pub(crate) unsafe fn draw_every_command(ctx: *mut Context) {
+    let bounds = Rect::new(10.0, 10.0, 300.0, 300.0);
+    nk_begin(ctx, b"every command\0".as_ptr() as _, bounds, NK_WINDOW_TITLE as Flags);
+    let b = nk_window_get_canvas(ctx);
+    let (red, blue) = (nk_rgba(255, 0, 0, 255), nk_rgba(0, 0, 255, 128));
+    let r = Rect::new(20.0, 40.0, 50.0, 30.0);
+    let radii = [1.0, 2.0, 3.0, 4.0];
+    let gradient = Gradient {
+        type_0: NK_GRADIENT_RADIAL,
+        start: Point::new(30.0, 50.0),
+        end: Point::new(60.0, 70.0),
+        start_color: red,
+        end_color: blue,
+    };
+    let dashes = [4.0, 2.0];
+    let style = nk_stroke_style(NK_LINE_CAP_ROUND, NK_LINE_JOIN_BEVEL);
+    let style = nk_stroke_style_dash(style, dashes.as_ptr(), 2, 1.0);
+    let mut points = [20.0, 40.0, 80.0, 45.0, 50.0, 90.0];
+    let image = nk_image_id(7);
+    nk_stroke_line(b, 20.0, 40.0, 90.0, 95.0, 2.0, red);
+    nk_stroke_curve(b, 20.0, 40.0, 30.0, 80.0, 70.0, 30.0, 90.0, 90.0, 1.0, blue);
+    nk_stroke_line_styled(b, 20.0, 40.0, 90.0, 95.0, 2.0, red, &style);
+    nk_stroke_curve_styled(b, 20.0, 40.0, 30.0, 80.0, 70.0, 30.0, 90.0, 90.0, 1.0, blue, &style);
+    nk_stroke_rect(b, r, 3.0, 1.5, red);
+    nk_fill_rect(b, r, 3.0, blue);
+    nk_fill_rect_shadow(b, r, 3.0, 6.0, blue);
+    nk_fill_rect_multi_color(b, r, red, blue, red, blue);
+    nk_stroke_rect_corners(b, r, radii, 1.0, red);
+    nk_fill_rect_corners(b, r, radii, blue);
+    nk_fill_rect_gradient(b, r, radii, gradient);
+    nk_stroke_circle(b, r, 1.0, red);
+    nk_fill_circle(b, r, blue);
+    nk_fill_circle_gradient(b, r, gradient);
+    nk_stroke_arc(b, 50.0, 60.0, 20.0, 0.0, 3.0, 1.0, red);
+    nk_fill_arc(b, 50.0, 60.0, 20.0, 0.0, 3.0, blue);
+    nk_stroke_triangle(b, 20.0, 40.0, 80.0, 45.0, 50.0, 90.0, 1.0, red);
+    nk_fill_triangle(b, 20.0, 40.0, 80.0, 45.0, 50.0, 90.0, blue);
+    nk_stroke_polygon(b, points.as_mut_ptr(), 3, 1.0, red);
+    nk_fill_polygon(b, points.as_mut_ptr(), 3, blue);
+    nk_fill_polygon_gradient(b, points.as_mut_ptr(), 3, gradient);
+    nk_stroke_polyline(b, points.as_mut_ptr(), 3, 1.0, red);
+    nk_stroke_polygon_styled(b, points.as_mut_ptr(), 3, 1.0, red, &style);
+    nk_stroke_polyline_styled(b, points.as_mut_ptr(), 3, 1.0, red, &style);
+    nk_draw_text(b, r, b"text\0".as_ptr() as _, 4, (*ctx).style.font, blue, red);
+    nk_draw_image(b, r, &image, red);
+    nk_end(ctx);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            image: Pixmap::new(2, 2),
+            widgets: Vec::new(),
+            dump: "rect_filled 0,0 2x2\n".to_owned(),
+        }
+    }
+
+    /* temporary directory of one test, removed with its files on drop */
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(test: &str) -> Self {
+            let dir = format!("nuke-harness-{}-{}", ::std::process::id(), test);
+            let dir = env::temp_dir().join(dir);
+            let _ = fs::create_dir_all(&dir);
+            Scratch(dir)
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn missing_reference_is_an_error() {
+        if blessing() {
+            return;
+        }
+        let snap = snapshot();
+        let scratch = Scratch::new("missing");
+        let text = scratch.path("missing.txt");
+        match snap.compare_text(&text) {
+            Err(SnapshotError::MissingReference(ref p)) if *p == text => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(!text.exists());
+        assert!(actual_path(&text).exists());
+
+        let image = scratch.path("missing.pam");
+        match snap.compare_image(&image, Tolerance::exact()) {
+            Err(SnapshotError::MissingReference(ref p)) if *p == image => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(!image.exists());
+    }
+
+    #[test]
+    fn existing_reference_compares() {
+        let snap = snapshot();
+        let scratch = Scratch::new("existing");
+        let text = scratch.path("existing.txt");
+        write_file(&text, snap.dump.as_bytes()).unwrap();
+        snap.compare_text(&text).unwrap();
+
+        let image = scratch.path("existing.pam");
+        write_file(&image, &snap.image.to_pam()).unwrap();
+        snap.compare_image(&image, Tolerance::exact()).unwrap();
+    }
+
+    /* checked-in reference, relative to the crate root */
+    fn reference(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/snapshots")
+            .join(name)
+    }
+
+    #[test]
+    fn small_window_matches_references() {
+        let mut harness = Harness::new(160, 112);
+        let bounds = Rect::new(8.0, 8.0, 144.0, 96.0);
+        let snap = harness.frame(&[], |ui| {
+            ui.window("Snapshot", bounds, NK_WINDOW_BORDER | NK_WINDOW_TITLE, |ui| {
+                ui.layout_row_dynamic(20.0, 1);
+                ui.label("Hello", NK_TEXT_LEFT);
+                ui.button("Apply");
+            });
+        });
+        assert!(snap.widget("Apply").is_some());
+        snap.assert_text(reference("small_window.txt"));
+        snap.assert_image(reference("small_window.pam"), Tolerance::default());
+    }
+
+    #[test]
+    fn pam_round_trip() {
+        let mut image = Pixmap::new(3, 2);
+        image.clear(Color { r: 1, g: 2, b: 3, a: 4 });
+        assert!(Pixmap::from_pam(&image.to_pam()) == Some(image));
+        assert!(Pixmap::from_pam(b"P7\nWIDTH 4294967295\nHEIGHT 4294967295\nDEPTH 4\n\
+                                   MAXVAL 255\nENDHDR\n")
+            .is_none());
+    }
+}
@@ -1,11 +1,13 @@
 use libc;
 use math::{Point, Rect};
+use nuklear::Buffer;
 
 #[derive(Copy, Clone)]
 #[repr(C)]
 pub struct Input {
     pub keyboard: Keyboard,
     pub mouse: Mouse,
+    pub touch: Touch,
 }
 #[derive(Copy, Clone)]
 #[repr(C)]
@@ -18,6 +20,7 @@ pub struct Mouse {
     pub grab: libc::c_uchar,
     pub grabbed: libc::c_uchar,
     pub ungrab: libc::c_uchar,
+    pub click_count: [libc::c_uint; 4],
 }
 
 #[derive(Copy, Clone)]
@@ -28,12 +31,52 @@ pub struct MouseButton {
     pub clicked_pos: Point,
 }
 
+/// Touch points and the gestures recognized from them, see `nk_input_touch`.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Touch {
+    pub points: [TouchPoint; 10],
+    /* slot of the touch that drives the mouse pointer, -1 if none */
+    pub primary: libc::c_int,
+    /// Gestures recognized this frame.
+    pub gestures: Gestures,
+    /// Where the last tap or long press happened.
+    pub tap_pos: Point,
+    /// Movement of a single-finger pan this frame.
+    pub pan: Point,
+    /// Change of the two-finger distance this frame, 1 without a pinch.
+    pub pinch_scale: f32,
+    /// Scale since the second finger touched down.
+    pub pinch_total: f32,
+    pub pinch_center: Point,
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct TouchPoint {
+    pub id: u64,
+    pub phase: TouchPhase,
+    pub pos: Point,
+    pub prev: Point,
+    pub start: Point,
+    /// Seconds since the touch began, advanced by `delta_time_seconds`.
+    pub held: f32,
+    /* still a tap or long press candidate */
+    pub tap: libc::c_int,
+}
+
 #[derive(Copy, Clone)]
 #[repr(C)]
 pub struct Keyboard {
     pub keys: [Key; 30],
-    pub text: [libc::c_char; 16],
+    /// UTF-8 text entered this frame, `text_len` bytes from the start of
+    /// its memory. Grows with the context allocator; fixed contexts keep
+    /// it in `text_memory`.
+    pub text: Buffer,
     pub text_len: libc::c_int,
+    pub text_memory: [libc::c_char; 16],
+    pub physical: [Key; 87],
+    pub modifiers: Modifiers,
 }
 
 #[derive(Copy, Clone)]
@@ -50,6 +93,25 @@ pub const NK_BUTTON_RIGHT: Buttons = 2;
 pub const NK_BUTTON_MIDDLE: Buttons = 1;
 pub const NK_BUTTON_LEFT: Buttons = 0;
 
+pub const NK_TOUCH_MAX: usize = 10;
+
+pub type TouchPhase = libc::c_uint;
+pub const NK_TOUCH_CANCELLED: TouchPhase = 5;
+pub const NK_TOUCH_ENDED: TouchPhase = 4;
+pub const NK_TOUCH_STATIONARY: TouchPhase = 3;
+pub const NK_TOUCH_MOVED: TouchPhase = 2;
+pub const NK_TOUCH_BEGAN: TouchPhase = 1;
+/* free slot */
+pub const NK_TOUCH_NONE: TouchPhase = 0;
+
+pub type Gestures = libc::c_uint;
+pub const NK_GESTURE_SCROLL: Gestures = 16;
+pub const NK_GESTURE_PINCH: Gestures = 8;
+pub const NK_GESTURE_PAN: Gestures = 4;
+pub const NK_GESTURE_LONG_PRESS: Gestures = 2;
+pub const NK_GESTURE_TAP: Gestures = 1;
+pub const NK_GESTURE_NONE: Gestures = 0;
+
 pub type Keys = libc::c_uint;
 pub const NK_KEY_MAX: Keys = 30;
 pub const NK_KEY_SCROLL_UP: Keys = 29;
@@ -85,6 +147,103 @@ pub const NK_KEY_CTRL: Keys = 2;
 pub const NK_KEY_SHIFT: Keys = 1;
 pub const NK_KEY_NONE: Keys = 0;
 
+pub type PhysicalKey = libc::c_uint;
+pub const NK_PKEY_MAX: PhysicalKey = 87;
+pub const NK_PKEY_PAUSE: PhysicalKey = 86;
+pub const NK_PKEY_PRINT_SCREEN: PhysicalKey = 85;
+pub const NK_PKEY_MENU: PhysicalKey = 84;
+pub const NK_PKEY_CAPS_LOCK: PhysicalKey = 83;
+pub const NK_PKEY_SLASH: PhysicalKey = 82;
+pub const NK_PKEY_PERIOD: PhysicalKey = 81;
+pub const NK_PKEY_COMMA: PhysicalKey = 80;
+pub const NK_PKEY_GRAVE: PhysicalKey = 79;
+pub const NK_PKEY_APOSTROPHE: PhysicalKey = 78;
+pub const NK_PKEY_SEMICOLON: PhysicalKey = 77;
+pub const NK_PKEY_BACKSLASH: PhysicalKey = 76;
+pub const NK_PKEY_RIGHT_BRACKET: PhysicalKey = 75;
+pub const NK_PKEY_LEFT_BRACKET: PhysicalKey = 74;
+pub const NK_PKEY_EQUAL: PhysicalKey = 73;
+pub const NK_PKEY_MINUS: PhysicalKey = 72;
+pub const NK_PKEY_RIGHT_SUPER: PhysicalKey = 71;
+pub const NK_PKEY_LEFT_SUPER: PhysicalKey = 70;
+pub const NK_PKEY_RIGHT_ALT: PhysicalKey = 69;
+pub const NK_PKEY_LEFT_ALT: PhysicalKey = 68;
+pub const NK_PKEY_RIGHT_CTRL: PhysicalKey = 67;
+pub const NK_PKEY_LEFT_CTRL: PhysicalKey = 66;
+pub const NK_PKEY_RIGHT_SHIFT: PhysicalKey = 65;
+pub const NK_PKEY_LEFT_SHIFT: PhysicalKey = 64;
+pub const NK_PKEY_SPACE: PhysicalKey = 63;
+pub const NK_PKEY_DOWN: PhysicalKey = 62;
+pub const NK_PKEY_UP: PhysicalKey = 61;
+pub const NK_PKEY_RIGHT: PhysicalKey = 60;
+pub const NK_PKEY_LEFT: PhysicalKey = 59;
+pub const NK_PKEY_PAGE_DOWN: PhysicalKey = 58;
+pub const NK_PKEY_PAGE_UP: PhysicalKey = 57;
+pub const NK_PKEY_END: PhysicalKey = 56;
+pub const NK_PKEY_HOME: PhysicalKey = 55;
+pub const NK_PKEY_DELETE: PhysicalKey = 54;
+pub const NK_PKEY_INSERT: PhysicalKey = 53;
+pub const NK_PKEY_BACKSPACE: PhysicalKey = 52;
+pub const NK_PKEY_TAB: PhysicalKey = 51;
+pub const NK_PKEY_ENTER: PhysicalKey = 50;
+pub const NK_PKEY_ESCAPE: PhysicalKey = 49;
+pub const NK_PKEY_F12: PhysicalKey = 48;
+pub const NK_PKEY_F11: PhysicalKey = 47;
+pub const NK_PKEY_F10: PhysicalKey = 46;
+pub const NK_PKEY_F9: PhysicalKey = 45;
+pub const NK_PKEY_F8: PhysicalKey = 44;
+pub const NK_PKEY_F7: PhysicalKey = 43;
+pub const NK_PKEY_F6: PhysicalKey = 42;
+pub const NK_PKEY_F5: PhysicalKey = 41;
+pub const NK_PKEY_F4: PhysicalKey = 40;
+pub const NK_PKEY_F3: PhysicalKey = 39;
+pub const NK_PKEY_F2: PhysicalKey = 38;
+pub const NK_PKEY_F1: PhysicalKey = 37;
+pub const NK_PKEY_D9: PhysicalKey = 36;
+pub const NK_PKEY_D8: PhysicalKey = 35;
+pub const NK_PKEY_D7: PhysicalKey = 34;
+pub const NK_PKEY_D6: PhysicalKey = 33;
+pub const NK_PKEY_D5: PhysicalKey = 32;
+pub const NK_PKEY_D4: PhysicalKey = 31;
+pub const NK_PKEY_D3: PhysicalKey = 30;
+pub const NK_PKEY_D2: PhysicalKey = 29;
+pub const NK_PKEY_D1: PhysicalKey = 28;
+pub const NK_PKEY_D0: PhysicalKey = 27;
+pub const NK_PKEY_Z: PhysicalKey = 26;
+pub const NK_PKEY_Y: PhysicalKey = 25;
+pub const NK_PKEY_X: PhysicalKey = 24;
+pub const NK_PKEY_W: PhysicalKey = 23;
+pub const NK_PKEY_V: PhysicalKey = 22;
+pub const NK_PKEY_U: PhysicalKey = 21;
+pub const NK_PKEY_T: PhysicalKey = 20;
+pub const NK_PKEY_S: PhysicalKey = 19;
+pub const NK_PKEY_R: PhysicalKey = 18;
+pub const NK_PKEY_Q: PhysicalKey = 17;
+pub const NK_PKEY_P: PhysicalKey = 16;
+pub const NK_PKEY_O: PhysicalKey = 15;
+pub const NK_PKEY_N: PhysicalKey = 14;
+pub const NK_PKEY_M: PhysicalKey = 13;
+pub const NK_PKEY_L: PhysicalKey = 12;
+pub const NK_PKEY_K: PhysicalKey = 11;
+pub const NK_PKEY_J: PhysicalKey = 10;
+pub const NK_PKEY_I: PhysicalKey = 9;
+pub const NK_PKEY_H: PhysicalKey = 8;
+pub const NK_PKEY_G: PhysicalKey = 7;
+pub const NK_PKEY_F: PhysicalKey = 6;
+pub const NK_PKEY_E: PhysicalKey = 5;
+pub const NK_PKEY_D: PhysicalKey = 4;
+pub const NK_PKEY_C: PhysicalKey = 3;
+pub const NK_PKEY_B: PhysicalKey = 2;
+pub const NK_PKEY_A: PhysicalKey = 1;
+pub const NK_PKEY_NONE: PhysicalKey = 0;
+
+pub type Modifiers = libc::c_uint;
+pub const NK_MOD_SUPER: Modifiers = 8;
+pub const NK_MOD_ALT: Modifiers = 4;
+pub const NK_MOD_CTRL: Modifiers = 2;
+pub const NK_MOD_SHIFT: Modifiers = 1;
+pub const NK_MOD_NONE: Modifiers = 0;
+
 pub unsafe fn nk_input_has_mouse_click(i: *const Input, id: Buttons) -> libc::c_int {
     if i.is_null() {
         return 0;
@@ -265,3 +424,157 @@ pub unsafe fn nk_input_is_mouse_hovering_rect(i: *const Input, rect: Rect) -> li
             as libc::c_int;
     };
 }
+
+/* The queries below take the `Input` of a context like the `nk_input_*`
+ * queries of nuklear.rs: `i` may be null, which reads as no input at all,
+ * and must otherwise point to a valid `Input`. */
+
+/// Physical key plus the exact set of modifiers that must be held with it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub modifiers: Modifiers,
+    pub key: PhysicalKey,
+}
+
+impl KeyChord {
+    pub fn new(modifiers: Modifiers, key: PhysicalKey) -> Self {
+        KeyChord { modifiers, key }
+    }
+}
+
+/// Whether the physical `key` is held.
+pub unsafe fn nk_input_is_physical_key_down(i: *const Input, key: PhysicalKey) -> libc::c_int {
+    if i.is_null() || key >= NK_PKEY_MAX {
+        0
+    } else {
+        (0 != (*i).keyboard.physical[key as usize].down) as libc::c_int
+    }
+}
+
+/// Whether the physical `key` went down this frame.
+pub unsafe fn nk_input_is_physical_key_pressed(i: *const Input, key: PhysicalKey) -> libc::c_int {
+    if i.is_null() || key >= NK_PKEY_MAX {
+        0
+    } else {
+        let k = &(*i).keyboard.physical[key as usize] as *const Key;
+        (0 != (*k).down && 0 != (*k).clicked || 0 == (*k).down && (*k).clicked >= 2)
+            as libc::c_int
+    }
+}
+
+/// Whether the physical `key` went up this frame.
+pub unsafe fn nk_input_is_physical_key_released(i: *const Input, key: PhysicalKey) -> libc::c_int {
+    if i.is_null() || key >= NK_PKEY_MAX {
+        0
+    } else {
+        let k = &(*i).keyboard.physical[key as usize] as *const Key;
+        (0 == (*k).down && 0 != (*k).clicked || 0 != (*k).down && (*k).clicked >= 2)
+            as libc::c_int
+    }
+}
+
+/// Whether the chord's key went down this frame while exactly its modifiers
+/// were held. A chord without a key matches on the modifiers alone.
+pub unsafe fn nk_input_is_chord_pressed(i: *const Input, chord: KeyChord) -> libc::c_int {
+    if i.is_null() || (*i).keyboard.modifiers != chord.modifiers {
+        0
+    } else if chord.key == NK_PKEY_NONE {
+        1
+    } else {
+        nk_input_is_physical_key_pressed(i, chord.key)
+    }
+}
+
+/// Whether the chord's key is held with exactly its modifiers.
+pub unsafe fn nk_input_is_chord_down(i: *const Input, chord: KeyChord) -> libc::c_int {
+    if i.is_null() || (*i).keyboard.modifiers != chord.modifiers {
+        0
+    } else if chord.key == NK_PKEY_NONE {
+        1
+    } else {
+        nk_input_is_physical_key_down(i, chord.key)
+    }
+}
+
+/// Consecutive clicks of the press of `id` this frame, 0 without a press.
+pub unsafe fn nk_input_mouse_click_count(i: *const Input, id: Buttons) -> libc::c_int {
+    if i.is_null() || 0 == nk_input_is_mouse_pressed(i, id) {
+        0
+    } else {
+        (*i).mouse.click_count[id as usize] as libc::c_int
+    }
+}
+
+/// Whether the touch in `slot` is down, including touches that began this
+/// frame.
+pub unsafe fn nk_input_is_touch_down(i: *const Input, slot: libc::c_int) -> libc::c_int {
+    if i.is_null() || slot < 0 || slot as usize >= NK_TOUCH_MAX {
+        0
+    } else {
+        let phase = (*i).touch.points[slot as usize].phase;
+        (NK_TOUCH_BEGAN..=NK_TOUCH_STATIONARY).contains(&phase) as libc::c_int
+    }
+}
+
+/// Number of touches that are down.
+pub unsafe fn nk_input_touch_count(i: *const Input) -> libc::c_int {
+    let mut count = 0;
+    let mut slot = 0;
+    while slot < NK_TOUCH_MAX as libc::c_int {
+        count += nk_input_is_touch_down(i, slot);
+        slot += 1
+    }
+    count
+}
+
+/// Whether `gesture` was recognized this frame.
+pub unsafe fn nk_input_has_gesture(i: *const Input, gesture: Gestures) -> libc::c_int {
+    if i.is_null() {
+        0
+    } else {
+        (0 != (*i).touch.gestures & gesture) as libc::c_int
+    }
+}
+
+/// Whether a tap landed in `r` this frame.
+pub unsafe fn nk_input_is_tap_in_rect(i: *const Input, r: Rect) -> libc::c_int {
+    if 0 == nk_input_has_gesture(i, NK_GESTURE_TAP) {
+        0
+    } else {
+        let p = (*i).touch.tap_pos;
+        (r.x <= p.x && p.x < r.x + r.w && r.y <= p.y && p.y < r.y + r.h) as libc::c_int
+    }
+}
+
+/// Whether a long press landed in `r` this frame.
+pub unsafe fn nk_input_is_long_press_in_rect(i: *const Input, r: Rect) -> libc::c_int {
+    if 0 == nk_input_has_gesture(i, NK_GESTURE_LONG_PRESS) {
+        0
+    } else {
+        let p = (*i).touch.tap_pos;
+        (r.x <= p.x && p.x < r.x + r.w && r.y <= p.y && p.y < r.y + r.h) as libc::c_int
+    }
+}
+
+/// Relative pinch scale of this frame (1 if there is no pinch); the point
+/// between the two fingers goes to `center` unless it is null.
+pub unsafe fn nk_input_pinch(i: *const Input, center: *mut Point) -> f32 {
+    if 0 == nk_input_has_gesture(i, NK_GESTURE_PINCH) {
+        1.0
+    } else {
+        if !center.is_null() {
+            *center = (*i).touch.pinch_center
+        }
+        (*i).touch.pinch_scale
+    }
+}
+
+/// Pinch scale accumulated since the second finger touched down, 1 while
+/// fewer than two fingers are down.
+pub unsafe fn nk_input_pinch_total(i: *const Input) -> f32 {
+    if i.is_null() || nk_input_touch_count(i) != 2 {
+        1.0
+    } else {
+        (*i).touch.pinch_total
+    }
+}
@@ -1,10 +1,43 @@
 extern crate libc;
 
+mod allocator;
+mod command;
+mod custom;
+mod dirty;
+#[cfg(any(test, feature = "testing"))]
+mod driver;
+mod events;
+#[cfg(any(test, feature = "testing"))]
+mod harness;
 mod input;
 mod math;
 mod nuklear;
 mod offset_to;
+mod raster;
+mod remote;
+mod replay;
+mod serialize;
+mod svg;
+mod ui;
 
+pub use allocator::*;
+pub use command::*;
+pub use custom::*;
+pub use dirty::*;
+#[cfg(any(test, feature = "testing"))]
+pub use driver::*;
+pub use events::*;
+#[cfg(any(test, feature = "testing"))]
+pub use harness::*;
 pub use input::*;
 pub use math::*;
 pub use nuklear::*;
+/* the typed command takes the name over from the raw header */
+pub use command::Command;
+pub use nuklear::Command as CommandHeader;
+pub use raster::*;
+pub use remote::*;
+pub use replay::*;
+pub use serialize::*;
+pub use svg::*;
+pub use ui::*;
@@ -9,6 +9,7 @@
          unused_unsafe,
          unused_variables)]
 
+use allocator::{alloc_with, free_with, RustAlloc};
 use input::*;
 use libc;
 use math::*;
@@ -48,6 +49,9 @@ pub struct Buffer {
     pub needed: Size,
     pub calls: Size,
     pub size: Size,
+    /// Allocations from the front and back that did not fit, since the
+    /// buffer was last cleared.
+    pub failed: [Size; 2],
 }
 
 #[derive(Copy, Clone)]
@@ -178,6 +182,7 @@ pub struct StyleItem {
 pub union nk_style_item_data {
     image: Image,
     color: Color,
+    gradient: Gradient,
 }
 #[derive(Copy, Clone)]
 #[repr(C)]
@@ -195,7 +200,43 @@ pub struct Image {
     pub h: Ushort,
     pub region: [Ushort; 4],
 }
+/* two color stops; linear gradients run from `start` to `end`, radial ones
+ * from the center `start` out to the distance of `end` */
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Gradient {
+    pub type_0: GradientType,
+    pub start: Point,
+    pub end: Point,
+    pub start_color: Color,
+    pub end_color: Color,
+}
+pub type GradientType = Uint;
+pub const NK_GRADIENT_RADIAL: GradientType = 1;
+pub const NK_GRADIENT_LINEAR: GradientType = 0;
+/* dash lengths alternate on/off starting with a visible dash; an odd
+ * pattern repeats once more with the roles swapped */
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct StrokeStyle {
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub miter_limit: f32,
+    pub dash_offset: f32,
+    pub dash_count: Uint,
+    pub dash: [f32; 8],
+}
+pub const NK_STROKE_MAX_DASHES: Uint = 8;
+pub type LineCap = Uint;
+pub const NK_LINE_CAP_SQUARE: LineCap = 2;
+pub const NK_LINE_CAP_ROUND: LineCap = 1;
+pub const NK_LINE_CAP_BUTT: LineCap = 0;
+pub type LineJoin = Uint;
+pub const NK_LINE_JOIN_BEVEL: LineJoin = 2;
+pub const NK_LINE_JOIN_ROUND: LineJoin = 1;
+pub const NK_LINE_JOIN_MITER: LineJoin = 0;
 pub type StyleItemType = Uint;
+pub const NK_STYLE_ITEM_GRADIENT: StyleItemType = 2;
 pub const NK_STYLE_ITEM_IMAGE: StyleItemType = 1;
 pub const NK_STYLE_ITEM_COLOR: StyleItemType = 0;
 #[derive(Copy, Clone)]
@@ -319,6 +360,9 @@ pub struct Panel {
     pub header_height: f32,
     pub border: f32,
     pub has_scrolling: Uint,
+    /* buffer offset of the drop shadow, resized once dynamic panels end */
+    pub has_shadow: Uint,
+    pub shadow: Size,
     pub clip: Rect,
     pub menu: MenuState,
     pub row: RowLayout,
@@ -419,6 +463,15 @@ pub struct Context {
     pub memory: Buffer,
     pub clip: Clipboard,
     pub last_widget_state: Flags,
+    pub last_widget_bounds: Rect,
+    pub nav: Navigation,
+    pub clicks: ClickState,
+    pub gestures: GestureConfig,
+    pub preedit: Preedit,
+    pub drag: DragDrop,
+    pub memory_report: MemoryReport,
+    pub oom: OomState,
+    pub redraw: RedrawState,
     pub button_behavior: ButtonBehavior,
     pub stacks: ConfigurationStacks,
     pub delta_time_seconds: f32,
@@ -451,6 +504,189 @@ pub union nk_page_data {
     pan: Panel,
     win: Window,
 }
+/// Keyboard/gamepad focus of the active window.
+///
+/// Focusable widgets are numbered in build order every frame; `focus` is
+/// the number of the focused one and moves by `NK_NAV_NEXT`/`NK_NAV_PREV`.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Navigation {
+    pub window: Hash,
+    pub focus: Int,
+    pub count: Int,
+    pub prev_count: Int,
+    pub actions: NavActions,
+    pub visible: Int,
+    pub bounds: Rect,
+}
+
+/// Multi-click detection for `nk_input_button_timed`.
+///
+/// Presses of the same button count as one multi-click while each follows
+/// the previous within `max_interval` seconds and `max_distance` pixels.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct ClickState {
+    pub max_interval: f64,
+    pub max_distance: f32,
+    pub last_time: [f64; 4],
+    pub last_pos: [Point; 4],
+}
+
+/// Thresholds of the gesture recognizer fed by `nk_input_touch`.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct GestureConfig {
+    /// Longest touch, in seconds, that still counts as a tap.
+    pub tap_time: f32,
+    /// Distance a touch may travel and still be a tap or long press.
+    pub tap_distance: f32,
+    pub long_press_time: f32,
+    /// Two-finger movement in pixels per unit of `scroll_delta`.
+    pub scroll_pixels: f32,
+    /// Lets a single finger drive the pointer and left mouse button.
+    pub emulate_mouse: Int,
+}
+
+/// IME composition shown inline by the active edit widget, see
+/// `nk_input_preedit`.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Preedit {
+    pub text: [Char; 128],
+    pub len: Int,
+    /// Caret inside the composition, in glyphs.
+    pub cursor: Int,
+    /// Clause being converted, in glyphs; empty if `sel_start == sel_end`.
+    pub sel_start: Int,
+    pub sel_end: Int,
+    /// Screen rectangle of the caret of the active edit widget.
+    pub caret: Rect,
+    /// Whether an edit widget reported `caret` since `nk_input_begin`.
+    pub has_caret: Int,
+}
+
+/// Payload in flight between a drag source and a drop target.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct DragDrop {
+    pub active: Int,
+    /* mouse was released: targets deliver this frame, nk_clear ends the drag */
+    pub dropping: Int,
+    pub delivered: Int,
+    /// The `size` payload bytes followed by the zero-terminated type tag
+    /// matched by drop targets.
+    pub payload: Buffer,
+    pub size: Int,
+    /* holds the payload of contexts without an allocator */
+    pub memory: [Uchar; 256],
+    /// Window and bounds of the widget the drag started from.
+    pub source_window: Hash,
+    pub source: Rect,
+    /// Distance the mouse has to travel with the button down to start a drag.
+    pub threshold: f32,
+}
+
+/// Memory regions of a `MemoryReport`, used as flags in its `overflow`.
+pub type MemoryRegion = Uint;
+pub const NK_MEMORY_FONT_ATLAS: MemoryRegion = 64;
+pub const NK_MEMORY_ELEMENTS: MemoryRegion = 32;
+pub const NK_MEMORY_VERTICES: MemoryRegion = 16;
+pub const NK_MEMORY_DRAW_COMMANDS: MemoryRegion = 8;
+pub const NK_MEMORY_TEXT_EDIT: MemoryRegion = 4;
+pub const NK_MEMORY_POOL: MemoryRegion = 2;
+pub const NK_MEMORY_COMMANDS: MemoryRegion = 1;
+
+/// Usage of one memory region at the end of the last frame, in bytes.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct MemoryUsage {
+    pub used: Size,
+    /// Bytes currently reserved; fixed contexts share one block between
+    /// `commands` and `pool`.
+    pub capacity: Size,
+    /// Highest `used` seen since the context was created.
+    pub peak: Size,
+    pub calls: Size,
+    /// Allocations that did not fit.
+    pub failed: Size,
+    /// Bytes requested during the frame, including those that did not fit.
+    pub needed: Size,
+}
+
+/// Per-frame memory report of a context, see `nk_memory_report`.
+///
+/// `nk_clear` samples the command buffer, the pool and the text edit
+/// storage, `nk_convert` the draw list buffers and `nk_memory_report` the
+/// font atlas.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct MemoryReport {
+    pub commands: MemoryUsage,
+    /// Page elements holding windows, tables and panels.
+    pub pool: MemoryUsage,
+    pub text_edit: MemoryUsage,
+    pub draw_commands: MemoryUsage,
+    pub vertices: MemoryUsage,
+    pub elements: MemoryUsage,
+    pub font_atlas: MemoryUsage,
+    pub windows: Uint,
+    /// Windows kept alive although they were not drawn last frame.
+    pub stale_windows: Uint,
+    pub tables: Uint,
+    pub panels: Uint,
+    pub free_elements: Uint,
+    pub total: Size,
+    pub peak_total: Size,
+    /// Regions that ran out of memory during the last sample of each.
+    pub overflow: Flags,
+    /// Regions that ever ran out of memory.
+    pub overflowed: Flags,
+    /// Windows left out last frame under `NK_OOM_TRUNCATE_WINDOWS`.
+    pub truncated_windows: Uint,
+    pub frames: Uint,
+}
+
+/// What a context does when its memory runs out, see `nk_set_oom_policy`.
+pub type OomPolicy = Uint;
+/// Leave out the commands of every window that did not fit completely.
+pub const NK_OOM_TRUNCATE_WINDOWS: OomPolicy = 2;
+/// Move into memory from a secondary allocator.
+pub const NK_OOM_FALLBACK: OomPolicy = 1;
+/// Drop the allocations that do not fit.
+pub const NK_OOM_DROP: OomPolicy = 0;
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct OomState {
+    pub policy: Flags,
+    /* failed front allocations when the current window started */
+    pub window_failed: Size,
+    pub truncated: Uint,
+    /* page elements in the back of the fixed memory, which they keep
+     * living in after its front moved to the fallback allocator */
+    pub fixed_back: Size,
+}
+
+/// Whether the host has to draw another frame, see `nk_redraw_timeout`.
+///
+/// Once enabled with `nk_track_redraws`, `nk_clear` hashes the commands of
+/// the finished frame into `hash` and sets `changed` if they differ from
+/// the frame before. It always moves the earliest deadline requested with
+/// `nk_request_redraw` into `timeout`.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct RedrawState {
+    pub hash: u64,
+    /// Always set while frames are not hashed.
+    pub changed: Int,
+    pub enabled: Int,
+    /// Seconds until a timer of the last frame runs out, negative if none.
+    pub timeout: f32,
+    /* earliest deadline requested during the current frame */
+    pub pending: f32,
+}
+
 #[derive(Copy, Clone)]
 #[repr(C)]
 pub struct Window {
@@ -553,6 +789,8 @@ pub struct Pool {
     pub capacity: Uint,
     pub size: Size,
     pub cap: Size,
+    /// Page elements that could not be allocated.
+    pub failed: Size,
 }
 
 #[derive(Copy, Clone)]
@@ -702,6 +940,27 @@ pub struct Style {
     pub tab: StyleTab,
     pub combo: StyleCombo,
     pub window: StyleWindow,
+    pub nav: StyleNavigation,
+    pub drag: StyleDragDrop,
+}
+
+/// Focus ring drawn around the focused widget.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct StyleNavigation {
+    pub color: Color,
+    pub border: f32,
+    pub rounding: f32,
+    pub padding: Point,
+}
+
+/// Outline of a drop target while a matching payload hovers it.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct StyleDragDrop {
+    pub highlight: Color,
+    pub border: f32,
+    pub rounding: f32,
 }
 
 #[derive(Copy, Clone)]
@@ -737,6 +996,10 @@ pub struct StyleWindow {
     pub contextual_padding: Point,
     pub menu_padding: Point,
     pub tooltip_padding: Point,
+    /* drop shadow behind windows and popups, off while transparent */
+    pub shadow_color: Color,
+    pub shadow_offset: Point,
+    pub shadow_spread: f32,
 }
 
 #[derive(Copy, Clone)]
@@ -1175,6 +1438,16 @@ pub struct Command {
 }
 
 pub type CommandType = Uint;
+pub const NK_COMMAND_RECT_SHADOW: CommandType = 28;
+pub const NK_COMMAND_POLYLINE_STYLED: CommandType = 27;
+pub const NK_COMMAND_POLYGON_STYLED: CommandType = 26;
+pub const NK_COMMAND_CURVE_STYLED: CommandType = 25;
+pub const NK_COMMAND_LINE_STYLED: CommandType = 24;
+pub const NK_COMMAND_POLYGON_GRADIENT: CommandType = 23;
+pub const NK_COMMAND_CIRCLE_GRADIENT: CommandType = 22;
+pub const NK_COMMAND_RECT_GRADIENT: CommandType = 21;
+pub const NK_COMMAND_RECT_CORNERS_FILLED: CommandType = 20;
+pub const NK_COMMAND_RECT_CORNERS: CommandType = 19;
 pub const NK_COMMAND_CUSTOM: CommandType = 18;
 pub const NK_COMMAND_IMAGE: CommandType = 17;
 pub const NK_COMMAND_TEXT: CommandType = 16;
@@ -1407,6 +1680,78 @@ pub struct CommandRectMultiColor {
     pub right: Color,
 }
 
+/* corner radii are ordered top-left, top-right, bottom-right, bottom-left */
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct CommandRectCorners {
+    pub header: Command,
+    pub rounding: [Ushort; 4],
+    pub line_thickness: Ushort,
+    pub x: Short,
+    pub y: Short,
+    pub w: Ushort,
+    pub h: Ushort,
+    pub color: Color,
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct CommandRectCornersFilled {
+    pub header: Command,
+    pub rounding: [Ushort; 4],
+    pub x: Short,
+    pub y: Short,
+    pub w: Ushort,
+    pub h: Ushort,
+    pub color: Color,
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct CommandRectGradient {
+    pub header: Command,
+    pub rounding: [Ushort; 4],
+    pub x: Short,
+    pub y: Short,
+    pub w: Ushort,
+    pub h: Ushort,
+    pub gradient: Gradient,
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct CommandCircleGradient {
+    pub header: Command,
+    pub x: Short,
+    pub y: Short,
+    pub w: Ushort,
+    pub h: Ushort,
+    pub gradient: Gradient,
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct CommandPolygonGradient {
+    pub header: Command,
+    pub gradient: Gradient,
+    pub point_count: Ushort,
+    pub points: [PointI; 1],
+}
+
+/* alpha fades out across a band `feather` wide centered on the outline */
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct CommandRectShadow {
+    pub header: Command,
+    pub rounding: Ushort,
+    pub feather: Ushort,
+    pub x: Short,
+    pub y: Short,
+    pub w: Ushort,
+    pub h: Ushort,
+    pub color: Color,
+}
+
 #[derive(Copy, Clone)]
 #[repr(C)]
 pub struct CommandRectFilled {
@@ -1453,6 +1798,51 @@ pub struct CommandLine {
     pub color: Color,
 }
 
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct CommandLineStyled {
+    pub header: Command,
+    pub line_thickness: Ushort,
+    pub begin: PointI,
+    pub end: PointI,
+    pub color: Color,
+    pub style: StrokeStyle,
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct CommandCurveStyled {
+    pub header: Command,
+    pub line_thickness: Ushort,
+    pub begin: PointI,
+    pub end: PointI,
+    pub ctrl: [PointI; 2],
+    pub color: Color,
+    pub style: StrokeStyle,
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct CommandPolygonStyled {
+    pub header: Command,
+    pub color: Color,
+    pub style: StrokeStyle,
+    pub line_thickness: Ushort,
+    pub point_count: Ushort,
+    pub points: [PointI; 1],
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct CommandPolylineStyled {
+    pub header: Command,
+    pub color: Color,
+    pub style: StrokeStyle,
+    pub line_thickness: Ushort,
+    pub point_count: Ushort,
+    pub points: [PointI; 1],
+}
+
 #[derive(Copy, Clone)]
 #[repr(C)]
 pub struct CommandScissor {
@@ -1499,6 +1889,22 @@ pub const NK_WIDGET_STATE_LEFT: WidgetStates = 64;
 pub const NK_WIDGET_STATE_ACTIVE: WidgetStates = 34;
 /* widget is being hovered */
 pub const NK_WIDGET_STATE_HOVERED: WidgetStates = 18;
+/* widget has the keyboard/gamepad focus */
+pub const NK_WIDGET_STATE_FOCUSED: WidgetStates = 128;
+
+pub type NavActions = Uint;
+/* move the focus to the next focusable widget */
+pub const NK_NAV_NEXT: NavActions = 1;
+/* move the focus to the previous focusable widget */
+pub const NK_NAV_PREV: NavActions = 2;
+/* press the focused widget */
+pub const NK_NAV_ACTIVATE: NavActions = 4;
+/* step the focused slider or property down */
+pub const NK_NAV_DECREASE: NavActions = 8;
+/* step the focused slider or property up */
+pub const NK_NAV_INCREASE: NavActions = 16;
+/* drop the focus */
+pub const NK_NAV_CANCEL: NavActions = 32;
 
 pub const NK_PANEL_SET_NONBLOCK: PanelSet = 240;
 
@@ -1753,6 +2159,8 @@ pub const NK_FONT_ATLAS_ALPHA8: FontAtlasFormat = 0;
 #[repr(C)]
 pub struct FontAtlas {
     pub pixel: *mut Void,
+    /// Size of the allocation behind `pixel`.
+    pub pixel_size: Size,
     pub tex_width: Int,
     pub tex_height: Int,
     pub permanent: Allocator,
@@ -2015,6 +2423,7 @@ pub struct MemoryStatus {
     pub allocated: Size,
     pub needed: Size,
     pub calls: Size,
+    pub failed: Size,
 }
 
 pub type TextEditMode = Uint;
@@ -2063,10 +2472,29 @@ pub unsafe fn nk_init(
         nk_setup(ctx, font);
         nk_buffer_init(&mut (*ctx).memory, alloc, (4i32 * 1024i32) as Size);
         nk_pool_init(&mut (*ctx).pool, alloc, 16i32 as Uint);
+        nk_growing_init(ctx, alloc);
         (*ctx).use_pool = nk_true as Int;
         return 1i32;
     };
 }
+/* keyboard text and drag payloads grow through `alloc`; without one they
+ * have to make do with the bytes inside the context until
+ * `nk_set_oom_policy` adds a fallback */
+unsafe fn nk_growing_init(mut ctx: *mut Context, mut alloc: *const Allocator) -> () {
+    let mut keyboard: *mut Keyboard = &mut (*ctx).input.keyboard;
+    let mut drag: *mut DragDrop = &mut (*ctx).drag;
+    if !alloc.is_null() && (*alloc).alloc.is_some() {
+        nk_buffer_init(&mut (*keyboard).text, alloc, 64i32 as Size);
+        nk_buffer_init(&mut (*drag).payload, alloc, 64i32 as Size);
+    } else {
+        let mut size: Size = mem::size_of_val(&(*keyboard).text_memory) as Size;
+        let mut memory: *mut Void = (*keyboard).text_memory.as_mut_ptr() as *mut Void;
+        nk_buffer_init_fixed(&mut (*keyboard).text, memory, size);
+        size = mem::size_of_val(&(*drag).memory) as Size;
+        memory = (*drag).memory.as_mut_ptr() as *mut Void;
+        nk_buffer_init_fixed(&mut (*drag).payload, memory, size);
+    }
+}
 unsafe fn nk_pool_init(mut pool: *mut Pool, mut alloc: *mut Allocator, mut capacity: Uint) -> () {
     nk_zero(pool as *mut Void, mem::size_of::<Pool>() as Ulong);
     (*pool).alloc = *alloc;
@@ -2176,6 +2604,21 @@ unsafe fn nk_setup(mut ctx: *mut Context, mut font: *const UserFont) -> () {
         nk_zero(ctx as *mut Void, mem::size_of::<Context>() as Ulong);
         nk_style_default(ctx);
         (*ctx).seq = 1i32 as Uint;
+        (*ctx).nav.focus = -1i32;
+        (*ctx).clicks.max_interval = 0.3f64;
+        (*ctx).clicks.max_distance = 6.0f32;
+        (*ctx).gestures.tap_time = 0.3f32;
+        (*ctx).gestures.tap_distance = 10.0f32;
+        (*ctx).gestures.long_press_time = 0.5f32;
+        (*ctx).gestures.scroll_pixels = 20.0f32;
+        (*ctx).gestures.emulate_mouse = nk_true as Int;
+        (*ctx).input.touch.primary = -1i32;
+        (*ctx).input.touch.pinch_scale = 1.0f32;
+        (*ctx).input.touch.pinch_total = 1.0f32;
+        (*ctx).drag.threshold = 4.0f32;
+        (*ctx).redraw.changed = nk_true as Int;
+        (*ctx).redraw.timeout = -1.0f32;
+        (*ctx).redraw.pending = -1.0f32;
         if !font.is_null() {
             (*ctx).style.font = font
         }
@@ -2778,6 +3221,18 @@ pub unsafe fn nk_style_from_table(mut ctx: *mut Context, mut table: *const Color
         (*win).contextual_padding = Point::new(4i32 as f32, 4i32 as f32);
         (*win).menu_padding = Point::new(4i32 as f32, 4i32 as f32);
         (*win).tooltip_padding = Point::new(4i32 as f32, 4i32 as f32);
+        (*win).shadow_color = nk_rgba(0i32, 0i32, 0i32, 0i32);
+        (*win).shadow_offset = Point::new(2i32 as f32, 4i32 as f32);
+        (*win).shadow_spread = 12.0f32;
+        /* focus ring */
+        (*style).nav.color = *table.offset(NK_COLOR_TEXT as Int as isize);
+        (*style).nav.border = 1.0f32;
+        (*style).nav.rounding = 0.0f32;
+        (*style).nav.padding = Point::new(2i32 as f32, 2i32 as f32);
+        /* drop target */
+        (*style).drag.highlight = *table.offset(NK_COLOR_SLIDER_CURSOR_ACTIVE as Int as isize);
+        (*style).drag.border = 2.0f32;
+        (*style).drag.rounding = 0.0f32;
         return;
     };
 }
@@ -3070,10 +3525,10 @@ static mut nk_default_color_style: [Color; 28] = unsafe {
     ]
 };
 unsafe fn nk_mfree(mut unused: Handle, mut ptr: *mut Void) -> () {
-    libc::free(ptr);
+    free_with(&RustAlloc, ptr);
 }
 unsafe fn nk_malloc(mut unused: Handle, mut old: *mut Void, mut size: Size) -> *mut Void {
-    return libc::malloc(size as _);
+    return alloc_with(&RustAlloc, size);
 }
 
 pub unsafe fn nk_init_fixed(
@@ -3087,6 +3542,7 @@ pub unsafe fn nk_init_fixed(
     } else {
         nk_setup(ctx, font);
         nk_buffer_init_fixed(&mut (*ctx).memory, memory, size);
+        nk_growing_init(ctx, 0 as *const Allocator);
         (*ctx).use_pool = nk_false as Int;
         return 1i32;
     };
@@ -3105,6 +3561,51 @@ pub unsafe fn nk_buffer_init_fixed(mut b: *mut Buffer, mut m: *mut Void, mut siz
     };
 }
 
+/// Lets the fixed buffer `b` move into memory from `a` once its front is
+/// full instead of failing. The buffer is dynamic from then on; the fixed
+/// memory is not freed and whatever was allocated from its back stays valid.
+pub unsafe fn nk_buffer_set_fallback(mut b: *mut Buffer, mut a: *const Allocator) -> () {
+    if b.is_null() || a.is_null() || (*b).type_0 != NK_BUFFER_FIXED {
+        return;
+    } else {
+        (*b).pool = *a;
+        return;
+    };
+}
+
+/// Sets what `ctx` does when its memory runs out, a combination of
+/// `NK_OOM_FALLBACK` and `NK_OOM_TRUNCATE_WINDOWS`. `fallback` is used by
+/// `NK_OOM_FALLBACK` for commands and windows that do not fit into the
+/// fixed memory anymore and has to outlive the context.
+pub unsafe fn nk_set_oom_policy(
+    mut ctx: *mut Context,
+    mut policy: Flags,
+    mut fallback: *const Allocator,
+) -> () {
+    if ctx.is_null() {
+        return;
+    } else {
+        (*ctx).oom.policy = policy;
+        if 0 == policy & NK_OOM_FALLBACK || fallback.is_null() {
+            return;
+        } else {
+            nk_buffer_set_fallback(&mut (*ctx).memory, fallback);
+            nk_buffer_set_fallback(&mut (*ctx).input.keyboard.text, fallback);
+            nk_buffer_set_fallback(&mut (*ctx).drag.payload, fallback);
+            if 0 == (*ctx).use_pool {
+                /* page elements are pointed to, so they never move with the
+                 * buffer but come from a pool once the back is full */
+                if (*ctx).pool.alloc.alloc.is_none() {
+                    nk_pool_init(&mut (*ctx).pool, fallback as *mut Allocator, 16i32 as Uint);
+                }
+            } else if (*ctx).pool.type_0 == NK_BUFFER_FIXED {
+                (*ctx).pool.alloc = *fallback
+            }
+            return;
+        }
+    };
+}
+
 pub unsafe fn nk_init_custom(
     mut ctx: *mut Context,
     mut cmds: *mut Buffer,
@@ -3117,6 +3618,11 @@ pub unsafe fn nk_init_custom(
     } else {
         nk_setup(ctx, font);
         (*ctx).memory = *cmds;
+        if (*cmds).type_0 == NK_BUFFER_FIXED {
+            nk_growing_init(ctx, 0 as *const Allocator);
+        } else {
+            nk_growing_init(ctx, &(*cmds).pool);
+        }
         if (*pool).type_0 as Uint == NK_BUFFER_FIXED as Int as Uint {
             /* take memory from buffer and alloc fixed pool */
             nk_pool_init_fixed(&mut (*ctx).pool, (*pool).memory.ptr, (*pool).memory.size);
@@ -3138,6 +3644,8 @@ unsafe fn nk_pool_init_fixed(mut pool: *mut Pool, mut memory: *mut Void, mut siz
             .wrapping_div(mem::size_of::<PageElement>() as Ulong)
             as Uint;
         (*pool).pages = memory as *mut Page;
+        (*(*pool).pages).next = 0 as *mut Page;
+        (*(*pool).pages).size = 0i32 as Uint;
         (*pool).type_0 = NK_BUFFER_FIXED;
         (*pool).size = size;
         return;
@@ -3150,6 +3658,32 @@ pub unsafe fn nk_clear(mut ctx: *mut Context) -> () {
     if ctx.is_null() {
         return;
     } else {
+        /* sample memory usage of the frame before it is thrown away */
+        let mut report: *mut MemoryReport = &mut (*ctx).memory_report;
+        (*report).frames = (*report).frames.wrapping_add(1);
+        (*report).truncated_windows = (*ctx).oom.truncated;
+        (*ctx).oom.truncated = 0;
+        /* for fixed contexts `needed` covers the windows in the back too */
+        nk_memory_track(
+            report,
+            NK_MEMORY_COMMANDS,
+            (*ctx).memory.allocated,
+            (*ctx).memory.memory.size,
+            (*ctx).memory.calls,
+            (*ctx).memory.failed[NK_BUFFER_FRONT as usize],
+            (*ctx).memory.needed,
+        );
+        (*ctx).memory.failed[NK_BUFFER_FRONT as usize] = 0;
+        nk_memory_track_buffer(report, NK_MEMORY_TEXT_EDIT, &mut (*ctx).text_edit.string.buffer);
+        (*ctx).text_edit.string.buffer.failed = [0; 2];
+        /* compare with the last frame and hand the timers to the host */
+        if 0 != (*ctx).redraw.enabled {
+            let mut hash: u64 = (*ctx).frame_hash();
+            (*ctx).redraw.changed = (hash != (*ctx).redraw.hash) as Int;
+            (*ctx).redraw.hash = hash
+        }
+        (*ctx).redraw.timeout = (*ctx).redraw.pending;
+        (*ctx).redraw.pending = -1.0f32;
         if 0 != (*ctx).use_pool {
             nk_buffer_clear(&mut (*ctx).memory);
         } else {
@@ -3158,6 +3692,13 @@ pub unsafe fn nk_clear(mut ctx: *mut Context) -> () {
         (*ctx).build = 0i32;
         (*ctx).memory.calls = 0i32 as Size;
         (*ctx).last_widget_state = 0i32 as Flags;
+        (*ctx).nav.prev_count = (*ctx).nav.count;
+        (*ctx).nav.count = 0i32;
+        if 0 != (*ctx).drag.dropping {
+            (*ctx).drag.active = nk_false as Int;
+            (*ctx).drag.dropping = nk_false as Int;
+            (*ctx).drag.delivered = nk_false as Int
+        }
         (*ctx).style.cursor_active = (*ctx).style.cursors[NK_CURSOR_ARROW as Int as usize];
         nk_memset(
             &mut (*ctx).overlay as *mut CommandBuffer as *mut Void,
@@ -3220,10 +3761,244 @@ pub unsafe fn nk_clear(mut ctx: *mut Context) -> () {
                 }
             }
         }
+        nk_memory_sample_pool(ctx);
         (*ctx).seq = (*ctx).seq.wrapping_add(1);
         return;
     };
 }
+
+/// Makes `nk_clear` hash every finished frame so that `nk_redraw_timeout`
+/// can tell unchanged frames apart. Off by default, since hashing walks
+/// the whole command queue.
+pub unsafe fn nk_track_redraws(mut ctx: *mut Context, mut enable: Int) -> () {
+    if ctx.is_null() {
+        return;
+    } else {
+        (*ctx).redraw.enabled = (0 != enable) as Int;
+        /* the first hashed frame has nothing to compare with */
+        (*ctx).redraw.changed = nk_true as Int;
+        (*ctx).redraw.hash = 0;
+        return;
+    };
+}
+
+/// Asks for another frame within `seconds`, e.g. when an animation or a
+/// timer changes the UI without new input. Call while building a frame;
+/// the earliest request shows up in `nk_redraw_timeout` after `nk_clear`.
+pub unsafe fn nk_request_redraw(mut ctx: *mut Context, mut seconds: f32) -> () {
+    if ctx.is_null() {
+        return;
+    } else {
+        seconds = if seconds > 0.0f32 { seconds } else { 0.0f32 };
+        if (*ctx).redraw.pending < 0.0f32 || seconds < (*ctx).redraw.pending {
+            (*ctx).redraw.pending = seconds
+        }
+        return;
+    };
+}
+
+/// Seconds the host may sleep after `nk_clear` before it has to draw the
+/// next frame even without input: 0 if the last frame changed, since
+/// state changes often show one frame late, the earliest timer otherwise,
+/// and a negative value if nothing but input can change the UI. Always 0
+/// unless `nk_track_redraws` is on.
+pub unsafe fn nk_redraw_timeout(mut ctx: *const Context) -> f32 {
+    if ctx.is_null() {
+        return 0.0f32;
+    } else if 0 != (*ctx).redraw.changed {
+        return 0.0f32;
+    } else {
+        return (*ctx).redraw.timeout;
+    };
+}
+
+/// Fills `report` with the memory usage of the last frame and the peaks
+/// since `ctx` was created. `atlas` may be null; otherwise its current
+/// allocations are added as `font_atlas`.
+pub unsafe fn nk_memory_report(
+    mut ctx: *mut Context,
+    mut atlas: *const FontAtlas,
+    mut report: *mut MemoryReport,
+) -> () {
+    if ctx.is_null() || report.is_null() {
+        return;
+    } else {
+        if !atlas.is_null() {
+            let mut used: Size = (*atlas).pixel_size;
+            let mut blocks: Size = if (*atlas).pixel.is_null() { 0 } else { 1 };
+            if !(*atlas).glyphs.is_null() {
+                used += (*atlas).glyph_count as Size * mem::size_of::<FontGlyph>() as Size;
+                blocks += 1
+            }
+            let mut font: *const Font = (*atlas).fonts;
+            while !font.is_null() {
+                used += mem::size_of::<Font>() as Size;
+                blocks += 1;
+                font = (*font).next
+            }
+            /* configs form a list of rings, one ring per font file */
+            let mut cfg: *const FontConfig = (*atlas).config;
+            while !cfg.is_null() {
+                let mut i: *const FontConfig = cfg;
+                loop {
+                    used += mem::size_of::<FontConfig>() as Size;
+                    blocks += 1;
+                    if !(*i).ttf_blob.is_null() {
+                        used += (*i).ttf_size;
+                        blocks += 1
+                    }
+                    i = (*i).n;
+                    if i.is_null() || i == cfg {
+                        break;
+                    }
+                }
+                cfg = (*cfg).next
+            }
+            nk_memory_track(
+                &mut (*ctx).memory_report,
+                NK_MEMORY_FONT_ATLAS,
+                used,
+                used,
+                blocks,
+                0,
+                used,
+            );
+        }
+        *report = (*ctx).memory_report;
+        return;
+    };
+}
+unsafe fn nk_memory_track(
+    mut report: *mut MemoryReport,
+    mut region: MemoryRegion,
+    mut used: Size,
+    mut capacity: Size,
+    mut calls: Size,
+    mut failed: Size,
+    mut needed: Size,
+) -> () {
+    let mut u: *mut MemoryUsage = match region {
+        NK_MEMORY_COMMANDS => &mut (*report).commands,
+        NK_MEMORY_POOL => &mut (*report).pool,
+        NK_MEMORY_TEXT_EDIT => &mut (*report).text_edit,
+        NK_MEMORY_DRAW_COMMANDS => &mut (*report).draw_commands,
+        NK_MEMORY_VERTICES => &mut (*report).vertices,
+        NK_MEMORY_ELEMENTS => &mut (*report).elements,
+        _ => &mut (*report).font_atlas,
+    };
+    (*u).used = used;
+    (*u).capacity = capacity;
+    (*u).calls = calls;
+    (*u).failed = failed;
+    (*u).needed = needed;
+    if used > (*u).peak {
+        (*u).peak = used
+    }
+    if 0 != failed {
+        (*report).overflow |= region;
+        (*report).overflowed |= region
+    } else {
+        (*report).overflow &= !region
+    }
+    (*report).total = (*report).commands.used
+        + (*report).pool.used
+        + (*report).text_edit.used
+        + (*report).draw_commands.used
+        + (*report).vertices.used
+        + (*report).elements.used
+        + (*report).font_atlas.used;
+    if (*report).total > (*report).peak_total {
+        (*report).peak_total = (*report).total
+    };
+}
+unsafe fn nk_memory_track_buffer(
+    mut report: *mut MemoryReport,
+    mut region: MemoryRegion,
+    mut b: *const Buffer,
+) -> () {
+    /* front allocations plus whatever was taken from the back */
+    nk_memory_track(
+        report,
+        region,
+        (*b).allocated + (*b).memory.size.wrapping_sub((*b).size),
+        (*b).memory.size,
+        (*b).calls,
+        (*b).failed[0] + (*b).failed[1],
+        (*b).needed,
+    );
+}
+unsafe fn nk_memory_count_window(
+    mut win: *const Window,
+    mut seq: Uint,
+    mut report: *mut MemoryReport,
+) -> () {
+    (*report).windows += 1;
+    if (*win).seq != seq {
+        (*report).stale_windows += 1
+    }
+    let mut it: *const Table = (*win).tables;
+    while !it.is_null() {
+        (*report).tables += 1;
+        it = (*it).next
+    }
+    if !(*win).popup.win.is_null() {
+        nk_memory_count_window((*win).popup.win, seq, report);
+    };
+}
+unsafe fn nk_memory_sample_pool(mut ctx: *mut Context) -> () {
+    let mut report: *mut MemoryReport = &mut (*ctx).memory_report;
+    let elem_size: Size = mem::size_of::<PageElement>() as Size;
+    (*report).windows = 0;
+    (*report).stale_windows = 0;
+    (*report).tables = 0;
+    (*report).free_elements = 0;
+    let mut iter: *const Window = (*ctx).begin;
+    while !iter.is_null() {
+        nk_memory_count_window(iter, (*ctx).seq, report);
+        iter = (*iter).next
+    }
+    let mut elem: *const PageElement = (*ctx).freelist;
+    while !elem.is_null() {
+        (*report).free_elements += 1;
+        elem = (*elem).next
+    }
+    let mut count: Size = 0;
+    let mut capacity: Size = 0;
+    let mut failed: Size = 0;
+    if 0 == (*ctx).use_pool {
+        /* page elements live in the back of the fixed command memory */
+        count = (*ctx).oom.fixed_back / elem_size;
+        capacity = (*ctx).memory.memory.size;
+        failed = (*ctx).memory.failed[NK_BUFFER_BACK as usize];
+        (*ctx).memory.failed[NK_BUFFER_BACK as usize] = 0
+    }
+    /* pool pages, including the fallback ones of a fixed context */
+    let mut page: *const Page = (*ctx).pool.pages;
+    while !page.is_null() {
+        count += (*page).size as Size;
+        if (*page).next.is_null() && (*ctx).pool.type_0 == NK_BUFFER_FIXED {
+            capacity += (*ctx).pool.size
+        } else {
+            capacity += (*ctx).pool.capacity as Size * elem_size
+        }
+        page = (*page).next
+    }
+    failed += (*ctx).pool.failed;
+    (*ctx).pool.failed = 0;
+    let mut live: Size = count.saturating_sub((*report).free_elements as Size);
+    (*report).panels = live
+        .saturating_sub((*report).windows as Size)
+        .saturating_sub((*report).tables as Size) as Uint;
+    nk_memory_track(
+        report,
+        NK_MEMORY_POOL,
+        live * elem_size,
+        capacity,
+        count,
+        failed,
+        (count + failed) * elem_size,
+    );
+}
 unsafe fn nk_free_window(mut ctx: *mut Context, mut win: *mut Window) -> () {
     /* unlink windows from list */
     let mut it: *mut Table = (*win).tables;
@@ -3244,20 +4019,8 @@ unsafe fn nk_free_window(mut ctx: *mut Context, mut win: *mut Window) -> () {
         it = n
     }
     /* link windows into freelist */
-    let mut pd: *mut nk_page_data = ((if 0 != 1i32 {
-        win
-    } else {
-        &mut (*(0 as *mut nk_page_data)).win as *mut Window
-    }) as *mut Char)
-        .offset(-(&mut (*(0 as *mut nk_page_data)).win as *mut Window as Ptr as isize))
-        as *mut Void as *mut nk_page_data;
-    let mut pe: *mut PageElement = ((if 0 != 1i32 {
-        pd
-    } else {
-        &mut (*(0 as *mut PageElement)).data as *mut nk_page_data
-    }) as *mut Char)
-        .offset(-(&mut (*(0 as *mut PageElement)).data as *mut nk_page_data as Ptr as isize))
-        as *mut Void as *mut PageElement;
+    /* the union member `win` and the `data` of its element both sit at offset 0 */
+    let mut pe: *mut PageElement = win as *mut PageElement;
     nk_free_page_element(ctx, pe);
 }
 unsafe fn nk_free_page_element(mut ctx: *mut Context, mut elem: *mut PageElement) -> () {
@@ -3293,20 +4056,8 @@ unsafe fn nk_link_page_element_into_freelist(
     };
 }
 unsafe fn nk_free_table(mut ctx: *mut Context, mut tbl: *mut Table) -> () {
-    let mut pd: *mut nk_page_data = ((if 0 != 1i32 {
-        tbl
-    } else {
-        &mut (*(0 as *mut nk_page_data)).tbl as *mut Table
-    }) as *mut Char)
-        .offset(-(&mut (*(0 as *mut nk_page_data)).tbl as *mut Table as Ptr as isize))
-        as *mut Void as *mut nk_page_data;
-    let mut pe: *mut PageElement = ((if 0 != 1i32 {
-        pd
-    } else {
-        &mut (*(0 as *mut PageElement)).data as *mut nk_page_data
-    }) as *mut Char)
-        .offset(-(&mut (*(0 as *mut PageElement)).data as *mut nk_page_data as Ptr as isize))
-        as *mut Void as *mut PageElement;
+    /* the union member `tbl` and the `data` of its element both sit at offset 0 */
+    let mut pe: *mut PageElement = tbl as *mut PageElement;
     nk_free_page_element(ctx, pe);
 }
 unsafe fn nk_remove_table(mut win: *mut Window, mut tbl: *mut Table) -> () {
@@ -3399,6 +4150,7 @@ pub unsafe fn nk_buffer_clear(mut b: *mut Buffer) -> () {
         (*b).size = (*b).memory.size;
         (*b).calls = 0i32 as Size;
         (*b).needed = 0i32 as Size;
+        (*b).failed = [0; 2];
         return;
     };
 }
@@ -3408,7 +4160,9 @@ pub unsafe fn nk_free(mut ctx: *mut Context) -> () {
         return;
     } else {
         nk_buffer_free(&mut (*ctx).memory);
-        if 0 != (*ctx).use_pool {
+        nk_buffer_free(&mut (*ctx).input.keyboard.text);
+        nk_buffer_free(&mut (*ctx).drag.payload);
+        if 0 != (*ctx).use_pool || !(*ctx).pool.pages.is_null() {
             nk_pool_free(&mut (*ctx).pool);
         }
         nk_zero(
@@ -3438,11 +4192,17 @@ unsafe fn nk_pool_free(mut pool: *mut Pool) -> () {
     let mut iter: *mut Page = (*pool).pages;
     if pool.is_null() {
         return;
-    } else if (*pool).type_0 as Uint == NK_BUFFER_FIXED as Int as Uint {
+    } else if (*pool).type_0 as Uint == NK_BUFFER_FIXED as Int as Uint
+        && (*pool).alloc.free.is_none()
+    {
         return;
     } else {
         while !iter.is_null() {
             let mut next: *mut Page = (*iter).next;
+            /* the last page of a fixed pool is the caller's memory */
+            if next.is_null() && (*pool).type_0 as Uint == NK_BUFFER_FIXED as Int as Uint {
+                break;
+            }
             (*pool).alloc.free.expect("non-null function pointer")(
                 (*pool).alloc.userdata,
                 iter as *mut Void,
@@ -3490,6 +4250,7 @@ pub unsafe fn nk_input_begin(mut ctx: *mut Context) -> () {
             (*in_0).mouse.buttons[i as usize].clicked = 0i32 as Uint;
             i += 1
         }
+        nk_buffer_clear(&mut (*in_0).keyboard.text);
         (*in_0).keyboard.text_len = 0i32;
         (*in_0).mouse.scroll_delta = Point::new(0i32 as f32, 0i32 as f32);
         (*in_0).mouse.prev.x = (*in_0).mouse.pos.x;
@@ -3501,6 +4262,27 @@ pub unsafe fn nk_input_begin(mut ctx: *mut Context) -> () {
             (*in_0).keyboard.keys[i as usize].clicked = 0i32 as Uint;
             i += 1
         }
+        i = 0i32;
+        while i < NK_PKEY_MAX as Int {
+            (*in_0).keyboard.physical[i as usize].clicked = 0i32 as Uint;
+            i += 1
+        }
+        i = 0i32;
+        while i < NK_TOUCH_MAX as Int {
+            let mut point: *mut TouchPoint = &mut (*in_0).touch.points[i as usize] as *mut TouchPoint;
+            if (*point).phase >= NK_TOUCH_ENDED {
+                (*point).phase = NK_TOUCH_NONE
+            } else if (*point).phase != NK_TOUCH_NONE {
+                (*point).phase = NK_TOUCH_STATIONARY
+            }
+            (*point).prev = (*point).pos;
+            i += 1
+        }
+        (*in_0).touch.gestures = NK_GESTURE_NONE;
+        (*in_0).touch.pan = Point::new(0i32 as f32, 0i32 as f32);
+        (*in_0).touch.pinch_scale = 1.0f32;
+        (*ctx).preedit.has_caret = nk_false as Int;
+        (*ctx).nav.actions = 0i32 as NavActions;
         return;
     };
 }
@@ -3534,8 +4316,181 @@ pub unsafe fn nk_input_key(mut ctx: *mut Context, mut key: Keys, mut down: Int)
     };
 }
 
-pub unsafe fn nk_input_button(
-    mut ctx: *mut Context,
+/* logical keys that follow a physical key one to one */
+fn nk_physical_key_logical(mut key: PhysicalKey) -> Keys {
+    match key {
+        NK_PKEY_LEFT_SHIFT | NK_PKEY_RIGHT_SHIFT => NK_KEY_SHIFT,
+        NK_PKEY_LEFT_CTRL | NK_PKEY_RIGHT_CTRL => NK_KEY_CTRL,
+        NK_PKEY_DELETE => NK_KEY_DEL,
+        NK_PKEY_ENTER => NK_KEY_ENTER,
+        NK_PKEY_TAB => NK_KEY_TAB,
+        NK_PKEY_BACKSPACE => NK_KEY_BACKSPACE,
+        NK_PKEY_UP => NK_KEY_UP,
+        NK_PKEY_DOWN => NK_KEY_DOWN,
+        NK_PKEY_LEFT => NK_KEY_LEFT,
+        NK_PKEY_RIGHT => NK_KEY_RIGHT,
+        _ => NK_KEY_NONE,
+    }
+}
+
+/* modifier a physical key contributes to `Keyboard::modifiers` */
+pub fn nk_physical_key_modifier(mut key: PhysicalKey) -> Modifiers {
+    match key {
+        NK_PKEY_LEFT_SHIFT | NK_PKEY_RIGHT_SHIFT => NK_MOD_SHIFT,
+        NK_PKEY_LEFT_CTRL | NK_PKEY_RIGHT_CTRL => NK_MOD_CTRL,
+        NK_PKEY_LEFT_ALT | NK_PKEY_RIGHT_ALT => NK_MOD_ALT,
+        NK_PKEY_LEFT_SUPER | NK_PKEY_RIGHT_SUPER => NK_MOD_SUPER,
+        _ => NK_MOD_NONE,
+    }
+}
+
+/// Updates a physical key together with `Keyboard::modifiers` and the
+/// logical key it stands for (shift, ctrl, enter, tab, backspace, delete
+/// and the arrows), so widgets react without separate `nk_input_key` calls.
+pub unsafe fn nk_input_physical_key(
+    mut ctx: *mut Context,
+    mut key: PhysicalKey,
+    mut down: Int,
+) -> () {
+    let mut in_0: *mut Input = 0 as *mut Input;
+    if ctx.is_null() || key == NK_PKEY_NONE || key >= NK_PKEY_MAX {
+        return;
+    } else {
+        in_0 = &mut (*ctx).input as *mut Input;
+        down = (0 != down) as Int;
+        let k = &mut (*in_0).keyboard.physical[key as usize];
+        if k.down != down {
+            k.clicked = k.clicked.wrapping_add(1)
+        }
+        k.down = down;
+        let modifier = nk_physical_key_modifier(key);
+        if modifier != NK_MOD_NONE {
+            let mut held = NK_MOD_NONE;
+            let mut i = 0;
+            while i < NK_PKEY_MAX {
+                if 0 != (*in_0).keyboard.physical[i as usize].down {
+                    held |= nk_physical_key_modifier(i)
+                }
+                i += 1
+            }
+            (*in_0).keyboard.modifiers = (*in_0).keyboard.modifiers & !modifier | held & modifier
+        }
+        let logical = nk_physical_key_logical(key);
+        if logical != NK_KEY_NONE {
+            if logical == NK_KEY_SHIFT || logical == NK_KEY_CTRL {
+                down = (0 != (*in_0).keyboard.modifiers & modifier) as Int
+            }
+            nk_input_key(ctx, logical, down);
+        }
+        return;
+    };
+}
+
+/// Overrides the held modifiers, for backends that report them per event.
+pub unsafe fn nk_input_modifiers(mut ctx: *mut Context, mut modifiers: Modifiers) -> () {
+    if ctx.is_null() {
+        return;
+    } else {
+        (*ctx).input.keyboard.modifiers = modifiers;
+        nk_input_key(ctx, NK_KEY_SHIFT, (0 != modifiers & NK_MOD_SHIFT) as Int);
+        nk_input_key(ctx, NK_KEY_CTRL, (0 != modifiers & NK_MOD_CTRL) as Int);
+        return;
+    };
+}
+
+/// Requests `NK_NAV_*` focus actions, e.g. from a gamepad d-pad.
+pub unsafe fn nk_input_nav(mut ctx: *mut Context, mut actions: NavActions) -> () {
+    if ctx.is_null() {
+        return;
+    } else {
+        (*ctx).nav.actions |= actions;
+        return;
+    };
+}
+
+/* translates navigation keys into actions */
+unsafe fn nk_nav_translate(mut ctx: *mut Context) -> () {
+    let mut in_0: *const Input = &(*ctx).input;
+    let mut nav: *mut Navigation = &mut (*ctx).nav;
+    let mut active: *mut Window = (*ctx).active;
+    /* text input keeps tab, arrows, enter and space for itself */
+    let mut editing = !active.is_null()
+        && (0 != (*active).edit.active || 0 != (*active).property.active);
+    if !editing {
+        if 0 != nk_input_is_key_pressed(in_0, NK_KEY_TAB) {
+            (*nav).actions |= if 0 != (*in_0).keyboard.modifiers & NK_MOD_SHIFT
+                || 0 != nk_input_is_key_down(in_0, NK_KEY_SHIFT)
+            {
+                NK_NAV_PREV
+            } else {
+                NK_NAV_NEXT
+            }
+        }
+        if 0 != nk_input_is_key_pressed(in_0, NK_KEY_UP) {
+            (*nav).actions |= NK_NAV_PREV
+        }
+        if 0 != nk_input_is_key_pressed(in_0, NK_KEY_DOWN) {
+            (*nav).actions |= NK_NAV_NEXT
+        }
+        if 0 != nk_input_is_key_pressed(in_0, NK_KEY_LEFT) {
+            (*nav).actions |= NK_NAV_DECREASE
+        }
+        if 0 != nk_input_is_key_pressed(in_0, NK_KEY_RIGHT) {
+            (*nav).actions |= NK_NAV_INCREASE
+        }
+        if 0 != nk_input_is_key_pressed(in_0, NK_KEY_ENTER)
+            || 0 != nk_input_is_physical_key_pressed(in_0, NK_PKEY_SPACE)
+        {
+            (*nav).actions |= NK_NAV_ACTIVATE
+        }
+        if 0 != nk_input_is_physical_key_pressed(in_0, NK_PKEY_ESCAPE) {
+            (*nav).actions |= NK_NAV_CANCEL
+        }
+    }
+}
+
+/* moves the focus for the coming frame */
+unsafe fn nk_nav_update(mut ctx: *mut Context) -> () {
+    let mut in_0: *const Input = &(*ctx).input;
+    let mut nav: *mut Navigation = &mut (*ctx).nav;
+    let mut active: *mut Window = (*ctx).active;
+    let mut name: Hash = if active.is_null() { 0 } else { (*active).name };
+    let mut i: Int = 0;
+    if (*nav).window != name {
+        (*nav).window = name;
+        (*nav).focus = -1i32
+    }
+    /* the ring only shows while the keyboard or gamepad is driving */
+    while i < NK_BUTTON_MAX as Int {
+        if 0 != nk_input_is_mouse_pressed(in_0, i as Buttons) {
+            (*nav).visible = 0i32
+        }
+        i += 1
+    }
+    let mut count: Int = (*nav).prev_count;
+    if (*nav).focus >= count {
+        (*nav).focus = count - 1i32
+    }
+    if 0 != (*nav).actions & NK_NAV_CANCEL {
+        (*nav).focus = -1i32;
+        (*nav).visible = 0i32
+    } else if count > 0i32 {
+        if 0 != (*nav).actions & NK_NAV_NEXT {
+            (*nav).focus = ((*nav).focus + 1i32) % count;
+            (*nav).visible = 1i32
+        } else if 0 != (*nav).actions & NK_NAV_PREV {
+            (*nav).focus = if (*nav).focus <= 0i32 {
+                count - 1i32
+            } else {
+                (*nav).focus - 1i32
+            };
+            (*nav).visible = 1i32
+        }
+    }
+}
+
+pub unsafe fn nk_input_button(
+    mut ctx: *mut Context,
     mut id: Buttons,
     mut x: Int,
     mut y: Int,
@@ -3560,6 +4515,55 @@ pub unsafe fn nk_input_button(
     };
 }
 
+/// Like `nk_input_button`, with `time` in seconds from any monotonic clock.
+///
+/// Counts consecutive presses into `Mouse::click_count` and mirrors the
+/// second press of the left button on `NK_BUTTON_DOUBLE`.
+pub unsafe fn nk_input_button_timed(
+    mut ctx: *mut Context,
+    mut id: Buttons,
+    mut x: Int,
+    mut y: Int,
+    mut down: Int,
+    mut time: f64,
+) -> () {
+    let mut clicks: *mut ClickState = 0 as *mut ClickState;
+    let mut count: *mut Uint = 0 as *mut Uint;
+    if ctx.is_null() || id >= NK_BUTTON_MAX {
+        return;
+    } else {
+        clicks = &mut (*ctx).clicks as *mut ClickState;
+        count = &mut (*ctx).input.mouse.click_count[id as usize] as *mut Uint;
+        if 0 != down && 0 == (*ctx).input.mouse.buttons[id as usize].down {
+            let mut pos: Point = Point::new(x as f32, y as f32);
+            let mut last: Point = (*clicks).last_pos[id as usize];
+            let mut dx: f32 = pos.x - last.x;
+            let mut dy: f32 = pos.y - last.y;
+            let mut dt: f64 = time - (*clicks).last_time[id as usize];
+            if 0 != *count
+                && dt >= 0.0f64
+                && dt <= (*clicks).max_interval
+                && dx * dx + dy * dy <= (*clicks).max_distance * (*clicks).max_distance
+            {
+                *count = (*count).wrapping_add(1)
+            } else {
+                *count = 1i32 as Uint
+            }
+            (*clicks).last_time[id as usize] = time;
+            (*clicks).last_pos[id as usize] = pos
+        }
+        nk_input_button(ctx, id, x, y, down);
+        if id == NK_BUTTON_LEFT {
+            if 0 == down {
+                nk_input_button(ctx, NK_BUTTON_DOUBLE, x, y, 0i32);
+            } else if *count == 2i32 as Uint {
+                nk_input_button(ctx, NK_BUTTON_DOUBLE, x, y, 1i32);
+            }
+        }
+        return;
+    };
+}
+
 pub unsafe fn nk_input_scroll(mut ctx: *mut Context, mut val: Point) -> () {
     if ctx.is_null() {
         return;
@@ -3590,13 +4594,14 @@ pub unsafe fn nk_input_glyph(mut ctx: *mut Context, mut glyph: *const Char) -> (
     } else {
         in_0 = &mut (*ctx).input as *mut Input;
         len = nk_utf_decode(glyph, &mut unicode, 4i32);
-        if 0 != len && (*in_0).keyboard.text_len + len < 16i32 {
-            nk_utf_encode(
-                unicode,
-                &mut (*in_0).keyboard.text[(*in_0).keyboard.text_len as usize],
-                16i32 - (*in_0).keyboard.text_len,
-            );
-            (*in_0).keyboard.text_len += len
+        if 0 != len {
+            /* the text buffer grows, unless it is fixed and has no fallback */
+            let mut memory: *mut Void =
+                nk_buffer_alloc(&mut (*in_0).keyboard.text, NK_BUFFER_FRONT, len as Size, 1);
+            if !memory.is_null() {
+                nk_utf_encode(unicode, memory as *mut Char, len);
+                (*in_0).keyboard.text_len += len
+            }
         }
         return;
     };
@@ -3744,6 +4749,20 @@ pub unsafe fn nk_input_unicode(mut ctx: *mut Context, mut unicode: Rune) -> () {
 }
 
 pub unsafe fn nk_input_end(mut ctx: *mut Context) -> () {
+    if ctx.is_null() {
+        return;
+    } else {
+        nk_touch_update(ctx);
+        nk_nav_translate(ctx);
+        nk_input_end_recorded(ctx);
+        return;
+    };
+}
+
+/// `nk_input_end` for input restored from a recording, which already holds
+/// the touch gestures and navigation actions of the recorded frame: these
+/// are not recognized a second time.
+pub unsafe fn nk_input_end_recorded(mut ctx: *mut Context) -> () {
     let mut in_0: *mut Input = 0 as *mut Input;
     if ctx.is_null() {
         return;
@@ -3757,10 +4776,300 @@ pub unsafe fn nk_input_end(mut ctx: *mut Context) -> () {
             (*in_0).mouse.ungrab = 0i32 as Uchar;
             (*in_0).mouse.grab = 0i32 as Uchar
         }
+        if 0 != (*ctx).drag.active && 0 == (*in_0).mouse.buttons[NK_BUTTON_LEFT as usize].down {
+            (*ctx).drag.dropping = nk_true as Int
+        }
+        nk_nav_update(ctx);
+        return;
+    };
+}
+
+/// Reports touch `id` at `x`/`y`: down while the finger is on the screen,
+/// released once with `down` 0. Gestures are recognized in `nk_input_end`.
+pub unsafe fn nk_input_touch(mut ctx: *mut Context, mut id: u64, mut x: Int, mut y: Int, mut down: Int) -> () {
+    let mut touch: *mut Touch = 0 as *mut Touch;
+    let mut point: *mut TouchPoint = 0 as *mut TouchPoint;
+    let mut slot: Int = 0;
+    let mut pos: Point = Point::new(x as f32, y as f32);
+    if ctx.is_null() {
+        return;
+    } else {
+        touch = &mut (*ctx).input.touch as *mut Touch;
+        slot = nk_touch_slot(touch, id);
+        if 0 == down {
+            if slot < 0 {
+                return;
+            } else {
+                point = &mut (*touch).points[slot as usize] as *mut TouchPoint;
+                (*point).pos = pos;
+                (*point).phase = NK_TOUCH_ENDED;
+                if slot == (*touch).primary {
+                    (*touch).primary = -1i32;
+                    if 0 != (*ctx).gestures.emulate_mouse {
+                        nk_input_motion(ctx, x, y);
+                        nk_input_button(ctx, NK_BUTTON_LEFT, x, y, 0i32);
+                    }
+                }
+                return;
+            }
+        } else {
+            if slot < 0 {
+                /* new touch: take a free slot, ignore it if there is none */
+                slot = 0i32;
+                while slot < NK_TOUCH_MAX as Int
+                    && (*touch).points[slot as usize].phase != NK_TOUCH_NONE
+                {
+                    slot += 1
+                }
+                if slot == NK_TOUCH_MAX as Int {
+                    return;
+                } else {
+                    let mut others: Int = nk_input_touch_count(&(*ctx).input);
+                    point = &mut (*touch).points[slot as usize] as *mut TouchPoint;
+                    (*point).id = id;
+                    (*point).phase = NK_TOUCH_BEGAN;
+                    (*point).pos = pos;
+                    (*point).prev = pos;
+                    (*point).start = pos;
+                    (*point).held = 0i32 as f32;
+                    (*point).tap = (others == 0) as Int;
+                    if others == 0 {
+                        (*touch).primary = slot
+                    } else {
+                        /* a second finger turns every touch into a multi-finger gesture */
+                        let mut i: Int = 0i32;
+                        while i < NK_TOUCH_MAX as Int {
+                            (*touch).points[i as usize].tap = nk_false as Int;
+                            i += 1
+                        }
+                        (*touch).pinch_total = 1.0f32;
+                        if (*touch).primary >= 0 && 0 != (*ctx).gestures.emulate_mouse {
+                            let mut p: Point = (*ctx).input.mouse.pos;
+                            nk_input_button(ctx, NK_BUTTON_LEFT, p.x as Int, p.y as Int, 0i32);
+                        }
+                        (*touch).primary = -1i32
+                    }
+                }
+            } else {
+                point = &mut (*touch).points[slot as usize] as *mut TouchPoint;
+                if (*point).pos.x != pos.x || (*point).pos.y != pos.y {
+                    (*point).pos = pos;
+                    if (*point).phase != NK_TOUCH_BEGAN {
+                        (*point).phase = NK_TOUCH_MOVED
+                    }
+                }
+            }
+            if slot == (*touch).primary && 0 != (*ctx).gestures.emulate_mouse {
+                nk_input_motion(ctx, x, y);
+                nk_input_button(ctx, NK_BUTTON_LEFT, x, y, 1i32);
+            }
+            return;
+        }
+    };
+}
+
+/// Sets the composition string of the input method. `cursor`, `sel_start`
+/// and `sel_end` count glyphs of `text`. The string is only displayed; the
+/// committed result arrives through `nk_input_unicode`/`nk_input_glyph`.
+pub unsafe fn nk_input_preedit(
+    mut ctx: *mut Context,
+    mut text: *const Char,
+    mut len: Int,
+    mut cursor: Int,
+    mut sel_start: Int,
+    mut sel_end: Int,
+) -> () {
+    let mut preedit: *mut Preedit = 0 as *mut Preedit;
+    let mut glyphs: Int = 0;
+    if ctx.is_null() {
+        return;
+    } else {
+        preedit = &mut (*ctx).preedit as *mut Preedit;
+        if text.is_null() || len < 0i32 {
+            len = 0i32
+        }
+        /* cut overlong compositions at a glyph boundary */
+        if len > (*preedit).text.len() as Int {
+            let mut unicode: Rune = 0;
+            let mut end: Int = 0i32;
+            loop {
+                let mut glyph_len: Int = nk_utf_decode(text.offset(end as isize), &mut unicode, len - end);
+                if 0 == glyph_len || end + glyph_len > (*preedit).text.len() as Int {
+                    break;
+                }
+                end += glyph_len
+            }
+            len = end
+        }
+        if len > 0i32 {
+            nk_memcopy(
+                (*preedit).text.as_mut_ptr() as *mut Void,
+                text as *const Void,
+                len as Size,
+            );
+        }
+        (*preedit).len = len;
+        glyphs = nk_utf_len((*preedit).text.as_ptr(), len);
+        (*preedit).cursor = cursor.max(0i32).min(glyphs);
+        (*preedit).sel_start = sel_start.max(0i32).min(glyphs);
+        (*preedit).sel_end = sel_end.max((*preedit).sel_start).min(glyphs);
+        return;
+    };
+}
+
+/// Ends the composition, e.g. after it was committed or cancelled.
+pub unsafe fn nk_input_preedit_clear(mut ctx: *mut Context) -> () {
+    if ctx.is_null() {
+        return;
+    } else {
+        (*ctx).preedit.len = 0i32;
+        (*ctx).preedit.cursor = 0i32;
+        (*ctx).preedit.sel_start = 0i32;
+        (*ctx).preedit.sel_end = 0i32;
+        return;
+    };
+}
+
+/// Caret rectangle of the active edit widget of the current frame, for
+/// placing the IME candidate window. Returns 0 if no edit has focus.
+pub unsafe fn nk_edit_caret(mut ctx: *const Context, mut caret: *mut Rect) -> Int {
+    if ctx.is_null() || 0 == (*ctx).preedit.has_caret {
+        return 0i32;
+    } else {
+        if !caret.is_null() {
+            *caret = (*ctx).preedit.caret
+        }
+        return 1i32;
+    };
+}
+
+/* byte offset of glyph `index` of the composition */
+unsafe fn nk_preedit_offset(mut preedit: *const Preedit, mut index: Int) -> Int {
+    let mut unicode: Rune = 0;
+    let mut len: Int = 0;
+    let mut at: *const Char = nk_utf_at((*preedit).text.as_ptr(), (*preedit).len, index, &mut unicode, &mut len);
+    if at.is_null() {
+        return (*preedit).len;
+    } else {
+        return (*preedit).text.as_ptr().ptr_offset_to(at).expect("bad offset_to") as Int;
+    };
+}
+
+/// Ends all touches without recognizing taps, e.g. when the system takes
+/// over the gesture.
+pub unsafe fn nk_input_touch_cancel(mut ctx: *mut Context) -> () {
+    let mut i: Int = 0;
+    if ctx.is_null() {
+        return;
+    } else {
+        while i < NK_TOUCH_MAX as Int {
+            if 0 != nk_input_is_touch_down(&(*ctx).input, i) {
+                (*ctx).input.touch.points[i as usize].phase = NK_TOUCH_CANCELLED;
+                (*ctx).input.touch.points[i as usize].tap = nk_false as Int
+            }
+            i += 1
+        }
+        if (*ctx).input.touch.primary >= 0 && 0 != (*ctx).gestures.emulate_mouse {
+            let mut pos: Point = (*ctx).input.mouse.pos;
+            nk_input_button(ctx, NK_BUTTON_LEFT, pos.x as Int, pos.y as Int, 0i32);
+        }
+        (*ctx).input.touch.primary = -1i32;
         return;
     };
 }
 
+unsafe fn nk_touch_slot(mut touch: *const Touch, mut id: u64) -> Int {
+    let mut i: Int = 0i32;
+    while i < NK_TOUCH_MAX as Int {
+        let mut phase: TouchPhase = (*touch).points[i as usize].phase;
+        if phase >= NK_TOUCH_BEGAN && phase <= NK_TOUCH_STATIONARY && (*touch).points[i as usize].id == id {
+            return i;
+        }
+        i += 1
+    }
+    return -1i32;
+}
+
+unsafe fn nk_touch_update(mut ctx: *mut Context) -> () {
+    let mut touch: *mut Touch = &mut (*ctx).input.touch as *mut Touch;
+    let mut config: GestureConfig = (*ctx).gestures;
+    let mut down: [Int; 2] = [-1i32, -1i32];
+    let mut count: Int = 0i32;
+    let mut i: Int = 0i32;
+    while i < NK_TOUCH_MAX as Int {
+        let mut point: *mut TouchPoint = &mut (*touch).points[i as usize] as *mut TouchPoint;
+        if (*point).phase != NK_TOUCH_NONE {
+            let mut dx: f32 = (*point).pos.x - (*point).start.x;
+            let mut dy: f32 = (*point).pos.y - (*point).start.y;
+            (*point).held += (*ctx).delta_time_seconds;
+            if dx * dx + dy * dy > config.tap_distance * config.tap_distance {
+                (*point).tap = nk_false as Int
+            }
+            if (*point).phase == NK_TOUCH_ENDED {
+                if 0 != (*point).tap && (*point).held <= config.tap_time {
+                    (*touch).gestures |= NK_GESTURE_TAP;
+                    (*touch).tap_pos = (*point).pos
+                }
+            } else if (*point).phase != NK_TOUCH_CANCELLED {
+                if count < 2i32 {
+                    down[count as usize] = i
+                }
+                count += 1;
+                if 0 != (*point).tap && (*point).held >= config.long_press_time {
+                    (*touch).gestures |= NK_GESTURE_LONG_PRESS;
+                    (*touch).tap_pos = (*point).pos;
+                    (*point).tap = nk_false as Int
+                } else if 0 != (*point).tap {
+                    /* the long press fires without further input */
+                    nk_request_redraw(ctx, config.long_press_time - (*point).held);
+                }
+            }
+        }
+        i += 1
+    }
+    if count == 1i32 {
+        let mut point: *const TouchPoint = &(*touch).points[down[0] as usize] as *const TouchPoint;
+        if 0 == (*point).tap && (*point).phase == NK_TOUCH_MOVED {
+            (*touch).gestures |= NK_GESTURE_PAN;
+            (*touch).pan.x = (*point).pos.x - (*point).prev.x;
+            (*touch).pan.y = (*point).pos.y - (*point).prev.y
+        }
+    } else if count == 2i32 {
+        let mut a: TouchPoint = (*touch).points[down[0] as usize];
+        let mut b: TouchPoint = (*touch).points[down[1] as usize];
+        /* a finger that just touched down has no previous position yet */
+        if a.phase != NK_TOUCH_BEGAN && b.phase != NK_TOUCH_BEGAN {
+            let mut d0: f32 = nk_touch_distance(a.prev, b.prev);
+            let mut d1: f32 = nk_touch_distance(a.pos, b.pos);
+            let mut c0: Point = Point::new((a.prev.x + b.prev.x) * 0.5f32, (a.prev.y + b.prev.y) * 0.5f32);
+            let mut c1: Point = Point::new((a.pos.x + b.pos.x) * 0.5f32, (a.pos.y + b.pos.y) * 0.5f32);
+            if d0 > 0i32 as f32 && d1 != d0 {
+                (*touch).gestures |= NK_GESTURE_PINCH;
+                (*touch).pinch_scale = d1 / d0;
+                (*touch).pinch_total *= d1 / d0;
+                (*touch).pinch_center = c1
+            }
+            if (c1.x != c0.x || c1.y != c0.y) && config.scroll_pixels > 0i32 as f32 {
+                (*touch).gestures |= NK_GESTURE_SCROLL;
+                nk_input_scroll(
+                    ctx,
+                    Point::new(
+                        (c1.x - c0.x) / config.scroll_pixels,
+                        (c1.y - c0.y) / config.scroll_pixels,
+                    ),
+                );
+            }
+        }
+    }
+}
+
+unsafe fn nk_touch_distance(mut a: Point, mut b: Point) -> f32 {
+    let mut dx: f32 = a.x - b.x;
+    let mut dy: f32 = a.y - b.y;
+    /* nk_sqrt is too coarse for stable pinch ratios */
+    return (dx * dx + dy * dy).sqrt();
+}
+
 pub unsafe fn nk__begin(mut ctx: *mut Context) -> *const Command {
     let mut iter: *mut Window = 0 as *mut Window;
     let mut buffer: *mut u8 = 0 as *mut u8;
@@ -4006,27 +5315,50 @@ unsafe fn nk_buffer_alloc(
         }
         if 0 != full {
             let mut capacity: Size = 0;
-            if (*b).type_0 as Uint != NK_BUFFER_DYNAMIC as Int as Uint {
-                return 0 as *mut Void;
-            } else if (*b).type_0 as Uint != NK_BUFFER_DYNAMIC as Int as Uint
+            /* a fixed buffer with a fallback allocator moves into it, but only
+             * for the front: the back may hold memory that is pointed to */
+            let mut promote: bool = (*b).type_0 == NK_BUFFER_FIXED && type_0 == NK_BUFFER_FRONT;
+            if (*b).type_0 as Uint != NK_BUFFER_DYNAMIC as Int as Uint && !promote
                 || (*b).pool.alloc.is_none()
                 || (*b).pool.free.is_none()
             {
+                (*b).failed[type_0 as usize] = (*b).failed[type_0 as usize].wrapping_add(1);
                 return 0 as *mut Void;
             } else {
-                /* buffer is full so allocate bigger buffer if dynamic */
-                capacity = ((*b).memory.size as f32 * (*b).grow_factor) as Size;
-                capacity = if capacity
-                    < nk_round_up_pow2((*b).allocated.wrapping_add(size) as u32) as Ulong
-                {
-                    nk_round_up_pow2((*b).allocated.wrapping_add(size) as u32) as Ulong
+                /* buffer is full so allocate bigger buffer if dynamic, with room
+                 * for both ends, the request and its alignment */
+                let mut back: Size = if promote {
+                    0
                 } else {
-                    capacity
+                    (*b).memory.size.wrapping_sub((*b).size)
+                };
+                let mut needed: Size = match (*b)
+                    .allocated
+                    .checked_add(back)
+                    .and_then(|n| n.checked_add(size))
+                    .and_then(|n| n.checked_add(align))
+                {
+                    Some(n) => n,
+                    None => {
+                        (*b).failed[type_0 as usize] =
+                            (*b).failed[type_0 as usize].wrapping_add(1);
+                        return 0 as *mut Void;
+                    }
                 };
-                (*b).memory.ptr = nk_buffer_realloc(b, capacity, &mut (*b).memory.size);
-                if (*b).memory.ptr.is_null() {
+                capacity = ((*b).memory.size as f64 * (*b).grow_factor as f64) as Size;
+                if capacity < needed {
+                    capacity = needed.checked_next_power_of_two().unwrap_or(needed)
+                }
+                let mut moved: *mut Void = nk_buffer_realloc(b, capacity, &mut (*b).memory.size);
+                if moved.is_null() {
+                    (*b).failed[type_0 as usize] = (*b).failed[type_0 as usize].wrapping_add(1);
                     return 0 as *mut Void;
                 } else {
+                    (*b).memory.ptr = moved;
+                    if promote {
+                        (*b).type_0 = NK_BUFFER_DYNAMIC;
+                        (*b).grow_factor = 2.0f32
+                    }
                     /* align newly allocated pointer */
                     if type_0 as Uint == NK_BUFFER_FRONT as Int as Uint {
                         unaligned = ((*b).memory.ptr as *mut u8).offset((*b).allocated as isize)
@@ -4100,14 +5432,23 @@ unsafe fn nk_buffer_realloc(
     if b.is_null() || size.is_null() || (*b).pool.alloc.is_none() || (*b).pool.free.is_none() {
         return 0 as *mut Void;
     } else {
+        /* fixed memory belongs to the caller and is left alone */
+        let mut fixed: bool = (*b).type_0 == NK_BUFFER_FIXED;
         buffer_size = (*b).memory.size;
         temp = (*b).pool.alloc.expect("non-null function pointer")(
             (*b).pool.userdata,
-            (*b).memory.ptr,
+            if fixed { 0 as *mut Void } else { (*b).memory.ptr },
             capacity,
         );
         if temp.is_null() {
             return 0 as *mut Void;
+        } else if fixed {
+            /* only the front moves; the back stays in the caller's memory,
+             * where whatever it holds is pointed to */
+            nk_memcopy(temp, (*b).memory.ptr, (*b).allocated);
+            *size = capacity;
+            (*b).size = capacity;
+            return temp;
         } else {
             *size = capacity;
             if temp != (*b).memory.ptr {
@@ -4473,6 +5814,7 @@ pub unsafe fn nk_convert(
                         (*c_1).color,
                         NK_STROKE_CLOSED,
                         (*c_1).line_thickness as f32,
+                        0 as *const StrokeStyle,
                     );
                 }
                 10 => {
@@ -4529,6 +5871,7 @@ pub unsafe fn nk_convert(
                         (*p).color,
                         NK_STROKE_CLOSED,
                         (*p).line_thickness as f32,
+                        0 as *const StrokeStyle,
                     );
                 }
                 14 => {
@@ -4562,6 +5905,7 @@ pub unsafe fn nk_convert(
                         (*p_1).color,
                         NK_STROKE_OPEN,
                         (*p_1).line_thickness as f32,
+                        0 as *const StrokeStyle,
                     );
                 }
                 16 => {
@@ -4606,56 +5950,204 @@ pub unsafe fn nk_convert(
                         (*c_3).callback_data,
                     );
                 }
-                0 | _ => {}
-            }
-            cmd = nk__next(ctx, cmd)
-        }
-        res |= (if (*cmds).needed
-            > (*cmds)
-                .allocated
-                .wrapping_add((*cmds).memory.size.wrapping_sub((*cmds).size))
-        {
-            NK_CONVERT_COMMAND_BUFFER_FULL as Int
-        } else {
-            0i32
-        }) as Uint;
-        res |= (if (*vertices).needed > (*vertices).allocated {
-            NK_CONVERT_VERTEX_BUFFER_FULL as Int
-        } else {
-            0i32
-        }) as Uint;
-        res |= (if (*elements).needed > (*elements).allocated {
-            NK_CONVERT_ELEMENT_BUFFER_FULL as Int
-        } else {
-            0i32
-        }) as Uint;
-        return res;
-    };
-}
-
-pub unsafe fn nk_draw_list_add_image(
-    mut list: *mut DrawList,
-    mut texture: Image,
-    mut rect: Rect,
-    mut color: Color,
-) -> () {
-    let mut uv: [Point; 2] = [Point { x: 0., y: 0. }; 2];
-    if list.is_null() {
-        return;
-    } else {
-        /* push new command with given texture */
-        nk_draw_list_push_image(list, texture.handle);
-        if 0 != nk_image_is_subimage(&mut texture) {
-            /* add region inside of the texture  */
-            uv = [Point { x: 0., y: 0. }; 2];
-            uv[0usize].x = texture.region[0usize] as f32 / texture.w as f32;
-            uv[0usize].y = texture.region[1usize] as f32 / texture.h as f32;
-            uv[1usize].x = (texture.region[0usize] as Int + texture.region[2usize] as Int) as f32
-                / texture.w as f32;
-            uv[1usize].y = (texture.region[1usize] as Int + texture.region[3usize] as Int) as f32
-                / texture.h as f32;
-            nk_draw_list_push_rect_uv(
-                list,
+                19 => {
+                    let mut r_2: *const CommandRectCorners = cmd as *const CommandRectCorners;
+                    nk_draw_list_stroke_rect_corners(
+                        &mut (*ctx).draw_list,
+                        Rect::new(
+                            (*r_2).x as f32,
+                            (*r_2).y as f32,
+                            (*r_2).w as f32,
+                            (*r_2).h as f32,
+                        ),
+                        (*r_2).color,
+                        nk_corner_radii_f((*r_2).rounding),
+                        (*r_2).line_thickness as f32,
+                    );
+                }
+                20 => {
+                    let mut r_3: *const CommandRectCornersFilled =
+                        cmd as *const CommandRectCornersFilled;
+                    nk_draw_list_fill_rect_corners(
+                        &mut (*ctx).draw_list,
+                        Rect::new(
+                            (*r_3).x as f32,
+                            (*r_3).y as f32,
+                            (*r_3).w as f32,
+                            (*r_3).h as f32,
+                        ),
+                        (*r_3).color,
+                        nk_corner_radii_f((*r_3).rounding),
+                    );
+                }
+                21 => {
+                    let mut r_4: *const CommandRectGradient = cmd as *const CommandRectGradient;
+                    nk_draw_list_fill_rect_gradient(
+                        &mut (*ctx).draw_list,
+                        Rect::new(
+                            (*r_4).x as f32,
+                            (*r_4).y as f32,
+                            (*r_4).w as f32,
+                            (*r_4).h as f32,
+                        ),
+                        &(*r_4).gradient,
+                        nk_corner_radii_f((*r_4).rounding),
+                    );
+                }
+                22 => {
+                    let mut c_4: *const CommandCircleGradient = cmd as *const CommandCircleGradient;
+                    nk_draw_list_fill_circle_gradient(
+                        &mut (*ctx).draw_list,
+                        Point::new(
+                            (*c_4).x as f32 + (*c_4).w as f32 / 2i32 as f32,
+                            (*c_4).y as f32 + (*c_4).h as f32 / 2i32 as f32,
+                        ),
+                        (*c_4).w as f32 / 2i32 as f32,
+                        &(*c_4).gradient,
+                        (*config).circle_segment_count,
+                    );
+                }
+                23 => {
+                    let mut i_3: Int = 0;
+                    let mut p_2: *const CommandPolygonGradient =
+                        cmd as *const CommandPolygonGradient;
+                    while i_3 < (*p_2).point_count as Int {
+                        let mut q_0: *const PointI = (*p_2).points.as_ptr().offset(i_3 as isize);
+                        let mut pnt_2: Point = Point::new((*q_0).x as f32, (*q_0).y as f32);
+                        nk_draw_list_path_line_to(&mut (*ctx).draw_list, pnt_2);
+                        i_3 += 1
+                    }
+                    nk_draw_list_path_fill_gradient(&mut (*ctx).draw_list, &(*p_2).gradient);
+                }
+                24 => {
+                    let mut l_0: *const CommandLineStyled = cmd as *const CommandLineStyled;
+                    nk_draw_list_path_line_to(
+                        &mut (*ctx).draw_list,
+                        Point::new((*l_0).begin.x as f32, (*l_0).begin.y as f32),
+                    );
+                    nk_draw_list_path_line_to(
+                        &mut (*ctx).draw_list,
+                        Point::new((*l_0).end.x as f32, (*l_0).end.y as f32),
+                    );
+                    nk_draw_list_path_stroke(
+                        &mut (*ctx).draw_list,
+                        (*l_0).color,
+                        NK_STROKE_OPEN,
+                        (*l_0).line_thickness as f32,
+                        &(*l_0).style,
+                    );
+                }
+                25 => {
+                    let mut q_1: *const CommandCurveStyled = cmd as *const CommandCurveStyled;
+                    nk_draw_list_path_line_to(
+                        &mut (*ctx).draw_list,
+                        Point::new((*q_1).begin.x as f32, (*q_1).begin.y as f32),
+                    );
+                    nk_draw_list_path_curve_to(
+                        &mut (*ctx).draw_list,
+                        Point::new((*q_1).ctrl[0usize].x as f32, (*q_1).ctrl[0usize].y as f32),
+                        Point::new((*q_1).ctrl[1usize].x as f32, (*q_1).ctrl[1usize].y as f32),
+                        Point::new((*q_1).end.x as f32, (*q_1).end.y as f32),
+                        (*config).curve_segment_count,
+                    );
+                    nk_draw_list_path_stroke(
+                        &mut (*ctx).draw_list,
+                        (*q_1).color,
+                        NK_STROKE_OPEN,
+                        (*q_1).line_thickness as f32,
+                        &(*q_1).style,
+                    );
+                }
+                26 | 27 => {
+                    let mut i_4: Int = 0;
+                    let mut p_3: *const CommandPolygonStyled = cmd as *const CommandPolygonStyled;
+                    while i_4 < (*p_3).point_count as Int {
+                        let mut q_2: *const PointI = (*p_3).points.as_ptr().offset(i_4 as isize);
+                        let mut pnt_3: Point = Point::new((*q_2).x as f32, (*q_2).y as f32);
+                        nk_draw_list_path_line_to(&mut (*ctx).draw_list, pnt_3);
+                        i_4 += 1
+                    }
+                    nk_draw_list_path_stroke(
+                        &mut (*ctx).draw_list,
+                        (*p_3).color,
+                        if (*cmd).type_0 == NK_COMMAND_POLYGON_STYLED {
+                            NK_STROKE_CLOSED
+                        } else {
+                            NK_STROKE_OPEN
+                        },
+                        (*p_3).line_thickness as f32,
+                        &(*p_3).style,
+                    );
+                }
+                28 => {
+                    let mut r_5: *const CommandRectShadow = cmd as *const CommandRectShadow;
+                    nk_draw_list_fill_rect_feathered(
+                        &mut (*ctx).draw_list,
+                        Rect::new(
+                            (*r_5).x as f32,
+                            (*r_5).y as f32,
+                            (*r_5).w as f32,
+                            (*r_5).h as f32,
+                        ),
+                        (*r_5).color,
+                        (*r_5).rounding as f32,
+                        (*r_5).feather as f32,
+                    );
+                }
+                0 | _ => {}
+            }
+            cmd = nk__next(ctx, cmd)
+        }
+        res |= (if (*cmds).needed
+            > (*cmds)
+                .allocated
+                .wrapping_add((*cmds).memory.size.wrapping_sub((*cmds).size))
+        {
+            NK_CONVERT_COMMAND_BUFFER_FULL as Int
+        } else {
+            0i32
+        }) as Uint;
+        res |= (if (*vertices).needed > (*vertices).allocated {
+            NK_CONVERT_VERTEX_BUFFER_FULL as Int
+        } else {
+            0i32
+        }) as Uint;
+        res |= (if (*elements).needed > (*elements).allocated {
+            NK_CONVERT_ELEMENT_BUFFER_FULL as Int
+        } else {
+            0i32
+        }) as Uint;
+        let mut report: *mut MemoryReport = &mut (*ctx).memory_report;
+        nk_memory_track_buffer(report, NK_MEMORY_DRAW_COMMANDS, cmds);
+        nk_memory_track_buffer(report, NK_MEMORY_VERTICES, vertices);
+        nk_memory_track_buffer(report, NK_MEMORY_ELEMENTS, elements);
+        return res;
+    };
+}
+
+pub unsafe fn nk_draw_list_add_image(
+    mut list: *mut DrawList,
+    mut texture: Image,
+    mut rect: Rect,
+    mut color: Color,
+) -> () {
+    let mut uv: [Point; 2] = [Point { x: 0., y: 0. }; 2];
+    if list.is_null() {
+        return;
+    } else {
+        /* push new command with given texture */
+        nk_draw_list_push_image(list, texture.handle);
+        if 0 != nk_image_is_subimage(&mut texture) {
+            /* add region inside of the texture  */
+            uv = [Point { x: 0., y: 0. }; 2];
+            uv[0usize].x = texture.region[0usize] as f32 / texture.w as f32;
+            uv[0usize].y = texture.region[1usize] as f32 / texture.h as f32;
+            uv[1usize].x = (texture.region[0usize] as Int + texture.region[2usize] as Int) as f32
+                / texture.w as f32;
+            uv[1usize].y = (texture.region[1usize] as Int + texture.region[3usize] as Int) as f32
+                / texture.h as f32;
+            nk_draw_list_push_rect_uv(
+                list,
                 Point::new(rect.x, rect.y),
                 Point::new(rect.x + rect.w, rect.y + rect.h),
                 uv[0usize],
@@ -5325,7 +6817,7 @@ unsafe fn nk_draw_list_push_image(mut list: *mut DrawList, mut texture: Handle)
     };
 }
 
-unsafe fn nk_draw_list_push_command(
+pub(crate) unsafe fn nk_draw_list_push_command(
     mut list: *mut DrawList,
     mut clip: Rect,
     mut texture: Handle,
@@ -5447,15 +6939,22 @@ pub unsafe fn nk_draw_list_add_text(
     };
 }
 
+/* a null style strokes the path solid, otherwise it gets the dashes, caps
+ * and joins of `style` */
 pub unsafe fn nk_draw_list_path_stroke(
     mut list: *mut DrawList,
     mut color: Color,
     mut closed: DrawListStroke,
     mut thickness: f32,
+    mut style: *const StrokeStyle,
 ) -> () {
     let mut points: *mut Point = 0 as *mut Point;
     if list.is_null() {
         return;
+    } else if !style.is_null() {
+        nk_draw_list_stroke_path_styled(list, color, closed, thickness, style);
+        nk_draw_list_path_clear(list);
+        return;
     } else {
         points = nk_buffer_memory((*list).buffer) as *mut Point;
         nk_draw_list_stroke_poly_line(
@@ -6085,124 +7584,252 @@ pub unsafe fn nk_draw_list_stroke_poly_line(
         return;
     };
 }
-unsafe fn nk_inv_sqrt(mut n: f32) -> f32 {
-    let mut x2: f32 = 0.;
-    let threehalfs: f32 = 1.5f32;
-    let mut conv: unnamed_3 = unnamed_3 { i: 0i32 as u32 };
-    conv.f = n;
-    x2 = n * 0.5f32;
-    conv.i = (1597463172i32 as Uint).wrapping_sub(conv.i >> 1i32);
-    conv.f = conv.f * (threehalfs - x2 * conv.f * conv.f);
-    return conv.f;
+
+/* scratch of the styled stroker in the front of the list buffer, right
+ * behind the path: the left and right rail point of every station along
+ * the dash being built, followed by its outline once it is filled */
+#[derive(Copy, Clone)]
+struct StrokeScratch {
+    offset: Size,
+    capacity: Uint,
+    count: Uint,
+    failed: bool,
 }
 
-pub unsafe fn nk_buffer_mark(mut buffer: *mut Buffer, mut type_0: BufferAllocationType) -> () {
-    if buffer.is_null() {
-        return;
+/* makes room for `count` points of scratch and returns where it starts;
+ * it grows in place as nothing else allocates from the front meanwhile */
+unsafe fn nk_draw_list_stroke_reserve(
+    mut list: *mut DrawList,
+    mut scratch: *mut StrokeScratch,
+    mut count: Uint,
+) -> *mut Point {
+    const pnt_align: Size = mem::align_of::<Point>() as Size;
+    const pnt_size: Size = mem::size_of::<Point>() as Size;
+    if (*scratch).failed {
+        return 0 as *mut Point;
     } else {
-        (*buffer).marker[type_0 as usize].active = nk_true as Int;
-        if type_0 as Uint == NK_BUFFER_BACK as Int as Uint {
-            (*buffer).marker[type_0 as usize].offset = (*buffer).size
-        } else {
-            (*buffer).marker[type_0 as usize].offset = (*buffer).allocated
+        if count > (*scratch).capacity {
+            let more: Uint = count
+                .wrapping_sub((*scratch).capacity)
+                .max((*scratch).capacity);
+            let memory: *mut Void = nk_buffer_alloc(
+                (*list).buffer,
+                NK_BUFFER_FRONT,
+                pnt_size.wrapping_mul(more as Size),
+                pnt_align,
+            );
+            if memory.is_null() {
+                (*scratch).failed = true;
+                return 0 as *mut Point;
+            }
+            if (*scratch).capacity == 0 {
+                (*scratch).offset = (nk_buffer_memory((*list).buffer) as *mut u8)
+                    .ptr_offset_to(memory as *mut u8)
+                    .expect("bad offset_to") as Long as Size
+            }
+            (*scratch).capacity = (*scratch).capacity.wrapping_add(more)
         }
-        return;
+        return (nk_buffer_memory((*list).buffer) as *mut u8).offset((*scratch).offset as isize)
+            as *mut Point;
     };
 }
 
-pub unsafe fn nk_draw_list_push_userdata(mut list: *mut DrawList, mut userdata: Handle) -> () {
-    (*list).userdata = userdata;
-}
-
-pub unsafe fn nk_draw_list_path_line_to(mut list: *mut DrawList, mut pos: Point) -> () {
-    let mut points: *mut Point = 0 as *mut Point;
-    let mut cmd: *mut DrawCommand = 0 as *mut DrawCommand;
-    if list.is_null() {
+unsafe fn nk_draw_list_stroke_station(
+    mut list: *mut DrawList,
+    mut scratch: *mut StrokeScratch,
+    mut left: Point,
+    mut right: Point,
+) -> () {
+    let n: Uint = (*scratch).count;
+    let points: *mut Point =
+        nk_draw_list_stroke_reserve(list, scratch, n.wrapping_add(1).wrapping_mul(2));
+    if points.is_null() {
         return;
     } else {
-        if 0 == (*list).cmd_count {
-            nk_draw_list_add_clip(list, nk_null_rect);
-        }
-        cmd = nk_draw_list_command_last(list);
-        if !cmd.is_null() && (*cmd).texture.ptr != (*list).config.null.texture.ptr {
-            nk_draw_list_push_image(list, (*list).config.null.texture);
-        }
-        points = nk_draw_list_alloc_path(list, 1i32);
-        if points.is_null() {
-            return;
-        } else {
-            *points.offset(0isize) = pos;
-            return;
-        }
+        *points.offset(n.wrapping_mul(2) as isize) = left;
+        *points.offset(n.wrapping_mul(2).wrapping_add(1) as isize) = right;
+        (*scratch).count = n.wrapping_add(1);
+        return;
     };
 }
-unsafe fn nk_draw_list_alloc_path(mut list: *mut DrawList, mut count: Int) -> *mut Point {
-    let mut memory: *mut Void = 0 as *mut Void;
-    let mut points: *mut Point = 0 as *mut Point;
-    const point_align: Size = mem::align_of::<Point>() as Size;
-    const point_size: Size = mem::size_of::<Point>() as Size;
-    points = nk_buffer_alloc(
-        (*list).buffer,
-        NK_BUFFER_FRONT,
-        point_size.wrapping_mul(count as Size),
-        point_align,
-    ) as *mut Point;
-    if points.is_null() {
-        return 0 as *mut Point;
-    } else {
-        if 0 == (*list).path_offset {
-            memory = nk_buffer_memory((*list).buffer);
-            (*list).path_offset = (memory as *mut u8)
-                .ptr_offset_to(points as *mut u8)
-                .expect("bad offset_to") as Long as Uint
+
+/* points on the circle of `radius` around `center` from the unit offset
+ * `from` on, turning by `sweep` radians; writes at most 64 points to `out`
+ * and returns how many */
+unsafe fn nk_draw_list_stroke_arc(
+    mut list: *mut DrawList,
+    mut center: Point,
+    mut from: Point,
+    mut radius: f32,
+    mut sweep: f32,
+    mut out: *mut Point,
+) -> Uint {
+    let mut segs: f32 = 0.;
+    let mut count: Uint = 0;
+    let mut i: Uint = 0;
+    let mut sin_d: f32 = 0.;
+    let mut cos_d: f32 = 0.;
+    let mut cx: f32 = from.x * radius;
+    let mut cy: f32 = from.y * radius;
+    segs = (if sweep < 0.0f32 { -sweep } else { sweep }) / (2.0f32 * 3.1415927410125734f32)
+        * (*list).config.circle_segment_count as f32;
+    count = (segs.ceil() as Uint).max(1i32 as Uint).min(63i32 as Uint);
+    /* nk_sin and nk_cos only fit positive angles */
+    sin_d = nk_sin((if sweep < 0.0f32 { -sweep } else { sweep }) / count as f32);
+    cos_d = nk_cos((if sweep < 0.0f32 { -sweep } else { sweep }) / count as f32);
+    if sweep < 0.0f32 {
+        sin_d = -sin_d
+    }
+    while i <= count {
+        let mut new_cx: f32 = 0.;
+        *out.offset(i as isize) = Point::new(center.x + cx, center.y + cy);
+        new_cx = cx * cos_d - cy * sin_d;
+        cy = cy * cos_d + cx * sin_d;
+        cx = new_cx;
+        i = i.wrapping_add(1)
+    }
+    return count.wrapping_add(1);
+}
+
+/* outline of the cap at `p` of a stroke leaving it along the unit `dir`,
+ * from its left rail round to its right one without those two points;
+ * writes at most 64 points to `out` and returns how many */
+unsafe fn nk_draw_list_stroke_cap(
+    mut list: *mut DrawList,
+    mut p: Point,
+    mut dir: Point,
+    mut half: f32,
+    mut style: *const StrokeStyle,
+    mut out: *mut Point,
+) -> Uint {
+    let n: Point = Point::new(-dir.y * half, dir.x * half);
+    let e: Point = Point::new(dir.x * half, dir.y * half);
+    if (*style).cap == NK_LINE_CAP_SQUARE {
+        *out.offset(0isize) = Point::new(p.x + n.x + e.x, p.y + n.y + e.y);
+        *out.offset(1isize) = Point::new(p.x - n.x + e.x, p.y - n.y + e.y);
+        return 2i32 as Uint;
+    } else if (*style).cap == NK_LINE_CAP_ROUND {
+        let mut arc: [Point; 64] = [Point::new(0.0f32, 0.0f32); 64];
+        let mut count: Uint = 0;
+        let mut i: Uint = 1i32 as Uint;
+        count = nk_draw_list_stroke_arc(
+            list,
+            p,
+            Point::new(-dir.y, dir.x),
+            half,
+            -3.1415927410125734f32,
+            arc.as_mut_ptr(),
+        );
+        while i.wrapping_add(1) < count {
+            *out.offset(i.wrapping_sub(1) as isize) = arc[i as usize];
+            i = i.wrapping_add(1)
         }
-        (*list).path_count = (*list).path_count.wrapping_add(count as Uint);
-        return points;
+        return count.wrapping_sub(2);
+    } else {
+        return 0i32 as Uint;
     };
 }
-unsafe fn nk_draw_list_add_clip(mut list: *mut DrawList, mut rect: Rect) -> () {
-    if list.is_null() {
-        return;
-    } else {
-        if 0 == (*list).cmd_count {
-            nk_draw_list_push_command(list, rect, (*list).config.null.texture);
+
+/* stations around the corner at `v` turning from `d0` into `d1`: the
+ * inner rail stays on the crossing of both sides while the outer one runs
+ * along the join. `reach` is how far the dash goes on along the shorter
+ * segment and keeps sharp inner corners from folding back over it. */
+unsafe fn nk_draw_list_stroke_join(
+    mut list: *mut DrawList,
+    mut scratch: *mut StrokeScratch,
+    mut v: Point,
+    mut d0: Point,
+    mut d1: Point,
+    mut half: f32,
+    mut reach: f32,
+    mut style: *const StrokeStyle,
+) -> () {
+    let mut outer: [Point; 64] = [Point::new(0.0f32, 0.0f32); 64];
+    let mut count: Uint = 0;
+    let mut inner: Point = v;
+    let mut cos_half: f32 = 0.0f32;
+    let mut i: Uint = 0;
+    let cross: f32 = d0.x * d1.y - d0.y * d1.x;
+    /* 1 if the stroke turns towards its left rail */
+    let s: f32 = if cross > 0.0f32 { 1.0f32 } else { -1.0f32 };
+    let u0: Point = Point::new(d0.y * s, -d0.x * s);
+    let u1: Point = Point::new(d1.y * s, -d1.x * s);
+    let mut m: Point = Point::new(u0.x + u1.x, u0.y + u1.y);
+    let mut len: f32 = m.x * m.x + m.y * m.y;
+    if len > 1e-6f32 {
+        len = len.sqrt();
+        m = Point::new(m.x / len, m.y / len);
+        cos_half = m.x * u0.x + m.y * u0.y
+    }
+    if cos_half > 1e-3f32 {
+        let k: f32 = (half / cos_half).min((half * half + reach * reach).sqrt());
+        inner = Point::new(v.x - m.x * k, v.y - m.y * k)
+    }
+    if (*style).join == NK_LINE_JOIN_ROUND {
+        let sweep: f32 = (u0.x * u1.y - u0.y * u1.x).atan2(u0.x * u1.x + u0.y * u1.y);
+        count = nk_draw_list_stroke_arc(list, v, u0, half, sweep, outer.as_mut_ptr())
+    } else if (*style).join == NK_LINE_JOIN_MITER
+        && cos_half > 0.0f32
+        && 1.0f32 / cos_half <= (*style).miter_limit
+    {
+        /* ratio of miter length to stroke width */
+        let k: f32 = half / cos_half;
+        outer[0usize] = Point::new(v.x + m.x * k, v.y + m.y * k);
+        count = 1i32 as Uint
+    } else {
+        outer[0usize] = Point::new(v.x + u0.x * half, v.y + u0.y * half);
+        outer[1usize] = Point::new(v.x + u1.x * half, v.y + u1.y * half);
+        count = 2i32 as Uint
+    }
+    while i < count {
+        if s > 0.0f32 {
+            nk_draw_list_stroke_station(list, scratch, inner, outer[i as usize]);
         } else {
-            let mut prev: *mut DrawCommand = nk_draw_list_command_last(list);
-            if (*prev).elem_count == 0i32 as Uint {
-                (*prev).clip_rect = rect
-            }
-            nk_draw_list_push_command(list, rect, (*prev).texture);
+            nk_draw_list_stroke_station(list, scratch, outer[i as usize], inner);
         }
-        return;
-    };
+        i = i.wrapping_add(1)
+    }
 }
 
-pub unsafe fn nk_draw_list_path_fill(mut list: *mut DrawList, mut color: Color) -> () {
-    let mut points: *mut Point = 0 as *mut Point;
-    if list.is_null() {
-        return;
-    } else {
-        points = nk_buffer_memory((*list).buffer) as *mut Point;
-        nk_draw_list_fill_poly_convex(
-            list,
-            points,
-            (*list).path_count,
-            color,
-            (*list).config.shape_AA,
-        );
-        nk_draw_list_path_clear(list);
-        return;
-    };
+fn nk_point_same(a: Point, b: Point) -> bool {
+    a.x == b.x && a.y == b.y
 }
 
-pub unsafe fn nk_draw_list_fill_poly_convex(
+unsafe fn nk_draw_list_stroke_elements(
+    mut ids: *mut DrawIndex,
+    mut a: Size,
+    mut b: Size,
+    mut c: Size,
+) -> *mut DrawIndex {
+    *ids.offset(0isize) = a as DrawIndex;
+    *ids.offset(1isize) = b as DrawIndex;
+    *ids.offset(2isize) = c as DrawIndex;
+    return ids.offset(3isize);
+}
+
+/* fills the outline of the stations in scratch and empties it. A `closed`
+ * outline runs from the last station back to the first, an open one gets
+ * caps at `p0`, leaving it against `d0`, and at `p1` along `d1`. The
+ * stations are triangulated as one strip and only the outline gets an
+ * anti-aliased fringe, so every pixel is covered once. */
+unsafe fn nk_draw_list_stroke_outline(
     mut list: *mut DrawList,
-    mut points: *const Point,
-    points_count: Uint,
+    mut scratch: *mut StrokeScratch,
+    mut p0: Point,
+    mut d0: Point,
+    mut p1: Point,
+    mut d1: Point,
+    mut half: f32,
+    mut closed: bool,
+    mut style: *const StrokeStyle,
     mut color: Color,
-    mut aliasing: AntiAliasing,
 ) -> () {
-    let mut scale: f32 = 0.;
+    let mut ends: [Point; 64] = [Point::new(0.0f32, 0.0f32); 64];
+    let mut starts: [Point; 64] = [Point::new(0.0f32, 0.0f32); 64];
+    let mut end_count: Uint = 0i32 as Uint;
+    let mut start_count: Uint = 0i32 as Uint;
+    let n: Uint = (*scratch).count;
+    let mut stations: *mut Point = nk_draw_list_stroke_reserve(list, scratch, n.wrapping_mul(2));
     let mut col: ColorF = ColorF {
         r: 0.,
         g: 0.,
@@ -6215,13 +7842,637 @@ pub unsafe fn nk_draw_list_fill_poly_convex(
         b: 0.,
         a: 0.,
     };
-    const pnt_align: Size = mem::align_of::<Point>() as Size;
-    const pnt_size: Size = mem::size_of::<Point>() as Size;
-    if list.is_null() || points_count < 3i32 as Uint {
+    let aa: bool = (*list).config.line_AA as Uint == NK_ANTI_ALIASING_ON as Int as Uint;
+    let stride: Size = if aa { 2i32 as Size } else { 1i32 as Size };
+    let mut left_count: Uint = 1i32 as Uint;
+    let mut right_count: Uint = 1i32 as Uint;
+    let mut wrap_left: bool = false;
+    let mut wrap_right: bool = false;
+    let mut ring: Uint = 0;
+    let mut tris: Uint = 0;
+    let mut outline: *mut Point = 0 as *mut Point;
+    let mut index: Size = 0;
+    let mut vtx: *mut Void = 0 as *mut Void;
+    let mut ids: *mut DrawIndex = 0 as *mut DrawIndex;
+    let mut i: Uint = 0;
+    let mut j: Uint = 0;
+    (*scratch).count = 0i32 as Uint;
+    if stations.is_null() || n == 0 {
         return;
     } else {
-        nk_draw_list_push_userdata(list, (*list).userdata);
-        color.a = (color.a as f32 * (*list).config.global_alpha) as u8;
+        /* rail points repeat while the other rail runs around a join */
+        i = 1i32 as Uint;
+        while i < n {
+            let l: isize = i.wrapping_mul(2) as isize;
+            if !nk_point_same(*stations.offset(l), *stations.offset(l - 2)) {
+                left_count = left_count.wrapping_add(1)
+            }
+            if !nk_point_same(*stations.offset(l + 1), *stations.offset(l - 1)) {
+                right_count = right_count.wrapping_add(1)
+            }
+            i = i.wrapping_add(1)
+        }
+        if closed {
+            let last: Uint = n.wrapping_sub(1).wrapping_mul(2);
+            wrap_left = !nk_point_same(*stations.offset(last as isize), *stations.offset(0isize));
+            wrap_right = !nk_point_same(
+                *stations.offset(last.wrapping_add(1) as isize),
+                *stations.offset(1isize),
+            );
+            tris = left_count
+                .wrapping_add(right_count)
+                .wrapping_sub(2)
+                .wrapping_add(wrap_left as Uint)
+                .wrapping_add(wrap_right as Uint)
+        } else {
+            end_count = nk_draw_list_stroke_cap(list, p1, d1, half, style, ends.as_mut_ptr());
+            start_count = nk_draw_list_stroke_cap(
+                list,
+                p0,
+                Point::new(-d0.x, -d0.y),
+                half,
+                style,
+                starts.as_mut_ptr(),
+            );
+            tris = left_count
+                .wrapping_add(right_count)
+                .wrapping_add(end_count)
+                .wrapping_add(start_count)
+                .wrapping_sub(2)
+        }
+        ring = left_count
+            .wrapping_add(end_count)
+            .wrapping_add(right_count)
+            .wrapping_add(start_count);
+        /* outline: left rail forwards, end cap, right rail backwards and
+         * start cap, so the outside is always to the left of its edges */
+        stations = nk_draw_list_stroke_reserve(list, scratch, n.wrapping_mul(2).wrapping_add(ring));
+        if stations.is_null() {
+            return;
+        }
+        outline = stations.offset(n.wrapping_mul(2) as isize);
+        i = 0i32 as Uint;
+        while i < n {
+            let l: Point = *stations.offset(i.wrapping_mul(2) as isize);
+            if i == 0
+                || !nk_point_same(
+                    l,
+                    *stations.offset(i.wrapping_sub(1).wrapping_mul(2) as isize),
+                )
+            {
+                *outline.offset(j as isize) = l;
+                j = j.wrapping_add(1)
+            }
+            i = i.wrapping_add(1)
+        }
+        i = 0i32 as Uint;
+        while i < end_count {
+            *outline.offset(j as isize) = ends[i as usize];
+            j = j.wrapping_add(1);
+            i = i.wrapping_add(1)
+        }
+        i = n;
+        while i > 0 {
+            let r: Point = *stations.offset(i.wrapping_mul(2).wrapping_sub(1) as isize);
+            if i == n
+                || !nk_point_same(
+                    r,
+                    *stations.offset(i.wrapping_mul(2).wrapping_add(1) as isize),
+                )
+            {
+                *outline.offset(j as isize) = r;
+                j = j.wrapping_add(1)
+            }
+            i = i.wrapping_sub(1)
+        }
+        i = 0i32 as Uint;
+        while i < start_count {
+            *outline.offset(j as isize) = starts[i as usize];
+            j = j.wrapping_add(1);
+            i = i.wrapping_add(1)
+        }
+
+        nk_draw_list_push_userdata(list, (*list).userdata);
+        color.a = (color.a as f32 * (*list).config.global_alpha) as u8;
+        nk_color_fv(&mut col.r, color);
+        col_trans = col;
+        col_trans.a = 0i32 as f32;
+        index = (*list).vertex_count as Size;
+        vtx = nk_draw_list_alloc_vertices(list, stride.wrapping_mul(ring as Size));
+        ids = nk_draw_list_alloc_elements(
+            list,
+            (tris as Size)
+                .wrapping_mul(3i32 as Size)
+                .wrapping_add(if aa { ring as Size * 6 } else { 0 }),
+        );
+        if vtx.is_null() || ids.is_null() {
+            return;
+        }
+        stations = nk_draw_list_stroke_reserve(list, scratch, n.wrapping_mul(2).wrapping_add(ring));
+        outline = stations.offset(n.wrapping_mul(2) as isize);
+
+        /* vertices, with the fringe grown out of the outline */
+        i = 0i32 as Uint;
+        while i < ring {
+            let uv: Point = (*list).config.null.uv;
+            let p: Point = *outline.offset(i as isize);
+            if aa {
+                /* a closed outline is the left rail and the right rail on
+                 * their own, both running round */
+                let (first, size) = if !closed {
+                    (0i32 as Uint, ring)
+                } else if i < left_count {
+                    (0i32 as Uint, left_count)
+                } else {
+                    (left_count, right_count)
+                };
+                let prev: Point =
+                    *outline.offset(first.wrapping_add(
+                        i.wrapping_sub(first).wrapping_add(size).wrapping_sub(1) % size,
+                    ) as isize);
+                let next: Point = *outline.offset(
+                    first.wrapping_add(i.wrapping_sub(first).wrapping_add(1) % size) as isize,
+                );
+                let mut n0: Point = Point::new(-(p.y - prev.y), p.x - prev.x);
+                let mut n1: Point = Point::new(-(next.y - p.y), next.x - p.x);
+                let mut len: f32 = n0.x * n0.x + n0.y * n0.y;
+                if len > 1e-12f32 {
+                    len = len.sqrt();
+                    n0 = Point::new(n0.x / len, n0.y / len)
+                }
+                len = n1.x * n1.x + n1.y * n1.y;
+                if len > 1e-12f32 {
+                    len = len.sqrt();
+                    n1 = Point::new(n1.x / len, n1.y / len)
+                }
+                let mut dm: Point = Point::new((n0.x + n1.x) * 0.5f32, (n0.y + n1.y) * 0.5f32);
+                let dmr2: f32 = dm.x * dm.x + dm.y * dm.y;
+                if dmr2 > 1e-6f32 {
+                    let scale: f32 = (1.0f32 / dmr2).min(100.0f32);
+                    dm = Point::new(dm.x * scale, dm.y * scale)
+                }
+                /* AA_SIZE is one pixel */
+                dm = Point::new(dm.x * 0.5f32, dm.y * 0.5f32);
+                vtx = nk_draw_vertex(
+                    vtx,
+                    &mut (*list).config,
+                    Point::new(p.x - dm.x, p.y - dm.y),
+                    uv,
+                    col,
+                );
+                vtx = nk_draw_vertex(
+                    vtx,
+                    &mut (*list).config,
+                    Point::new(p.x + dm.x, p.y + dm.y),
+                    uv,
+                    col_trans,
+                );
+            } else {
+                vtx = nk_draw_vertex(vtx, &mut (*list).config, p, uv, col);
+            }
+            i = i.wrapping_add(1)
+        }
+
+        /* strip between the rails; `r` counts right rail points, which the
+         * outline holds backwards */
+        let at = |k: Uint| index.wrapping_add((k as Size).wrapping_mul(stride));
+        let right = |r: Uint| {
+            at(ring
+                .wrapping_sub(start_count)
+                .wrapping_sub(1)
+                .wrapping_sub(r))
+        };
+        let mut l: Uint = 0i32 as Uint;
+        let mut r: Uint = 0i32 as Uint;
+        i = 1i32 as Uint;
+        while i < n {
+            let mut nl: Uint = l;
+            let mut nr: Uint = r;
+            if !nk_point_same(
+                *stations.offset(i.wrapping_mul(2) as isize),
+                *stations.offset(i.wrapping_sub(1).wrapping_mul(2) as isize),
+            ) {
+                nl = l.wrapping_add(1)
+            }
+            if !nk_point_same(
+                *stations.offset(i.wrapping_mul(2).wrapping_add(1) as isize),
+                *stations.offset(i.wrapping_mul(2).wrapping_sub(1) as isize),
+            ) {
+                nr = r.wrapping_add(1)
+            }
+            if nl != l {
+                ids = nk_draw_list_stroke_elements(ids, at(l), at(nl), right(nr));
+            }
+            if nr != r {
+                ids = nk_draw_list_stroke_elements(ids, at(l), right(nr), right(r));
+            }
+            l = nl;
+            r = nr;
+            i = i.wrapping_add(1)
+        }
+        if closed {
+            if wrap_left {
+                let nr: Uint = if wrap_right { 0i32 as Uint } else { r };
+                ids = nk_draw_list_stroke_elements(ids, at(l), at(0), right(nr));
+            }
+            if wrap_right {
+                ids = nk_draw_list_stroke_elements(ids, at(l), right(0), right(r));
+            }
+        } else {
+            /* caps fan out from the rail point they start at */
+            let end: Uint = left_count.wrapping_sub(1);
+            let start: Uint = ring.wrapping_sub(start_count).wrapping_sub(1);
+            i = 0i32 as Uint;
+            while i < end_count {
+                let k: Uint = end.wrapping_add(1).wrapping_add(i);
+                ids = nk_draw_list_stroke_elements(ids, at(end), at(k), at(k.wrapping_add(1)));
+                i = i.wrapping_add(1)
+            }
+            i = 0i32 as Uint;
+            while i < start_count {
+                let k: Uint = start.wrapping_add(1).wrapping_add(i);
+                ids = nk_draw_list_stroke_elements(
+                    ids,
+                    at(start),
+                    at(k),
+                    at(k.wrapping_add(1) % ring),
+                );
+                i = i.wrapping_add(1)
+            }
+        }
+        if aa {
+            i = 0i32 as Uint;
+            while i < ring {
+                let (first, size) = if !closed {
+                    (0i32 as Uint, ring)
+                } else if i < left_count {
+                    (0i32 as Uint, left_count)
+                } else {
+                    (left_count, right_count)
+                };
+                let next: Uint = first.wrapping_add(i.wrapping_sub(first).wrapping_add(1) % size);
+                ids = nk_draw_list_stroke_elements(ids, at(i), at(next), at(next) + 1);
+                ids = nk_draw_list_stroke_elements(ids, at(next) + 1, at(i) + 1, at(i));
+                i = i.wrapping_add(1)
+            }
+        }
+        return;
+    };
+}
+
+/* Strokes the path with the dashes, caps and joins of `style`, filling
+ * every dash as one outline. */
+unsafe fn nk_draw_list_stroke_path_styled(
+    mut list: *mut DrawList,
+    mut color: Color,
+    mut closed: DrawListStroke,
+    mut thickness: f32,
+    mut style: *const StrokeStyle,
+) -> () {
+    let half: f32 = thickness * 0.5f32;
+    let points_count: Uint = (*list).path_count;
+    let mut points: *const Point = nk_buffer_memory((*list).buffer) as *const Point;
+    let mut scratch: StrokeScratch = StrokeScratch {
+        offset: 0i32 as Size,
+        capacity: 0i32 as Uint,
+        count: 0i32 as Uint,
+        failed: false,
+    };
+    let mut count: Uint = points_count;
+    let mut pattern: [f32; 16] = [0.0f32; 16];
+    let mut pattern_count: Uint = 0i32 as Uint;
+    let mut total: f32 = 0.0f32;
+    let mut length: f32 = 0.0f32;
+    let mut idx: Uint = 0i32 as Uint;
+    let mut rem: f32 = 0.;
+    let mut drawing: bool = false;
+    /* a solid closed path is one outline running round */
+    let mut around: bool = false;
+    let mut prev: Point = Point::new(0.0f32, 0.0f32);
+    let mut start: Point = Point::new(0.0f32, 0.0f32);
+    let mut start_dir: Point = Point::new(0.0f32, 0.0f32);
+    /* how far the current dash reaches back along the last segment */
+    let mut back: f32 = 0.0f32;
+    let mut i: Uint = 0;
+    if points_count < 2i32 as Uint || thickness <= 0.0f32 {
+        return;
+    } else {
+        if closed as Uint == NK_STROKE_CLOSED as Int as Uint {
+            count = count.wrapping_add(1)
+        }
+        i = 1i32 as Uint;
+        while i < count {
+            let p0: Point = *points.offset(i.wrapping_sub(1) as isize);
+            let p1: Point = *points.offset(i.wrapping_rem(points_count) as isize);
+            /* nk_sqrt is too coarse, dashes would drift along the path */
+            length += ((p1.x - p0.x) * (p1.x - p0.x) + (p1.y - p0.y) * (p1.y - p0.y)).sqrt();
+            i = i.wrapping_add(1)
+        }
+        /* odd patterns are repeated once so on and off stay in step */
+        i = 0i32 as Uint;
+        while i < (*style).dash_count && i < NK_STROKE_MAX_DASHES {
+            let d: f32 = if (*style).dash[i as usize] > 0.0f32 {
+                (*style).dash[i as usize]
+            } else {
+                0.0f32
+            };
+            pattern[i as usize] = d;
+            total += d;
+            i = i.wrapping_add(1)
+        }
+        pattern_count = i;
+        if pattern_count & 1i32 as Uint != 0 {
+            i = 0i32 as Uint;
+            while i < pattern_count {
+                pattern[i.wrapping_add(pattern_count) as usize] = pattern[i as usize];
+                i = i.wrapping_add(1)
+            }
+            pattern_count = pattern_count.wrapping_mul(2);
+            total *= 2.0f32
+        }
+        /* fall back to a solid line for empty or absurdly fine patterns */
+        if total <= 0.0f32 || length / total > 4096.0f32 {
+            pattern_count = 0i32 as Uint
+        }
+        if pattern_count == 0 {
+            rem = length + 1.0f32
+        } else {
+            let mut offset: f32 = (*style).dash_offset % total;
+            if offset < 0.0f32 {
+                offset += total
+            }
+            rem = pattern[0usize];
+            while offset >= rem {
+                offset -= rem;
+                idx = idx.wrapping_add(1).wrapping_rem(pattern_count);
+                rem = pattern[idx as usize]
+            }
+            rem -= offset
+        }
+        /* a solid closed outline starts with the join over its first point */
+        if pattern_count == 0 && closed as Uint == NK_STROKE_CLOSED as Int as Uint {
+            i = count.wrapping_sub(1);
+            while i > 0 {
+                let p0: Point = *points.offset(i.wrapping_sub(1) as isize);
+                let p1: Point = *points.offset(i.wrapping_rem(points_count) as isize);
+                let len: f32 = (p1.x - p0.x) * (p1.x - p0.x) + (p1.y - p0.y) * (p1.y - p0.y);
+                if len > 1e-12f32 {
+                    let len: f32 = len.sqrt();
+                    prev = Point::new((p1.x - p0.x) / len, (p1.y - p0.y) / len);
+                    back = len;
+                    drawing = true;
+                    around = true;
+                    break;
+                } else {
+                    i = i.wrapping_sub(1)
+                }
+            }
+        }
+        i = 1i32 as Uint;
+        while i < count {
+            /* the scratch behind the path may move it */
+            points = nk_buffer_memory((*list).buffer) as *const Point;
+            let p0: Point = *points.offset(i.wrapping_sub(1) as isize);
+            let p1: Point = *points.offset(i.wrapping_rem(points_count) as isize);
+            let mut len: f32 = (p1.x - p0.x) * (p1.x - p0.x) + (p1.y - p0.y) * (p1.y - p0.y);
+            let mut t: f32 = 0.0f32;
+            let mut from: f32 = 0.0f32;
+            if len > 1e-12f32 {
+                len = len.sqrt();
+                let d: Point = Point::new((p1.x - p0.x) / len, (p1.y - p0.y) / len);
+                let n: Point = Point::new(-d.y * half, d.x * half);
+                if drawing {
+                    let ahead: f32 = if rem < len { rem } else { len };
+                    let reach: f32 = if back < ahead { back } else { ahead };
+                    nk_draw_list_stroke_join(list, &mut scratch, p0, prev, d, half, reach, style);
+                }
+                while t < len {
+                    let on: bool = idx & 1i32 as Uint == 0;
+                    let step: f32 = if rem < len - t { rem } else { len - t };
+                    let a: Point = Point::new(p0.x + d.x * t, p0.y + d.y * t);
+                    let b: Point = Point::new(a.x + d.x * step, a.y + d.y * step);
+                    if on && !drawing {
+                        let (l, r) = (
+                            Point::new(a.x + n.x, a.y + n.y),
+                            Point::new(a.x - n.x, a.y - n.y),
+                        );
+                        nk_draw_list_stroke_station(list, &mut scratch, l, r);
+                        start = a;
+                        start_dir = d;
+                        from = t;
+                        drawing = true
+                    }
+                    t += step;
+                    if pattern_count != 0 {
+                        rem -= step;
+                        if rem <= 0.0f32 {
+                            if on {
+                                let (l, r) = (
+                                    Point::new(b.x + n.x, b.y + n.y),
+                                    Point::new(b.x - n.x, b.y - n.y),
+                                );
+                                nk_draw_list_stroke_station(list, &mut scratch, l, r);
+                                nk_draw_list_stroke_outline(
+                                    list,
+                                    &mut scratch,
+                                    start,
+                                    start_dir,
+                                    b,
+                                    d,
+                                    half,
+                                    false,
+                                    style,
+                                    color,
+                                );
+                                drawing = false
+                            }
+                            idx = idx.wrapping_add(1).wrapping_rem(pattern_count);
+                            rem = pattern[idx as usize]
+                        }
+                    }
+                }
+                back = len - from;
+                prev = d
+            }
+            i = i.wrapping_add(1)
+        }
+        if drawing && around {
+            nk_draw_list_stroke_outline(
+                list,
+                &mut scratch,
+                start,
+                start_dir,
+                start,
+                prev,
+                half,
+                true,
+                style,
+                color,
+            );
+        } else if drawing {
+            let last: Uint = count.wrapping_sub(1).wrapping_rem(points_count);
+            let end: Point =
+                *(nk_buffer_memory((*list).buffer) as *const Point).offset(last as isize);
+            let n: Point = Point::new(-prev.y * half, prev.x * half);
+            let (l, r) = (
+                Point::new(end.x + n.x, end.y + n.y),
+                Point::new(end.x - n.x, end.y - n.y),
+            );
+            nk_draw_list_stroke_station(list, &mut scratch, l, r);
+            nk_draw_list_stroke_outline(
+                list,
+                &mut scratch,
+                start,
+                start_dir,
+                end,
+                prev,
+                half,
+                false,
+                style,
+                color,
+            );
+        }
+        return;
+    };
+}
+
+unsafe fn nk_inv_sqrt(mut n: f32) -> f32 {
+    let mut x2: f32 = 0.;
+    let threehalfs: f32 = 1.5f32;
+    let mut conv: unnamed_3 = unnamed_3 { i: 0i32 as u32 };
+    conv.f = n;
+    x2 = n * 0.5f32;
+    conv.i = (1597463172i32 as Uint).wrapping_sub(conv.i >> 1i32);
+    conv.f = conv.f * (threehalfs - x2 * conv.f * conv.f);
+    return conv.f;
+}
+
+pub unsafe fn nk_buffer_mark(mut buffer: *mut Buffer, mut type_0: BufferAllocationType) -> () {
+    if buffer.is_null() {
+        return;
+    } else {
+        (*buffer).marker[type_0 as usize].active = nk_true as Int;
+        if type_0 as Uint == NK_BUFFER_BACK as Int as Uint {
+            (*buffer).marker[type_0 as usize].offset = (*buffer).size
+        } else {
+            (*buffer).marker[type_0 as usize].offset = (*buffer).allocated
+        }
+        return;
+    };
+}
+
+pub unsafe fn nk_draw_list_push_userdata(mut list: *mut DrawList, mut userdata: Handle) -> () {
+    (*list).userdata = userdata;
+}
+
+pub unsafe fn nk_draw_list_path_line_to(mut list: *mut DrawList, mut pos: Point) -> () {
+    let mut points: *mut Point = 0 as *mut Point;
+    let mut cmd: *mut DrawCommand = 0 as *mut DrawCommand;
+    if list.is_null() {
+        return;
+    } else {
+        if 0 == (*list).cmd_count {
+            nk_draw_list_add_clip(list, nk_null_rect);
+        }
+        cmd = nk_draw_list_command_last(list);
+        if !cmd.is_null() && (*cmd).texture.ptr != (*list).config.null.texture.ptr {
+            nk_draw_list_push_image(list, (*list).config.null.texture);
+        }
+        points = nk_draw_list_alloc_path(list, 1i32);
+        if points.is_null() {
+            return;
+        } else {
+            *points.offset(0isize) = pos;
+            return;
+        }
+    };
+}
+unsafe fn nk_draw_list_alloc_path(mut list: *mut DrawList, mut count: Int) -> *mut Point {
+    let mut memory: *mut Void = 0 as *mut Void;
+    let mut points: *mut Point = 0 as *mut Point;
+    const point_align: Size = mem::align_of::<Point>() as Size;
+    const point_size: Size = mem::size_of::<Point>() as Size;
+    points = nk_buffer_alloc(
+        (*list).buffer,
+        NK_BUFFER_FRONT,
+        point_size.wrapping_mul(count as Size),
+        point_align,
+    ) as *mut Point;
+    if points.is_null() {
+        return 0 as *mut Point;
+    } else {
+        if 0 == (*list).path_offset {
+            memory = nk_buffer_memory((*list).buffer);
+            (*list).path_offset = (memory as *mut u8)
+                .ptr_offset_to(points as *mut u8)
+                .expect("bad offset_to") as Long as Uint
+        }
+        (*list).path_count = (*list).path_count.wrapping_add(count as Uint);
+        return points;
+    };
+}
+unsafe fn nk_draw_list_add_clip(mut list: *mut DrawList, mut rect: Rect) -> () {
+    if list.is_null() {
+        return;
+    } else {
+        if 0 == (*list).cmd_count {
+            nk_draw_list_push_command(list, rect, (*list).config.null.texture);
+        } else {
+            let mut prev: *mut DrawCommand = nk_draw_list_command_last(list);
+            if (*prev).elem_count == 0i32 as Uint {
+                (*prev).clip_rect = rect
+            }
+            nk_draw_list_push_command(list, rect, (*prev).texture);
+        }
+        return;
+    };
+}
+
+pub unsafe fn nk_draw_list_path_fill(mut list: *mut DrawList, mut color: Color) -> () {
+    let mut points: *mut Point = 0 as *mut Point;
+    if list.is_null() {
+        return;
+    } else {
+        points = nk_buffer_memory((*list).buffer) as *mut Point;
+        nk_draw_list_fill_poly_convex(
+            list,
+            points,
+            (*list).path_count,
+            color,
+            (*list).config.shape_AA,
+        );
+        nk_draw_list_path_clear(list);
+        return;
+    };
+}
+
+pub unsafe fn nk_draw_list_fill_poly_convex(
+    mut list: *mut DrawList,
+    mut points: *const Point,
+    points_count: Uint,
+    mut color: Color,
+    mut aliasing: AntiAliasing,
+) -> () {
+    let mut scale: f32 = 0.;
+    let mut col: ColorF = ColorF {
+        r: 0.,
+        g: 0.,
+        b: 0.,
+        a: 0.,
+    };
+    let mut col_trans: ColorF = ColorF {
+        r: 0.,
+        g: 0.,
+        b: 0.,
+        a: 0.,
+    };
+    const pnt_align: Size = mem::align_of::<Point>() as Size;
+    const pnt_size: Size = mem::size_of::<Point>() as Size;
+    if list.is_null() || points_count < 3i32 as Uint {
+        return;
+    } else {
+        nk_draw_list_push_userdata(list, (*list).userdata);
+        color.a = (color.a as f32 * (*list).config.global_alpha) as u8;
         nk_color_fv(&mut col.r, color);
         col_trans = col;
         col_trans.a = 0i32 as f32;
@@ -6424,7 +8675,7 @@ pub unsafe fn nk_draw_list_stroke_triangle(
         nk_draw_list_path_line_to(list, a);
         nk_draw_list_path_line_to(list, b);
         nk_draw_list_path_line_to(list, c);
-        nk_draw_list_path_stroke(list, col, NK_STROKE_CLOSED, thickness);
+        nk_draw_list_path_stroke(list, col, NK_STROKE_CLOSED, thickness, 0 as *const StrokeStyle);
         return;
     };
 }
@@ -6515,7 +8766,7 @@ pub unsafe fn nk_draw_list_stroke_circle(
     } else {
         a_max = 3.1415927410125734f32 * 2.0f32 * (segs as f32 - 1.0f32) / segs as f32;
         nk_draw_list_path_arc_to(list, center, radius, 0.0f32, a_max, segs);
-        nk_draw_list_path_stroke(list, col, NK_STROKE_CLOSED, thickness);
+        nk_draw_list_path_stroke(list, col, NK_STROKE_CLOSED, thickness, 0 as *const StrokeStyle);
         return;
     };
 }
@@ -6637,6 +8888,142 @@ pub unsafe fn nk_draw_list_fill_rect(
     };
 }
 
+/* Rounded rect whose alpha fades from `col` to transparent across a band
+ * `feather` wide centered on the outline; a cheap stand-in for a blurred
+ * drop shadow. Both edges of the band are rounded rects with matching
+ * vertices, so the fade keeps its width around the corners. */
+pub unsafe fn nk_draw_list_fill_rect_feathered(
+    mut list: *mut DrawList,
+    mut rect: Rect,
+    mut col: Color,
+    mut rounding: f32,
+    mut feather: f32,
+) -> () {
+    let mut vtx: *mut Void = 0 as *mut Void;
+    let mut ids: *mut DrawIndex = 0 as *mut DrawIndex;
+    let mut col_f: ColorF = ColorF {
+        r: 0.,
+        g: 0.,
+        b: 0.,
+        a: 0.,
+    };
+    let mut col_trans: ColorF = ColorF {
+        r: 0.,
+        g: 0.,
+        b: 0.,
+        a: 0.,
+    };
+    let mut inner: Rect = rect;
+    let mut half: f32 = 0.;
+    let mut r: f32 = 0.;
+    let mut segs: Uint = 0;
+    let mut count: Uint = 0;
+    let mut index: Size = 0;
+    let mut i: Uint = 0;
+    if list.is_null() || 0 == col.a || rect.w <= 0.0f32 || rect.h <= 0.0f32 {
+        return;
+    } else if feather <= 0.0f32 {
+        nk_draw_list_fill_rect(list, rect, col, rounding);
+        return;
+    } else {
+        /* the inner edge cannot shrink past the center of the rect */
+        half = feather * 0.5f32;
+        inner.x += if half < rect.w * 0.5f32 { half } else { rect.w * 0.5f32 };
+        inner.y += if half < rect.h * 0.5f32 { half } else { rect.h * 0.5f32 };
+        inner.w = rect.x + rect.w - (inner.x - rect.x) - inner.x;
+        inner.h = rect.y + rect.h - (inner.y - rect.y) - inner.y;
+        r = rounding - half;
+        r = if r < inner.w * 0.5f32 { r } else { inner.w * 0.5f32 };
+        r = if r < inner.h * 0.5f32 { r } else { inner.h * 0.5f32 };
+        r = if r > 0.0f32 { r } else { 0.0f32 };
+        /* the outer edge sits `feather` out from the inner one */
+        feather = (inner.x - rect.x) + half;
+        segs = (*list).config.circle_segment_count / 4i32 as Uint;
+        segs = if segs < 2i32 as Uint { 2i32 as Uint } else { segs };
+        segs = if segs > 16i32 as Uint { 16i32 as Uint } else { segs };
+        count = segs.wrapping_add(1).wrapping_mul(4i32 as Uint);
+        col.a = (col.a as f32 * (*list).config.global_alpha) as u8;
+        nk_color_fv(&mut col_f.r, col);
+        col_trans = col_f;
+        col_trans.a = 0.0f32;
+        nk_draw_list_push_image(list, (*list).config.null.texture);
+        index = (*list).vertex_count as Size;
+        vtx = nk_draw_list_alloc_vertices(list, count.wrapping_mul(2i32 as Uint) as Size);
+        ids = nk_draw_list_alloc_elements(
+            list,
+            count
+                .wrapping_sub(2i32 as Uint)
+                .wrapping_mul(3i32 as Uint)
+                .wrapping_add(count.wrapping_mul(6i32 as Uint)) as Size,
+        );
+        if vtx.is_null() || ids.is_null() {
+            return;
+        } else {
+            /* corners clockwise from the top-left, inner and outer vertex in turn */
+            let centers: [Point; 4] = [
+                Point::new(inner.x + r, inner.y + r),
+                Point::new(inner.x + inner.w - r, inner.y + r),
+                Point::new(inner.x + inner.w - r, inner.y + inner.h - r),
+                Point::new(inner.x + r, inner.y + inner.h - r),
+            ];
+            i = 0i32 as Uint;
+            while i < count {
+                let corner: Uint = i.wrapping_div(segs.wrapping_add(1));
+                let step: Uint = i.wrapping_rem(segs.wrapping_add(1));
+                /* quarter turns starting at the top-left corner, kept in the
+                 * range nk_sin and nk_cos are fit for */
+                let a: f32 = 3.1415927410125734f32
+                    * ((corner + 2i32 as Uint).wrapping_rem(4i32 as Uint) as f32
+                        + step as f32 / segs as f32)
+                    * 0.5f32;
+                let c: Point = centers[corner as usize];
+                let dx: f32 = nk_cos(a);
+                let dy: f32 = nk_sin(a);
+                vtx = nk_draw_vertex(
+                    vtx,
+                    &mut (*list).config,
+                    Point::new(c.x + dx * r, c.y + dy * r),
+                    (*list).config.null.uv,
+                    col_f,
+                );
+                vtx = nk_draw_vertex(
+                    vtx,
+                    &mut (*list).config,
+                    Point::new(c.x + dx * (r + feather), c.y + dy * (r + feather)),
+                    (*list).config.null.uv,
+                    col_trans,
+                );
+                i = i.wrapping_add(1)
+            }
+            /* solid inside */
+            i = 2i32 as Uint;
+            while i < count {
+                *ids.offset(0isize) = index as DrawIndex;
+                *ids.offset(1isize) = index.wrapping_add(((i - 1) * 2) as Size) as DrawIndex;
+                *ids.offset(2isize) = index.wrapping_add((i * 2) as Size) as DrawIndex;
+                ids = ids.offset(3isize);
+                i = i.wrapping_add(1)
+            }
+            /* fading band */
+            i = 0i32 as Uint;
+            while i < count {
+                let i0: Size = index.wrapping_add((i * 2) as Size);
+                let next: Uint = i.wrapping_add(1).wrapping_rem(count);
+                let i1: Size = index.wrapping_add((next * 2) as Size);
+                *ids.offset(0isize) = i0 as DrawIndex;
+                *ids.offset(1isize) = i1 as DrawIndex;
+                *ids.offset(2isize) = i1.wrapping_add(1) as DrawIndex;
+                *ids.offset(3isize) = i0 as DrawIndex;
+                *ids.offset(4isize) = i1.wrapping_add(1) as DrawIndex;
+                *ids.offset(5isize) = i0.wrapping_add(1) as DrawIndex;
+                ids = ids.offset(6isize);
+                i = i.wrapping_add(1)
+            }
+            return;
+        }
+    };
+}
+
 pub unsafe fn nk_draw_list_path_rect_to(
     mut list: *mut DrawList,
     mut a: Point,
@@ -6685,6 +9072,376 @@ pub unsafe fn nk_draw_list_path_rect_to(
     };
 }
 
+/* corner radii are ordered top-left, top-right, bottom-right, bottom-left
+ * and limited to half the shorter side */
+pub unsafe fn nk_draw_list_path_rect_corners_to(
+    mut list: *mut DrawList,
+    mut a: Point,
+    mut b: Point,
+    mut rounding: [f32; 4],
+) -> () {
+    let mut r: [f32; 4] = [0.; 4];
+    let mut i: usize = 0;
+    if list.is_null() {
+        return;
+    } else {
+        let w: f32 = if b.x - a.x < 0i32 as f32 { -(b.x - a.x) } else { b.x - a.x };
+        let h: f32 = if b.y - a.y < 0i32 as f32 { -(b.y - a.y) } else { b.y - a.y };
+        let max: f32 = (if w < h { w } else { h }) * 0.5f32;
+        while i < 4 {
+            r[i] = if rounding[i] < 0i32 as f32 {
+                0i32 as f32
+            } else if rounding[i] > max {
+                max
+            } else {
+                rounding[i]
+            };
+            i += 1
+        }
+        if r[0] == 0.0f32 {
+            nk_draw_list_path_line_to(list, a);
+        } else {
+            nk_draw_list_path_arc_to_fast(
+                list,
+                Point::new(a.x + r[0], a.y + r[0]),
+                r[0],
+                6i32,
+                9i32,
+            );
+        }
+        if r[1] == 0.0f32 {
+            nk_draw_list_path_line_to(list, Point::new(b.x, a.y));
+        } else {
+            nk_draw_list_path_arc_to_fast(
+                list,
+                Point::new(b.x - r[1], a.y + r[1]),
+                r[1],
+                9i32,
+                12i32,
+            );
+        }
+        if r[2] == 0.0f32 {
+            nk_draw_list_path_line_to(list, b);
+        } else {
+            nk_draw_list_path_arc_to_fast(
+                list,
+                Point::new(b.x - r[2], b.y - r[2]),
+                r[2],
+                0i32,
+                3i32,
+            );
+        }
+        if r[3] == 0.0f32 {
+            nk_draw_list_path_line_to(list, Point::new(a.x, b.y));
+        } else {
+            nk_draw_list_path_arc_to_fast(
+                list,
+                Point::new(a.x + r[3], b.y - r[3]),
+                r[3],
+                3i32,
+                6i32,
+            );
+        }
+        return;
+    };
+}
+
+pub unsafe fn nk_draw_list_stroke_rect_corners(
+    mut list: *mut DrawList,
+    mut rect: Rect,
+    mut col: Color,
+    mut rounding: [f32; 4],
+    mut thickness: f32,
+) -> () {
+    if list.is_null() || 0 == col.a {
+        return;
+    } else {
+        if (*list).line_AA as Uint == NK_ANTI_ALIASING_ON as Int as Uint {
+            nk_draw_list_path_rect_corners_to(
+                list,
+                Point::new(rect.x, rect.y),
+                Point::new(rect.x + rect.w, rect.y + rect.h),
+                rounding,
+            );
+        } else {
+            nk_draw_list_path_rect_corners_to(
+                list,
+                Point::new(rect.x - 0.5f32, rect.y - 0.5f32),
+                Point::new(rect.x + rect.w, rect.y + rect.h),
+                rounding,
+            );
+        }
+        nk_draw_list_path_stroke(list, col, NK_STROKE_CLOSED, thickness, 0 as *const StrokeStyle);
+        return;
+    };
+}
+
+pub unsafe fn nk_draw_list_fill_rect_corners(
+    mut list: *mut DrawList,
+    mut rect: Rect,
+    mut col: Color,
+    mut rounding: [f32; 4],
+) -> () {
+    if list.is_null() || 0 == col.a {
+        return;
+    } else {
+        if (*list).line_AA as Uint == NK_ANTI_ALIASING_ON as Int as Uint {
+            nk_draw_list_path_rect_corners_to(
+                list,
+                Point::new(rect.x, rect.y),
+                Point::new(rect.x + rect.w, rect.y + rect.h),
+                rounding,
+            );
+        } else {
+            nk_draw_list_path_rect_corners_to(
+                list,
+                Point::new(rect.x - 0.5f32, rect.y - 0.5f32),
+                Point::new(rect.x + rect.w, rect.y + rect.h),
+                rounding,
+            );
+        }
+        nk_draw_list_path_fill(list, col);
+        return;
+    };
+}
+
+pub unsafe fn nk_draw_list_fill_rect_gradient(
+    mut list: *mut DrawList,
+    mut rect: Rect,
+    mut g: *const Gradient,
+    mut rounding: [f32; 4],
+) -> () {
+    if list.is_null() || g.is_null() {
+        return;
+    } else {
+        if (*list).line_AA as Uint == NK_ANTI_ALIASING_ON as Int as Uint {
+            nk_draw_list_path_rect_corners_to(
+                list,
+                Point::new(rect.x, rect.y),
+                Point::new(rect.x + rect.w, rect.y + rect.h),
+                rounding,
+            );
+        } else {
+            nk_draw_list_path_rect_corners_to(
+                list,
+                Point::new(rect.x - 0.5f32, rect.y - 0.5f32),
+                Point::new(rect.x + rect.w, rect.y + rect.h),
+                rounding,
+            );
+        }
+        nk_draw_list_path_fill_gradient(list, g);
+        return;
+    };
+}
+
+pub unsafe fn nk_draw_list_fill_circle_gradient(
+    mut list: *mut DrawList,
+    mut center: Point,
+    mut radius: f32,
+    mut g: *const Gradient,
+    mut segs: Uint,
+) -> () {
+    let mut a_max: f32 = 0.;
+    if list.is_null() || g.is_null() {
+        return;
+    } else {
+        a_max = 3.1415927410125734f32 * 2.0f32 * (segs as f32 - 1.0f32) / segs as f32;
+        nk_draw_list_path_arc_to(list, center, radius, 0.0f32, a_max, segs);
+        nk_draw_list_path_fill_gradient(list, g);
+        return;
+    };
+}
+
+pub unsafe fn nk_draw_list_path_fill_gradient(
+    mut list: *mut DrawList,
+    mut g: *const Gradient,
+) -> () {
+    let mut points: *mut Point = 0 as *mut Point;
+    if list.is_null() {
+        return;
+    } else {
+        points = nk_buffer_memory((*list).buffer) as *mut Point;
+        nk_draw_list_fill_poly_gradient(
+            list,
+            points,
+            (*list).path_count,
+            g,
+            (*list).config.shape_AA,
+        );
+        nk_draw_list_path_clear(list);
+        return;
+    };
+}
+
+/* Per vertex colors are only exact for linear gradients that do not clamp
+ * inside the shape, so the polygon is split into rings around its centroid
+ * to follow radial and clamped gradients closely enough. */
+const NK_GRADIENT_RINGS: Uint = 8;
+
+pub unsafe fn nk_draw_list_fill_poly_gradient(
+    mut list: *mut DrawList,
+    mut points: *const Point,
+    points_count: Uint,
+    mut g: *const Gradient,
+    mut aliasing: AntiAliasing,
+) -> () {
+    let mut grad: Gradient = Gradient {
+        type_0: NK_GRADIENT_LINEAR,
+        start: Point::new(0.0f32, 0.0f32),
+        end: Point::new(0.0f32, 0.0f32),
+        start_color: nk_rgba(0i32, 0i32, 0i32, 0i32),
+        end_color: nk_rgba(0i32, 0i32, 0i32, 0i32),
+    };
+    let mut center: Point = Point::new(0.0f32, 0.0f32);
+    let mut i: Uint = 0;
+    let mut k: Uint = 0;
+    if list.is_null() || g.is_null() || points_count < 3i32 as Uint {
+        return;
+    } else {
+        nk_draw_list_push_userdata(list, (*list).userdata);
+        grad = *g;
+        grad.start_color.a = (grad.start_color.a as f32 * (*list).config.global_alpha) as u8;
+        grad.end_color.a = (grad.end_color.a as f32 * (*list).config.global_alpha) as u8;
+        let aa: bool = aliasing as Uint == NK_ANTI_ALIASING_ON as Int as Uint;
+        let rings: Uint = NK_GRADIENT_RINGS;
+        let uv: Point = (*list).config.null.uv;
+        let index: Uint = (*list).vertex_count;
+        let fringe: Uint = index + 1 + rings * points_count;
+        let vtx_count: Size =
+            (1 + rings * points_count + if aa { points_count } else { 0 }) as Size;
+        let idx_count: Size = (points_count * 3
+            + (rings - 1) * points_count * 6
+            + if aa { points_count * 6 } else { 0 }) as Size;
+        let mut vtx: *mut Void = nk_draw_list_alloc_vertices(list, vtx_count);
+        let mut ids: *mut DrawIndex = nk_draw_list_alloc_elements(list, idx_count);
+        if vtx.is_null() || ids.is_null() {
+            return;
+        }
+        i = 0;
+        while i < points_count {
+            center.x += (*points.offset(i as isize)).x;
+            center.y += (*points.offset(i as isize)).y;
+            i += 1
+        }
+        center = Point::new(center.x / points_count as f32, center.y / points_count as f32);
+        /* center vertex, then the rings from the inside out */
+        let mut col: ColorF = ColorF {
+            r: 0.,
+            g: 0.,
+            b: 0.,
+            a: 0.,
+        };
+        nk_color_fv(&mut col.r, nk_gradient_color(&grad, center));
+        vtx = nk_draw_vertex(vtx, &mut (*list).config, center, uv, col);
+        k = 1;
+        while k <= rings {
+            let t: f32 = k as f32 / rings as f32;
+            i = 0;
+            while i < points_count {
+                let mut p: Point = *points.offset(i as isize);
+                if aa {
+                    /* keep half the fringe inside the shape like
+                     * nk_draw_list_fill_poly_convex */
+                    let dm: Point = nk_poly_vertex_normal(points, points_count, i, 0.5f32);
+                    p = Point::new(p.x - dm.x, p.y - dm.y);
+                }
+                p = Point::new(center.x + (p.x - center.x) * t, center.y + (p.y - center.y) * t);
+                nk_color_fv(&mut col.r, nk_gradient_color(&grad, p));
+                vtx = nk_draw_vertex(vtx, &mut (*list).config, p, uv, col);
+                i += 1
+            }
+            k += 1
+        }
+        if aa {
+            i = 0;
+            while i < points_count {
+                let p: Point = *points.offset(i as isize);
+                let dm: Point = nk_poly_vertex_normal(points, points_count, i, 0.5f32);
+                nk_color_fv(&mut col.r, nk_gradient_color(&grad, p));
+                col.a = 0i32 as f32;
+                vtx = nk_draw_vertex(
+                    vtx,
+                    &mut (*list).config,
+                    Point::new(p.x + dm.x, p.y + dm.y),
+                    uv,
+                    col,
+                );
+                i += 1
+            }
+        }
+        /* add indexes */
+        i = 0;
+        while i < points_count {
+            let i1: Uint = (i + 1) % points_count;
+            *ids.offset(0isize) = index as DrawIndex;
+            *ids.offset(1isize) = (index + 1 + i) as DrawIndex;
+            *ids.offset(2isize) = (index + 1 + i1) as DrawIndex;
+            ids = ids.offset(3isize);
+            k = 1;
+            while k < rings {
+                let inner: Uint = index + 1 + (k - 1) * points_count;
+                let outer: Uint = inner + points_count;
+                *ids.offset(0isize) = (inner + i) as DrawIndex;
+                *ids.offset(1isize) = (outer + i) as DrawIndex;
+                *ids.offset(2isize) = (outer + i1) as DrawIndex;
+                *ids.offset(3isize) = (inner + i) as DrawIndex;
+                *ids.offset(4isize) = (outer + i1) as DrawIndex;
+                *ids.offset(5isize) = (inner + i1) as DrawIndex;
+                ids = ids.offset(6isize);
+                k += 1
+            }
+            if aa {
+                let edge: Uint = index + 1 + (rings - 1) * points_count;
+                *ids.offset(0isize) = (edge + i) as DrawIndex;
+                *ids.offset(1isize) = (fringe + i) as DrawIndex;
+                *ids.offset(2isize) = (fringe + i1) as DrawIndex;
+                *ids.offset(3isize) = (edge + i) as DrawIndex;
+                *ids.offset(4isize) = (fringe + i1) as DrawIndex;
+                *ids.offset(5isize) = (edge + i1) as DrawIndex;
+                ids = ids.offset(6isize);
+            }
+            i += 1
+        }
+        return;
+    };
+}
+
+/* averaged outward normal at vertex `i` of a polygon, scaled like the
+ * anti-aliasing fringe of nk_draw_list_fill_poly_convex */
+unsafe fn nk_poly_vertex_normal(
+    mut points: *const Point,
+    points_count: Uint,
+    i: Uint,
+    size: f32,
+) -> Point {
+    let mut n: [Point; 2] = [Point::new(0.0f32, 0.0f32); 2];
+    let mut e: usize = 0;
+    while e < 2 {
+        let i0: Uint = (i + points_count - 1 + e as Uint) % points_count;
+        let i1: Uint = (i0 + 1) % points_count;
+        let p0: Point = *points.offset(i0 as isize);
+        let p1: Point = *points.offset(i1 as isize);
+        let mut diff: Point = Point::new(p1.x - p0.x, p1.y - p0.y);
+        let mut len: f32 = diff.x * diff.x + diff.y * diff.y;
+        if len != 0.0f32 {
+            len = nk_inv_sqrt(len)
+        } else {
+            len = 1.0f32
+        }
+        diff = Point::new(diff.x * len, diff.y * len);
+        n[e] = Point::new(diff.y, -diff.x);
+        e += 1
+    }
+    let mut dm: Point = Point::new((n[0].x + n[1].x) * 0.5f32, (n[0].y + n[1].y) * 0.5f32);
+    let dmr2: f32 = dm.x * dm.x + dm.y * dm.y;
+    if dmr2 > 9.999999974752428e-7f32 {
+        let mut scale: f32 = 1.0f32 / dmr2;
+        scale = if scale < 100.0f32 { scale } else { 100.0f32 };
+        dm = Point::new(dm.x * scale, dm.y * scale)
+    }
+    return Point::new(dm.x * size, dm.y * size);
+}
+
 pub unsafe fn nk_draw_list_path_arc_to_fast(
     mut list: *mut DrawList,
     mut center: Point,
@@ -6738,7 +9495,7 @@ pub unsafe fn nk_draw_list_stroke_rect(
                 rounding,
             );
         }
-        nk_draw_list_path_stroke(list, col, NK_STROKE_CLOSED, thickness);
+        nk_draw_list_path_stroke(list, col, NK_STROKE_CLOSED, thickness, 0 as *const StrokeStyle);
         return;
     };
 }
@@ -6758,7 +9515,7 @@ pub unsafe fn nk_draw_list_stroke_curve(
     } else {
         nk_draw_list_path_line_to(list, p0);
         nk_draw_list_path_curve_to(list, cp0, cp1, p1, segments);
-        nk_draw_list_path_stroke(list, col, NK_STROKE_OPEN, thickness);
+        nk_draw_list_path_stroke(list, col, NK_STROKE_OPEN, thickness, 0 as *const StrokeStyle);
         return;
     };
 }
@@ -6837,7 +9594,7 @@ pub unsafe fn nk_draw_list_stroke_line(
                 ),
             );
         }
-        nk_draw_list_path_stroke(list, col, NK_STROKE_OPEN, thickness);
+        nk_draw_list_path_stroke(list, col, NK_STROKE_OPEN, thickness, 0 as *const StrokeStyle);
         return;
     };
 }
@@ -7033,6 +9790,7 @@ pub unsafe fn nk_begin_titled(
             (*win).layout = ptr::null_mut();
             return 0;
         } else {
+            (*ctx).oom.window_failed = (*ctx).memory.failed[NK_BUFFER_FRONT as usize];
             nk_start(ctx, win);
             /* window overlapping */
             if 0 == (*win).flags & NK_WINDOW_HIDDEN as Uint
@@ -7162,6 +9920,10 @@ pub unsafe fn nk_begin_titled(
             }
             (*win).layout = nk_create_panel(ctx) as *mut Panel;
             (*ctx).current = win;
+            if (*win).layout.is_null() {
+                /* out of memory: nk_end only has to reset `current` */
+                return 0;
+            }
             ret = nk_panel_begin(ctx, title, NK_PANEL_WINDOW);
             (*(*win).layout).offset_x = &mut (*win).scrollbar.x as *mut u32;
             (*(*win).layout).offset_y = &mut (*win).scrollbar.y as *mut u32;
@@ -7263,6 +10025,26 @@ unsafe fn nk_panel_begin(
                         (*ctx).style.cursors[NK_CURSOR_MOVE as Int as usize]
                 }
             }
+            /* drop shadow, drawn first so the window covers it */
+            if panel_type as Uint != NK_PANEL_GROUP as Int as Uint
+                && 0 != (*style).window.shadow_color.a
+            {
+                let mut shadow: Rect = (*win).bounds;
+                let end: Size = (*out).end;
+                shadow.x += (*style).window.shadow_offset.x;
+                shadow.y += (*style).window.shadow_offset.y;
+                nk_fill_rect_shadow(
+                    out,
+                    shadow,
+                    (*style).window.rounding,
+                    (*style).window.shadow_spread,
+                    (*style).window.shadow_color,
+                );
+                if (*out).end != end {
+                    (*layout).has_shadow = nk_true as Int as Uint;
+                    (*layout).shadow = (*out).last
+                }
+            }
             /* setup panel */
             (*layout).type_0 = panel_type;
             (*layout).flags = (*win).flags;
@@ -7357,8 +10139,8 @@ unsafe fn nk_panel_begin(
                         nk_white,
                     );
                 } else {
-                    text.background = (*background).data.color;
-                    nk_fill_rect(out, header_0, 0i32 as f32, (*background).data.color);
+                    text.background = nk_style_item_background(background);
+                    nk_fill_style_item(out, header_0, 0i32 as f32, background);
                 }
                 /* window close button */
                 let mut button: Rect = Rect {
@@ -7504,11 +10286,11 @@ unsafe fn nk_panel_begin(
                         nk_white,
                     );
                 } else {
-                    nk_fill_rect(
+                    nk_fill_style_item(
                         out,
                         body,
                         0i32 as f32,
-                        (*style).window.fixed_background.data.color,
+                        &(*style).window.fixed_background,
                     );
                 }
             }
@@ -7649,6 +10431,48 @@ pub unsafe fn nk_fill_rect(
     };
 }
 
+pub unsafe fn nk_fill_rect_shadow(
+    mut b: *mut CommandBuffer,
+    mut rect: Rect,
+    mut rounding: f32,
+    mut feather: f32,
+    mut c: Color,
+) -> () {
+    let mut cmd: *mut CommandRectShadow = 0 as *mut CommandRectShadow;
+    let half: f32 = if feather > 0.0f32 { feather * 0.5f32 } else { 0.0f32 };
+    if b.is_null() || c.a as Int == 0i32 || rect.w <= 0i32 as f32 || rect.h <= 0i32 as f32 {
+        return;
+    } else {
+        if 0 != (*b).use_clipping {
+            let mut clip: *const Rect = &mut (*b).clip as *mut Rect;
+            if (*clip).x > rect.x + rect.w + half
+                || (*clip).x + (*clip).w < rect.x - half
+                || (*clip).y > rect.y + rect.h + half
+                || (*clip).y + (*clip).h < rect.y - half
+            {
+                return;
+            }
+        }
+        cmd = nk_command_buffer_push(
+            b,
+            NK_COMMAND_RECT_SHADOW,
+            mem::size_of::<CommandRectShadow>() as Ulong,
+        ) as *mut CommandRectShadow;
+        if cmd.is_null() {
+            return;
+        } else {
+            (*cmd).rounding = rounding as Ushort;
+            (*cmd).feather = (half * 2.0f32) as Ushort;
+            (*cmd).x = rect.x as Short;
+            (*cmd).y = rect.y as Short;
+            (*cmd).w = rect.w as Ushort;
+            (*cmd).h = rect.h as Ushort;
+            (*cmd).color = c;
+            return;
+        }
+    };
+}
+
 pub unsafe fn nk_strlen(mut str: *const Char) -> Int {
     let mut siz: Int = 0i32;
     while !str.is_null() && {
@@ -8260,7 +11084,7 @@ unsafe fn nk_draw_button(
     if (*background).type_0 as Uint == NK_STYLE_ITEM_IMAGE as Int as Uint {
         nk_draw_image(out, *bounds, &(*background).data.image, nk_white);
     } else {
-        nk_fill_rect(out, *bounds, (*style).rounding, (*background).data.color);
+        nk_fill_style_item(out, *bounds, (*style).rounding, background);
         nk_stroke_rect(
             out,
             *bounds,
@@ -8484,8 +11308,18 @@ unsafe fn nk_create_page_element(mut ctx: *mut Context) -> *mut PageElement {
     } else {
         const size: Size = mem::size_of::<PageElement>() as Size;
         const align: Size = mem::size_of::<PageElement>() as Size;
-        /* allocate new page element from back of fixed size memory buffer */
-        elem = nk_buffer_alloc(&mut (*ctx).memory, NK_BUFFER_BACK, size, align) as *mut PageElement;
+        /* allocate new page element from back of fixed size memory buffer,
+         * or from the fallback pool once it is full or moved */
+        let mut room: Size = (*ctx).memory.size.wrapping_sub((*ctx).memory.allocated);
+        let mut fallback: bool = (*ctx).pool.alloc.alloc.is_some();
+        if (*ctx).memory.type_0 == NK_BUFFER_FIXED && (!fallback || room >= size + align) {
+            elem = nk_buffer_alloc(&mut (*ctx).memory, NK_BUFFER_BACK, size, align)
+                as *mut PageElement;
+            (*ctx).oom.fixed_back = (*ctx).memory.memory.size.wrapping_sub((*ctx).memory.size)
+        }
+        if elem.is_null() && fallback {
+            elem = nk_pool_alloc(&mut (*ctx).pool)
+        }
         if elem.is_null() {
             return 0 as *mut PageElement;
         }
@@ -8499,22 +11333,33 @@ unsafe fn nk_pool_alloc(mut pool: *mut Pool) -> *mut PageElement {
     if (*pool).pages.is_null() || (*(*pool).pages).size >= (*pool).capacity {
         /* allocate new page */
         let mut page: *mut Page = 0 as *mut Page;
-        if (*pool).type_0 as Uint == NK_BUFFER_FIXED as Int as Uint {
-            if (*pool).pages.is_null() {
-                return 0 as *mut PageElement;
-            } else {
-                return 0 as *mut PageElement;
-            }
+        if (*pool).type_0 as Uint == NK_BUFFER_FIXED as Int as Uint && (*pool).alloc.alloc.is_none()
+        {
+            (*pool).failed = (*pool).failed.wrapping_add(1);
+            return 0 as *mut PageElement;
         } else {
-            let mut size: Size = mem::size_of::<Page>() as Ulong;
-            size = (size as Ulong).wrapping_add(
-                (16i32 as Ulong).wrapping_mul(mem::size_of::<nk_page_data>() as Ulong),
-            ) as Size as Size;
+            /* pages of a fixed pool with a fallback are as big as the fixed one;
+             * `Page` already holds the first element */
+            let mut extra: Ulong = ((*pool).capacity as Ulong).saturating_sub(1);
+            let mut size: Size = match extra
+                .checked_mul(mem::size_of::<PageElement>() as Ulong)
+                .and_then(|n| n.checked_add(mem::size_of::<Page>() as Ulong))
+            {
+                Some(n) => n as Size,
+                None => {
+                    (*pool).failed = (*pool).failed.wrapping_add(1);
+                    return 0 as *mut PageElement;
+                }
+            };
             page = (*pool).alloc.alloc.expect("non-null function pointer")(
                 (*pool).alloc.userdata,
                 0 as *mut Void,
                 size,
             ) as *mut Page;
+            if page.is_null() {
+                (*pool).failed = (*pool).failed.wrapping_add(1);
+                return 0 as *mut PageElement;
+            }
             (*page).next = (*pool).pages;
             (*pool).pages = page;
             (*page).size = 0i32 as Uint
@@ -8684,7 +11529,8 @@ pub unsafe fn nk_murmur_hash(mut key: *const Void, mut len: Int, mut seed: Hash)
         blocks = conv.i;
         i = -nblocks;
         while 0 != i {
-            k1 = *blocks.offset(i as isize);
+            /* names are byte strings without any alignment */
+            k1 = ptr::read_unaligned(blocks.offset(i as isize));
             k1 = (k1 as Uint).wrapping_mul(c1) as u32 as u32;
             k1 = k1 << 15i32 | k1 >> 32i32 - 15i32;
             k1 = (k1 as Uint).wrapping_mul(c2) as u32 as u32;
@@ -8756,6 +11602,18 @@ pub unsafe fn nk_end(mut ctx: *mut Context) -> () {
             return;
         } else {
             nk_panel_end(ctx);
+            if 0 != (*ctx).oom.policy & NK_OOM_TRUNCATE_WINDOWS
+                && (*layout).type_0 == NK_PANEL_WINDOW
+                && (*ctx).memory.failed[NK_BUFFER_FRONT as usize] != (*ctx).oom.window_failed
+            {
+                /* drop the half built window and hand its memory to the next */
+                let mut win: *mut Window = (*ctx).current;
+                (*ctx).memory.allocated = (*win).buffer.begin;
+                (*win).buffer.end = (*win).buffer.begin;
+                (*win).buffer.last = (*win).buffer.begin;
+                (*win).popup.buf.active = nk_false as Int;
+                (*ctx).oom.truncated = (*ctx).oom.truncated.wrapping_add(1)
+            }
             nk_free_panel(ctx, (*(*ctx).current).layout);
             (*ctx).current = 0 as *mut Window;
             return;
@@ -8763,20 +11621,8 @@ pub unsafe fn nk_end(mut ctx: *mut Context) -> () {
     };
 }
 unsafe fn nk_free_panel(mut ctx: *mut Context, mut pan: *mut Panel) -> () {
-    let mut pd: *mut nk_page_data = ((if 0 != 1i32 {
-        pan
-    } else {
-        &mut (*(0 as *mut nk_page_data)).pan as *mut Panel
-    }) as *mut Char)
-        .offset(-(&mut (*(0 as *mut nk_page_data)).pan as *mut Panel as Ptr as isize))
-        as *mut Void as *mut nk_page_data;
-    let mut pe: *mut PageElement = ((if 0 != 1i32 {
-        pd
-    } else {
-        &mut (*(0 as *mut PageElement)).data as *mut nk_page_data
-    }) as *mut Char)
-        .offset(-(&mut (*(0 as *mut PageElement)).data as *mut nk_page_data as Ptr as isize))
-        as *mut Void as *mut PageElement;
+    /* the union member `pan` and the `data` of its element both sit at offset 0 */
+    let mut pe: *mut PageElement = pan as *mut PageElement;
     nk_free_page_element(ctx, pe);
 }
 unsafe fn nk_panel_end(mut ctx: *mut Context) -> () {
@@ -8999,9 +11845,33 @@ unsafe fn nk_panel_end(mut ctx: *mut Context) -> () {
             } else {
                 (*window).scrollbar_hiding_timer = 0i32 as f32
             }
+            /* wake up in time to hide the scrollbars */
+            if (*window).scrollbar_hiding_timer < 4.0f32 {
+                nk_request_redraw(ctx, 4.0f32 - (*window).scrollbar_hiding_timer);
+            }
         } else {
             (*window).scrollbar_hiding_timer = 0i32 as f32
         }
+        /* fit the drop shadow to minimized and dynamic panels */
+        if 0 != (*layout).has_shadow
+            && (0 != (*layout).flags & NK_WINDOW_MINIMIZED as Int as Uint
+                || 0 != (*layout).flags & NK_WINDOW_DYNAMIC as Int as Uint)
+        {
+            let mut shadow: *mut CommandRectShadow = ((*(*out).base).memory.ptr as *mut u8)
+                .offset((*layout).shadow as isize)
+                as *mut CommandRectShadow;
+            let mut bottom: f32 = if 0 != (*layout).flags & NK_WINDOW_MINIMIZED as Int as Uint {
+                (*window).bounds.y + (*layout).header_height
+            } else {
+                (*layout).bounds.y + (*layout).bounds.h + (*layout).footer_height
+            };
+            bottom += (*layout).border;
+            (*shadow).h = if bottom > (*window).bounds.y {
+                (bottom - (*window).bounds.y) as Ushort
+            } else {
+                0i32 as Ushort
+            }
+        }
         /* window border */
         if 0 != (*layout).flags & NK_WINDOW_BORDER as Int as Uint {
             border_color = nk_panel_get_border_color(style, (*layout).type_0);
@@ -9043,28 +11913,47 @@ unsafe fn nk_panel_end(mut ctx: *mut Context) -> () {
             let mut item: *const StyleItem = &(*style).window.scaler as *const StyleItem;
             if (*item).type_0 as Uint == NK_STYLE_ITEM_IMAGE as Int as Uint {
                 nk_draw_image(out, scaler, &(*item).data.image, nk_white);
-            } else if 0 != (*layout).flags & NK_WINDOW_SCALE_LEFT as Int as Uint {
-                nk_fill_triangle(
-                    out,
-                    scaler.x,
-                    scaler.y,
-                    scaler.x,
-                    scaler.y + scaler.h,
-                    scaler.x + scaler.w,
-                    scaler.y + scaler.h,
-                    (*item).data.color,
-                );
             } else {
-                nk_fill_triangle(
-                    out,
-                    scaler.x + scaler.w,
-                    scaler.y,
-                    scaler.x + scaler.w,
-                    scaler.y + scaler.h,
-                    scaler.x,
-                    scaler.y + scaler.h,
-                    (*item).data.color,
-                );
+                let mut tri: [f32; 6] = if 0
+                    != (*layout).flags & NK_WINDOW_SCALE_LEFT as Int as Uint
+                {
+                    [
+                        scaler.x,
+                        scaler.y,
+                        scaler.x,
+                        scaler.y + scaler.h,
+                        scaler.x + scaler.w,
+                        scaler.y + scaler.h,
+                    ]
+                } else {
+                    [
+                        scaler.x + scaler.w,
+                        scaler.y,
+                        scaler.x + scaler.w,
+                        scaler.y + scaler.h,
+                        scaler.x,
+                        scaler.y + scaler.h,
+                    ]
+                };
+                if (*item).type_0 as Uint == NK_STYLE_ITEM_GRADIENT as Int as Uint {
+                    nk_fill_polygon_gradient(
+                        out,
+                        tri.as_mut_ptr(),
+                        3i32,
+                        nk_gradient_in_rect(&(*item).data.gradient, scaler),
+                    );
+                } else {
+                    nk_fill_triangle(
+                        out,
+                        tri[0],
+                        tri[1],
+                        tri[2],
+                        tri[3],
+                        tri[4],
+                        tri[5],
+                        (*item).data.color,
+                    );
+                }
             }
             /* do window scaling */
             if 0 == (*window).flags & NK_WINDOW_ROM as Int as Uint {
@@ -9414,8 +12303,8 @@ unsafe fn nk_draw_scrollbar(
         cursor = &(*style).cursor_normal as *const StyleItem
     }
     /* draw background */
-    if (*background).type_0 as Uint == NK_STYLE_ITEM_COLOR as Int as Uint {
-        nk_fill_rect(out, *bounds, (*style).rounding, (*background).data.color);
+    if (*background).type_0 as Uint != NK_STYLE_ITEM_IMAGE as Int as Uint {
+        nk_fill_style_item(out, *bounds, (*style).rounding, background);
         nk_stroke_rect(
             out,
             *bounds,
@@ -9427,8 +12316,8 @@ unsafe fn nk_draw_scrollbar(
         nk_draw_image(out, *bounds, &(*background).data.image, nk_white);
     }
     /* draw cursor */
-    if (*cursor).type_0 as Uint == NK_STYLE_ITEM_COLOR as Int as Uint {
-        nk_fill_rect(out, *scroll, (*style).rounding_cursor, (*cursor).data.color);
+    if (*cursor).type_0 as Uint != NK_STYLE_ITEM_IMAGE as Int as Uint {
+        nk_fill_style_item(out, *scroll, (*style).rounding_cursor, cursor);
         nk_stroke_rect(
             out,
             *scroll,
@@ -11008,6 +13897,9 @@ pub unsafe fn nk_group_scrolled_offset_begin(
         panel.scrollbar.y = *y_offset;
         panel.buffer = (*win).buffer;
         panel.layout = nk_create_panel(ctx) as *mut Panel;
+        if panel.layout.is_null() {
+            return 0i32;
+        }
         (*ctx).current = &mut panel;
         nk_panel_begin(
             ctx,
@@ -11567,20 +14459,20 @@ unsafe fn nk_tree_state_base(
         nk_layout_set_min_row_height(ctx, row_height);
         nk_layout_row_dynamic(ctx, row_height, 1i32);
         nk_layout_reset_min_row_height(ctx);
-        widget_state = nk_widget(&mut header, ctx);
+        widget_state = nk_widget_nav(&mut header, ctx);
         if type_0 as Uint == NK_TREE_TAB as Int as Uint {
             let mut background: *const StyleItem = &(*style).tab.background as *const StyleItem;
             if (*background).type_0 as Uint == NK_STYLE_ITEM_IMAGE as Int as Uint {
                 nk_draw_image(out, header, &(*background).data.image, nk_white);
                 text.background = nk_rgba(0i32, 0i32, 0i32, 0i32)
             } else {
-                text.background = (*background).data.color;
+                text.background = nk_style_item_background(background);
                 nk_fill_rect(out, header, 0i32 as f32, (*style).tab.border_color);
-                nk_fill_rect(
+                nk_fill_style_item(
                     out,
                     nk_shrink_rect(header, (*style).tab.border),
                     (*style).tab.rounding,
-                    (*background).data.color,
+                    background,
                 );
             }
         } else {
@@ -11685,6 +14577,90 @@ unsafe fn nk_tree_state_base(
     };
 }
 
+/// Registers a focusable widget right after `nk_widget` allocated it.
+///
+/// Draws the focus ring around the widget if it is focused and returns the
+/// `NK_NAV_*` actions it should perform this frame, plus
+/// `NK_WIDGET_STATE_FOCUSED` if it is focused at all.
+pub unsafe fn nk_nav_widget(
+    mut ctx: *mut Context,
+    mut bounds: Rect,
+    mut state: WidgetLayoutStates,
+) -> Flags {
+    let mut win: *mut Window = 0 as *mut Window;
+    let mut root: *mut Window = 0 as *mut Window;
+    let mut nav: *mut Navigation = 0 as *mut Navigation;
+    let mut index: Int = 0;
+    if ctx.is_null() || (*ctx).current.is_null() || (*(*ctx).current).layout.is_null() {
+        return 0i32 as Flags;
+    } else {
+        win = (*ctx).current;
+        root = win;
+        while !(*root).parent.is_null() {
+            root = (*root).parent
+        }
+        if root != (*ctx).active {
+            return 0i32 as Flags;
+        } else {
+            nav = &mut (*ctx).nav;
+            index = (*nav).count;
+            (*nav).count += 1;
+            /* clicking a widget moves the focus there as well, even if the
+             * click only just activated its window */
+            if state != NK_WIDGET_INVALID
+                && 0 != nk_input_is_mouse_pressed(&(*ctx).input, NK_BUTTON_LEFT)
+                && 0 != nk_input_is_mouse_hovering_rect(&(*ctx).input, bounds)
+            {
+                (*nav).window = (*root).name;
+                (*nav).focus = index
+            }
+            if index != (*nav).focus {
+                return 0i32 as Flags;
+            } else {
+                (*nav).bounds = bounds;
+                if 0 == (*nav).visible || state == NK_WIDGET_INVALID {
+                    return NK_WIDGET_STATE_FOCUSED as Flags;
+                } else {
+                    let mut style: *const StyleNavigation = &(*ctx).style.nav;
+                    let mut ring: Rect = nk_pad_rect(
+                        bounds,
+                        Point::new(-(*style).padding.x, -(*style).padding.y),
+                    );
+                    nk_stroke_rect(
+                        &mut (*win).buffer,
+                        ring,
+                        (*style).rounding,
+                        (*style).border,
+                        (*style).color,
+                    );
+                    /* NK_WIDGET_ROM only means the mouse is elsewhere */
+                    if 0 != (*(*win).layout).flags & NK_WINDOW_ROM as Int as Uint {
+                        return NK_WIDGET_STATE_FOCUSED as Flags;
+                    } else {
+                        return NK_WIDGET_STATE_FOCUSED as Flags
+                            | (*nav).actions
+                                & (NK_NAV_ACTIVATE | NK_NAV_DECREASE | NK_NAV_INCREASE);
+                    }
+                }
+            }
+        }
+    };
+}
+
+/// Marks `last_widget_state` as focused and returns whether the widget was
+/// activated through `nk_nav_widget`.
+pub unsafe fn nk_nav_finish(mut ctx: *mut Context, mut nav: Flags) -> Int {
+    if 0 != nav & NK_WIDGET_STATE_FOCUSED as Flags {
+        (*ctx).last_widget_state |= NK_WIDGET_STATE_FOCUSED as Flags
+    }
+    return (0 != nav & NK_NAV_ACTIVATE) as Int;
+}
+
+/* -1, 0 or 1 for the decrease/increase actions in `nav` */
+fn nk_nav_direction(mut nav: Flags) -> Int {
+    return (0 != nav & NK_NAV_INCREASE) as Int - (0 != nav & NK_NAV_DECREASE) as Int;
+}
+
 pub unsafe fn nk_widget(mut bounds: *mut Rect, mut ctx: *const Context) -> WidgetLayoutStates {
     let mut c: Rect = Rect {
         x: 0.,
@@ -11749,6 +14725,21 @@ pub unsafe fn nk_widget(mut bounds: *mut Rect, mut ctx: *const Context) -> Widge
     };
 }
 
+/* `nk_widget` for the widgets of this library: also remembers where the
+ * widget went, for drag and drop, layout dumps and test drivers */
+pub(crate) unsafe fn nk_widget_nav(
+    mut bounds: *mut Rect,
+    mut ctx: *mut Context,
+) -> WidgetLayoutStates {
+    if ctx.is_null() || (*ctx).current.is_null() || (*(*ctx).current).layout.is_null() {
+        return NK_WIDGET_INVALID;
+    } else {
+        let mut state: WidgetLayoutStates = nk_widget(bounds, ctx);
+        (*ctx).last_widget_bounds = *bounds;
+        return state;
+    };
+}
+
 pub unsafe fn nk_tree_image_push_hashed(
     mut ctx: *mut Context,
     mut type_0: TreeType,
@@ -11926,20 +14917,20 @@ unsafe fn nk_tree_element_image_push_hashed_base(
         nk_layout_set_min_row_height(ctx, row_height);
         nk_layout_row_dynamic(ctx, row_height, 1i32);
         nk_layout_reset_min_row_height(ctx);
-        widget_state = nk_widget(&mut header, ctx);
+        widget_state = nk_widget_nav(&mut header, ctx);
         if type_0 as Uint == NK_TREE_TAB as Int as Uint {
             let mut background: *const StyleItem = &(*style).tab.background as *const StyleItem;
             if (*background).type_0 as Uint == NK_STYLE_ITEM_IMAGE as Int as Uint {
                 nk_draw_image(out, header, &(*background).data.image, nk_white);
                 text.background = nk_rgba(0i32, 0i32, 0i32, 0i32)
             } else {
-                text.background = (*background).data.color;
+                text.background = nk_style_item_background(background);
                 nk_fill_rect(out, header, 0i32 as f32, (*style).tab.border_color);
-                nk_fill_rect(
+                nk_fill_style_item(
                     out,
                     nk_shrink_rect(header, (*style).tab.border),
                     (*style).tab.rounding,
-                    (*background).data.color,
+                    background,
                 );
             }
         } else {
@@ -12189,8 +15180,8 @@ unsafe fn nk_draw_selectable(
         nk_draw_image(out, *bounds, &(*background).data.image, nk_white);
         text.background = nk_rgba(0i32, 0i32, 0i32, 0i32)
     } else {
-        nk_fill_rect(out, *bounds, (*style).rounding, (*background).data.color);
-        text.background = (*background).data.color
+        nk_fill_style_item(out, *bounds, (*style).rounding, background);
+        text.background = nk_style_item_background(background)
     }
     if !icon.is_null() {
         if !img.is_null() {
@@ -12445,7 +15436,7 @@ pub unsafe fn nk_widget_fitting(
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
         layout = (*win).layout;
-        state = nk_widget(bounds, ctx);
+        state = nk_widget_nav(bounds, ctx);
         panel_padding = nk_panel_get_padding(style, (*layout).type_0);
         if (*layout).row.index == 1i32 {
             (*bounds).w += panel_padding.x;
@@ -12562,6 +15553,287 @@ pub unsafe fn nk_widget_height(mut ctx: *mut Context) -> f32 {
     };
 }
 
+/// Number of consecutive clicks (1 single, 2 double, 3 triple, ...) with
+/// which `button` was pressed on the last widget this frame, 0 otherwise.
+///
+/// Click counts come from `nk_input_button_timed`.
+pub unsafe fn nk_widget_clicks(mut ctx: *mut Context, mut button: Buttons) -> Int {
+    let mut in_0: *const Input = 0 as *const Input;
+    if ctx.is_null()
+        || (*ctx).current.is_null()
+        || (*(*ctx).current).layout.is_null()
+        || 0 != (*(*(*ctx).current).layout).flags & NK_WINDOW_ROM as Int as Uint
+        || button >= NK_BUTTON_MAX
+    {
+        return 0i32;
+    } else {
+        in_0 = &(*ctx).input as *const Input;
+        if 0 == nk_input_is_mouse_pressed(in_0, button)
+            || 0 == nk_input_has_mouse_click_in_rect(in_0, button, (*ctx).last_widget_bounds)
+        {
+            return 0i32;
+        } else {
+            return (*in_0).mouse.click_count[button as usize] as Int;
+        }
+    };
+}
+
+/// Whether the last widget was double-clicked with the left button, e.g.
+/// right after `nk_selectable_label` or `nk_tree_push_hashed`.
+pub unsafe fn nk_widget_is_double_clicked(mut ctx: *mut Context) -> Int {
+    return (nk_widget_clicks(ctx, NK_BUTTON_LEFT) == 2i32) as Int;
+}
+
+pub unsafe fn nk_widget_is_triple_clicked(mut ctx: *mut Context) -> Int {
+    return (nk_widget_clicks(ctx, NK_BUTTON_LEFT) == 3i32) as Int;
+}
+
+pub type DropStates = u32;
+pub const NK_DROP_DELIVERED: DropStates = 2;
+pub const NK_DROP_HOVER: DropStates = 1;
+pub const NK_DROP_NONE: DropStates = 0;
+
+/// Makes the last widget a drag source. Once the left mouse button was
+/// pressed on it and moved past `DragDrop::threshold`, `size` bytes of
+/// `data` are copied into the context under the type `tag` and 1 is
+/// returned. A context without an allocator or OOM fallback refuses
+/// payloads and tags that do not fit into `DragDrop::memory` together.
+pub unsafe fn nk_drag_source(
+    mut ctx: *mut Context,
+    mut tag: *const Char,
+    mut data: *const Void,
+    mut size: Int,
+) -> Int {
+    let mut in_0: *const Input = 0 as *const Input;
+    let mut drag: *mut DragDrop = 0 as *mut DragDrop;
+    let mut tag_len: Int = 0;
+    if ctx.is_null()
+        || tag.is_null()
+        || (*ctx).current.is_null()
+        || (*(*ctx).current).layout.is_null()
+        || 0 != (*(*(*ctx).current).layout).flags & NK_WINDOW_ROM as Int as Uint
+    {
+        return 0i32;
+    } else {
+        in_0 = &(*ctx).input as *const Input;
+        drag = &mut (*ctx).drag as *mut DragDrop;
+        tag_len = nk_strlen(tag);
+        if 0 != (*drag).active
+            || 0 != (*in_0).mouse.grabbed
+            || size < 0i32
+            || size > 0i32 && data.is_null()
+        {
+            return 0i32;
+        } else if 0 == nk_input_has_mouse_click_down_in_rect(
+            in_0,
+            NK_BUTTON_LEFT,
+            (*ctx).last_widget_bounds,
+            nk_true as Int,
+        ) {
+            return 0i32;
+        } else {
+            let mut dx: f32 = (*in_0).mouse.pos.x
+                - (*in_0).mouse.buttons[NK_BUTTON_LEFT as usize].clicked_pos.x;
+            let mut dy: f32 = (*in_0).mouse.pos.y
+                - (*in_0).mouse.buttons[NK_BUTTON_LEFT as usize].clicked_pos.y;
+            if dx * dx + dy * dy <= (*drag).threshold * (*drag).threshold {
+                return 0i32;
+            } else {
+                /* the payload goes first so it keeps the buffer's alignment */
+                let mut payload: *mut Buffer = &mut (*drag).payload;
+                nk_buffer_clear(payload);
+                nk_buffer_push(payload, NK_BUFFER_FRONT, data, size as Size, 1);
+                let mut tag_size: Size = (tag_len + 1i32) as Size;
+                nk_buffer_push(payload, NK_BUFFER_FRONT, tag as *const Void, tag_size, 1);
+                if (*payload).allocated != size as Size + tag_size {
+                    nk_buffer_clear(payload);
+                    return 0i32;
+                }
+                (*drag).size = size;
+                (*drag).source_window = (*(*ctx).current).name;
+                (*drag).source = (*ctx).last_widget_bounds;
+                (*drag).active = nk_true as Int;
+                (*drag).dropping = nk_false as Int;
+                (*drag).delivered = nk_false as Int;
+                return 1i32;
+            }
+        }
+    };
+}
+
+/// Whether the last widget is the source of the drag in progress, e.g. to
+/// draw a gap where a dragged list item was.
+pub unsafe fn nk_drag_is_source(mut ctx: *mut Context) -> Int {
+    if ctx.is_null() || (*ctx).current.is_null() || 0 == (*ctx).drag.active {
+        return 0i32;
+    } else {
+        let mut a: Rect = (*ctx).drag.source;
+        let mut b: Rect = (*ctx).last_widget_bounds;
+        return ((*(*ctx).current).name == (*ctx).drag.source_window
+            && a.x == b.x
+            && a.y == b.y
+            && a.w == b.w
+            && a.h == b.h) as Int;
+    };
+}
+
+/// Whether a payload of type `tag` (any type if `tag` is null) is dragged.
+pub unsafe fn nk_drag_is_active(mut ctx: *const Context, mut tag: *const Char) -> Int {
+    if ctx.is_null() || 0 == (*ctx).drag.active {
+        return 0i32;
+    } else if tag.is_null() {
+        return 1i32;
+    } else {
+        /* tags are compared case sensitively */
+        let mut own: *const Char =
+            ((*ctx).drag.payload.memory.ptr as *const Char).offset((*ctx).drag.size as isize);
+        let mut i: isize = 0;
+        loop {
+            if *own.offset(i) != *tag.offset(i) {
+                return 0i32;
+            } else if *own.offset(i) == 0i32 as Char {
+                return 1i32;
+            }
+            i += 1
+        }
+    };
+}
+
+/// Payload of the drag in progress if its type is `tag`, null otherwise.
+pub unsafe fn nk_drag_payload(mut ctx: *const Context, mut tag: *const Char, mut size: *mut Int) -> *const Void {
+    if 0 == nk_drag_is_active(ctx, tag) {
+        return 0 as *const Void;
+    } else {
+        if !size.is_null() {
+            *size = (*ctx).drag.size
+        }
+        return (*ctx).drag.payload.memory.ptr as *const Void;
+    };
+}
+
+/// Drops the payload without delivering it.
+pub unsafe fn nk_drag_cancel(mut ctx: *mut Context) -> () {
+    if ctx.is_null() {
+        return;
+    } else {
+        (*ctx).drag.active = nk_false as Int;
+        (*ctx).drag.dropping = nk_false as Int;
+        (*ctx).drag.delivered = nk_false as Int;
+        return;
+    };
+}
+
+/* topmost window (or open popup) under the mouse that takes input */
+unsafe fn nk_drag_window_under_mouse(mut ctx: *mut Context) -> *mut Window {
+    let mut iter: *mut Window = (*ctx).end;
+    while !iter.is_null() {
+        if 0 == (*iter).flags
+            & (NK_WINDOW_HIDDEN as Int | NK_WINDOW_CLOSED as Int | NK_WINDOW_NO_INPUT as Int) as Uint
+            && 0 != nk_input_is_mouse_hovering_rect(&(*ctx).input, (*iter).bounds)
+        {
+            let mut popup: *mut Window = (*iter).popup.win;
+            if !popup.is_null()
+                && 0 != (*iter).popup.active
+                && 0 != nk_input_is_mouse_hovering_rect(&(*ctx).input, (*popup).bounds)
+            {
+                return popup;
+            } else {
+                return iter;
+            }
+        }
+        iter = (*iter).prev
+    }
+    return 0 as *mut Window;
+}
+
+/// Makes `bounds` of the current window a drop target for payloads of type
+/// `tag`. Returns `NK_DROP_HOVER` while a matching payload is over it and
+/// `NK_DROP_DELIVERED` on the frame it is dropped there; read it with
+/// `nk_drag_payload` during that frame. Only the first target to see the
+/// drop receives it.
+pub unsafe fn nk_drop_target_in_rect(mut ctx: *mut Context, mut tag: *const Char, mut bounds: Rect) -> DropStates {
+    let mut win: *mut Window = 0 as *mut Window;
+    let mut ret: DropStates = NK_DROP_NONE;
+    if ctx.is_null()
+        || (*ctx).current.is_null()
+        || (*(*ctx).current).layout.is_null()
+        || 0 == nk_drag_is_active(ctx, tag)
+    {
+        return NK_DROP_NONE;
+    } else {
+        win = (*ctx).current;
+        if nk_drag_window_under_mouse(ctx) != win
+            || 0 == nk_input_is_mouse_hovering_rect(&(*ctx).input, bounds)
+            || 0 == nk_input_is_mouse_hovering_rect(&(*ctx).input, (*(*win).layout).clip)
+        {
+            return NK_DROP_NONE;
+        } else {
+            ret = NK_DROP_HOVER;
+            if 0 != (*ctx).drag.dropping && 0 == (*ctx).drag.delivered {
+                (*ctx).drag.delivered = nk_true as Int;
+                ret = NK_DROP_DELIVERED
+            }
+            let mut style: *const StyleDragDrop = &(*ctx).style.drag;
+            nk_stroke_rect(
+                &mut (*win).buffer,
+                bounds,
+                (*style).rounding,
+                (*style).border,
+                (*style).highlight,
+            );
+            return ret;
+        }
+    };
+}
+
+/// `nk_drop_target_in_rect` for the last widget.
+pub unsafe fn nk_drop_target(mut ctx: *mut Context, mut tag: *const Char) -> DropStates {
+    if ctx.is_null() {
+        return NK_DROP_NONE;
+    } else {
+        return nk_drop_target_in_rect(ctx, tag, (*ctx).last_widget_bounds);
+    };
+}
+
+/// `nk_drop_target_in_rect` for the visible content of the current window
+/// or group. Call it after their widgets so drop targets inside take
+/// precedence.
+pub unsafe fn nk_window_drop_target(mut ctx: *mut Context, mut tag: *const Char) -> DropStates {
+    if ctx.is_null() || (*ctx).current.is_null() || (*(*ctx).current).layout.is_null() {
+        return NK_DROP_NONE;
+    } else {
+        return nk_drop_target_in_rect(ctx, tag, (*(*(*ctx).current).layout).clip);
+    };
+}
+
+/// Opens a window of `size` under the cursor while a drag is in progress.
+/// Call outside of other windows and, only if it returned 1, close it with
+/// `nk_drag_preview_end`.
+pub unsafe fn nk_drag_preview_begin(mut ctx: *mut Context, mut size: Point) -> Int {
+    if ctx.is_null() || 0 == (*ctx).drag.active || !(*ctx).current.is_null() {
+        return 0i32;
+    } else {
+        let mut bounds: Rect = Rect {
+            x: (*ctx).input.mouse.pos.x + 12.0f32,
+            y: (*ctx).input.mouse.pos.y + 12.0f32,
+            w: size.x,
+            h: size.y,
+        };
+        nk_begin(
+            ctx,
+            b"nk_drag_preview\x00" as *const u8 as *const Char,
+            bounds,
+            (NK_WINDOW_NO_INPUT as Int | NK_WINDOW_NO_SCROLLBAR as Int | NK_WINDOW_BORDER as Int)
+                as Flags,
+        );
+        return 1i32;
+    };
+}
+
+pub unsafe fn nk_drag_preview_end(mut ctx: *mut Context) -> () {
+    nk_end(ctx);
+}
+
 pub unsafe fn nk_widget_is_hovered(mut ctx: *mut Context) -> Int {
     let mut c: Rect = Rect {
         x: 0.,
@@ -13017,7 +16289,7 @@ pub unsafe fn Image(mut ctx: *mut Context, mut img: Image) -> () {
         return;
     } else {
         win = (*ctx).current;
-        if 0 == nk_widget(&mut bounds, ctx) as u64 {
+        if 0 == nk_widget_nav(&mut bounds, ctx) as u64 {
             return;
         } else {
             nk_draw_image(&mut (*win).buffer, bounds, &mut img, nk_white);
@@ -13038,7 +16310,7 @@ pub unsafe fn nk_image_color(mut ctx: *mut Context, mut img: Image, mut col: Col
         return;
     } else {
         win = (*ctx).current;
-        if 0 == nk_widget(&mut bounds, ctx) as u64 {
+        if 0 == nk_widget_nav(&mut bounds, ctx) as u64 {
             return;
         } else {
             nk_draw_image(&mut (*win).buffer, bounds, &mut img, col);
@@ -13080,7 +16352,8 @@ pub unsafe fn nk_button_text_styled(
     } else {
         win = (*ctx).current;
         layout = (*win).layout;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
+        let nav = nk_nav_widget(ctx, bounds, state);
         if 0 == state as u64 {
             return 0i32;
         } else {
@@ -13091,7 +16364,7 @@ pub unsafe fn nk_button_text_styled(
             } else {
                 &mut (*ctx).input as *mut Input
             };
-            return nk_do_button_text(
+            let ret = nk_do_button_text(
                 &mut (*ctx).last_widget_state,
                 &mut (*win).buffer,
                 bounds,
@@ -13103,6 +16376,7 @@ pub unsafe fn nk_button_text_styled(
                 in_0,
                 (*ctx).style.font,
             );
+            return ret | nk_nav_finish(ctx, nav);
         }
     };
 }
@@ -13303,7 +16577,8 @@ pub unsafe fn nk_button_color(mut ctx: *mut Context, mut color: Color) -> Int {
     } else {
         win = (*ctx).current;
         layout = (*win).layout;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
+        let nav = nk_nav_widget(ctx, bounds, state);
         if 0 == state as u64 {
             return 0i32;
         } else {
@@ -13333,7 +16608,7 @@ pub unsafe fn nk_button_color(mut ctx: *mut Context, mut color: Color) -> Int {
                 (*ctx).last_widget_state,
                 &mut button,
             );
-            return ret;
+            return ret | nk_nav_finish(ctx, nav);
         }
     };
 }
@@ -13366,7 +16641,8 @@ pub unsafe fn nk_button_symbol_styled(
     } else {
         win = (*ctx).current;
         layout = (*win).layout;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
+        let nav = nk_nav_widget(ctx, bounds, state);
         if 0 == state as u64 {
             return 0i32;
         } else {
@@ -13377,7 +16653,7 @@ pub unsafe fn nk_button_symbol_styled(
             } else {
                 &mut (*ctx).input as *mut Input
             };
-            return nk_do_button_symbol(
+            let ret = nk_do_button_symbol(
                 &mut (*ctx).last_widget_state,
                 &mut (*win).buffer,
                 bounds,
@@ -13387,6 +16663,7 @@ pub unsafe fn nk_button_symbol_styled(
                 in_0,
                 (*ctx).style.font,
             );
+            return ret | nk_nav_finish(ctx, nav);
         }
     };
 }
@@ -13419,7 +16696,8 @@ pub unsafe fn nk_button_image_styled(
     } else {
         win = (*ctx).current;
         layout = (*win).layout;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
+        let nav = nk_nav_widget(ctx, bounds, state);
         if 0 == state as u64 {
             return 0i32;
         } else {
@@ -13430,7 +16708,7 @@ pub unsafe fn nk_button_image_styled(
             } else {
                 &mut (*ctx).input as *mut Input
             };
-            return nk_do_button_image(
+            let ret = nk_do_button_image(
                 &mut (*ctx).last_widget_state,
                 &mut (*win).buffer,
                 bounds,
@@ -13439,6 +16717,7 @@ pub unsafe fn nk_button_image_styled(
                 style,
                 in_0,
             );
+            return ret | nk_nav_finish(ctx, nav);
         }
     };
 }
@@ -13541,7 +16820,8 @@ pub unsafe fn nk_button_symbol_text_styled(
     } else {
         win = (*ctx).current;
         layout = (*win).layout;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
+        let nav = nk_nav_widget(ctx, bounds, state);
         if 0 == state as u64 {
             return 0i32;
         } else {
@@ -13552,7 +16832,7 @@ pub unsafe fn nk_button_symbol_text_styled(
             } else {
                 &mut (*ctx).input as *mut Input
             };
-            return nk_do_button_text_symbol(
+            let ret = nk_do_button_text_symbol(
                 &mut (*ctx).last_widget_state,
                 &mut (*win).buffer,
                 bounds,
@@ -13565,6 +16845,7 @@ pub unsafe fn nk_button_symbol_text_styled(
                 (*ctx).style.font,
                 in_0,
             );
+            return ret | nk_nav_finish(ctx, nav);
         }
     };
 }
@@ -13748,7 +17029,8 @@ pub unsafe fn nk_button_image_text_styled(
     } else {
         win = (*ctx).current;
         layout = (*win).layout;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
+        let nav = nk_nav_widget(ctx, bounds, state);
         if 0 == state as u64 {
             return 0i32;
         } else {
@@ -13759,7 +17041,7 @@ pub unsafe fn nk_button_image_text_styled(
             } else {
                 &mut (*ctx).input as *mut Input
             };
-            return nk_do_button_text_image(
+            let ret = nk_do_button_text_image(
                 &mut (*ctx).last_widget_state,
                 &mut (*win).buffer,
                 bounds,
@@ -13772,6 +17054,7 @@ pub unsafe fn nk_button_image_text_styled(
                 (*ctx).style.font,
                 in_0,
             );
+            return ret | nk_nav_finish(ctx, nav);
         }
     };
 }
@@ -14013,7 +17296,8 @@ pub unsafe fn nk_check_text(
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
         layout = (*win).layout;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
+        let nav = nk_nav_widget(ctx, bounds, state);
         if 0 == state as u64 {
             return active;
         } else {
@@ -14036,6 +17320,9 @@ pub unsafe fn nk_check_text(
                 in_0,
                 (*style).font,
             );
+            if 0 != nk_nav_finish(ctx, nav) {
+                active = (0 == active) as Int
+            }
             return active;
         }
     };
@@ -14198,13 +17485,9 @@ unsafe fn nk_draw_option(
         text.text = (*style).text_normal
     }
     /* draw background and cursor */
-    if (*background).type_0 as Uint == NK_STYLE_ITEM_COLOR as Int as Uint {
+    if (*background).type_0 as Uint != NK_STYLE_ITEM_IMAGE as Int as Uint {
         nk_fill_circle(out, *selector, (*style).border_color);
-        nk_fill_circle(
-            out,
-            nk_shrink_rect(*selector, (*style).border),
-            (*background).data.color,
-        );
+        nk_fill_style_item_circle(out, nk_shrink_rect(*selector, (*style).border), background);
     } else {
         nk_draw_image(out, *selector, &(*background).data.image, nk_white);
     }
@@ -14212,7 +17495,7 @@ unsafe fn nk_draw_option(
         if (*cursor).type_0 as Uint == NK_STYLE_ITEM_IMAGE as Int as Uint {
             nk_draw_image(out, *cursors, &(*cursor).data.image, nk_white);
         } else {
-            nk_fill_circle(out, *cursors, (*cursor).data.color);
+            nk_fill_style_item_circle(out, *cursors, cursor);
         }
     }
     text.padding.x = 0i32 as f32;
@@ -14272,13 +17555,13 @@ unsafe fn nk_draw_checkbox(
         text.text = (*style).text_normal
     }
     /* draw background and cursor */
-    if (*background).type_0 as Uint == NK_STYLE_ITEM_COLOR as Int as Uint {
+    if (*background).type_0 as Uint != NK_STYLE_ITEM_IMAGE as Int as Uint {
         nk_fill_rect(out, *selector, 0i32 as f32, (*style).border_color);
-        nk_fill_rect(
+        nk_fill_style_item(
             out,
             nk_shrink_rect(*selector, (*style).border),
             0i32 as f32,
-            (*background).data.color,
+            background,
         );
     } else {
         nk_draw_image(out, *selector, &(*background).data.image, nk_white);
@@ -14287,7 +17570,7 @@ unsafe fn nk_draw_checkbox(
         if (*cursor).type_0 as Uint == NK_STYLE_ITEM_IMAGE as Int as Uint {
             nk_draw_image(out, *cursors, &(*cursor).data.image, nk_white);
         } else {
-            nk_fill_rect(out, *cursors, 0i32 as f32, (*cursor).data.color);
+            nk_fill_style_item(out, *cursors, 0i32 as f32, cursor);
         }
     }
     text.padding.x = 0i32 as f32;
@@ -14463,7 +17746,8 @@ pub unsafe fn nk_option_text(
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
         layout = (*win).layout;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
+        let nav = nk_nav_widget(ctx, bounds, state);
         if 0 == state as u64 {
             return state as Int;
         } else {
@@ -14486,6 +17770,9 @@ pub unsafe fn nk_option_text(
                 in_0,
                 (*style).font,
             );
+            if 0 != nk_nav_finish(ctx, nav) {
+                is_active = nk_true as Int
+            }
             return is_active;
         }
     };
@@ -14536,7 +17823,7 @@ pub unsafe fn nk_selectable_text(
         win = (*ctx).current;
         layout = (*win).layout;
         style = &mut (*ctx).style as *mut Style;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
         if 0 == state as u64 {
             return 0i32;
         } else {
@@ -14602,7 +17889,7 @@ pub unsafe fn nk_selectable_image_text(
         win = (*ctx).current;
         layout = (*win).layout;
         style = &mut (*ctx).style as *mut Style;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
         if 0 == state as u64 {
             return 0i32;
         } else {
@@ -14669,7 +17956,7 @@ pub unsafe fn nk_selectable_symbol_text(
         win = (*ctx).current;
         layout = (*win).layout;
         style = &mut (*ctx).style as *mut Style;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
         if 0 == state as u64 {
             return 0i32;
         } else {
@@ -14890,7 +18177,8 @@ pub unsafe fn nk_slider_float(
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
         layout = (*win).layout;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
+        let nav = nk_nav_widget(ctx, bounds, state);
         if 0 == state as u64 {
             return ret;
         } else {
@@ -14913,6 +18201,17 @@ pub unsafe fn nk_slider_float(
                 in_0,
                 (*style).font,
             );
+            nk_nav_finish(ctx, nav);
+            if 0 != nk_nav_direction(nav) {
+                *value = *value + value_step * nk_nav_direction(nav) as f32;
+                *value = if *value < min_value {
+                    min_value
+                } else if *value > max_value {
+                    max_value
+                } else {
+                    *value
+                }
+            }
             return (old_value > *value || old_value < *value) as Int;
         }
     };
@@ -15126,7 +18425,7 @@ unsafe fn nk_draw_slider(
     if (*background).type_0 as Uint == NK_STYLE_ITEM_IMAGE as Int as Uint {
         nk_draw_image(out, *bounds, &(*background).data.image, nk_white);
     } else {
-        nk_fill_rect(out, *bounds, (*style).rounding, (*background).data.color);
+        nk_fill_style_item(out, *bounds, (*style).rounding, background);
         nk_stroke_rect(
             out,
             *bounds,
@@ -15142,7 +18441,7 @@ unsafe fn nk_draw_slider(
     if (*cursor).type_0 as Uint == NK_STYLE_ITEM_IMAGE as Int as Uint {
         nk_draw_image(out, *visual_cursor, &(*cursor).data.image, nk_white);
     } else {
-        nk_fill_circle(out, *visual_cursor, (*cursor).data.color);
+        nk_fill_style_item_circle(out, *visual_cursor, cursor);
     };
 }
 unsafe fn nk_slider_behavior(
@@ -15274,7 +18573,7 @@ pub unsafe fn nk_progress(
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
         layout = (*win).layout;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
         if 0 == state as u64 {
             return 0i32;
         } else {
@@ -15378,8 +18677,8 @@ unsafe fn nk_draw_progress(
         cursor = &(*style).cursor_normal as *const StyleItem
     }
     /* draw background */
-    if (*background).type_0 as Uint == NK_STYLE_ITEM_COLOR as Int as Uint {
-        nk_fill_rect(out, *bounds, (*style).rounding, (*background).data.color);
+    if (*background).type_0 as Uint != NK_STYLE_ITEM_IMAGE as Int as Uint {
+        nk_fill_style_item(out, *bounds, (*style).rounding, background);
         nk_stroke_rect(
             out,
             *bounds,
@@ -15391,8 +18690,8 @@ unsafe fn nk_draw_progress(
         nk_draw_image(out, *bounds, &(*background).data.image, nk_white);
     }
     /* draw cursor */
-    if (*cursor).type_0 as Uint == NK_STYLE_ITEM_COLOR as Int as Uint {
-        nk_fill_rect(out, *scursor, (*style).rounding, (*cursor).data.color);
+    if (*cursor).type_0 as Uint != NK_STYLE_ITEM_IMAGE as Int as Uint {
+        nk_fill_style_item(out, *scursor, (*style).rounding, cursor);
         nk_stroke_rect(
             out,
             *scursor,
@@ -15535,7 +18834,7 @@ pub unsafe fn nk_color_pick(
         win = (*ctx).current;
         config = &mut (*ctx).style as *mut Style;
         layout = (*win).layout;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
         if 0 == state as u64 {
             return 0i32;
         } else {
@@ -16178,6 +19477,43 @@ pub unsafe fn nk_property_int(
         return;
     };
 }
+/* steps a property by `dir` times its step like its arrow buttons do */
+unsafe fn nk_nav_property_step(mut variant: *mut PropertyVariant, mut dir: Int) -> () {
+    let v = &mut *variant;
+    match v.kind {
+        NK_PROPERTY_INT => {
+            let i = v.value.i + v.step.i * dir;
+            v.value.i = if i < v.min_value.i {
+                v.min_value.i
+            } else if i > v.max_value.i {
+                v.max_value.i
+            } else {
+                i
+            }
+        }
+        NK_PROPERTY_FLOAT => {
+            let f = v.value.f + v.step.f * dir as f32;
+            v.value.f = if f < v.min_value.f {
+                v.min_value.f
+            } else if f > v.max_value.f {
+                v.max_value.f
+            } else {
+                f
+            }
+        }
+        _ => {
+            let d = v.value.d + v.step.d * dir as f64;
+            v.value.d = if d < v.min_value.d {
+                v.min_value.d
+            } else if d > v.max_value.d {
+                v.max_value.d
+            } else {
+                d
+            }
+        }
+    }
+}
+
 unsafe fn Property(
     mut ctx: *mut Context,
     mut name: *const Char,
@@ -16216,7 +19552,8 @@ unsafe fn Property(
         win = (*ctx).current;
         layout = (*win).layout;
         style = &mut (*ctx).style as *mut Style;
-        s = nk_widget(&mut bounds, ctx);
+        s = nk_widget_nav(&mut bounds, ctx);
+        let nav = nk_nav_widget(ctx, bounds, s);
         if 0 == s as u64 {
             return;
         } else {
@@ -16298,6 +19635,10 @@ unsafe fn Property(
                     (*ctx).input.mouse.grabbed = nk_true as Int as Uchar
                 }
             }
+            nk_nav_finish(ctx, nav);
+            if 0 != nk_nav_direction(nav) && 0 == (*win).property.active {
+                nk_nav_property_step(variant, nk_nav_direction(nav));
+            }
             /* check if previously active property is now inactive */
             if *state == NK_PROPERTY_DEFAULT as Int && old_state != NK_PROPERTY_DEFAULT as Int {
                 if old_state == NK_PROPERTY_DRAG as Int {
@@ -16892,6 +20233,7 @@ unsafe fn nk_do_property(
             0 as *mut Input
         },
         font,
+        0 as *mut Preedit,
     );
     *length = (*text_edit).string.len;
     *cursor = (*text_edit).cursor;
@@ -17218,6 +20560,7 @@ unsafe fn nk_do_edit(
     mut style: *const StyleEdit,
     mut in_0: *mut Input,
     mut font: *const UserFont,
+    mut preedit: *mut Preedit,
 ) -> Flags {
     let mut l: Int = 0;
     let mut remaining: *const Char = 0 as *const Char;
@@ -17404,7 +20747,7 @@ unsafe fn nk_do_edit(
             if 0 != (*in_0).keyboard.text_len {
                 nk_textedit_text(
                     edit,
-                    (*in_0).keyboard.text.as_mut_ptr(),
+                    (*in_0).keyboard.text.memory.ptr as *const Char,
                     (*in_0).keyboard.text_len,
                 );
                 cursor_follow = nk_true as Int as Char;
@@ -17485,7 +20828,7 @@ unsafe fn nk_do_edit(
             background = &(*style).normal as *const StyleItem
         }
         /* draw background frame */
-        if (*background).type_0 as Uint == NK_STYLE_ITEM_COLOR as Int as Uint {
+        if (*background).type_0 as Uint != NK_STYLE_ITEM_IMAGE as Int as Uint {
             nk_stroke_rect(
                 out,
                 bounds,
@@ -17493,7 +20836,7 @@ unsafe fn nk_do_edit(
                 (*style).border,
                 (*style).border_color,
             );
-            nk_fill_rect(out, bounds, (*style).rounding, (*background).data.color);
+            nk_fill_style_item(out, bounds, (*style).rounding, background);
         } else {
             nk_draw_image(out, bounds, &(*background).data.image, nk_white);
         }
@@ -17735,6 +21078,38 @@ unsafe fn nk_do_edit(
                     font,
                 )
             }
+            /* IME composition, drawn at the cursor while nothing is selected */
+            let mut preedit_len: Int = 0i32;
+            let mut preedit_width: f32 = 0i32 as f32;
+            let mut caret_offset: f32 = 0i32 as f32;
+            if !preedit.is_null() && !in_0.is_null() {
+                if 0 != (*preedit).len && (*edit).select_start == (*edit).select_end {
+                    preedit_len = (*preedit).len;
+                    preedit_width = (*font).width.expect("non-null function pointer")(
+                        (*font).userdata,
+                        (*font).height,
+                        (*preedit).text.as_ptr(),
+                        preedit_len,
+                    );
+                    caret_offset = (*font).width.expect("non-null function pointer")(
+                        (*font).userdata,
+                        (*font).height,
+                        (*preedit).text.as_ptr(),
+                        nk_preedit_offset(preedit, (*preedit).cursor),
+                    )
+                }
+                (*preedit).caret.x = area.x + cursor_pos.x + caret_offset - (*edit).scrollbar.x;
+                (*preedit).caret.y = area.y + cursor_pos.y + row_height / 2.0f32
+                    - (*font).height / 2.0f32
+                    - (*edit).scrollbar.y;
+                (*preedit).caret.w = if (*style).cursor_size < 1.0f32 {
+                    1.0f32
+                } else {
+                    (*style).cursor_size
+                };
+                (*preedit).caret.h = (*font).height;
+                (*preedit).has_caret = nk_true as Int
+            }
             /* draw text */
             let mut background_color: Color = Color {
                 r: 0,
@@ -17800,9 +21175,60 @@ unsafe fn nk_do_edit(
             if (*background_0).type_0 as Uint == NK_STYLE_ITEM_IMAGE as Int as Uint {
                 background_color = nk_rgba(0i32, 0i32, 0i32, 0i32)
             } else {
-                background_color = (*background_0).data.color
+                background_color = nk_style_item_background(background_0)
             }
-            if (*edit).select_start == (*edit).select_end {
+            if 0 != preedit_len {
+                /* text before the cursor, the composition and the rest moved past it */
+                begin_0 = nk_str_get_const(&mut (*edit).string);
+                l = nk_str_len_char(&mut (*edit).string);
+                let mut split: Int = if cursor_ptr.is_null() {
+                    l
+                } else {
+                    begin_0.ptr_offset_to(cursor_ptr).expect("bad offset_to") as Int
+                };
+                nk_edit_draw_text(
+                    out,
+                    style,
+                    area.x - (*edit).scrollbar.x,
+                    area.y - (*edit).scrollbar.y,
+                    0i32 as f32,
+                    begin_0,
+                    split,
+                    row_height,
+                    font,
+                    background_color,
+                    text_color,
+                    nk_false as Int,
+                );
+                nk_edit_draw_preedit(
+                    out,
+                    preedit,
+                    area.x + cursor_pos.x - (*edit).scrollbar.x,
+                    area.y + cursor_pos.y - (*edit).scrollbar.y,
+                    row_height,
+                    font,
+                    background_color,
+                    text_color,
+                    sel_background_color,
+                    sel_text_color,
+                );
+                if split < l {
+                    nk_edit_draw_text(
+                        out,
+                        style,
+                        area.x - (*edit).scrollbar.x,
+                        area.y + cursor_pos.y - (*edit).scrollbar.y,
+                        cursor_pos.x + preedit_width,
+                        begin_0.offset(split as isize),
+                        l - split,
+                        row_height,
+                        font,
+                        background_color,
+                        text_color,
+                        nk_false as Int,
+                    );
+                }
+            } else if (*edit).select_start == (*edit).select_end {
                 /* no selection so just draw the complete text */
                 begin_0 = nk_str_get_const(&mut (*edit).string);
                 l = nk_str_len_char(&mut (*edit).string);
@@ -17889,7 +21315,10 @@ unsafe fn nk_do_edit(
                 }
             }
             /* cursor */
-            if (*edit).select_start == (*edit).select_end {
+            if 0 != preedit_len {
+                /* thin caret inside the composition */
+                nk_fill_rect(out, (*preedit).caret, 0i32 as f32, cursor_color);
+            } else if (*edit).select_start == (*edit).select_end {
                 if (*edit).cursor >= nk_str_len(&mut (*edit).string)
                     || !cursor_ptr.is_null() && *cursor_ptr as Int == '\n' as i32
                 {
@@ -17987,7 +21416,7 @@ unsafe fn nk_do_edit(
             if (*background_1).type_0 as Uint == NK_STYLE_ITEM_IMAGE as Int as Uint {
                 background_color_0 = nk_rgba(0i32, 0i32, 0i32, 0i32)
             } else {
-                background_color_0 = (*background_1).data.color
+                background_color_0 = nk_style_item_background(background_1)
             }
             nk_edit_draw_text(
                 out,
@@ -18009,6 +21438,76 @@ unsafe fn nk_do_edit(
     };
 }
 
+/* composition text with the converted clause highlighted and underlined */
+unsafe fn nk_edit_draw_preedit(
+    mut out: *mut CommandBuffer,
+    mut preedit: *const Preedit,
+    mut x: f32,
+    mut y: f32,
+    mut row_height: f32,
+    mut font: *const UserFont,
+    mut background: Color,
+    mut foreground: Color,
+    mut sel_background: Color,
+    mut sel_foreground: Color,
+) -> () {
+    let mut text: *const Char = (*preedit).text.as_ptr();
+    let mut bounds: [Int; 4] = [
+        0i32,
+        nk_preedit_offset(preedit, (*preedit).sel_start),
+        nk_preedit_offset(preedit, (*preedit).sel_end),
+        (*preedit).len,
+    ];
+    let mut underline_y: f32 = y + row_height / 2.0f32 + (*font).height / 2.0f32;
+    let mut i: Int = 0i32;
+    while i < 3i32 {
+        let mut begin: Int = bounds[i as usize];
+        let mut len: Int = bounds[(i + 1i32) as usize] - begin;
+        if len > 0i32 {
+            let mut selected: Int = (i == 1i32) as Int;
+            let mut txt: Text = Text {
+                padding: Point::new(0i32 as f32, 0i32 as f32),
+                background: if 0 != selected { sel_background } else { background },
+                text: if 0 != selected { sel_foreground } else { foreground },
+            };
+            let mut label: Rect = Rect {
+                x: x,
+                y: y,
+                w: (*font).width.expect("non-null function pointer")(
+                    (*font).userdata,
+                    (*font).height,
+                    text.offset(begin as isize),
+                    len,
+                ),
+                h: row_height,
+            };
+            if 0 != selected {
+                nk_fill_rect(out, label, 0i32 as f32, sel_background);
+            }
+            nk_widget_text(
+                out,
+                label,
+                text.offset(begin as isize),
+                len,
+                &mut txt,
+                NK_TEXT_LEFT as Int as Flags,
+                font,
+            );
+            nk_stroke_line(
+                out,
+                label.x,
+                underline_y,
+                label.x + label.w,
+                underline_y,
+                if 0 != selected { 2.0f32 } else { 1.0f32 },
+                foreground,
+            );
+            x += label.w
+        }
+        i += 1
+    }
+}
+
 pub unsafe fn nk_str_len_char(mut s: *mut Str) -> Int {
     if s.is_null() || 0 == (*s).len || 0 == (*s).buffer.allocated {
         return 0i32;
@@ -20095,14 +23594,14 @@ unsafe fn nk_draw_property(
         nk_draw_image(out, *bounds, &(*background).data.image, nk_white);
         text.background = nk_rgba(0i32, 0i32, 0i32, 0i32)
     } else {
-        text.background = (*background).data.color;
-        nk_fill_rect(out, *bounds, (*style).rounding, (*background).data.color);
+        text.background = nk_style_item_background(background);
+        nk_fill_style_item(out, *bounds, (*style).rounding, background);
         nk_stroke_rect(
             out,
             *bounds,
             (*style).rounding,
             (*style).border,
-            (*background).data.color,
+            nk_style_item_background(background),
         );
     }
     /* draw label */
@@ -20714,7 +24213,7 @@ pub unsafe fn nk_edit_buffer(
     } else {
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
-        state = nk_widget(&mut bounds, ctx);
+        state = nk_widget_nav(&mut bounds, ctx);
         if 0 == state as u64 {
             return state as Flags;
         } else {
@@ -20764,6 +24263,7 @@ pub unsafe fn nk_edit_buffer(
                 &mut (*style).edit,
                 in_0,
                 (*style).font,
+                &mut (*ctx).preedit,
             );
             if 0 != (*ctx).last_widget_state & NK_WIDGET_STATE_HOVER as Int as Uint {
                 (*ctx).style.cursor_active = (*ctx).style.cursors[NK_CURSOR_TEXT as Int as usize]
@@ -20883,7 +24383,7 @@ pub unsafe fn nk_chart_begin_colored(
     };
     if ctx.is_null() || (*ctx).current.is_null() || (*(*ctx).current).layout.is_null() {
         return 0i32;
-    } else if 0 == nk_widget(&mut bounds, ctx) as u64 {
+    } else if 0 == nk_widget_nav(&mut bounds, ctx) as u64 {
         chart = &mut (*(*(*ctx).current).layout).chart as *mut Chart;
         nk_zero(chart as *mut Void, mem::size_of::<Chart>() as Ulong);
         return 0i32;
@@ -20943,11 +24443,11 @@ pub unsafe fn nk_chart_begin_colored(
                 (*style).rounding,
                 (*style).border_color,
             );
-            nk_fill_rect(
+            nk_fill_style_item(
                 &mut (*win).buffer,
                 nk_shrink_rect(bounds, (*style).border),
                 (*style).rounding,
-                (*style).background.data.color,
+                background,
             );
         }
         return 1i32;
@@ -21375,6 +24875,10 @@ pub unsafe fn nk_popup_begin(
         (*popup).bounds = rect;
         (*popup).seq = (*ctx).seq;
         (*popup).layout = nk_create_panel(ctx) as *mut Panel;
+        if (*popup).layout.is_null() {
+            (*ctx).current = win;
+            return 0i32;
+        }
         (*popup).flags = flags;
         (*popup).flags |= NK_WINDOW_BORDER as Int as Uint;
         if type_0 as Uint == NK_POPUP_DYNAMIC as Int as Uint {
@@ -21706,7 +25210,8 @@ pub unsafe fn nk_combo_begin_text(
     } else {
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
-        s = nk_widget(&mut header, ctx);
+        s = nk_widget_nav(&mut header, ctx);
+        let nav = nk_nav_widget(ctx, header, s);
         if s as Uint == NK_WIDGET_INVALID as Int as Uint {
             return 0i32;
         } else {
@@ -21745,13 +25250,8 @@ pub unsafe fn nk_combo_begin_text(
                     nk_white,
                 );
             } else {
-                text.background = (*background).data.color;
-                nk_fill_rect(
-                    &mut (*win).buffer,
-                    header,
-                    (*style).combo.rounding,
-                    (*background).data.color,
-                );
+                text.background = nk_style_item_background(background);
+                nk_fill_style_item(&mut (*win).buffer, header, (*style).combo.rounding, background);
                 nk_stroke_rect(
                     &mut (*win).buffer,
                     header,
@@ -21822,6 +25322,9 @@ pub unsafe fn nk_combo_begin_text(
                 sym,
                 (*style).font,
             );
+            if 0 != nk_nav_finish(ctx, nav) {
+                is_clicked = nk_true as Int
+            }
             return nk_combo_begin(ctx, win, size, is_clicked, header);
         }
     };
@@ -21937,6 +25440,9 @@ unsafe fn nk_nonblock_begin(
             (*popup).bounds = body;
             (*popup).parent = win;
             (*popup).layout = nk_create_panel(ctx) as *mut Panel;
+            if (*popup).layout.is_null() {
+                return 0i32;
+            }
             (*popup).flags = flags;
             (*popup).flags |= NK_WINDOW_BORDER as Int as Uint;
             (*popup).flags |= NK_WINDOW_DYNAMIC as Int as Uint;
@@ -22195,7 +25701,8 @@ pub unsafe fn nk_combo_begin_color(
     } else {
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
-        s = nk_widget(&mut header, ctx);
+        s = nk_widget_nav(&mut header, ctx);
+        let nav = nk_nav_widget(ctx, header, s);
         if s as Uint == NK_WIDGET_INVALID as Int as Uint {
             return 0i32;
         } else {
@@ -22230,12 +25737,7 @@ pub unsafe fn nk_combo_begin_color(
                     nk_white,
                 );
             } else {
-                nk_fill_rect(
-                    &mut (*win).buffer,
-                    header,
-                    (*style).combo.rounding,
-                    (*background).data.color,
-                );
+                nk_fill_style_item(&mut (*win).buffer, header, (*style).combo.rounding, background);
                 nk_stroke_rect(
                     &mut (*win).buffer,
                     header,
@@ -22296,6 +25798,9 @@ pub unsafe fn nk_combo_begin_color(
                 sym,
                 (*style).font,
             );
+            if 0 != nk_nav_finish(ctx, nav) {
+                is_clicked = nk_true as Int
+            }
             return nk_combo_begin(ctx, win, size, is_clicked, header);
         }
     };
@@ -22335,7 +25840,8 @@ pub unsafe fn nk_combo_begin_symbol(
     } else {
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
-        s = nk_widget(&mut header, ctx);
+        s = nk_widget_nav(&mut header, ctx);
+        let nav = nk_nav_widget(ctx, header, s);
         if s as Uint == NK_WIDGET_INVALID as Int as Uint {
             return 0i32;
         } else {
@@ -22374,13 +25880,8 @@ pub unsafe fn nk_combo_begin_symbol(
                     nk_white,
                 );
             } else {
-                sym_background = (*background).data.color;
-                nk_fill_rect(
-                    &mut (*win).buffer,
-                    header,
-                    (*style).combo.rounding,
-                    (*background).data.color,
-                );
+                sym_background = nk_style_item_background(background);
+                nk_fill_style_item(&mut (*win).buffer, header, (*style).combo.rounding, background);
                 nk_stroke_rect(
                     &mut (*win).buffer,
                     header,
@@ -22448,6 +25949,9 @@ pub unsafe fn nk_combo_begin_symbol(
                 sym,
                 (*style).font,
             );
+            if 0 != nk_nav_finish(ctx, nav) {
+                is_clicked = nk_true as Int
+            }
             return nk_combo_begin(ctx, win, size, is_clicked, header);
         }
     };
@@ -22507,7 +26011,8 @@ pub unsafe fn nk_combo_begin_symbol_text(
     } else {
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
-        s = nk_widget(&mut header, ctx);
+        s = nk_widget_nav(&mut header, ctx);
+        let nav = nk_nav_widget(ctx, header, s);
         if 0 == s as u64 {
             return 0i32;
         } else {
@@ -22549,13 +26054,8 @@ pub unsafe fn nk_combo_begin_symbol_text(
                     nk_white,
                 );
             } else {
-                text.background = (*background).data.color;
-                nk_fill_rect(
-                    &mut (*win).buffer,
-                    header,
-                    (*style).combo.rounding,
-                    (*background).data.color,
-                );
+                text.background = nk_style_item_background(background);
+                nk_fill_style_item(&mut (*win).buffer, header, (*style).combo.rounding, background);
                 nk_stroke_rect(
                     &mut (*win).buffer,
                     header,
@@ -22644,6 +26144,9 @@ pub unsafe fn nk_combo_begin_symbol_text(
                 NK_TEXT_LEFT as Int as Flags,
                 (*style).font,
             );
+            if 0 != nk_nav_finish(ctx, nav) {
+                is_clicked = nk_true as Int
+            }
             return nk_combo_begin(ctx, win, size, is_clicked, header);
         }
     };
@@ -22667,7 +26170,8 @@ pub unsafe fn nk_combo_begin_image(mut ctx: *mut Context, mut img: Image, mut si
     } else {
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
-        s = nk_widget(&mut header, ctx);
+        s = nk_widget_nav(&mut header, ctx);
+        let nav = nk_nav_widget(ctx, header, s);
         if s as Uint == NK_WIDGET_INVALID as Int as Uint {
             return 0i32;
         } else {
@@ -22702,12 +26206,7 @@ pub unsafe fn nk_combo_begin_image(mut ctx: *mut Context, mut img: Image, mut si
                     nk_white,
                 );
             } else {
-                nk_fill_rect(
-                    &mut (*win).buffer,
-                    header,
-                    (*style).combo.rounding,
-                    (*background).data.color,
-                );
+                nk_fill_style_item(&mut (*win).buffer, header, (*style).combo.rounding, background);
                 nk_stroke_rect(
                     &mut (*win).buffer,
                     header,
@@ -22767,6 +26266,9 @@ pub unsafe fn nk_combo_begin_image(mut ctx: *mut Context, mut img: Image, mut si
                 sym,
                 (*style).font,
             );
+            if 0 != nk_nav_finish(ctx, nav) {
+                is_clicked = nk_true as Int
+            }
             return nk_combo_begin(ctx, win, size, is_clicked, header);
         }
     };
@@ -22820,7 +26322,8 @@ pub unsafe fn nk_combo_begin_image_text(
     } else {
         win = (*ctx).current;
         style = &mut (*ctx).style as *mut Style;
-        s = nk_widget(&mut header, ctx);
+        s = nk_widget_nav(&mut header, ctx);
+        let nav = nk_nav_widget(ctx, header, s);
         if 0 == s as u64 {
             return 0i32;
         } else {
@@ -22859,13 +26362,8 @@ pub unsafe fn nk_combo_begin_image_text(
                     nk_white,
                 );
             } else {
-                text.background = (*background).data.color;
-                nk_fill_rect(
-                    &mut (*win).buffer,
-                    header,
-                    (*style).combo.rounding,
-                    (*background).data.color,
-                );
+                text.background = nk_style_item_background(background);
+                nk_fill_style_item(&mut (*win).buffer, header, (*style).combo.rounding, background);
                 nk_stroke_rect(
                     &mut (*win).buffer,
                     header,
@@ -22946,6 +26444,9 @@ pub unsafe fn nk_combo_begin_image_text(
                 NK_TEXT_LEFT as Int as Flags,
                 (*style).font,
             );
+            if 0 != nk_nav_finish(ctx, nav) {
+                is_clicked = nk_true as Int
+            }
             return nk_combo_begin(ctx, win, size, is_clicked, header);
         }
     };
@@ -23394,7 +26895,7 @@ pub unsafe fn nk_menu_begin_text(
         return 0i32;
     } else {
         win = (*ctx).current;
-        state = nk_widget(&mut header, ctx) as Flags;
+        state = nk_widget_nav(&mut header, ctx) as Flags;
         if 0 == state {
             return 0i32;
         } else {
@@ -23512,7 +27013,7 @@ pub unsafe fn nk_menu_begin_image(
         return 0i32;
     } else {
         win = (*ctx).current;
-        state = nk_widget(&mut header, ctx) as Flags;
+        state = nk_widget_nav(&mut header, ctx) as Flags;
         if 0 == state {
             return 0i32;
         } else {
@@ -23561,7 +27062,7 @@ pub unsafe fn nk_menu_begin_image_text(
         return 0i32;
     } else {
         win = (*ctx).current;
-        state = nk_widget(&mut header, ctx) as Flags;
+        state = nk_widget_nav(&mut header, ctx) as Flags;
         if 0 == state {
             return 0i32;
         } else {
@@ -23622,7 +27123,7 @@ pub unsafe fn nk_menu_begin_symbol(
         return 0i32;
     } else {
         win = (*ctx).current;
-        state = nk_widget(&mut header, ctx) as Flags;
+        state = nk_widget_nav(&mut header, ctx) as Flags;
         if 0 == state {
             return 0i32;
         } else {
@@ -23672,7 +27173,7 @@ pub unsafe fn nk_menu_begin_symbol_text(
         return 0i32;
     } else {
         win = (*ctx).current;
-        state = nk_widget(&mut header, ctx) as Flags;
+        state = nk_widget_nav(&mut header, ctx) as Flags;
         if 0 == state {
             return 0i32;
         } else {
@@ -25293,7 +28794,8 @@ pub unsafe fn nk_font_atlas_begin(mut atlas: *mut FontAtlas) -> () {
                 (*atlas).permanent.userdata,
                 (*atlas).pixel,
             );
-            (*atlas).pixel = 0 as *mut Void
+            (*atlas).pixel = 0 as *mut Void;
+            (*atlas).pixel_size = 0
         }
         return;
     };
@@ -26631,6 +30133,7 @@ pub unsafe fn nk_font_atlas_bake(
                                 img_size,
                             );
                         if !(*atlas).pixel.is_null() {
+                            (*atlas).pixel_size = img_size;
                             /* bake glyphs and custom white pixel into image */
                             nk_font_bake(
                                 baker,
@@ -26670,6 +30173,7 @@ pub unsafe fn nk_font_atlas_bake(
                                         (*atlas).pixel,
                                     );
                                     (*atlas).pixel = img_rgba;
+                                    (*atlas).pixel_size = (*width * *height * 4i32) as Size;
                                     current_block = 1394248824506584008;
                                 }
                             } else {
@@ -26855,7 +30359,8 @@ pub unsafe fn nk_font_atlas_bake(
                     (*atlas).temporary.userdata,
                     (*atlas).pixel,
                 );
-                (*atlas).pixel = 0 as *mut Void
+                (*atlas).pixel = 0 as *mut Void;
+                (*atlas).pixel_size = 0
             }
             return 0 as *const Void;
         }
@@ -30618,6 +34123,7 @@ pub unsafe fn nk_font_atlas_end(
         (*atlas).pixel,
     );
     (*atlas).pixel = 0 as *mut Void;
+    (*atlas).pixel_size = 0;
     (*atlas).tex_width = 0i32;
     (*atlas).tex_height = 0i32;
     (*atlas).custom.x = 0i32 as Short;
@@ -30745,6 +34251,7 @@ pub unsafe fn nk_buffer_info(mut s: *mut MemoryStatus, mut b: *mut Buffer) -> ()
         (*s).needed = (*b).needed;
         (*s).memory = (*b).memory.ptr;
         (*s).calls = (*b).calls;
+        (*s).failed = (*b).failed[0].wrapping_add((*b).failed[1]);
         return;
     };
 }
@@ -31016,116 +34523,402 @@ pub unsafe fn nk_filter_hex(mut box_0: *const TextEdit, mut unicode: Rune) -> In
     };
 }
 
-pub unsafe fn nk_filter_oct(mut box_0: *const TextEdit, mut unicode: Rune) -> Int {
-    if unicode < '0' as i32 as Uint || unicode > '7' as i32 as Uint {
-        return nk_false as Int;
+pub unsafe fn nk_filter_oct(mut box_0: *const TextEdit, mut unicode: Rune) -> Int {
+    if unicode < '0' as i32 as Uint || unicode > '7' as i32 as Uint {
+        return nk_false as Int;
+    } else {
+        return nk_true as Int;
+    };
+}
+
+pub unsafe fn nk_filter_binary(mut box_0: *const TextEdit, mut unicode: Rune) -> Int {
+    if unicode != '0' as i32 as Uint && unicode != '1' as i32 as Uint {
+        return nk_false as Int;
+    } else {
+        return nk_true as Int;
+    };
+}
+
+pub unsafe fn nk_textedit_init_default(mut state: *mut TextEdit) -> () {
+    if state.is_null() {
+        return;
+    } else {
+        nk_memset(
+            state as *mut Void,
+            0i32,
+            mem::size_of::<TextEdit>() as Ulong,
+        );
+        nk_textedit_clear_state(state, NK_TEXT_EDIT_SINGLE_LINE, None);
+        nk_str_init_default(&mut (*state).string);
+        return;
+    };
+}
+
+pub unsafe fn nk_textedit_init(
+    mut state: *mut TextEdit,
+    mut alloc: *mut Allocator,
+    mut size: Size,
+) -> () {
+    if state.is_null() || alloc.is_null() {
+        return;
+    } else {
+        nk_memset(
+            state as *mut Void,
+            0i32,
+            mem::size_of::<TextEdit>() as Ulong,
+        );
+        nk_textedit_clear_state(state, NK_TEXT_EDIT_SINGLE_LINE, None);
+        nk_str_init(&mut (*state).string, alloc, size);
+        return;
+    };
+}
+
+pub unsafe fn nk_textedit_init_fixed(
+    mut state: *mut TextEdit,
+    mut memory: *mut Void,
+    mut size: Size,
+) -> () {
+    if state.is_null() || memory.is_null() || 0 == size {
+        return;
+    } else {
+        nk_memset(
+            state as *mut Void,
+            0i32,
+            mem::size_of::<TextEdit>() as Ulong,
+        );
+        nk_textedit_clear_state(state, NK_TEXT_EDIT_SINGLE_LINE, None);
+        nk_str_init_fixed(&mut (*state).string, memory, size);
+        return;
+    };
+}
+
+pub unsafe fn nk_textedit_free(mut state: *mut TextEdit) -> () {
+    if state.is_null() {
+        return;
+    } else {
+        nk_str_free(&mut (*state).string);
+        return;
+    };
+}
+
+pub unsafe fn nk_textedit_paste(
+    mut state: *mut TextEdit,
+    mut ctext: *const Char,
+    mut len: Int,
+) -> Int {
+    /* API paste: replace existing selection with passed-in text */
+    let mut glyphs: Int = 0;
+    let mut text: *const Char = ctext;
+    if (*state).mode as Int == NK_TEXT_EDIT_MODE_VIEW as Int {
+        return 0i32;
+    } else {
+        /* if there's a selection, the paste should delete it */
+        nk_textedit_clamp(state);
+        nk_textedit_delete_selection(state);
+        /* try to insert the characters */
+        glyphs = nk_utf_len(ctext, len);
+        if 0 != nk_str_insert_text_char(&mut (*state).string, (*state).cursor, text, len) {
+            nk_textedit_makeundo_insert(state, (*state).cursor, glyphs);
+            (*state).cursor += len;
+            (*state).has_preferred_x = 0i32 as Uchar;
+            return 1i32;
+        } else {
+            /* remove the undo since we didn't actually insert the characters */
+            if 0 != (*state).undo.undo_point {
+                (*state).undo.undo_point -= 1
+            }
+            return 0i32;
+        }
+    };
+}
+
+pub unsafe fn nk_stroke_curve(
+    mut b: *mut CommandBuffer,
+    mut ax: f32,
+    mut ay: f32,
+    mut ctrl0x: f32,
+    mut ctrl0y: f32,
+    mut ctrl1x: f32,
+    mut ctrl1y: f32,
+    mut bx: f32,
+    mut by: f32,
+    mut line_thickness: f32,
+    mut col: Color,
+) -> () {
+    let mut cmd: *mut CommandCurve = 0 as *mut CommandCurve;
+    if b.is_null() || col.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
+        return;
     } else {
-        return nk_true as Int;
+        cmd = nk_command_buffer_push(b, NK_COMMAND_CURVE, mem::size_of::<CommandCurve>() as Ulong)
+            as *mut CommandCurve;
+        if cmd.is_null() {
+            return;
+        } else {
+            (*cmd).line_thickness = line_thickness as Ushort;
+            (*cmd).begin.x = ax as Short;
+            (*cmd).begin.y = ay as Short;
+            (*cmd).ctrl[0usize].x = ctrl0x as Short;
+            (*cmd).ctrl[0usize].y = ctrl0y as Short;
+            (*cmd).ctrl[1usize].x = ctrl1x as Short;
+            (*cmd).ctrl[1usize].y = ctrl1y as Short;
+            (*cmd).end.x = bx as Short;
+            (*cmd).end.y = by as Short;
+            (*cmd).color = col;
+            return;
+        }
     };
 }
 
-pub unsafe fn nk_filter_binary(mut box_0: *const TextEdit, mut unicode: Rune) -> Int {
-    if unicode != '0' as i32 as Uint && unicode != '1' as i32 as Uint {
-        return nk_false as Int;
+pub unsafe fn nk_stroke_circle(
+    mut b: *mut CommandBuffer,
+    mut r: Rect,
+    mut line_thickness: f32,
+    mut c: Color,
+) -> () {
+    let mut cmd: *mut CommandCircle = 0 as *mut CommandCircle;
+    if b.is_null() || r.w == 0i32 as f32 || r.h == 0i32 as f32 || line_thickness <= 0i32 as f32 {
+        return;
     } else {
-        return nk_true as Int;
+        if 0 != (*b).use_clipping {
+            let mut clip: *const Rect = &mut (*b).clip as *mut Rect;
+            if (*clip).x > r.x + r.w
+                || (*clip).x + (*clip).w < r.x
+                || (*clip).y > r.y + r.h
+                || (*clip).y + (*clip).h < r.y
+            {
+                return;
+            }
+        }
+        cmd = nk_command_buffer_push(
+            b,
+            NK_COMMAND_CIRCLE,
+            mem::size_of::<CommandCircle>() as Ulong,
+        ) as *mut CommandCircle;
+        if cmd.is_null() {
+            return;
+        } else {
+            (*cmd).line_thickness = line_thickness as Ushort;
+            (*cmd).x = r.x as Short;
+            (*cmd).y = r.y as Short;
+            (*cmd).w = (if r.w < 0i32 as f32 {
+                0i32 as f32
+            } else {
+                r.w
+            }) as Ushort;
+            (*cmd).h = (if r.h < 0i32 as f32 {
+                0i32 as f32
+            } else {
+                r.h
+            }) as Ushort;
+            (*cmd).color = c;
+            return;
+        }
     };
 }
 
-pub unsafe fn nk_textedit_init_default(mut state: *mut TextEdit) -> () {
-    if state.is_null() {
+pub unsafe fn nk_stroke_arc(
+    mut b: *mut CommandBuffer,
+    mut cx: f32,
+    mut cy: f32,
+    mut radius: f32,
+    mut a_min: f32,
+    mut a_max: f32,
+    mut line_thickness: f32,
+    mut c: Color,
+) -> () {
+    let mut cmd: *mut CommandArc = 0 as *mut CommandArc;
+    if b.is_null() || c.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
         return;
     } else {
-        nk_memset(
-            state as *mut Void,
-            0i32,
-            mem::size_of::<TextEdit>() as Ulong,
-        );
-        nk_textedit_clear_state(state, NK_TEXT_EDIT_SINGLE_LINE, None);
-        nk_str_init_default(&mut (*state).string);
-        return;
+        cmd = nk_command_buffer_push(b, NK_COMMAND_ARC, mem::size_of::<CommandArc>() as Ulong)
+            as *mut CommandArc;
+        if cmd.is_null() {
+            return;
+        } else {
+            (*cmd).line_thickness = line_thickness as Ushort;
+            (*cmd).cx = cx as Short;
+            (*cmd).cy = cy as Short;
+            (*cmd).r = radius as Ushort;
+            (*cmd).a[0usize] = a_min;
+            (*cmd).a[1usize] = a_max;
+            (*cmd).color = c;
+            return;
+        }
     };
 }
 
-pub unsafe fn nk_textedit_init(
-    mut state: *mut TextEdit,
-    mut alloc: *mut Allocator,
-    mut size: Size,
+pub unsafe fn nk_stroke_triangle(
+    mut b: *mut CommandBuffer,
+    mut x0: f32,
+    mut y0: f32,
+    mut x1: f32,
+    mut y1: f32,
+    mut x2: f32,
+    mut y2: f32,
+    mut line_thickness: f32,
+    mut c: Color,
 ) -> () {
-    if state.is_null() || alloc.is_null() {
+    let mut cmd: *mut CommandTriangle = 0 as *mut CommandTriangle;
+    if b.is_null() || c.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
         return;
     } else {
-        nk_memset(
-            state as *mut Void,
-            0i32,
-            mem::size_of::<TextEdit>() as Ulong,
-        );
-        nk_textedit_clear_state(state, NK_TEXT_EDIT_SINGLE_LINE, None);
-        nk_str_init(&mut (*state).string, alloc, size);
-        return;
+        if 0 != (*b).use_clipping {
+            let mut clip: *const Rect = &mut (*b).clip as *mut Rect;
+            if !((*clip).x <= x0
+                && x0 < (*clip).x + (*clip).w
+                && ((*clip).y <= y0 && y0 < (*clip).y + (*clip).h))
+                && !((*clip).x <= x1
+                    && x1 < (*clip).x + (*clip).w
+                    && ((*clip).y <= y1 && y1 < (*clip).y + (*clip).h))
+                && !((*clip).x <= x2
+                    && x2 < (*clip).x + (*clip).w
+                    && ((*clip).y <= y2 && y2 < (*clip).y + (*clip).h))
+            {
+                return;
+            }
+        }
+        cmd = nk_command_buffer_push(
+            b,
+            NK_COMMAND_TRIANGLE,
+            mem::size_of::<CommandTriangle>() as Ulong,
+        ) as *mut CommandTriangle;
+        if cmd.is_null() {
+            return;
+        } else {
+            (*cmd).line_thickness = line_thickness as Ushort;
+            (*cmd).a.x = x0 as Short;
+            (*cmd).a.y = y0 as Short;
+            (*cmd).b.x = x1 as Short;
+            (*cmd).b.y = y1 as Short;
+            (*cmd).c.x = x2 as Short;
+            (*cmd).c.y = y2 as Short;
+            (*cmd).color = c;
+            return;
+        }
     };
 }
 
-pub unsafe fn nk_textedit_init_fixed(
-    mut state: *mut TextEdit,
-    mut memory: *mut Void,
-    mut size: Size,
+pub unsafe fn nk_stroke_polyline(
+    mut b: *mut CommandBuffer,
+    mut points: *mut f32,
+    mut point_count: Int,
+    mut line_thickness: f32,
+    mut col: Color,
 ) -> () {
-    if state.is_null() || memory.is_null() || 0 == size {
+    let mut i: Int = 0;
+    let mut size: Size = 0i32 as Size;
+    let mut cmd: *mut CommandPolyline = 0 as *mut CommandPolyline;
+    if b.is_null() || col.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
         return;
     } else {
-        nk_memset(
-            state as *mut Void,
-            0i32,
-            mem::size_of::<TextEdit>() as Ulong,
+        size = (mem::size_of::<CommandPolyline>() as Ulong).wrapping_add(
+            (mem::size_of::<Short>() as Ulong)
+                .wrapping_mul(2i32 as Ulong)
+                .wrapping_mul(point_count as Size),
         );
-        nk_textedit_clear_state(state, NK_TEXT_EDIT_SINGLE_LINE, None);
-        nk_str_init_fixed(&mut (*state).string, memory, size);
-        return;
+        cmd = nk_command_buffer_push(b, NK_COMMAND_POLYLINE, size) as *mut CommandPolyline;
+        if cmd.is_null() {
+            return;
+        } else {
+            (*cmd).color = col;
+            (*cmd).point_count = point_count as Ushort;
+            (*cmd).line_thickness = line_thickness as Ushort;
+            i = 0i32;
+            while i < point_count {
+                let mut q: *mut PointI = (*cmd).points.as_mut_ptr().offset(i as isize);
+                (*q).x = *points.offset((i * 2i32) as isize) as Short;
+                (*q).y = *points.offset((i * 2i32 + 1i32) as isize) as Short;
+                i += 1
+            }
+            return;
+        }
     };
 }
 
-pub unsafe fn nk_textedit_free(mut state: *mut TextEdit) -> () {
-    if state.is_null() {
+pub unsafe fn nk_stroke_polygon(
+    mut b: *mut CommandBuffer,
+    mut points: *mut f32,
+    mut point_count: Int,
+    mut line_thickness: f32,
+    mut col: Color,
+) -> () {
+    let mut i: Int = 0;
+    let mut size: Size = 0i32 as Size;
+    let mut cmd: *mut CommandPolygon = 0 as *mut CommandPolygon;
+    if b.is_null() || col.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
         return;
     } else {
-        nk_str_free(&mut (*state).string);
+        size = (mem::size_of::<CommandPolygon>() as Ulong).wrapping_add(
+            (mem::size_of::<Short>() as Ulong)
+                .wrapping_mul(2i32 as Ulong)
+                .wrapping_mul(point_count as Size),
+        );
+        cmd = nk_command_buffer_push(b, NK_COMMAND_POLYGON, size) as *mut CommandPolygon;
+        if cmd.is_null() {
+            return;
+        } else {
+            (*cmd).color = col;
+            (*cmd).line_thickness = line_thickness as Ushort;
+            (*cmd).point_count = point_count as Ushort;
+            i = 0i32;
+            while i < point_count {
+                let mut q: *mut PointI = (*cmd).points.as_mut_ptr().offset(i as isize);
+                (*q).x = *points.offset((i * 2i32) as isize) as Short;
+                (*q).y = *points.offset((i * 2i32 + 1i32) as isize) as Short;
+                i += 1
+            }
+            return;
+        }
+    };
+}
+
+pub unsafe fn nk_stroke_line_styled(
+    mut b: *mut CommandBuffer,
+    mut x0: f32,
+    mut y0: f32,
+    mut x1: f32,
+    mut y1: f32,
+    mut line_thickness: f32,
+    mut c: Color,
+    mut style: *const StrokeStyle,
+) -> () {
+    let mut cmd: *mut CommandLineStyled = 0 as *mut CommandLineStyled;
+    if b.is_null() || style.is_null() || c.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
         return;
-    };
-}
-
-pub unsafe fn nk_textedit_paste(
-    mut state: *mut TextEdit,
-    mut ctext: *const Char,
-    mut len: Int,
-) -> Int {
-    /* API paste: replace existing selection with passed-in text */
-    let mut glyphs: Int = 0;
-    let mut text: *const Char = ctext;
-    if (*state).mode as Int == NK_TEXT_EDIT_MODE_VIEW as Int {
-        return 0i32;
     } else {
-        /* if there's a selection, the paste should delete it */
-        nk_textedit_clamp(state);
-        nk_textedit_delete_selection(state);
-        /* try to insert the characters */
-        glyphs = nk_utf_len(ctext, len);
-        if 0 != nk_str_insert_text_char(&mut (*state).string, (*state).cursor, text, len) {
-            nk_textedit_makeundo_insert(state, (*state).cursor, glyphs);
-            (*state).cursor += len;
-            (*state).has_preferred_x = 0i32 as Uchar;
-            return 1i32;
-        } else {
-            /* remove the undo since we didn't actually insert the characters */
-            if 0 != (*state).undo.undo_point {
-                (*state).undo.undo_point -= 1
+        if 0 != (*b).use_clipping {
+            /* bounds of the line including its caps */
+            let mut clip: *const Rect = &mut (*b).clip as *mut Rect;
+            let mut half: f32 = line_thickness * 0.5f32;
+            if (*clip).x > x0.max(x1) + half
+                || (*clip).x + (*clip).w < x0.min(x1) - half
+                || (*clip).y > y0.max(y1) + half
+                || (*clip).y + (*clip).h < y0.min(y1) - half
+            {
+                return;
             }
-            return 0i32;
+        }
+        cmd = nk_command_buffer_push(
+            b,
+            NK_COMMAND_LINE_STYLED,
+            mem::size_of::<CommandLineStyled>() as Ulong,
+        ) as *mut CommandLineStyled;
+        if cmd.is_null() {
+            return;
+        } else {
+            (*cmd).line_thickness = line_thickness as Ushort;
+            (*cmd).begin.x = x0 as Short;
+            (*cmd).begin.y = y0 as Short;
+            (*cmd).end.x = x1 as Short;
+            (*cmd).end.y = y1 as Short;
+            (*cmd).color = c;
+            (*cmd).style = *style;
+            return;
         }
     };
 }
 
-pub unsafe fn nk_stroke_curve(
+pub unsafe fn nk_stroke_curve_styled(
     mut b: *mut CommandBuffer,
     mut ax: f32,
     mut ay: f32,
@@ -31137,13 +34930,29 @@ pub unsafe fn nk_stroke_curve(
     mut by: f32,
     mut line_thickness: f32,
     mut col: Color,
+    mut style: *const StrokeStyle,
 ) -> () {
-    let mut cmd: *mut CommandCurve = 0 as *mut CommandCurve;
-    if b.is_null() || col.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
+    let mut cmd: *mut CommandCurveStyled = 0 as *mut CommandCurveStyled;
+    if b.is_null() || style.is_null() || col.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
         return;
     } else {
-        cmd = nk_command_buffer_push(b, NK_COMMAND_CURVE, mem::size_of::<CommandCurve>() as Ulong)
-            as *mut CommandCurve;
+        if 0 != (*b).use_clipping {
+            /* the curve stays inside the hull of its control points */
+            let mut clip: *const Rect = &mut (*b).clip as *mut Rect;
+            let mut half: f32 = line_thickness * 0.5f32;
+            if (*clip).x > ax.max(ctrl0x).max(ctrl1x).max(bx) + half
+                || (*clip).x + (*clip).w < ax.min(ctrl0x).min(ctrl1x).min(bx) - half
+                || (*clip).y > ay.max(ctrl0y).max(ctrl1y).max(by) + half
+                || (*clip).y + (*clip).h < ay.min(ctrl0y).min(ctrl1y).min(by) - half
+            {
+                return;
+            }
+        }
+        cmd = nk_command_buffer_push(
+            b,
+            NK_COMMAND_CURVE_STYLED,
+            mem::size_of::<CommandCurveStyled>() as Ulong,
+        ) as *mut CommandCurveStyled;
         if cmd.is_null() {
             return;
         } else {
@@ -31157,78 +34966,112 @@ pub unsafe fn nk_stroke_curve(
             (*cmd).end.x = bx as Short;
             (*cmd).end.y = by as Short;
             (*cmd).color = col;
+            (*cmd).style = *style;
             return;
         }
     };
 }
 
-pub unsafe fn nk_stroke_circle(
+/* polygon and polyline commands share the same layout */
+unsafe fn nk_stroke_poly_styled(
     mut b: *mut CommandBuffer,
-    mut r: Rect,
+    mut type_0: CommandType,
+    mut points: *mut f32,
+    mut point_count: Int,
     mut line_thickness: f32,
-    mut c: Color,
+    mut col: Color,
+    mut style: *const StrokeStyle,
 ) -> () {
-    let mut cmd: *mut CommandCircle = 0 as *mut CommandCircle;
-    if b.is_null() || r.w == 0i32 as f32 || r.h == 0i32 as f32 || line_thickness <= 0i32 as f32 {
+    let mut i: Int = 0;
+    let mut size: Size = 0i32 as Size;
+    let mut cmd: *mut CommandPolygonStyled = 0 as *mut CommandPolygonStyled;
+    if b.is_null() || style.is_null() || col.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
         return;
     } else {
-        if 0 != (*b).use_clipping {
-            let mut clip: *const Rect = &mut (*b).clip as *mut Rect;
-            if (*clip).x > r.x + r.w
-                || (*clip).x + (*clip).w < r.x
-                || (*clip).y > r.y + r.h
-                || (*clip).y + (*clip).h < r.y
-            {
-                return;
-            }
-        }
-        cmd = nk_command_buffer_push(
-            b,
-            NK_COMMAND_CIRCLE,
-            mem::size_of::<CommandCircle>() as Ulong,
-        ) as *mut CommandCircle;
+        size = (mem::size_of::<CommandPolygonStyled>() as Ulong).wrapping_add(
+            (mem::size_of::<Short>() as Ulong)
+                .wrapping_mul(2i32 as Ulong)
+                .wrapping_mul(point_count as Size),
+        );
+        cmd = nk_command_buffer_push(b, type_0, size) as *mut CommandPolygonStyled;
         if cmd.is_null() {
             return;
         } else {
+            (*cmd).color = col;
+            (*cmd).style = *style;
             (*cmd).line_thickness = line_thickness as Ushort;
-            (*cmd).x = r.x as Short;
-            (*cmd).y = r.y as Short;
-            (*cmd).w = (if r.w < 0i32 as f32 {
-                0i32 as f32
-            } else {
-                r.w
-            }) as Ushort;
-            (*cmd).h = (if r.h < 0i32 as f32 {
-                0i32 as f32
-            } else {
-                r.h
-            }) as Ushort;
-            (*cmd).color = c;
+            (*cmd).point_count = point_count as Ushort;
+            i = 0i32;
+            while i < point_count {
+                let mut q: *mut PointI = (*cmd).points.as_mut_ptr().offset(i as isize);
+                (*q).x = *points.offset((i * 2i32 + 0i32) as isize) as Short;
+                (*q).y = *points.offset((i * 2i32 + 1i32) as isize) as Short;
+                i += 1
+            }
             return;
         }
     };
 }
 
-pub unsafe fn nk_stroke_arc(
+pub unsafe fn nk_stroke_polyline_styled(
+    mut b: *mut CommandBuffer,
+    mut points: *mut f32,
+    mut point_count: Int,
+    mut line_thickness: f32,
+    mut col: Color,
+    mut style: *const StrokeStyle,
+) -> () {
+    nk_stroke_poly_styled(
+        b,
+        NK_COMMAND_POLYLINE_STYLED,
+        points,
+        point_count,
+        line_thickness,
+        col,
+        style,
+    );
+}
+
+pub unsafe fn nk_stroke_polygon_styled(
+    mut b: *mut CommandBuffer,
+    mut points: *mut f32,
+    mut point_count: Int,
+    mut line_thickness: f32,
+    mut col: Color,
+    mut style: *const StrokeStyle,
+) -> () {
+    nk_stroke_poly_styled(
+        b,
+        NK_COMMAND_POLYGON_STYLED,
+        points,
+        point_count,
+        line_thickness,
+        col,
+        style,
+    );
+}
+
+pub unsafe fn nk_fill_arc(
     mut b: *mut CommandBuffer,
     mut cx: f32,
     mut cy: f32,
     mut radius: f32,
     mut a_min: f32,
     mut a_max: f32,
-    mut line_thickness: f32,
     mut c: Color,
 ) -> () {
-    let mut cmd: *mut CommandArc = 0 as *mut CommandArc;
-    if b.is_null() || c.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
+    let mut cmd: *mut CommandArcFilled = 0 as *mut CommandArcFilled;
+    if b.is_null() || c.a as Int == 0i32 {
         return;
     } else {
-        cmd = nk_command_buffer_push(b, NK_COMMAND_ARC, mem::size_of::<CommandArc>() as Ulong)
-            as *mut CommandArc;
+        cmd = nk_command_buffer_push(
+            b,
+            NK_COMMAND_ARC_FILLED,
+            mem::size_of::<CommandArcFilled>() as Ulong,
+        ) as *mut CommandArcFilled;
         if cmd.is_null() {
             return;
         } else {
-            (*cmd).line_thickness = line_thickness as Ushort;
             (*cmd).cx = cx as Short;
             (*cmd).cy = cy as Short;
             (*cmd).r = radius as Ushort;
@@ -31240,189 +35083,241 @@ pub unsafe fn nk_stroke_arc(
     };
 }
 
-pub unsafe fn nk_stroke_triangle(
+pub unsafe fn nk_fill_polygon(
     mut b: *mut CommandBuffer,
-    mut x0: f32,
-    mut y0: f32,
-    mut x1: f32,
-    mut y1: f32,
-    mut x2: f32,
-    mut y2: f32,
+    mut points: *mut f32,
+    mut point_count: Int,
+    mut col: Color,
+) -> () {
+    let mut i: Int = 0;
+    let mut size: Size = 0i32 as Size;
+    let mut cmd: *mut CommandPolygonFilled = 0 as *mut CommandPolygonFilled;
+    if b.is_null() || col.a as Int == 0i32 {
+        return;
+    } else {
+        size = (mem::size_of::<CommandPolygonFilled>() as Ulong).wrapping_add(
+            (mem::size_of::<Short>() as Ulong)
+                .wrapping_mul(2i32 as Ulong)
+                .wrapping_mul(point_count as Size),
+        );
+        cmd =
+            nk_command_buffer_push(b, NK_COMMAND_POLYGON_FILLED, size) as *mut CommandPolygonFilled;
+        if cmd.is_null() {
+            return;
+        } else {
+            (*cmd).color = col;
+            (*cmd).point_count = point_count as Ushort;
+            i = 0i32;
+            while i < point_count {
+                let mut q: *mut PointI = (*cmd).points.as_mut_ptr().offset(i as isize);
+                (*q).x = *points.offset((i * 2i32 + 0i32) as isize) as Short;
+                (*q).y = *points.offset((i * 2i32 + 1i32) as isize) as Short;
+                i += 1
+            }
+            return;
+        }
+    };
+}
+
+pub unsafe fn nk_stroke_rect_corners(
+    mut b: *mut CommandBuffer,
+    mut rect: Rect,
+    mut rounding: [f32; 4],
     mut line_thickness: f32,
     mut c: Color,
 ) -> () {
-    let mut cmd: *mut CommandTriangle = 0 as *mut CommandTriangle;
-    if b.is_null() || c.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
+    let mut cmd: *mut CommandRectCorners = 0 as *mut CommandRectCorners;
+    if b.is_null()
+        || c.a as Int == 0i32
+        || rect.w == 0i32 as f32
+        || rect.h == 0i32 as f32
+        || line_thickness <= 0i32 as f32
+    {
         return;
     } else {
         if 0 != (*b).use_clipping {
             let mut clip: *const Rect = &mut (*b).clip as *mut Rect;
-            if !((*clip).x <= x0
-                && x0 < (*clip).x + (*clip).w
-                && ((*clip).y <= y0 && y0 < (*clip).y + (*clip).h))
-                && !((*clip).x <= x1
-                    && x1 < (*clip).x + (*clip).w
-                    && ((*clip).y <= y1 && y1 < (*clip).y + (*clip).h))
-                && !((*clip).x <= x2
-                    && x2 < (*clip).x + (*clip).w
-                    && ((*clip).y <= y2 && y2 < (*clip).y + (*clip).h))
+            if (*clip).x > rect.x + rect.w
+                || (*clip).x + (*clip).w < rect.x
+                || (*clip).y > rect.y + rect.h
+                || (*clip).y + (*clip).h < rect.y
             {
                 return;
             }
         }
         cmd = nk_command_buffer_push(
             b,
-            NK_COMMAND_TRIANGLE,
-            mem::size_of::<CommandTriangle>() as Ulong,
-        ) as *mut CommandTriangle;
+            NK_COMMAND_RECT_CORNERS,
+            mem::size_of::<CommandRectCorners>() as Ulong,
+        ) as *mut CommandRectCorners;
         if cmd.is_null() {
             return;
         } else {
+            (*cmd).rounding = nk_corner_radii(rounding);
             (*cmd).line_thickness = line_thickness as Ushort;
-            (*cmd).a.x = x0 as Short;
-            (*cmd).a.y = y0 as Short;
-            (*cmd).b.x = x1 as Short;
-            (*cmd).b.y = y1 as Short;
-            (*cmd).c.x = x2 as Short;
-            (*cmd).c.y = y2 as Short;
+            (*cmd).x = rect.x as Short;
+            (*cmd).y = rect.y as Short;
+            (*cmd).w = (if (0i32 as f32) < rect.w { rect.w } else { 0i32 as f32 }) as Ushort;
+            (*cmd).h = (if (0i32 as f32) < rect.h { rect.h } else { 0i32 as f32 }) as Ushort;
             (*cmd).color = c;
             return;
         }
     };
 }
 
-pub unsafe fn nk_stroke_polyline(
+pub unsafe fn nk_fill_rect_corners(
     mut b: *mut CommandBuffer,
-    mut points: *mut f32,
-    mut point_count: Int,
-    mut line_thickness: f32,
-    mut col: Color,
+    mut rect: Rect,
+    mut rounding: [f32; 4],
+    mut c: Color,
 ) -> () {
-    let mut i: Int = 0;
-    let mut size: Size = 0i32 as Size;
-    let mut cmd: *mut CommandPolyline = 0 as *mut CommandPolyline;
-    if b.is_null() || col.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
+    let mut cmd: *mut CommandRectCornersFilled = 0 as *mut CommandRectCornersFilled;
+    if b.is_null() || c.a as Int == 0i32 || rect.w == 0i32 as f32 || rect.h == 0i32 as f32 {
         return;
     } else {
-        size = (mem::size_of::<CommandPolyline>() as Ulong).wrapping_add(
-            (mem::size_of::<Short>() as Ulong)
-                .wrapping_mul(2i32 as Ulong)
-                .wrapping_mul(point_count as Size),
-        );
-        cmd = nk_command_buffer_push(b, NK_COMMAND_POLYLINE, size) as *mut CommandPolyline;
+        if 0 != (*b).use_clipping {
+            let mut clip: *const Rect = &mut (*b).clip as *mut Rect;
+            if (*clip).x > rect.x + rect.w
+                || (*clip).x + (*clip).w < rect.x
+                || (*clip).y > rect.y + rect.h
+                || (*clip).y + (*clip).h < rect.y
+            {
+                return;
+            }
+        }
+        cmd = nk_command_buffer_push(
+            b,
+            NK_COMMAND_RECT_CORNERS_FILLED,
+            mem::size_of::<CommandRectCornersFilled>() as Ulong,
+        ) as *mut CommandRectCornersFilled;
         if cmd.is_null() {
             return;
         } else {
-            (*cmd).color = col;
-            (*cmd).point_count = point_count as Ushort;
-            (*cmd).line_thickness = line_thickness as Ushort;
-            i = 0i32;
-            while i < point_count {
-                (*cmd).points[i as usize].x = *points.offset((i * 2i32) as isize) as Short;
-                (*cmd).points[i as usize].y = *points.offset((i * 2i32 + 1i32) as isize) as Short;
-                i += 1
-            }
+            (*cmd).rounding = nk_corner_radii(rounding);
+            (*cmd).x = rect.x as Short;
+            (*cmd).y = rect.y as Short;
+            (*cmd).w = (if (0i32 as f32) < rect.w { rect.w } else { 0i32 as f32 }) as Ushort;
+            (*cmd).h = (if (0i32 as f32) < rect.h { rect.h } else { 0i32 as f32 }) as Ushort;
+            (*cmd).color = c;
             return;
         }
     };
 }
 
-pub unsafe fn nk_stroke_polygon(
+pub unsafe fn nk_fill_rect_gradient(
     mut b: *mut CommandBuffer,
-    mut points: *mut f32,
-    mut point_count: Int,
-    mut line_thickness: f32,
-    mut col: Color,
+    mut rect: Rect,
+    mut rounding: [f32; 4],
+    mut g: Gradient,
 ) -> () {
-    let mut i: Int = 0;
-    let mut size: Size = 0i32 as Size;
-    let mut cmd: *mut CommandPolygon = 0 as *mut CommandPolygon;
-    if b.is_null() || col.a as Int == 0i32 || line_thickness <= 0i32 as f32 {
+    let mut cmd: *mut CommandRectGradient = 0 as *mut CommandRectGradient;
+    if b.is_null()
+        || g.start_color.a as Int == 0i32 && g.end_color.a as Int == 0i32
+        || rect.w == 0i32 as f32
+        || rect.h == 0i32 as f32
+    {
         return;
     } else {
-        size = (mem::size_of::<CommandPolygon>() as Ulong).wrapping_add(
-            (mem::size_of::<Short>() as Ulong)
-                .wrapping_mul(2i32 as Ulong)
-                .wrapping_mul(point_count as Size),
-        );
-        cmd = nk_command_buffer_push(b, NK_COMMAND_POLYGON, size) as *mut CommandPolygon;
+        if 0 != (*b).use_clipping {
+            let mut clip: *const Rect = &mut (*b).clip as *mut Rect;
+            if (*clip).x > rect.x + rect.w
+                || (*clip).x + (*clip).w < rect.x
+                || (*clip).y > rect.y + rect.h
+                || (*clip).y + (*clip).h < rect.y
+            {
+                return;
+            }
+        }
+        cmd = nk_command_buffer_push(
+            b,
+            NK_COMMAND_RECT_GRADIENT,
+            mem::size_of::<CommandRectGradient>() as Ulong,
+        ) as *mut CommandRectGradient;
         if cmd.is_null() {
             return;
         } else {
-            (*cmd).color = col;
-            (*cmd).line_thickness = line_thickness as Ushort;
-            (*cmd).point_count = point_count as Ushort;
-            i = 0i32;
-            while i < point_count {
-                (*cmd).points[i as usize].x = *points.offset((i * 2i32) as isize) as Short;
-                (*cmd).points[i as usize].y = *points.offset((i * 2i32 + 1i32) as isize) as Short;
-                i += 1
-            }
+            (*cmd).rounding = nk_corner_radii(rounding);
+            (*cmd).x = rect.x as Short;
+            (*cmd).y = rect.y as Short;
+            (*cmd).w = (if (0i32 as f32) < rect.w { rect.w } else { 0i32 as f32 }) as Ushort;
+            (*cmd).h = (if (0i32 as f32) < rect.h { rect.h } else { 0i32 as f32 }) as Ushort;
+            (*cmd).gradient = g;
             return;
         }
     };
 }
 
-pub unsafe fn nk_fill_arc(
+pub unsafe fn nk_fill_circle_gradient(
     mut b: *mut CommandBuffer,
-    mut cx: f32,
-    mut cy: f32,
-    mut radius: f32,
-    mut a_min: f32,
-    mut a_max: f32,
-    mut c: Color,
+    mut r: Rect,
+    mut g: Gradient,
 ) -> () {
-    let mut cmd: *mut CommandArcFilled = 0 as *mut CommandArcFilled;
-    if b.is_null() || c.a as Int == 0i32 {
+    let mut cmd: *mut CommandCircleGradient = 0 as *mut CommandCircleGradient;
+    if b.is_null()
+        || g.start_color.a as Int == 0i32 && g.end_color.a as Int == 0i32
+        || r.w == 0i32 as f32
+        || r.h == 0i32 as f32
+    {
         return;
     } else {
+        if 0 != (*b).use_clipping {
+            let mut clip: *const Rect = &mut (*b).clip as *mut Rect;
+            if (*clip).x > r.x + r.w
+                || (*clip).x + (*clip).w < r.x
+                || (*clip).y > r.y + r.h
+                || (*clip).y + (*clip).h < r.y
+            {
+                return;
+            }
+        }
         cmd = nk_command_buffer_push(
             b,
-            NK_COMMAND_ARC_FILLED,
-            mem::size_of::<CommandArcFilled>() as Ulong,
-        ) as *mut CommandArcFilled;
+            NK_COMMAND_CIRCLE_GRADIENT,
+            mem::size_of::<CommandCircleGradient>() as Ulong,
+        ) as *mut CommandCircleGradient;
         if cmd.is_null() {
             return;
         } else {
-            (*cmd).cx = cx as Short;
-            (*cmd).cy = cy as Short;
-            (*cmd).r = radius as Ushort;
-            (*cmd).a[0usize] = a_min;
-            (*cmd).a[1usize] = a_max;
-            (*cmd).color = c;
+            (*cmd).x = r.x as Short;
+            (*cmd).y = r.y as Short;
+            (*cmd).w = (if r.w < 0i32 as f32 { 0i32 as f32 } else { r.w }) as Ushort;
+            (*cmd).h = (if r.h < 0i32 as f32 { 0i32 as f32 } else { r.h }) as Ushort;
+            (*cmd).gradient = g;
             return;
         }
     };
 }
 
-pub unsafe fn nk_fill_polygon(
+/* `points` must describe a convex polygon */
+pub unsafe fn nk_fill_polygon_gradient(
     mut b: *mut CommandBuffer,
     mut points: *mut f32,
     mut point_count: Int,
-    mut col: Color,
+    mut g: Gradient,
 ) -> () {
     let mut i: Int = 0;
     let mut size: Size = 0i32 as Size;
-    let mut cmd: *mut CommandPolygonFilled = 0 as *mut CommandPolygonFilled;
-    if b.is_null() || col.a as Int == 0i32 {
+    let mut cmd: *mut CommandPolygonGradient = 0 as *mut CommandPolygonGradient;
+    if b.is_null() || g.start_color.a as Int == 0i32 && g.end_color.a as Int == 0i32 {
         return;
     } else {
-        size = (mem::size_of::<CommandPolygonFilled>() as Ulong).wrapping_add(
+        size = (mem::size_of::<CommandPolygonGradient>() as Ulong).wrapping_add(
             (mem::size_of::<Short>() as Ulong)
                 .wrapping_mul(2i32 as Ulong)
                 .wrapping_mul(point_count as Size),
         );
-        cmd =
-            nk_command_buffer_push(b, NK_COMMAND_POLYGON_FILLED, size) as *mut CommandPolygonFilled;
+        cmd = nk_command_buffer_push(b, NK_COMMAND_POLYGON_GRADIENT, size)
+            as *mut CommandPolygonGradient;
         if cmd.is_null() {
             return;
         } else {
-            (*cmd).color = col;
+            (*cmd).gradient = g;
             (*cmd).point_count = point_count as Ushort;
             i = 0i32;
             while i < point_count {
-                (*cmd).points[i as usize].x = *points.offset((i * 2i32 + 0i32) as isize) as Short;
-                (*cmd).points[i as usize].y = *points.offset((i * 2i32 + 1i32) as isize) as Short;
+                let mut q: *mut PointI = (*cmd).points.as_mut_ptr().offset(i as isize);
+                (*q).x = *points.offset((i * 2i32 + 0i32) as isize) as Short;
+                (*q).y = *points.offset((i * 2i32 + 1i32) as isize) as Short;
                 i += 1
             }
             return;
@@ -31430,6 +35325,25 @@ pub unsafe fn nk_fill_polygon(
     };
 }
 
+unsafe fn nk_corner_radii_f(mut rounding: [Ushort; 4]) -> [f32; 4] {
+    return [
+        rounding[0] as f32,
+        rounding[1] as f32,
+        rounding[2] as f32,
+        rounding[3] as f32,
+    ];
+}
+
+unsafe fn nk_corner_radii(mut rounding: [f32; 4]) -> [Ushort; 4] {
+    let mut res: [Ushort; 4] = [0; 4];
+    let mut i: usize = 0;
+    while i < 4 {
+        res[i] = (if rounding[i] < 0i32 as f32 { 0i32 as f32 } else { rounding[i] }) as Ushort;
+        i += 1
+    }
+    return res;
+}
+
 pub unsafe fn nk_push_custom(
     mut b: *mut CommandBuffer,
     mut r: Rect,
@@ -31497,6 +35411,161 @@ pub unsafe fn nk_style_item_image(mut img: Image) -> StyleItem {
     return i;
 }
 
+/* gradient points of style items are relative to the drawn bounds, (0, 0)
+ * is the top left and (1, 1) the bottom right corner */
+pub unsafe fn nk_style_item_gradient(mut gradient: Gradient) -> StyleItem {
+    let mut i: StyleItem = nk_style_item_hide();
+    i.type_0 = NK_STYLE_ITEM_GRADIENT;
+    i.data.gradient = gradient;
+    return i;
+}
+
+/* fills `r` with a color or gradient style item */
+unsafe fn nk_fill_style_item(
+    mut out: *mut CommandBuffer,
+    mut r: Rect,
+    mut rounding: f32,
+    mut item: *const StyleItem,
+) -> () {
+    if (*item).type_0 as Uint == NK_STYLE_ITEM_GRADIENT as Int as Uint {
+        nk_fill_rect_gradient(
+            out,
+            r,
+            [rounding; 4],
+            nk_gradient_in_rect(&(*item).data.gradient, r),
+        );
+    } else {
+        nk_fill_rect(out, r, rounding, (*item).data.color);
+    };
+}
+
+unsafe fn nk_fill_style_item_circle(
+    mut out: *mut CommandBuffer,
+    mut r: Rect,
+    mut item: *const StyleItem,
+) -> () {
+    if (*item).type_0 as Uint == NK_STYLE_ITEM_GRADIENT as Int as Uint {
+        nk_fill_circle_gradient(out, r, nk_gradient_in_rect(&(*item).data.gradient, r));
+    } else {
+        nk_fill_circle(out, r, (*item).data.color);
+    };
+}
+
+/* single color standing in for a color or gradient style item, e.g. as
+ * text background; gradients have none */
+unsafe fn nk_style_item_background(mut item: *const StyleItem) -> Color {
+    if (*item).type_0 as Uint == NK_STYLE_ITEM_GRADIENT as Int as Uint {
+        return nk_rgba(0i32, 0i32, 0i32, 0i32);
+    } else {
+        return (*item).data.color;
+    };
+}
+
+pub unsafe fn nk_gradient_linear(
+    mut start: Point,
+    mut end: Point,
+    mut start_color: Color,
+    mut end_color: Color,
+) -> Gradient {
+    return Gradient {
+        type_0: NK_GRADIENT_LINEAR,
+        start: start,
+        end: end,
+        start_color: start_color,
+        end_color: end_color,
+    };
+}
+
+pub unsafe fn nk_gradient_radial(
+    mut center: Point,
+    mut radius: f32,
+    mut inner: Color,
+    mut outer: Color,
+) -> Gradient {
+    return Gradient {
+        type_0: NK_GRADIENT_RADIAL,
+        start: center,
+        end: Point::new(center.x + radius, center.y),
+        start_color: inner,
+        end_color: outer,
+    };
+}
+
+pub unsafe fn nk_stroke_style(mut cap: LineCap, mut join: LineJoin) -> StrokeStyle {
+    return StrokeStyle {
+        cap: cap,
+        join: join,
+        miter_limit: 4.0f32,
+        dash_offset: 0.0f32,
+        dash_count: 0i32 as Uint,
+        dash: [0.0f32; 8],
+    };
+}
+
+/* copies at most NK_STROKE_MAX_DASHES lengths; an empty pattern is solid */
+pub unsafe fn nk_stroke_style_dash(
+    mut style: StrokeStyle,
+    mut dashes: *const f32,
+    mut count: Int,
+    mut offset: f32,
+) -> StrokeStyle {
+    let mut res: StrokeStyle = style;
+    let mut i: Int = 0;
+    if dashes.is_null() || count < 0i32 {
+        count = 0i32
+    }
+    if count > NK_STROKE_MAX_DASHES as Int {
+        count = NK_STROKE_MAX_DASHES as Int
+    }
+    res.dash = [0.0f32; 8];
+    while i < count {
+        res.dash[i as usize] = *dashes.offset(i as isize);
+        i += 1
+    }
+    res.dash_count = count as Uint;
+    res.dash_offset = offset;
+    return res;
+}
+
+/* maps a gradient given relative to a rectangle into screen space */
+pub unsafe fn nk_gradient_in_rect(mut g: *const Gradient, mut r: Rect) -> Gradient {
+    let mut res: Gradient = *g;
+    res.start = Point::new(r.x + (*g).start.x * r.w, r.y + (*g).start.y * r.h);
+    res.end = Point::new(r.x + (*g).end.x * r.w, r.y + (*g).end.y * r.h);
+    return res;
+}
+
+pub unsafe fn nk_gradient_color(mut g: *const Gradient, mut p: Point) -> Color {
+    let mut t: f32 = 0.;
+    let dx: f32 = (*g).end.x - (*g).start.x;
+    let dy: f32 = (*g).end.y - (*g).start.y;
+    let len2: f32 = dx * dx + dy * dy;
+    if len2 == 0.0f32 {
+        t = 1.0f32
+    } else if (*g).type_0 == NK_GRADIENT_RADIAL {
+        let px: f32 = p.x - (*g).start.x;
+        let py: f32 = p.y - (*g).start.y;
+        t = nk_sqrt((px * px + py * py) / len2)
+    } else {
+        t = ((p.x - (*g).start.x) * dx + (p.y - (*g).start.y) * dy) / len2
+    }
+    t = if t < 0.0f32 {
+        0.0f32
+    } else if t > 1.0f32 {
+        1.0f32
+    } else {
+        t
+    };
+    let a: Color = (*g).start_color;
+    let b: Color = (*g).end_color;
+    return Color {
+        r: (a.r as f32 + (b.r as f32 - a.r as f32) * t + 0.5f32) as u8,
+        g: (a.g as f32 + (b.g as f32 - a.g as f32) * t + 0.5f32) as u8,
+        b: (a.b as f32 + (b.b as f32 - a.b as f32) * t + 0.5f32) as u8,
+        a: (a.a as f32 + (b.a as f32 - a.a as f32) * t + 0.5f32) as u8,
+    };
+}
+
 const nk_red: Color = Color {
     r: 255,
     g: 0,
@@ -31531,3 +35600,689 @@ static mut nk_yellow: Color = Color {
     b: 0,
     a: 255,
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use allocator::{rust_allocator, OwnedContext};
+    use command::Command;
+    use driver::Driver;
+    use harness::{describe_command, Harness, InputOp};
+    use raster::Pixmap;
+
+    /* one input frame of `ctx` running `f` between begin and end */
+    unsafe fn input_frame<F: FnOnce(*mut Context)>(ctx: *mut Context, f: F) {
+        nk_input_begin(ctx);
+        f(ctx);
+        nk_input_end(ctx);
+    }
+
+    /* presses and releases the left button at `x`, `y` and `time`, returning
+     * the click count and whether the double-click button went down */
+    unsafe fn click(ctx: *mut Context, x: Int, y: Int, time: f64) -> (Int, bool) {
+        input_frame(ctx, |ctx| {
+            nk_input_button_timed(ctx, NK_BUTTON_LEFT, x, y, 1, time)
+        });
+        let count = nk_input_mouse_click_count(&(*ctx).input, NK_BUTTON_LEFT);
+        let double = 0 != nk_input_is_mouse_pressed(&(*ctx).input, NK_BUTTON_DOUBLE);
+        input_frame(ctx, |ctx| {
+            nk_input_button_timed(ctx, NK_BUTTON_LEFT, x, y, 0, time)
+        });
+        assert_eq!(
+            (*ctx).input.mouse.buttons[NK_BUTTON_DOUBLE as usize].down,
+            0
+        );
+        (count, double)
+    }
+
+    #[test]
+    fn clicks_count_up_within_the_interval() {
+        let mut ctx = OwnedContext::new();
+        let ctx = ctx.as_mut_ptr();
+        unsafe {
+            assert_eq!(click(ctx, 10, 10, 1.0), (1, false));
+            assert_eq!(click(ctx, 10, 10, 1.2), (2, true));
+            assert_eq!(click(ctx, 10, 10, 1.45), (3, false));
+            /* 0.36s after the last press is too late */
+            assert_eq!(click(ctx, 10, 10, 1.81), (1, false));
+            assert_eq!(click(ctx, 10, 10, 2.0), (2, true));
+            /* a clock running backwards starts over */
+            assert_eq!(click(ctx, 10, 10, 1.9), (1, false));
+        }
+    }
+
+    #[test]
+    fn clicks_count_up_within_the_distance() {
+        let mut ctx = OwnedContext::new();
+        let ctx = ctx.as_mut_ptr();
+        unsafe {
+            assert_eq!(click(ctx, 10, 10, 0.0), (1, false));
+            /* 6 pixels away is still the same spot */
+            assert_eq!(click(ctx, 16, 10, 0.1), (2, true));
+            assert_eq!(click(ctx, 20, 14, 0.2), (3, false));
+            assert_eq!(click(ctx, 27, 14, 0.3), (1, false));
+            (*ctx).clicks.max_distance = 10.0;
+            assert_eq!(click(ctx, 35, 20, 0.4), (2, true));
+        }
+    }
+
+    #[test]
+    fn out_of_range_buttons_are_ignored() {
+        let mut ctx = OwnedContext::new();
+        let ctx = ctx.as_mut_ptr();
+        unsafe {
+            input_frame(ctx, |ctx| {
+                nk_input_button_timed(ctx, NK_BUTTON_MAX, 1, 2, 1, 0.0);
+                nk_input_button_timed(ctx, Buttons::MAX, 1, 2, 1, 0.0);
+            });
+            let mouse = &(*ctx).input.mouse;
+            assert!(mouse.buttons.iter().all(|b| b.down == 0 && b.clicked == 0));
+            assert!(mouse.click_count.iter().all(|&c| c == 0));
+            assert!((*ctx).clicks.last_time.iter().all(|&t| t == 0.0));
+            assert_eq!(nk_widget_clicks(ctx, NK_BUTTON_MAX), 0);
+        }
+    }
+
+    #[test]
+    fn widgets_report_their_clicks() {
+        let mut driver = Driver::new(Harness::new(200, 60), Vec::new(), |ui, clicks| {
+            ui.window("clicks", Rect::new(0.0, 0.0, 200.0, 60.0), 0, |ui| {
+                ui.layout_row_dynamic(30.0, 2);
+                ui.button("here");
+                let n = ui.widget_clicks(NK_BUTTON_LEFT);
+                if n != 0 {
+                    clicks.push((n, ui.is_widget_double_clicked()));
+                }
+                ui.button("there");
+                assert_eq!(ui.widget_clicks(NK_BUTTON_LEFT), 0);
+            });
+        });
+        driver.multi_click("here", 3).unwrap();
+        assert_eq!(driver.state, [(1, false), (2, true), (3, false)]);
+    }
+
+    #[test]
+    fn single_finger_drags_report_their_pan() {
+        let mut ctx = OwnedContext::new();
+        let ctx = ctx.as_mut_ptr();
+        unsafe {
+            input_frame(ctx, |ctx| nk_input_touch(ctx, 7, 10, 10, 1));
+            assert_eq!(nk_input_has_gesture(&(*ctx).input, NK_GESTURE_PAN), 0);
+            /* still within the tap distance */
+            input_frame(ctx, |ctx| nk_input_touch(ctx, 7, 16, 10, 1));
+            assert_eq!(nk_input_has_gesture(&(*ctx).input, NK_GESTURE_PAN), 0);
+            input_frame(ctx, |ctx| nk_input_touch(ctx, 7, 30, 10, 1));
+            assert_ne!(nk_input_has_gesture(&(*ctx).input, NK_GESTURE_PAN), 0);
+            assert_eq!(
+                ((*ctx).input.touch.pan.x, (*ctx).input.touch.pan.y),
+                (14.0, 0.0)
+            );
+            input_frame(ctx, |ctx| nk_input_touch(ctx, 7, 35, 14, 1));
+            assert_eq!(
+                ((*ctx).input.touch.pan.x, (*ctx).input.touch.pan.y),
+                (5.0, 4.0)
+            );
+            /* a resting finger stops panning */
+            input_frame(ctx, |_| ());
+            assert_eq!(nk_input_has_gesture(&(*ctx).input, NK_GESTURE_PAN), 0);
+            assert_eq!(
+                ((*ctx).input.touch.pan.x, (*ctx).input.touch.pan.y),
+                (0.0, 0.0)
+            );
+            input_frame(ctx, |ctx| nk_input_touch(ctx, 7, 35, 14, 0));
+            assert_eq!(nk_input_has_gesture(&(*ctx).input, NK_GESTURE_TAP), 0);
+        }
+    }
+
+    #[test]
+    fn two_fingers_report_their_pinch() {
+        let mut ctx = OwnedContext::new();
+        let ctx = ctx.as_mut_ptr();
+        let mut center = Point::new(0.0, 0.0);
+        unsafe {
+            input_frame(ctx, |ctx| {
+                nk_input_touch(ctx, 1, 50, 50, 1);
+                nk_input_touch(ctx, 2, 70, 50, 1);
+            });
+            assert_eq!(nk_input_pinch(&(*ctx).input, &mut center), 1.0);
+            input_frame(ctx, |ctx| {
+                nk_input_touch(ctx, 1, 40, 50, 1);
+                nk_input_touch(ctx, 2, 80, 50, 1);
+            });
+            assert_eq!(nk_input_pinch(&(*ctx).input, &mut center), 2.0);
+            assert_eq!((center.x, center.y), (60.0, 50.0));
+            /* the fingers spread symmetrically, so nothing scrolls */
+            assert_eq!(nk_input_has_gesture(&(*ctx).input, NK_GESTURE_SCROLL), 0);
+            input_frame(ctx, |ctx| {
+                nk_input_touch(ctx, 1, 30, 50, 1);
+                nk_input_touch(ctx, 2, 90, 50, 1);
+            });
+            assert_eq!(nk_input_pinch(&(*ctx).input, &mut center), 1.5);
+            assert_eq!(nk_input_pinch_total(&(*ctx).input), 3.0);
+            assert_eq!(nk_input_has_gesture(&(*ctx).input, NK_GESTURE_PAN), 0);
+            input_frame(ctx, |ctx| {
+                nk_input_touch(ctx, 1, 45, 50, 1);
+                nk_input_touch(ctx, 2, 75, 50, 1);
+            });
+            assert_eq!(nk_input_pinch(&(*ctx).input, &mut center), 0.5);
+            assert_eq!(nk_input_pinch_total(&(*ctx).input), 1.5);
+            input_frame(ctx, |ctx| nk_input_touch(ctx, 2, 75, 50, 0));
+            assert_eq!(nk_input_pinch_total(&(*ctx).input), 1.0);
+        }
+    }
+
+    #[test]
+    fn overlong_preedits_are_cut_at_a_glyph_boundary() {
+        let mut ctx = OwnedContext::new();
+        let ctx = ctx.as_mut_ptr();
+        /* 1 + 3 * 50 bytes; a plain cut at 128 would split the 43rd glyph */
+        let text = format!("a{}", "\u{3042}".repeat(50));
+        unsafe {
+            nk_input_preedit(ctx, text.as_ptr() as _, text.len() as Int, 60, 40, 60);
+            let preedit = &(*ctx).preedit;
+            assert_eq!(preedit.len, 127);
+            let kept = ::std::slice::from_raw_parts(preedit.text.as_ptr() as *const u8, 127);
+            assert_eq!(kept, &text.as_bytes()[..127]);
+            assert_eq!(
+                (preedit.cursor, preedit.sel_start, preedit.sel_end),
+                (43, 40, 43)
+            );
+        }
+    }
+
+    #[test]
+    fn edits_report_the_caret_inside_the_preedit() {
+        let mut driver = Driver::new(
+            Harness::new(200, 60),
+            (String::from("ab"), None),
+            |ui, state: &mut (String, Option<Rect>)| {
+                ui.window("edit", Rect::new(0.0, 0.0, 200.0, 60.0), 0, |ui| {
+                    ui.layout_row_dynamic(30.0, 1);
+                    ui.edit_string(NK_EDIT_FIELD as Flags, &mut state.0, 64);
+                    state.1 = ui.edit_caret();
+                });
+            },
+        );
+        driver.frame(&[InputOp::Motion(100, 20)]);
+        driver.click_button(NK_BUTTON_LEFT);
+        let text = "\u{3042}".repeat(50);
+        let mut caret_at = |len: usize, cursor: Int| unsafe {
+            let ctx = driver.harness.context_mut() as *mut Context;
+            nk_input_preedit(ctx, text.as_ptr() as _, len as Int, cursor, 0, 0);
+            driver.frame(&[]);
+            driver.state.1.expect("no caret reported")
+        };
+        let typed = caret_at(0, 0);
+        let start = caret_at(text.len(), 0);
+        let middle = caret_at(text.len(), 2);
+        let end = caret_at(text.len(), 100);
+        let font = driver.harness.context().style.font;
+        let width = |len: Int| unsafe {
+            (*font).width.unwrap()((*font).userdata, (*font).height, text.as_ptr() as _, len)
+        };
+        let ctx = driver.harness.context();
+        assert_eq!(ctx.preedit.len, 126);
+        unsafe {
+            assert_eq!(
+                (start.w, start.h),
+                (ctx.style.edit.cursor_size, (*font).height)
+            );
+        }
+        /* the composition starts at the caret of the typed text */
+        assert_eq!((start.x, start.y), (typed.x, typed.y));
+        assert_eq!(middle.x - start.x, width(6));
+        assert_eq!(end.x - start.x, width(126));
+        assert_eq!((middle.y, end.y), (start.y, start.y));
+        /* clicking elsewhere ends the edit and its caret */
+        driver.frame(&[InputOp::Motion(100, 45)]);
+        driver.click_button(NK_BUTTON_LEFT);
+        assert!(driver.state.1.is_none());
+    }
+
+    #[test]
+    fn buffer_info_counts_failed_allocations() {
+        let mut memory = [0u8; 64];
+        let mut b: Buffer = unsafe { mem::zeroed() };
+        let mut info: MemoryStatus = unsafe { mem::zeroed() };
+        unsafe {
+            nk_buffer_init_fixed(&mut b, memory.as_mut_ptr() as _, 64);
+            assert!(!nk_buffer_alloc(&mut b, NK_BUFFER_FRONT, 48, 1).is_null());
+            assert!(nk_buffer_alloc(&mut b, NK_BUFFER_FRONT, 32, 1).is_null());
+            nk_buffer_info(&mut info, &mut b);
+            assert_eq!((info.allocated, info.needed, info.failed), (48, 80, 1));
+            assert!(nk_buffer_alloc(&mut b, NK_BUFFER_BACK, 32, 1).is_null());
+            assert!(!nk_buffer_alloc(&mut b, NK_BUFFER_BACK, 8, 1).is_null());
+            nk_buffer_info(&mut info, &mut b);
+            assert_eq!((info.needed, info.failed), (120, 2));
+        }
+    }
+
+    #[test]
+    fn promoted_buffer_leaves_its_back_in_place() {
+        let mut memory = [0u8; 64];
+        let alloc = rust_allocator();
+        let mut b: Buffer = unsafe { mem::zeroed() };
+        let mut info: MemoryStatus = unsafe { mem::zeroed() };
+        unsafe {
+            nk_buffer_init_fixed(&mut b, memory.as_mut_ptr() as _, 64);
+            nk_buffer_set_fallback(&mut b, &alloc);
+            let back = nk_buffer_alloc(&mut b, NK_BUFFER_BACK, 16, 1) as *mut u8;
+            ptr::write_bytes(back, 0xbb, 16);
+            let front = nk_buffer_alloc(&mut b, NK_BUFFER_FRONT, 40, 1) as *mut u8;
+            ptr::write_bytes(front, 0xff, 40);
+            assert!(!nk_buffer_alloc(&mut b, NK_BUFFER_FRONT, 32, 1).is_null());
+            assert_eq!(b.type_0, NK_BUFFER_DYNAMIC);
+            nk_buffer_info(&mut info, &mut b);
+            assert!(info.memory != memory.as_mut_ptr() as *mut Void);
+            assert_eq!(info.allocated, 72);
+            /* the whole new memory is free for the front, none held by a back */
+            assert_eq!(b.size, info.size);
+            let moved = std::slice::from_raw_parts(info.memory as *const u8, 40);
+            assert!(moved.iter().all(|&v| v == 0xff));
+            nk_buffer_free(&mut b);
+        }
+        assert!(memory[48..].iter().all(|&v| v == 0xbb));
+    }
+
+    #[test]
+    fn atlas_reports_the_baked_image_size() {
+        unsafe {
+            for &(format, bpp) in &[(NK_FONT_ATLAS_ALPHA8, 1), (NK_FONT_ATLAS_RGBA32, 4)] {
+                let mut atlas: Box<FontAtlas> = Box::new(mem::zeroed());
+                nk_font_atlas_init_default(&mut *atlas);
+                nk_font_atlas_begin(&mut *atlas);
+                nk_font_atlas_add_default(&mut *atlas, 13.0, ptr::null());
+                let (mut w, mut h) = (0, 0);
+                assert!(!nk_font_atlas_bake(&mut *atlas, &mut w, &mut h, format).is_null());
+                assert_eq!(atlas.pixel_size, (w * h * bpp) as Size);
+
+                let mut ctx = OwnedContext::new();
+                let mut report: MemoryReport = mem::zeroed();
+                nk_memory_report(ctx.as_mut_ptr(), &*atlas, &mut report);
+                assert!(report.font_atlas.used > atlas.pixel_size);
+                assert_eq!(
+                    report.total,
+                    report.font_atlas.used
+                        + report.commands.used
+                        + report.pool.used
+                        + report.text_edit.used
+                );
+                nk_font_atlas_clear(&mut *atlas);
+                assert_eq!(atlas.pixel_size, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn unchanged_frames_need_no_redraw() {
+        let mut harness = Harness::new(120, 60);
+        let mut frame = |harness: &mut Harness, label: &str| {
+            harness.frame(&[], |ui| {
+                ui.window("redraw", Rect::new(0.0, 0.0, 120.0, 60.0), 0, |ui| {
+                    ui.layout_row_dynamic(30.0, 1);
+                    ui.button(label);
+                });
+            });
+            unsafe { nk_redraw_timeout(harness.context()) }
+        };
+        /* frames are not hashed until asked for */
+        frame(&mut harness, "a");
+        assert_eq!(frame(&mut harness, "a"), 0.0);
+        assert_eq!(harness.context().redraw.hash, 0);
+
+        unsafe { nk_track_redraws(harness.context_mut(), 1) };
+        assert_eq!(frame(&mut harness, "a"), 0.0);
+        assert!(frame(&mut harness, "a") < 0.0);
+        assert!(frame(&mut harness, "a") < 0.0);
+        assert_eq!(frame(&mut harness, "b"), 0.0);
+        assert!(frame(&mut harness, "b") < 0.0);
+        /* timers still wake the host up while nothing changes */
+        harness.frame(&[], |ui| ui.request_redraw(0.5));
+        harness.frame(&[], |ui| ui.request_redraw(0.5));
+        assert_eq!(unsafe { nk_redraw_timeout(harness.context()) }, 0.5);
+
+        unsafe { nk_track_redraws(harness.context_mut(), 0) };
+        assert_eq!(frame(&mut harness, "b"), 0.0);
+        assert_eq!(frame(&mut harness, "b"), 0.0);
+    }
+
+    #[derive(Copy, Clone)]
+    #[repr(C)]
+    struct Vertex {
+        position: [f32; 2],
+        uv: [f32; 2],
+        col: [u8; 4],
+    }
+
+    /* the vertices nk_convert makes of the frame built in `ctx` */
+    unsafe fn convert(ctx: *mut Context, aa: AntiAliasing) -> Vec<Vertex> {
+        let layout = [
+            DrawVertexLayoutElement {
+                attribute: NK_VERTEX_POSITION,
+                format: NK_FORMAT_FLOAT,
+                offset: 0,
+            },
+            DrawVertexLayoutElement {
+                attribute: NK_VERTEX_TEXCOORD,
+                format: NK_FORMAT_FLOAT,
+                offset: 8,
+            },
+            DrawVertexLayoutElement {
+                attribute: NK_VERTEX_COLOR,
+                format: NK_FORMAT_R8G8B8A8,
+                offset: 16,
+            },
+            DrawVertexLayoutElement {
+                attribute: NK_VERTEX_ATTRIBUTE_COUNT,
+                format: NK_FORMAT_COUNT,
+                offset: 0,
+            },
+        ];
+        let mut config: ConvertConfig = mem::zeroed();
+        config.vertex_layout = layout.as_ptr();
+        config.vertex_size = mem::size_of::<Vertex>() as Size;
+        config.vertex_alignment = mem::align_of::<Vertex>() as Size;
+        config.circle_segment_count = 22;
+        config.curve_segment_count = 22;
+        config.arc_segment_count = 22;
+        config.global_alpha = 1.0;
+        config.shape_AA = aa;
+        config.line_AA = aa;
+        let mut buffers: [Buffer; 3] = mem::zeroed();
+        for b in buffers.iter_mut() {
+            nk_buffer_init_default(b);
+        }
+        let [ref mut cmds, ref mut vertices, ref mut elements] = buffers;
+        nk_convert(ctx, cmds, vertices, elements, &config);
+        let out = std::slice::from_raw_parts(
+            vertices.memory.ptr as *const Vertex,
+            (*ctx).draw_list.vertex_count as usize,
+        )
+        .to_vec();
+        for b in buffers.iter_mut() {
+            nk_buffer_free(b);
+        }
+        out
+    }
+
+    /* the vertices of a 32x32 window over `background` with `draw` on its
+     * canvas */
+    fn window_vertices<F: FnOnce(*mut CommandBuffer)>(
+        aa: AntiAliasing,
+        background: StyleItem,
+        draw: F,
+    ) -> Vec<Vertex> {
+        let mut harness = Harness::new(32, 32);
+        unsafe {
+            let ctx: *mut Context = harness.context_mut();
+            (*ctx).style.window.fixed_background = background;
+            let bounds = Rect::new(0.0, 0.0, 32.0, 32.0);
+            let flags = NK_WINDOW_NO_SCROLLBAR as Flags;
+            nk_begin(ctx, b"convert\0".as_ptr() as _, bounds, flags);
+            draw(nk_window_get_canvas(ctx));
+            nk_end(ctx);
+            let vertices = convert(ctx, aa);
+            nk_clear(ctx);
+            vertices
+        }
+    }
+
+    #[test]
+    fn gradient_backgrounds_reach_their_end_colors_at_the_edges() {
+        let gradient = unsafe {
+            nk_style_item_gradient(nk_gradient_linear(
+                Point::new(0.0, 0.0),
+                Point::new(1.0, 0.0),
+                nk_rgb(255, 0, 0),
+                nk_rgb(0, 0, 255),
+            ))
+        };
+        let vertices = window_vertices(NK_ANTI_ALIASING_ON, gradient, |_| ());
+        /* the center first, then rings out to the edge and its fringe */
+        assert_eq!(vertices[0].position, [16.0, 16.0]);
+        assert_eq!(vertices[0].col, [128, 0, 128, 255]);
+        for v in &vertices {
+            let x = v.position[0];
+            if v.col[3] == 0 {
+                /* the fringe outside the window takes the clamped end colors */
+                assert!(!(0.0..=32.0).contains(&x));
+                let end = if x < 0.0 {
+                    [255, 0, 0, 0]
+                } else {
+                    [0, 0, 255, 0]
+                };
+                assert_eq!(v.col, end);
+            } else if x < 1.0 {
+                assert!(v.col[0] >= 250 && v.col[2] <= 5, "{:?} at {}", v.col, x);
+            } else if x > 31.0 {
+                assert!(v.col[0] <= 5 && v.col[2] >= 250, "{:?} at {}", v.col, x);
+            }
+        }
+        let mut opaque: Vec<&Vertex> = vertices.iter().filter(|v| v.col[3] == 255).collect();
+        opaque.sort_by(|a, b| a.position[0].partial_cmp(&b.position[0]).unwrap());
+        assert!(opaque.windows(2).all(|w| w[0].col[0] >= w[1].col[0]));
+    }
+
+    #[test]
+    fn radial_gradient_circles_fade_from_the_center_out() {
+        let vertices = window_vertices(
+            NK_ANTI_ALIASING_ON,
+            unsafe { nk_style_item_hide() },
+            |b| unsafe {
+                let g = nk_gradient_radial(
+                    Point::new(16.0, 16.0),
+                    12.0,
+                    nk_rgb(255, 255, 255),
+                    nk_rgba(0, 0, 0, 128),
+                );
+                nk_fill_circle_gradient(b, Rect::new(4.0, 4.0, 24.0, 24.0), g);
+            },
+        );
+        let radius = |v: &Vertex| (v.position[0] - 16.0).hypot(v.position[1] - 16.0);
+        /* arcs and square roots are approximated, so allow a few steps */
+        assert!(radius(&vertices[0]) < 0.2);
+        assert!(vertices[0].col.iter().all(|&c| c >= 250));
+        assert!(vertices
+            .iter()
+            .all(|v| v.col[0] == v.col[1] && v.col[1] == v.col[2]));
+        let fringe: Vec<&Vertex> = vertices.iter().filter(|v| v.col[3] == 0).collect();
+        assert!(!fringe.is_empty());
+        assert!(fringe.iter().all(|v| radius(v) > 11.9 && v.col[0] <= 12));
+        let mut inside: Vec<&Vertex> = vertices.iter().filter(|v| v.col[3] != 0).collect();
+        inside.sort_by(|a, b| radius(a).partial_cmp(&radius(b)).unwrap());
+        assert!(inside.windows(2).all(|w| w[0].col[3] + 1 >= w[1].col[3]));
+        assert!(inside.last().unwrap().col[3] < 140);
+    }
+
+    /* x ranges of the dashes of a horizontal line from 2 to 28 */
+    fn dashes(dash: &[f32], offset: f32) -> Vec<(f32, f32)> {
+        let vertices = window_vertices(
+            NK_ANTI_ALIASING_OFF,
+            unsafe { nk_style_item_hide() },
+            |b| unsafe {
+                let style = nk_stroke_style(NK_LINE_CAP_BUTT, NK_LINE_JOIN_MITER);
+                let style = nk_stroke_style_dash(style, dash.as_ptr(), dash.len() as Int, offset);
+                nk_stroke_line_styled(b, 2.0, 16.0, 28.0, 16.0, 2.0, nk_rgb(255, 255, 255), &style);
+            },
+        );
+        /* butt caps leave one quad per dash */
+        assert_eq!(vertices.len() % 4, 0);
+        vertices
+            .chunks(4)
+            .map(|q| {
+                let xs = q.iter().map(|v| v.position[0]);
+                (
+                    xs.clone().fold(f32::MAX, f32::min),
+                    xs.fold(f32::MIN, f32::max),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dashed_lines_split_into_their_pattern() {
+        let pattern = [
+            (2.0, 6.0),
+            (8.0, 12.0),
+            (14.0, 18.0),
+            (20.0, 24.0),
+            (26.0, 28.0),
+        ];
+        assert_eq!(dashes(&[4.0, 2.0], 0.0), pattern);
+        /* the offset shortens the first dash */
+        let offset = [
+            (2.0, 3.0),
+            (5.0, 9.0),
+            (11.0, 15.0),
+            (17.0, 21.0),
+            (23.0, 27.0),
+        ];
+        assert_eq!(dashes(&[4.0, 2.0], 3.0), offset);
+        /* odd patterns swap on and off every other round */
+        let odd = [
+            (2.0, 5.0),
+            (8.0, 11.0),
+            (14.0, 17.0),
+            (20.0, 23.0),
+            (26.0, 28.0),
+        ];
+        assert_eq!(dashes(&[3.0], 0.0), odd);
+        assert_eq!(dashes(&[], 0.0), [(2.0, 28.0)]);
+    }
+
+    #[test]
+    fn window_shadows_fade_out_around_the_window() {
+        let mut harness = Harness::new(64, 64);
+        let mut image = Pixmap::new(64, 64);
+        let vertices = unsafe {
+            image.clear(nk_rgb(255, 255, 255));
+            let ctx: *mut Context = harness.context_mut();
+            (*ctx).style.window.shadow_color = nk_rgba(0, 0, 0, 200);
+            let bounds = Rect::new(10.0, 10.0, 40.0, 30.0);
+            nk_begin(ctx, b"shadow\0".as_ptr() as _, bounds, 0);
+            nk_end(ctx);
+            /* offset by (2, 4) and drawn before anything else of the window */
+            let first = (*ctx).commands().next().map(|cmd| describe_command(&cmd));
+            assert_eq!(first.unwrap(), "rect_shadow 12 14 40 30 r=0 f=12 #000000c8");
+            let vertices = convert(ctx, NK_ANTI_ALIASING_ON);
+            harness.rasterizer.render(&mut *ctx, &mut image);
+            nk_clear(ctx);
+            vertices
+        };
+        /* inner and outer edge in turn, the outer one transparent and a
+         * feather further out */
+        for pair in vertices[..48].chunks(2) {
+            assert_eq!((pair[0].col, pair[1].col), ([0, 0, 0, 200], [0, 0, 0, 0]));
+            let dx = pair[1].position[0] - pair[0].position[0];
+            let dy = pair[1].position[1] - pair[0].position[1];
+            assert!((dx.hypot(dy) - 12.0).abs() < 0.05, "{} {}", dx, dy);
+        }
+        /* below the window the shadow lightens until its outer edge at 50 */
+        let column: Vec<u8> = (40..52).map(|y| image.pixel(30, y)[0]).collect();
+        assert!(column[..10].windows(2).all(|w| w[0] < w[1]), "{:?}", column);
+        assert_eq!(&column[10..], [255, 255]);
+    }
+
+    #[test]
+    fn styled_lines_skip_invisible_and_clipped_strokes() {
+        let mut harness = Harness::new(32, 32);
+        unsafe {
+            let ctx: *mut Context = harness.context_mut();
+            let white = nk_rgb(255, 255, 255);
+            let style = nk_stroke_style(NK_LINE_CAP_SQUARE, NK_LINE_JOIN_MITER);
+            let bounds = Rect::new(0.0, 0.0, 32.0, 32.0);
+            nk_begin(ctx, b"lines\0".as_ptr() as _, bounds, 0);
+            let b = nk_window_get_canvas(ctx);
+            nk_push_scissor(b, bounds);
+            nk_stroke_line_styled(b, 2.0, 16.0, 28.0, 16.0, 2.0, nk_rgba(0, 0, 0, 0), &style);
+            nk_stroke_line_styled(b, 2.0, 40.0, 28.0, 40.0, 2.0, white, &style);
+            nk_stroke_line_styled(b, 40.0, 2.0, 40.0, 28.0, 2.0, white, &style);
+            /* half the thickness reaches into the clip rectangle */
+            nk_stroke_line_styled(b, 2.0, 33.0, 28.0, 33.0, 4.0, white, &style);
+            nk_stroke_line_styled(b, 2.0, 16.0, 28.0, 16.0, 2.0, white, &style);
+            /* curves are culled by the hull of their control points */
+            nk_stroke_curve_styled(
+                b, 40.0, 2.0, 50.0, 10.0, 50.0, 20.0, 40.0, 28.0, 2.0, white, &style,
+            );
+            nk_stroke_curve_styled(
+                b, 2.0, 40.0, 10.0, 31.0, 20.0, 31.0, 28.0, 40.0, 2.0, white, &style,
+            );
+            nk_end(ctx);
+            let lines: Vec<f32> = (*ctx)
+                .commands()
+                .filter_map(|cmd| match cmd {
+                    Command::LineStyled { begin, .. } => Some(begin.y),
+                    Command::CurveStyled { begin, .. } => Some(-begin.y),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(lines, [33.0, 16.0, -40.0]);
+            nk_clear(ctx);
+        }
+    }
+
+    /* alpha of every pixel of a translucent polyline with a right angle
+     * and a sharp corner, or of the polygon it closes */
+    fn stroked_corners(aa: AntiAliasing, style: &StrokeStyle, closed: bool) -> Vec<Vec<u8>> {
+        let mut harness = Harness::new(48, 48);
+        harness.rasterizer.anti_aliasing = aa;
+        let mut image = Pixmap::new(48, 48);
+        unsafe {
+            let ctx: *mut Context = harness.context_mut();
+            (*ctx).style.window.fixed_background = nk_style_item_hide();
+            let bounds = Rect::new(0.0, 0.0, 48.0, 48.0);
+            let flags = NK_WINDOW_NO_SCROLLBAR as Flags;
+            nk_begin(ctx, b"corners\0".as_ptr() as _, bounds, flags);
+            let mut points = [6.0f32, 10.0, 38.0, 10.0, 38.0, 40.0, 14.0, 18.0];
+            let (b, color) = (nk_window_get_canvas(ctx), nk_rgba(255, 0, 0, 128));
+            if closed {
+                nk_stroke_polygon_styled(b, points.as_mut_ptr(), 4, 8.0, color, style);
+            } else {
+                nk_stroke_polyline_styled(b, points.as_mut_ptr(), 4, 8.0, color, style);
+            }
+            nk_end(ctx);
+            harness.rasterizer.render(&mut *ctx, &mut image);
+            nk_clear(ctx);
+        }
+        (0..48)
+            .map(|y| (0..48).map(|x| image.pixel(x, y)[3]).collect())
+            .collect()
+    }
+
+    fn max_alpha(alpha: &[Vec<u8>]) -> u8 {
+        alpha
+            .iter()
+            .flat_map(|row| row.iter())
+            .cloned()
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn translucent_strokes_cover_their_joins_once() {
+        let dash = [40.0f32, 10.0];
+        for &join in &[NK_LINE_JOIN_MITER, NK_LINE_JOIN_ROUND, NK_LINE_JOIN_BEVEL] {
+            for &cap in &[NK_LINE_CAP_BUTT, NK_LINE_CAP_ROUND, NK_LINE_CAP_SQUARE] {
+                /* both dashes run through a corner */
+                let style = unsafe {
+                    nk_stroke_style_dash(nk_stroke_style(cap, join), dash.as_ptr(), 2, 0.0)
+                };
+                let alpha = stroked_corners(NK_ANTI_ALIASING_OFF, &style, false);
+                assert_eq!(max_alpha(&alpha), 128, "cap {} join {}", cap, join);
+                let alpha = stroked_corners(NK_ANTI_ALIASING_ON, &style, false);
+                /* the right angle, its outer wedge and the sharp corner */
+                assert_eq!(alpha[10][38], 128, "cap {} join {}", cap, join);
+                assert_eq!(alpha[8][39], 128, "cap {} join {}", cap, join);
+                assert_eq!(alpha[38][37], 128, "cap {} join {}", cap, join);
+                assert_eq!(max_alpha(&alpha), 128, "cap {} join {}", cap, join);
+            }
+            /* a solid polygon is one outline running round */
+            let style = unsafe { nk_stroke_style(NK_LINE_CAP_BUTT, join) };
+            for &aa in &[NK_ANTI_ALIASING_OFF, NK_ANTI_ALIASING_ON] {
+                let alpha = stroked_corners(aa, &style, true);
+                assert_eq!(alpha[10][38], 128, "join {}", join);
+                assert_eq!(max_alpha(&alpha), 128, "join {}", join);
+            }
+        }
+    }
+}
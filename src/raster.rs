@@ -0,0 +1,740 @@
+//! CPU rasterizer for the `nk_convert` vertex/element output.
+//!
+//! Renders a frame into an RGBA `Pixmap` without any GPU. Triangles are
+//! filled with the same blending as the OpenGL example (straight alpha,
+//! vertex color times texel), so anti-aliasing comes from the alpha fringe
+//! `nk_convert` generates and clipping from the draw command scissors.
+
+use custom::CustomDraws;
+use math::{Point, Rect};
+use nuklear::*;
+use std::collections::HashMap;
+use std::{mem, slice};
+
+/// RGBA8 image with straight (not premultiplied) alpha.
+#[derive(Clone, PartialEq)]
+pub struct Pixmap {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/* bytes of a `width` x `height` RGBA8 image; panics if it overflows */
+fn rgba_len(width: u32, height: u32) -> usize {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(4))
+        .expect("Pixmap: image size overflows usize")
+}
+
+impl Pixmap {
+    pub fn new(width: u32, height: u32) -> Self {
+        Pixmap {
+            width,
+            height,
+            pixels: vec![0; rgba_len(width, height)],
+        }
+    }
+
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), rgba_len(width, height));
+        Pixmap {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Copies a tightly packed RGBA32 image, e.g. the result of
+    /// `nk_font_atlas_bake(.., NK_FONT_ATLAS_RGBA32)`.
+    ///
+    /// # Safety
+    ///
+    /// `pixels` must point to `width * height * 4` readable bytes.
+    pub unsafe fn from_raw_rgba(width: u32, height: u32, pixels: *const Void) -> Self {
+        let len = rgba_len(width, height);
+        Self::from_rgba(
+            width,
+            height,
+            slice::from_raw_parts(pixels as *const u8, len).to_vec(),
+        )
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        for px in self.pixels.chunks_mut(4) {
+            px.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+
+    /// Sets the pixels whose centers lie in `rect` to `color`.
+    pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+        let x0 = (rect.x - 0.5).ceil().max(0.0) as u32;
+        let y0 = (rect.y - 0.5).ceil().max(0.0) as u32;
+        let x1 = ((rect.x + rect.w - 0.5).ceil().max(0.0) as u32).min(self.width);
+        let y1 = ((rect.y + rect.h - 0.5).ceil().max(0.0) as u32).min(self.height);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let i = self.index(x, y);
+                self.pixels[i..i + 4].copy_from_slice(&[color.r, color.g, color.b, color.a]);
+            }
+        }
+    }
+
+    /* copies the pixels of `source` whose centers lie in `rect` */
+    fn copy_rect(&mut self, source: &Pixmap, rect: Rect) {
+        let x0 = (rect.x - 0.5).ceil().max(0.0) as u32;
+        let y0 = (rect.y - 0.5).ceil().max(0.0) as u32;
+        let x1 = ((rect.x + rect.w - 0.5).ceil().max(0.0) as u32).min(self.width);
+        let y1 = ((rect.y + rect.h - 0.5).ceil().max(0.0) as u32).min(self.height);
+        for y in y0..y1 {
+            if x0 < x1 {
+                let (a, b) = (self.index(x0, y), self.index(x1 - 1, y) + 4);
+                self.pixels[a..b].copy_from_slice(&source.pixels[a..b]);
+            }
+        }
+    }
+
+    #[inline]
+    fn index(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    #[inline]
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = self.index(x, y);
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    /// Bilinear sample with clamp-to-edge addressing, in `0.0..=1.0`.
+    fn sample(&self, u: f32, v: f32) -> [f32; 4] {
+        if self.width == 0 || self.height == 0 {
+            return [1.0; 4];
+        }
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let clamp = |v: f32, max: u32| (v.max(0.0) as u32).min(max - 1);
+        let (xa, xb) = (clamp(x0, self.width), clamp(x0 + 1.0, self.width));
+        let (ya, yb) = (clamp(y0, self.height), clamp(y0 + 1.0, self.height));
+        let (p00, p10) = (self.pixel(xa, ya), self.pixel(xb, ya));
+        let (p01, p11) = (self.pixel(xa, yb), self.pixel(xb, yb));
+        let mut out = [0.0; 4];
+        for i in 0..4 {
+            let top = p00[i] as f32 * (1.0 - fx) + p10[i] as f32 * fx;
+            let bottom = p01[i] as f32 * (1.0 - fx) + p11[i] as f32 * fx;
+            out[i] = (top * (1.0 - fy) + bottom * fy) / 255.0;
+        }
+        out
+    }
+
+    /// Source-over blend of a straight alpha color in `0.0..=1.0`.
+    #[inline]
+    fn blend(&mut self, x: u32, y: u32, src: [f32; 4]) {
+        let i = self.index(x, y);
+        let sa = src[3];
+        if sa <= 0.0 {
+            return;
+        }
+        let da = self.pixels[i + 3] as f32 / 255.0;
+        let oa = sa + da * (1.0 - sa);
+        for (c, s) in src[..3].iter().enumerate() {
+            let d = self.pixels[i + c] as f32 / 255.0;
+            let o = (s * sa + d * da * (1.0 - sa)) / oa;
+            self.pixels[i + c] = (o * 255.0 + 0.5).min(255.0) as u8;
+        }
+        self.pixels[i + 3] = (oa * 255.0 + 0.5).min(255.0) as u8;
+    }
+}
+
+/// Vertex layout produced for the rasterizer by `nk_convert`.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct RasterVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub col: [u8; 4],
+}
+
+pub struct Rasterizer {
+    pub null: DrawNullTexture,
+    pub anti_aliasing: AntiAliasing,
+    pub global_alpha: f32,
+    pub segment_count: Uint,
+    textures: HashMap<usize, Pixmap>,
+    cmds: Buffer,
+    vertices: Buffer,
+    elements: Buffer,
+}
+
+#[inline]
+fn texture_key(handle: Handle) -> usize {
+    unsafe { handle.ptr as usize }
+}
+
+#[inline]
+fn edge(a: Point, b: Point, x: f32, y: f32) -> f32 {
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+#[inline]
+fn intersect(a: Rect, b: Rect) -> Rect {
+    let x0 = a.x.max(b.x);
+    let y0 = a.y.max(b.y);
+    let x1 = (a.x + a.w).min(b.x + b.w);
+    let y1 = (a.y + a.h).min(b.y + b.h);
+    Rect::new(x0, y0, (x1 - x0).max(0.0), (y1 - y0).max(0.0))
+}
+
+/* `regions` grown to whole pixels inside `target` and cut apart so that
+ * no pixel is in two of them, or translucent shapes would blend twice */
+fn disjoint_regions(regions: &[Rect], target: &Pixmap) -> Vec<Rect> {
+    let (w, h) = (target.width as f32, target.height as f32);
+    let mut out: Vec<[f32; 4]> = Vec::new();
+    for r in regions {
+        let mut pieces = vec![[
+            r.x.floor().max(0.0),
+            r.y.floor().max(0.0),
+            (r.x + r.w).ceil().min(w),
+            (r.y + r.h).ceil().min(h),
+        ]];
+        for b in &out {
+            let mut rest = Vec::new();
+            for a in pieces {
+                if a[0] >= b[2] || b[0] >= a[2] || a[1] >= b[3] || b[1] >= a[3] {
+                    rest.push(a);
+                    continue;
+                }
+                /* the parts of `a` above, below, left and right of `b` */
+                let (y0, y1) = (a[1].max(b[1]), a[3].min(b[3]));
+                rest.push([a[0], a[1], a[2], b[1]]);
+                rest.push([a[0], b[3], a[2], a[3]]);
+                rest.push([a[0], y0, b[0], y1]);
+                rest.push([b[2], y0, a[2], y1]);
+            }
+            pieces = rest
+                .into_iter()
+                .filter(|p| p[2] > p[0] && p[3] > p[1])
+                .collect();
+        }
+        out.extend(pieces.into_iter().filter(|p| p[2] > p[0] && p[3] > p[1]));
+    }
+    out.iter()
+        .map(|p| Rect::new(p[0], p[1], p[2] - p[0], p[3] - p[1]))
+        .collect()
+}
+
+/* top-left fill rule so pixels on shared edges are only blended once;
+ * `fill_triangle` winds clockwise on screen, so top edges run to the
+ * right and left edges upwards */
+#[inline]
+fn is_top_left(a: Point, b: Point) -> bool {
+    (a.y == b.y && b.x > a.x) || b.y < a.y
+}
+
+impl Rasterizer {
+    /// `null` is the white texture region returned by `nk_font_atlas_end`.
+    pub fn new(null: DrawNullTexture) -> Self {
+        unsafe {
+            let mut raster = Rasterizer {
+                null,
+                anti_aliasing: NK_ANTI_ALIASING_ON,
+                global_alpha: 1.0,
+                segment_count: 22,
+                textures: HashMap::new(),
+                cmds: mem::zeroed(),
+                vertices: mem::zeroed(),
+                elements: mem::zeroed(),
+            };
+            nk_buffer_init_default(&mut raster.cmds);
+            nk_buffer_init_default(&mut raster.vertices);
+            nk_buffer_init_default(&mut raster.elements);
+            raster
+        }
+    }
+
+    /// Registers the pixels behind `handle`, which is either the font atlas
+    /// handle passed to `nk_font_atlas_end` or the handle of a user `Image`.
+    pub fn set_texture(&mut self, handle: Handle, pixmap: Pixmap) {
+        self.textures.insert(texture_key(handle), pixmap);
+    }
+
+    pub fn remove_texture(&mut self, handle: Handle) -> Option<Pixmap> {
+        self.textures.remove(&texture_key(handle))
+    }
+
+    /// Converts the command queue of `ctx` and draws it into `target`.
+    ///
+    /// Returns the `NK_CONVERT_*` flags of `nk_convert`. The context is not
+    /// cleared, so call `nk_clear` afterwards as with any other backend.
+    pub fn render(&mut self, ctx: &mut Context, target: &mut Pixmap) -> Flags {
+        let all = Rect::new(0.0, 0.0, target.width as f32, target.height as f32);
+        self.render_clipped(ctx, target, &[all], None)
+    }
+
+    /// Like `render`, running the closures of `draws` where their custom
+    /// commands are queued.
+    pub fn render_custom(
+        &mut self,
+        ctx: &mut Context,
+        target: &mut Pixmap,
+        draws: &mut CustomDraws<Pixmap>,
+    ) -> Flags {
+        let all = Rect::new(0.0, 0.0, target.width as f32, target.height as f32);
+        self.render_clipped(ctx, target, &[all], Some(draws))
+    }
+
+    /// Like `render`, but only repaints `regions` of `target`, e.g. the
+    /// result of `DirtyRects::update`: they are snapped outward to whole
+    /// pixels, each pixel is cleared to `background` once and the whole
+    /// frame is drawn again with them as scissor.
+    pub fn render_regions(
+        &mut self,
+        ctx: &mut Context,
+        target: &mut Pixmap,
+        regions: &[Rect],
+        background: Color,
+    ) -> Flags {
+        let regions = disjoint_regions(regions, target);
+        for r in &regions {
+            target.fill_rect(*r, background);
+        }
+        self.render_clipped(ctx, target, &regions, None)
+    }
+
+    /// Like `render_regions`, running the closures of `draws` with their
+    /// output kept to the regions.
+    pub fn render_regions_custom(
+        &mut self,
+        ctx: &mut Context,
+        target: &mut Pixmap,
+        regions: &[Rect],
+        background: Color,
+        draws: &mut CustomDraws<Pixmap>,
+    ) -> Flags {
+        let regions = disjoint_regions(regions, target);
+        for r in &regions {
+            target.fill_rect(*r, background);
+        }
+        self.render_clipped(ctx, target, &regions, Some(draws))
+    }
+
+    fn render_clipped(
+        &mut self,
+        ctx: &mut Context,
+        target: &mut Pixmap,
+        regions: &[Rect],
+        mut draws: Option<&mut CustomDraws<Pixmap>>,
+    ) -> Flags {
+        let layout = [
+            DrawVertexLayoutElement {
+                attribute: NK_VERTEX_POSITION,
+                format: NK_FORMAT_FLOAT,
+                offset: 0,
+            },
+            DrawVertexLayoutElement {
+                attribute: NK_VERTEX_TEXCOORD,
+                format: NK_FORMAT_FLOAT,
+                offset: 8,
+            },
+            DrawVertexLayoutElement {
+                attribute: NK_VERTEX_COLOR,
+                format: NK_FORMAT_R8G8B8A8,
+                offset: 16,
+            },
+            DrawVertexLayoutElement {
+                attribute: NK_VERTEX_ATTRIBUTE_COUNT,
+                format: NK_FORMAT_COUNT,
+                offset: 0,
+            },
+        ];
+        unsafe {
+            let mut config: ConvertConfig = mem::zeroed();
+            config.vertex_layout = layout.as_ptr();
+            config.vertex_size = mem::size_of::<RasterVertex>() as Size;
+            config.vertex_alignment = mem::align_of::<RasterVertex>() as Size;
+            config.null = self.null;
+            config.circle_segment_count = self.segment_count;
+            config.curve_segment_count = self.segment_count;
+            config.arc_segment_count = self.segment_count;
+            config.global_alpha = self.global_alpha;
+            config.shape_AA = self.anti_aliasing;
+            config.line_AA = self.anti_aliasing;
+
+            nk_buffer_clear(&mut self.cmds);
+            nk_buffer_clear(&mut self.vertices);
+            nk_buffer_clear(&mut self.elements);
+            let res = nk_convert(
+                ctx,
+                &mut self.cmds,
+                &mut self.vertices,
+                &mut self.elements,
+                &config,
+            );
+            if res & NK_CONVERT_INVALID_PARAM != 0 {
+                return res;
+            }
+            let vertices = slice::from_raw_parts(
+                self.vertices.memory.ptr as *const RasterVertex,
+                ctx.draw_list.vertex_count as usize,
+            );
+            let elements = slice::from_raw_parts(
+                self.elements.memory.ptr as *const DrawIndex,
+                ctx.draw_list.element_count as usize,
+            );
+            let mut offset = 0usize;
+            let mut cmd = nk__draw_begin(ctx, &self.cmds);
+            while !cmd.is_null() {
+                let count = (*cmd).elem_count as usize;
+                if count != 0 && offset + count <= elements.len() {
+                    for region in regions {
+                        let clip = intersect((*cmd).clip_rect, *region);
+                        self.draw_triangles(
+                            target,
+                            vertices,
+                            &elements[offset..offset + count],
+                            clip,
+                            (*cmd).texture,
+                        );
+                    }
+                }
+                if count == 0 {
+                    if let Some(ref mut draws) = draws {
+                        if draws.id_of(&*cmd).is_some() {
+                            /* closures may paint anywhere, so only what lands
+                             * inside the scissor and the regions is kept */
+                            let before = target.clone();
+                            draws.draw_command(&*cmd, target);
+                            let drawn = mem::replace(target, before);
+                            for region in regions {
+                                target.copy_rect(&drawn, intersect((*cmd).clip_rect, *region));
+                            }
+                        }
+                    }
+                }
+                offset += count;
+                cmd = nk__draw_next(cmd, &self.cmds, ctx);
+            }
+            res
+        }
+    }
+
+    /// Fills indexed triangles clipped to `clip`, sampling `texture`.
+    pub fn draw_triangles(
+        &self,
+        target: &mut Pixmap,
+        vertices: &[RasterVertex],
+        indices: &[DrawIndex],
+        clip: Rect,
+        texture: Handle,
+    ) {
+        let texture = self.textures.get(&texture_key(texture));
+        let x0 = clip.x.max(0.0);
+        let y0 = clip.y.max(0.0);
+        let x1 = (clip.x + clip.w).min(target.width as f32);
+        let y1 = (clip.y + clip.h).min(target.height as f32);
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+        let clip = Rect::new(x0, y0, x1 - x0, y1 - y0);
+        for tri in indices.chunks(3) {
+            if tri.len() < 3 {
+                break;
+            }
+            let (a, b, c) = match (
+                vertices.get(tri[0] as usize),
+                vertices.get(tri[1] as usize),
+                vertices.get(tri[2] as usize),
+            ) {
+                (Some(a), Some(b), Some(c)) => (a, b, c),
+                _ => continue,
+            };
+            fill_triangle(target, clip, texture, [a, b, c]);
+        }
+    }
+}
+
+fn fill_triangle(target: &mut Pixmap, clip: Rect, texture: Option<&Pixmap>, v: [&RasterVertex; 3]) {
+    let mut p = [
+        Point::new(v[0].position[0], v[0].position[1]),
+        Point::new(v[1].position[0], v[1].position[1]),
+        Point::new(v[2].position[0], v[2].position[1]),
+    ];
+    let mut v = v;
+    let mut area = edge(p[0], p[1], p[2].x, p[2].y);
+    if area == 0.0 || !area.is_finite() {
+        return;
+    }
+    if area < 0.0 {
+        p.swap(1, 2);
+        v.swap(1, 2);
+        area = -area;
+    }
+    let min_x = p[0].x.min(p[1].x).min(p[2].x).max(clip.x).floor() as i64;
+    let max_x = p[0].x.max(p[1].x).max(p[2].x).min(clip.x + clip.w).ceil() as i64;
+    let min_y = p[0].y.min(p[1].y).min(p[2].y).max(clip.y).floor() as i64;
+    let max_y = p[0].y.max(p[1].y).max(p[2].y).min(clip.y + clip.h).ceil() as i64;
+    let owned = [
+        is_top_left(p[1], p[2]),
+        is_top_left(p[2], p[0]),
+        is_top_left(p[0], p[1]),
+    ];
+    let inside = |w: f32, owned: bool| w > 0.0 || (w == 0.0 && owned);
+    let (cx0, cy0) = (clip.x.floor() as i64, clip.y.floor() as i64);
+    let (cx1, cy1) = (
+        (clip.x + clip.w).ceil() as i64,
+        (clip.y + clip.h).ceil() as i64,
+    );
+    for y in min_y.max(cy0)..max_y.min(cy1) {
+        let py = y as f32 + 0.5;
+        for x in min_x.max(cx0)..max_x.min(cx1) {
+            let px = x as f32 + 0.5;
+            let w0 = edge(p[1], p[2], px, py);
+            let w1 = edge(p[2], p[0], px, py);
+            let w2 = edge(p[0], p[1], px, py);
+            if !(inside(w0, owned[0]) && inside(w1, owned[1]) && inside(w2, owned[2])) {
+                continue;
+            }
+            let (b0, b1, b2) = (w0 / area, w1 / area, w2 / area);
+            let lerp =
+                |f: &dyn Fn(&RasterVertex) -> f32| f(v[0]) * b0 + f(v[1]) * b1 + f(v[2]) * b2;
+            let mut color = [0.0f32; 4];
+            for (i, c) in color.iter_mut().enumerate() {
+                *c = lerp(&|vx: &RasterVertex| vx.col[i] as f32) / 255.0;
+            }
+            if let Some(tex) = texture {
+                let texel = tex.sample(lerp(&|vx| vx.uv[0]), lerp(&|vx| vx.uv[1]));
+                for i in 0..4 {
+                    color[i] *= texel[i];
+                }
+            }
+            target.blend(x as u32, y as u32, color);
+        }
+    }
+}
+
+impl Drop for Rasterizer {
+    fn drop(&mut self) {
+        unsafe {
+            nk_buffer_free(&mut self.cmds);
+            nk_buffer_free(&mut self.vertices);
+            nk_buffer_free(&mut self.elements);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harness::Harness;
+
+    fn vertex(x: f32, y: f32, u: f32, v: f32, col: [u8; 4]) -> RasterVertex {
+        RasterVertex {
+            position: [x, y],
+            uv: [u, v],
+            col,
+        }
+    }
+
+    /* two triangles sharing the top-left to bottom-right diagonal */
+    fn quad(r: Rect, col: [u8; 4]) -> [RasterVertex; 4] {
+        [
+            vertex(r.x, r.y, 0.0, 0.0, col),
+            vertex(r.x + r.w, r.y, 1.0, 0.0, col),
+            vertex(r.x + r.w, r.y + r.h, 1.0, 1.0, col),
+            vertex(r.x, r.y + r.h, 0.0, 1.0, col),
+        ]
+    }
+
+    const QUAD: [DrawIndex; 6] = [0, 1, 2, 0, 2, 3];
+
+    fn covered(image: &Pixmap) -> Vec<(u32, u32)> {
+        let mut out = Vec::new();
+        for y in 0..image.height {
+            for x in 0..image.width {
+                if image.pixel(x, y)[3] != 0 {
+                    out.push((x, y));
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn rect_fills_pixel_centers_on_its_top_left_edges_once() {
+        let raster = Rasterizer::new(unsafe { mem::zeroed() });
+        let mut image = Pixmap::new(8, 6);
+        /* every edge runs through pixel centers */
+        let v = quad(Rect::new(1.5, 0.5, 4.0, 3.0), [255, 0, 0, 128]);
+        let all = Rect::new(0.0, 0.0, 8.0, 6.0);
+        raster.draw_triangles(&mut image, &v, &QUAD, all, unsafe { nk_handle_id(0) });
+        let mut expected = Vec::new();
+        for y in 0..3 {
+            for x in 1..5 {
+                expected.push((x, y));
+            }
+        }
+        assert_eq!(covered(&image), expected);
+        /* pixels on the shared diagonal are blended once, not twice */
+        for &(x, y) in &expected {
+            assert_eq!(image.pixel(x, y), [255, 0, 0, 128], "pixel {},{}", x, y);
+        }
+    }
+
+    #[test]
+    fn scissor_clips_triangles() {
+        let raster = Rasterizer::new(unsafe { mem::zeroed() });
+        let mut image = Pixmap::new(8, 6);
+        let v = quad(Rect::new(0.0, 0.0, 8.0, 6.0), [0, 0, 255, 255]);
+        let clip = Rect::new(2.0, 1.0, 3.0, 2.0);
+        raster.draw_triangles(&mut image, &v, &QUAD, clip, unsafe { nk_handle_id(0) });
+        let expected: Vec<(u32, u32)> = (1..3).flat_map(|y| (2..5).map(move |x| (x, y))).collect();
+        assert_eq!(covered(&image), expected);
+        assert_eq!(image.pixel(2, 1), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn textured_quad_samples_bilinearly() {
+        let mut raster = Rasterizer::new(unsafe { mem::zeroed() });
+        let texels = vec![
+            200, 0, 0, 255, 0, 200, 0, 255, //
+            0, 0, 200, 255, 100, 100, 100, 255,
+        ];
+        let handle = unsafe { nk_handle_id(3) };
+        raster.set_texture(handle, Pixmap::from_rgba(2, 2, texels));
+        let mut image = Pixmap::new(4, 4);
+        let v = quad(Rect::new(0.0, 0.0, 4.0, 4.0), [255, 255, 255, 255]);
+        let all = Rect::new(0.0, 0.0, 4.0, 4.0);
+        raster.draw_triangles(&mut image, &v, &QUAD, all, handle);
+        /* outer pixels clamp to the nearest texel, inner ones mix */
+        assert_eq!(image.pixel(0, 0), [200, 0, 0, 255]);
+        assert_eq!(image.pixel(3, 0), [0, 200, 0, 255]);
+        assert_eq!(image.pixel(0, 3), [0, 0, 200, 255]);
+        assert_eq!(image.pixel(3, 3), [100, 100, 100, 255]);
+        assert_eq!(image.pixel(1, 0), [150, 50, 0, 255]);
+        assert_eq!(image.pixel(0, 1), [150, 0, 50, 255]);
+    }
+
+    #[test]
+    fn anti_aliased_edge_fades_across_one_pixel() {
+        let mut harness = Harness::new(32, 32);
+        let mut image = Pixmap::new(32, 32);
+        unsafe {
+            let ctx: *mut Context = harness.context_mut();
+            (*ctx).style.window.fixed_background = nk_style_item_hide();
+            let (bounds, white) = (Rect::new(0.0, 0.0, 32.0, 32.0), nk_rgb(255, 255, 255));
+            nk_begin(ctx, b"aa\0".as_ptr() as _, bounds, 0);
+            let b = nk_window_get_canvas(ctx);
+            /* the hypotenuse runs along x + y = 32 */
+            nk_fill_triangle(b, 8.0, 8.0, 24.0, 8.0, 8.0, 24.0, white);
+            nk_end(ctx);
+            harness.rasterizer.render(&mut *ctx, &mut image);
+            nk_clear(ctx);
+        }
+        assert_eq!(image.pixel(15, 15), [255, 255, 255, 255]);
+        assert_eq!(image.pixel(16, 15), [255, 255, 255, 128]);
+        assert_eq!(image.pixel(15, 16), [255, 255, 255, 128]);
+        assert_eq!(image.pixel(16, 16)[3], 0);
+    }
+
+    #[test]
+    fn overlapping_regions_match_a_full_render() {
+        let mut harness = Harness::new(32, 32);
+        let background = Color {
+            r: 0,
+            g: 0,
+            b: 64,
+            a: 255,
+        };
+        let sentinel = Color {
+            r: 1,
+            g: 2,
+            b: 3,
+            a: 4,
+        };
+        let (mut full, mut partial) = (Pixmap::new(32, 32), Pixmap::new(32, 32));
+        full.clear(background);
+        partial.clear(sentinel);
+        /* fractional edges, overlapping around (20, 20) */
+        let regions = [
+            Rect::new(10.5, 10.5, 10.0, 10.0),
+            Rect::new(15.25, 15.25, 10.0, 10.0),
+        ];
+        unsafe {
+            let ctx: *mut Context = harness.context_mut();
+            (*ctx).style.window.fixed_background = nk_style_item_hide();
+            let bounds = Rect::new(0.0, 0.0, 32.0, 32.0);
+            nk_begin(ctx, b"regions\0".as_ptr() as _, bounds, 0);
+            let b = nk_window_get_canvas(ctx);
+            nk_fill_rect(
+                b,
+                Rect::new(4.0, 4.0, 24.0, 24.0),
+                0.0,
+                nk_rgba(255, 0, 0, 128),
+            );
+            nk_end(ctx);
+            harness.rasterizer.render(&mut *ctx, &mut full);
+            harness
+                .rasterizer
+                .render_regions(&mut *ctx, &mut partial, &regions, background);
+            nk_clear(ctx);
+        }
+        let inside = |x: u32, y: u32| {
+            (10..21).contains(&x) && (10..21).contains(&y)
+                || (15..26).contains(&x) && (15..26).contains(&y)
+        };
+        for y in 0..32 {
+            for x in 0..32 {
+                if inside(x, y) {
+                    assert_eq!(partial.pixel(x, y), full.pixel(x, y), "pixel {},{}", x, y);
+                } else {
+                    assert_eq!(partial.pixel(x, y), [1, 2, 3, 4], "pixel {},{}", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn custom_draws_stay_inside_the_regions() {
+        let mut harness = Harness::new(32, 32);
+        let mut draws = CustomDraws::new();
+        let mut image = Pixmap::new(32, 32);
+        image.clear(Color {
+            r: 1,
+            g: 2,
+            b: 3,
+            a: 4,
+        });
+        unsafe {
+            let ctx: *mut Context = harness.context_mut();
+            (*ctx).style.window.fixed_background = nk_style_item_hide();
+            let bounds = Rect::new(0.0, 0.0, 32.0, 32.0);
+            nk_begin(ctx, b"custom\0".as_ptr() as _, bounds, 0);
+            let id = draws.push(&mut *ctx, bounds, |target: &mut Pixmap, _| {
+                target.clear(nk_rgb(0, 255, 0))
+            });
+            assert!(id.is_some());
+            nk_end(ctx);
+            let region = Rect::new(8.0, 8.0, 4.0, 4.0);
+            let background = nk_rgb(0, 0, 0);
+            harness.rasterizer.render_regions_custom(
+                &mut *ctx,
+                &mut image,
+                &[region],
+                background,
+                &mut draws,
+            );
+            nk_clear(ctx);
+        }
+        assert_eq!(image.pixel(8, 8), [0, 255, 0, 255]);
+        assert_eq!(image.pixel(11, 11), [0, 255, 0, 255]);
+        assert_eq!(image.pixel(7, 8), [1, 2, 3, 4]);
+        assert_eq!(image.pixel(12, 11), [1, 2, 3, 4]);
+        assert_eq!(image.pixel(0, 0), [1, 2, 3, 4]);
+    }
+}
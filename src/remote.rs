@@ -0,0 +1,715 @@
+//! Remote UI over a socket.
+//!
+//! `RemoteServer` sends the commands of every frame of a `Context` to a
+//! `RemoteClient`, which draws them and sends `InputEvent`s back. The
+//! server runs the events through an `InputQueue`, so a headless tool can
+//! be driven from a viewer in another process. Both ends work over any
+//! stream that can be split into a reading and a writing half; TCP and
+//! Unix sockets are supported out of the box.
+//!
+//! Server to client (integers unsigned LEB128 varints):
+//!
+//! ```text
+//! header     "NKRU" version:u8
+//! frame      0x01 length:varint body     body as in `encode_frame`
+//! unchanged  0x02                        same commands as the last frame
+//! end        0x00
+//! ```
+//!
+//! Frames are not diffed command by command: a frame that differs from
+//! the previous one in any way is sent whole, an identical one is skipped
+//! as `unchanged`. Idle UIs cost one byte per frame, animated ones a full
+//! frame.
+//!
+//! Client to server (`i` zigzag varints, floats little-endian IEEE 754
+//! bits, strings length followed by UTF-8 bytes):
+//!
+//! ```text
+//! header     "NKRI" version:u8
+//! event      0x01 kind:u8 fields...
+//! end        0x00
+//!
+//! 0 key           key down:u8 modifiers
+//! 1 physical key  key down:u8 modifiers
+//! 2 text          string
+//! 3 preedit       string i cursor i selection.0 i selection.1
+//! 4 mouse move    i x i y
+//! 5 mouse button  button i x i y down:u8 has_time:u8 [time:f64]
+//! 6 wheel         f32 x f32 y
+//! 7 touch         id:u64 i x i y down:u8
+//! 8 touch cancel
+//! 9 focus lost
+//! ```
+
+use events::{InputEvent, InputQueue};
+use input::*;
+use nuklear::*;
+use replay::{invalid, read_f32, read_u8, read_varint, write_f32, write_varint};
+use serialize::{encode_frame, unzigzag, zigzag, CommandFrame, FontNames};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread::{self, JoinHandle};
+
+pub const REMOTE_FRAMES_MAGIC: [u8; 4] = *b"NKRU";
+pub const REMOTE_INPUT_MAGIC: [u8; 4] = *b"NKRI";
+pub const REMOTE_VERSION: u8 = 2;
+
+const TAG_END: u8 = 0;
+const TAG_FRAME: u8 = 1;
+const TAG_UNCHANGED: u8 = 2;
+const TAG_EVENT: u8 = 1;
+
+const EVENT_KEY: u8 = 0;
+const EVENT_PHYSICAL_KEY: u8 = 1;
+const EVENT_TEXT: u8 = 2;
+const EVENT_PREEDIT: u8 = 3;
+const EVENT_MOUSE_MOVE: u8 = 4;
+const EVENT_MOUSE_BUTTON: u8 = 5;
+const EVENT_WHEEL: u8 = 6;
+const EVENT_TOUCH: u8 = 7;
+const EVENT_TOUCH_CANCEL: u8 = 8;
+const EVENT_FOCUS_LOST: u8 = 9;
+
+/* same limit as command stream frames; strings far below it */
+const MAX_FRAME_SIZE: usize = 1 << 26;
+const MAX_STRING_SIZE: usize = 1 << 16;
+
+/// Socket a remote session runs over.
+pub trait RemoteStream: Read + Write + Send + Sized + 'static {
+    /// Another handle to the same socket, used to read while writing.
+    fn try_clone(&self) -> io::Result<Self>;
+    /// Closes both directions, waking up a blocked reader on any handle.
+    fn shutdown(&self) -> io::Result<()>;
+}
+
+impl RemoteStream for TcpStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+    fn shutdown(&self) -> io::Result<()> {
+        TcpStream::shutdown(self, Shutdown::Both)
+    }
+}
+
+#[cfg(unix)]
+impl RemoteStream for UnixStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        UnixStream::try_clone(self)
+    }
+    fn shutdown(&self) -> io::Result<()> {
+        UnixStream::shutdown(self, Shutdown::Both)
+    }
+}
+
+fn write_bytes<W: Write>(out: &mut W, bytes: &[u8]) -> io::Result<()> {
+    write_varint(out, bytes.len() as u32)?;
+    out.write_all(bytes)
+}
+
+fn read_string<R: Read>(input: &mut R) -> io::Result<String> {
+    let len = read_varint(input)? as usize;
+    if len > MAX_STRING_SIZE {
+        return Err(invalid("remote input string too long"));
+    }
+    let mut bytes = vec![0u8; len];
+    input.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| invalid("remote input string is not UTF-8"))
+}
+
+fn read_bool<R: Read>(input: &mut R) -> io::Result<bool> {
+    match read_u8(input)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(invalid("remote input flag out of range")),
+    }
+}
+
+fn read_int<R: Read>(input: &mut R) -> io::Result<i32> {
+    read_varint(input).map(unzigzag)
+}
+
+fn read_below<R: Read>(input: &mut R, max: u32) -> io::Result<u32> {
+    let v = read_varint(input)?;
+    if v >= max {
+        return Err(invalid("remote input key or button out of range"));
+    }
+    Ok(v)
+}
+
+fn read_header<R: Read>(input: &mut R, magic: [u8; 4]) -> io::Result<()> {
+    let mut header = [0u8; 5];
+    input.read_exact(&mut header)?;
+    if header[..4] != magic {
+        return Err(invalid("not a remote UI stream"));
+    }
+    if header[4] != REMOTE_VERSION {
+        return Err(invalid("unsupported remote UI version"));
+    }
+    Ok(())
+}
+
+fn write_event<W: Write>(out: &mut W, event: &InputEvent) -> io::Result<()> {
+    out.write_all(&[TAG_EVENT])?;
+    match *event {
+        InputEvent::Key {
+            key,
+            down,
+            modifiers,
+        } => {
+            out.write_all(&[EVENT_KEY])?;
+            write_varint(out, key)?;
+            out.write_all(&[down as u8])?;
+            write_varint(out, modifiers)
+        }
+        InputEvent::PhysicalKey {
+            key,
+            down,
+            modifiers,
+        } => {
+            out.write_all(&[EVENT_PHYSICAL_KEY])?;
+            write_varint(out, key)?;
+            out.write_all(&[down as u8])?;
+            write_varint(out, modifiers)
+        }
+        InputEvent::Text(ref text) => {
+            out.write_all(&[EVENT_TEXT])?;
+            write_bytes(out, text.as_bytes())
+        }
+        InputEvent::Preedit {
+            ref text,
+            cursor,
+            selection,
+        } => {
+            out.write_all(&[EVENT_PREEDIT])?;
+            write_bytes(out, text.as_bytes())?;
+            write_varint(out, zigzag(cursor))?;
+            write_varint(out, zigzag(selection.0))?;
+            write_varint(out, zigzag(selection.1))
+        }
+        InputEvent::MouseMove { x, y } => {
+            out.write_all(&[EVENT_MOUSE_MOVE])?;
+            write_varint(out, zigzag(x))?;
+            write_varint(out, zigzag(y))
+        }
+        InputEvent::MouseButton {
+            button,
+            x,
+            y,
+            down,
+            time,
+        } => {
+            out.write_all(&[EVENT_MOUSE_BUTTON])?;
+            write_varint(out, button)?;
+            write_varint(out, zigzag(x))?;
+            write_varint(out, zigzag(y))?;
+            out.write_all(&[down as u8, time.is_some() as u8])?;
+            match time {
+                Some(time) => out.write_all(&time.to_bits().to_le_bytes()),
+                None => Ok(()),
+            }
+        }
+        InputEvent::Wheel { x, y } => {
+            out.write_all(&[EVENT_WHEEL])?;
+            write_f32(out, x)?;
+            write_f32(out, y)
+        }
+        InputEvent::Touch { id, x, y, down } => {
+            out.write_all(&[EVENT_TOUCH])?;
+            out.write_all(&id.to_le_bytes())?;
+            write_varint(out, zigzag(x))?;
+            write_varint(out, zigzag(y))?;
+            out.write_all(&[down as u8])
+        }
+        InputEvent::TouchCancel => out.write_all(&[EVENT_TOUCH_CANCEL]),
+        InputEvent::FocusLost => out.write_all(&[EVENT_FOCUS_LOST]),
+    }
+}
+
+/* reads the next event, `None` at the end of the stream */
+fn read_event<R: Read>(input: &mut R) -> io::Result<Option<InputEvent>> {
+    let mut tag = [0u8; 1];
+    if input.read(&mut tag)? == 0 || tag[0] == TAG_END {
+        return Ok(None);
+    }
+    if tag[0] != TAG_EVENT {
+        return Err(invalid("unknown remote input tag"));
+    }
+    let event = match read_u8(input)? {
+        EVENT_KEY => InputEvent::Key {
+            key: read_below(input, NK_KEY_MAX)?,
+            down: read_bool(input)?,
+            modifiers: read_varint(input)?,
+        },
+        EVENT_PHYSICAL_KEY => InputEvent::PhysicalKey {
+            key: read_below(input, NK_PKEY_MAX)?,
+            down: read_bool(input)?,
+            modifiers: read_varint(input)?,
+        },
+        EVENT_TEXT => InputEvent::Text(read_string(input)?),
+        EVENT_PREEDIT => InputEvent::Preedit {
+            text: read_string(input)?,
+            cursor: read_int(input)?,
+            selection: (read_int(input)?, read_int(input)?),
+        },
+        EVENT_MOUSE_MOVE => InputEvent::MouseMove {
+            x: read_int(input)?,
+            y: read_int(input)?,
+        },
+        EVENT_MOUSE_BUTTON => {
+            let button = read_below(input, NK_BUTTON_MAX)?;
+            let x = read_int(input)?;
+            let y = read_int(input)?;
+            let down = read_bool(input)?;
+            let time = if read_bool(input)? {
+                let mut b = [0u8; 8];
+                input.read_exact(&mut b)?;
+                Some(f64::from_bits(u64::from_le_bytes(b)))
+            } else {
+                None
+            };
+            InputEvent::MouseButton {
+                button,
+                x,
+                y,
+                down,
+                time,
+            }
+        }
+        EVENT_WHEEL => InputEvent::Wheel {
+            x: read_f32(input)?,
+            y: read_f32(input)?,
+        },
+        EVENT_TOUCH => {
+            let mut b = [0u8; 8];
+            input.read_exact(&mut b)?;
+            InputEvent::Touch {
+                id: u64::from_le_bytes(b),
+                x: read_int(input)?,
+                y: read_int(input)?,
+                down: read_bool(input)?,
+            }
+        }
+        EVENT_TOUCH_CANCEL => InputEvent::TouchCancel,
+        EVENT_FOCUS_LOST => InputEvent::FocusLost,
+        _ => return Err(invalid("unknown remote input event")),
+    };
+    Ok(Some(event))
+}
+
+/// Serving end: streams the frames of a `Context` and feeds it the
+/// client's input.
+///
+/// Events arrive on a background thread, so `input` never blocks; frames
+/// are written synchronously and block only when the client stops
+/// reading. Dropping the server closes the socket and joins the reader
+/// thread.
+pub struct RemoteServer<S: RemoteStream> {
+    out: BufWriter<S>,
+    events: Receiver<io::Result<InputEvent>>,
+    reader: Option<JoinHandle<()>>,
+    queue: InputQueue,
+    fonts: FontNames,
+    last: Option<Vec<u8>>,
+    connected: bool,
+}
+
+impl RemoteServer<TcpStream> {
+    /// Waits for a client on `listener`.
+    pub fn accept_tcp(listener: &TcpListener) -> io::Result<Self> {
+        let (stream, _) = listener.accept()?;
+        stream.set_nodelay(true)?;
+        RemoteServer::new(stream)
+    }
+}
+
+#[cfg(unix)]
+impl RemoteServer<UnixStream> {
+    /// Waits for a client on `listener`.
+    pub fn accept_unix(listener: &UnixListener) -> io::Result<Self> {
+        let (stream, _) = listener.accept()?;
+        RemoteServer::new(stream)
+    }
+}
+
+impl<S: RemoteStream> RemoteServer<S> {
+    pub fn new(stream: S) -> io::Result<Self> {
+        let mut input = BufReader::new(stream.try_clone()?);
+        let (tx, rx) = mpsc::channel();
+        let reader = thread::spawn(move || {
+            if let Err(e) = read_header(&mut input, REMOTE_INPUT_MAGIC) {
+                let _ = tx.send(Err(e));
+                return;
+            }
+            loop {
+                match read_event(&mut input) {
+                    Ok(Some(event)) => {
+                        if tx.send(Ok(event)).is_err() {
+                            return;
+                        }
+                    }
+                    Ok(None) => return,
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        return;
+                    }
+                }
+            }
+        });
+        let mut out = BufWriter::new(stream);
+        out.write_all(&REMOTE_FRAMES_MAGIC)?;
+        out.write_all(&[REMOTE_VERSION])?;
+        out.flush()?;
+        Ok(RemoteServer {
+            out,
+            events: rx,
+            reader: Some(reader),
+            queue: InputQueue::new(),
+            fonts: FontNames::new(),
+            last: None,
+            connected: true,
+        })
+    }
+
+    /// Names `font` for the client, see `CommandWriter::register_font`.
+    pub fn register_font(&mut self, font: &UserFont, name: &str) {
+        self.fonts.insert(font, name);
+    }
+
+    /// `false` once the client ended its input stream or went away.
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Moves the events received so far into the input queue and returns
+    /// their number. A malformed input stream is reported once, after
+    /// which the client counts as disconnected.
+    pub fn receive(&mut self) -> io::Result<usize> {
+        let mut count = 0;
+        loop {
+            match self.events.try_recv() {
+                Ok(Ok(event)) => {
+                    self.queue.push(event);
+                    count += 1;
+                }
+                Ok(Err(e)) => {
+                    self.connected = false;
+                    return Err(e);
+                }
+                Err(TryRecvError::Empty) => return Ok(count),
+                Err(TryRecvError::Disconnected) => {
+                    self.connected = false;
+                    return Ok(count);
+                }
+            }
+        }
+    }
+
+    /// Input queue fed by the client; local events may be pushed too.
+    pub fn queue(&mut self) -> &mut InputQueue {
+        &mut self.queue
+    }
+
+    /// Does the input part of a frame: receives the client's events and
+    /// runs `InputQueue::process`. Returns `true` if events are left for
+    /// the next frame.
+    pub fn input(&mut self, ctx: &mut Context) -> io::Result<bool> {
+        self.receive()?;
+        Ok(self.queue.process(ctx))
+    }
+
+    /// Sends the commands of the current frame; call before `nk_clear`.
+    /// The frame is sent whole if anything changed, otherwise as a single
+    /// byte. Returns whether the frame changed.
+    pub fn send_frame(&mut self, ctx: &mut Context) -> io::Result<bool> {
+        let body = encode_frame(ctx.commands(), &self.fonts);
+        let changed = self.last.as_ref() != Some(&body);
+        if changed {
+            self.out.write_all(&[TAG_FRAME])?;
+            write_varint(&mut self.out, body.len() as u32)?;
+            self.out.write_all(&body)?;
+            self.last = Some(body);
+        } else {
+            self.out.write_all(&[TAG_UNCHANGED])?;
+        }
+        self.out.flush()?;
+        Ok(changed)
+    }
+
+    /// Tells the client no more frames follow.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.write_all(&[TAG_END])?;
+        self.out.flush()
+    }
+}
+
+impl<S: RemoteStream> Drop for RemoteServer<S> {
+    fn drop(&mut self) {
+        let _ = self.out.flush();
+        let _ = self.out.get_ref().shutdown();
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// What `RemoteClient::read_update` received.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RemoteUpdate {
+    /// A new frame, available from `RemoteClient::frame`.
+    Frame,
+    /// The server drew the same commands as last frame.
+    Unchanged,
+}
+
+/// Viewing end: receives frames and sends input back. Dropping the client
+/// closes the socket.
+pub struct RemoteClient<S: RemoteStream> {
+    input: BufReader<S>,
+    out: BufWriter<S>,
+    frame: Option<CommandFrame>,
+    done: bool,
+}
+
+impl RemoteClient<TcpStream> {
+    pub fn connect_tcp<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        RemoteClient::new(stream)
+    }
+}
+
+#[cfg(unix)]
+impl RemoteClient<UnixStream> {
+    pub fn connect_unix<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        RemoteClient::new(UnixStream::connect(path)?)
+    }
+}
+
+impl<S: RemoteStream> RemoteClient<S> {
+    pub fn new(stream: S) -> io::Result<Self> {
+        let mut out = BufWriter::new(stream.try_clone()?);
+        out.write_all(&REMOTE_INPUT_MAGIC)?;
+        out.write_all(&[REMOTE_VERSION])?;
+        out.flush()?;
+        let mut input = BufReader::new(stream);
+        read_header(&mut input, REMOTE_FRAMES_MAGIC)?;
+        Ok(RemoteClient {
+            input,
+            out,
+            frame: None,
+            done: false,
+        })
+    }
+
+    /// Queues `event` for the server; sent by `flush` or the next
+    /// `read_update`.
+    pub fn send(&mut self, event: &InputEvent) -> io::Result<()> {
+        write_event(&mut self.out, event)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Sends pending events and waits for the next frame. Returns `None`
+    /// once the server finished.
+    pub fn read_update(&mut self) -> io::Result<Option<RemoteUpdate>> {
+        self.out.flush()?;
+        if self.done {
+            return Ok(None);
+        }
+        let mut tag = [0u8; 1];
+        if self.input.read(&mut tag)? == 0 || tag[0] == TAG_END {
+            self.done = true;
+            return Ok(None);
+        }
+        match tag[0] {
+            TAG_FRAME => {
+                let len = read_varint(&mut self.input)? as usize;
+                if len > MAX_FRAME_SIZE {
+                    return Err(invalid("remote frame too large"));
+                }
+                let mut body = vec![0u8; len];
+                self.input.read_exact(&mut body)?;
+                self.frame = Some(CommandFrame::decode(&body)?);
+                Ok(Some(RemoteUpdate::Frame))
+            }
+            TAG_UNCHANGED if self.frame.is_some() => Ok(Some(RemoteUpdate::Unchanged)),
+            TAG_UNCHANGED => Err(invalid("unchanged remote frame before the first one")),
+            _ => Err(invalid("unknown remote frame tag")),
+        }
+    }
+
+    /// The last frame received.
+    pub fn frame(&self) -> Option<&CommandFrame> {
+        self.frame.as_ref()
+    }
+
+    /// Ends the input stream; the server sees the client disconnect.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.write_all(&[TAG_END])?;
+        self.out.flush()
+    }
+}
+
+impl<S: RemoteStream> Drop for RemoteClient<S> {
+    fn drop(&mut self) {
+        let _ = self.out.flush();
+        let _ = self.input.get_ref().shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harness::{describe_command, draw_every_command, Harness};
+    use std::time::{Duration, Instant};
+
+    fn every_event() -> Vec<InputEvent> {
+        vec![
+            InputEvent::Key {
+                key: NK_KEY_ENTER,
+                down: true,
+                modifiers: NK_MOD_CTRL,
+            },
+            InputEvent::PhysicalKey {
+                key: NK_PKEY_LEFT_SHIFT,
+                down: false,
+                modifiers: NK_MOD_SHIFT,
+            },
+            InputEvent::Text("n\u{fc}k\u{2603}".to_owned()),
+            InputEvent::Preedit {
+                text: "\u{304b}\u{306a}".to_owned(),
+                cursor: 2,
+                selection: (0, -1),
+            },
+            InputEvent::MouseMove { x: -3, y: 70000 },
+            InputEvent::MouseButton {
+                button: NK_BUTTON_RIGHT,
+                x: 5,
+                y: 6,
+                down: true,
+                time: Some(1.25),
+            },
+            InputEvent::MouseButton {
+                button: NK_BUTTON_LEFT,
+                x: 5,
+                y: 6,
+                down: false,
+                time: None,
+            },
+            InputEvent::Wheel { x: 0.5, y: -2.0 },
+            InputEvent::Touch {
+                id: u64::MAX,
+                x: 1,
+                y: 2,
+                down: true,
+            },
+            InputEvent::TouchCancel,
+            InputEvent::FocusLost,
+        ]
+    }
+
+    #[test]
+    fn events_round_trip() {
+        let mut bytes = Vec::new();
+        for event in every_event() {
+            write_event(&mut bytes, &event).unwrap();
+        }
+        bytes.push(TAG_END);
+        let mut input = &bytes[..];
+        let mut read = Vec::new();
+        while let Some(event) = read_event(&mut input).unwrap() {
+            read.push(event);
+        }
+        assert_eq!(read, every_event());
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn rejects_out_of_range_events() {
+        let mut bytes = Vec::new();
+        write_event(&mut bytes, &InputEvent::MouseMove { x: 0, y: 0 }).unwrap();
+        bytes[1] = EVENT_FOCUS_LOST + 1;
+        assert!(read_event(&mut &bytes[..]).is_err());
+        let key = InputEvent::Key {
+            key: NK_KEY_MAX,
+            down: true,
+            modifiers: 0,
+        };
+        bytes.clear();
+        write_event(&mut bytes, &key).unwrap();
+        assert!(read_event(&mut &bytes[..]).is_err());
+    }
+
+    /* sends every command and every event across `server` and `client` */
+    fn exchange<S: RemoteStream>(server: &mut RemoteServer<S>, client: &mut RemoteClient<S>) {
+        let mut harness = Harness::new(320, 320);
+        let ctx = harness.context_mut();
+        let font = unsafe { &*ctx.style.font };
+        server.register_font(font, "proggy");
+
+        unsafe { draw_every_command(ctx) };
+        let sent: Vec<String> = ctx.commands().map(|c| describe_command(&c)).collect();
+        assert!(server.send_frame(ctx).unwrap());
+        assert!(!server.send_frame(ctx).unwrap());
+        unsafe { nk_clear(ctx) };
+        assert_eq!(client.read_update().unwrap(), Some(RemoteUpdate::Frame));
+        let frame = client.frame().unwrap();
+        assert_eq!(frame.fonts()[0].name, "proggy");
+        let got: Vec<String> = frame
+            .commands(|_| font)
+            .map(|c| describe_command(&c))
+            .collect();
+        assert_eq!(got, sent);
+        assert_eq!(client.read_update().unwrap(), Some(RemoteUpdate::Unchanged));
+
+        for event in every_event() {
+            client.send(&event).unwrap();
+        }
+        client.flush().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while server.queue().len() < every_event().len() {
+            server.receive().unwrap();
+            assert!(Instant::now() < deadline, "events did not arrive");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn session_carries_frames_and_input() {
+        let (a, b) = UnixStream::pair().unwrap();
+        let mut server = RemoteServer::new(a).unwrap();
+        let mut client = RemoteClient::new(b).unwrap();
+        exchange(&mut server, &mut client);
+
+        client.finish().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while server.is_connected() {
+            server.receive().unwrap();
+            assert!(Instant::now() < deadline, "client did not disconnect");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(server.queue().len(), every_event().len());
+    }
+
+    #[test]
+    fn tcp_session_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepting = thread::spawn(move || RemoteServer::accept_tcp(&listener));
+        let mut client = RemoteClient::connect_tcp(addr).unwrap();
+        let mut server = accepting.join().unwrap().unwrap();
+        exchange(&mut server, &mut client);
+
+        /* dropping the server wakes and joins its reader thread */
+        drop(server);
+        assert_eq!(client.read_update().unwrap(), None);
+    }
+}
@@ -0,0 +1,752 @@
+//! Recording and deterministic replay of per-frame input.
+//!
+//! `InputRecorder` captures `Context::input` and `delta_time_seconds` right
+//! after `nk_input_end`; `InputReplayer` restores them between its own
+//! `nk_input_begin`/`nk_input_end_recorded`, so a replayed frame sees exactly
+//! the state the recorded one saw, without touch gestures and navigation
+//! being recognized again.
+//!
+//! File format (all integers unsigned LEB128 varints, floats as
+//! little-endian IEEE 754 bits):
+//!
+//! ```text
+//! header  "NKIR" version:u8
+//! frame   0x01 changed:varint fields...
+//! end     0x00 (optional, EOF at a frame boundary is accepted)
+//! ```
+//!
+//! `changed` is a bit set of the groups that differ from the previous frame
+//! (initially all zero); only those groups follow, in bit order:
+//!
+//! ```text
+//! 0  delta_time  f32
+//! 1  pos         f32 x, f32 y
+//! 2  prev        f32 x, f32 y
+//! 3  delta       f32 x, f32 y
+//! 4  scroll      f32 x, f32 y
+//! 5  grab        u8 grab | grabbed << 1 | ungrab << 2
+//! 6  buttons     4 x (down, clicked, f32 clicked_pos.x, f32 clicked_pos.y)
+//! 7  keys        (down mask, clicked mask) per 32 keys, then the
+//!                clicked count of every key with its clicked bit set
+//! 8  text        length, bytes
+//! 9  physical    same layout as keys
+//! 10 modifiers   bit set
+//! 11 clicks      4 x consecutive click count
+//! 12 touch       per slot: phase, and unless it is free id (low, high),
+//!                pos, prev, start, f32 held, tap; then gestures, primary + 1,
+//!                tap_pos, pan, f32 pinch_scale, f32 pinch_total, pinch_center
+//! 13 nav         `NK_NAV_*` bit set
+//! ```
+
+use input::*;
+use math::Point;
+use nuklear::*;
+use std::io::{self, Read, Write};
+use std::{mem, slice};
+
+pub const INPUT_RECORDING_MAGIC: [u8; 4] = *b"NKIR";
+pub const INPUT_RECORDING_VERSION: u8 = 2;
+
+const TAG_END: u8 = 0;
+const TAG_FRAME: u8 = 1;
+
+const CHANGED_DELTA_TIME: u32 = 1 << 0;
+const CHANGED_POS: u32 = 1 << 1;
+const CHANGED_PREV: u32 = 1 << 2;
+const CHANGED_DELTA: u32 = 1 << 3;
+const CHANGED_SCROLL: u32 = 1 << 4;
+const CHANGED_GRAB: u32 = 1 << 5;
+const CHANGED_BUTTONS: u32 = 1 << 6;
+const CHANGED_KEYS: u32 = 1 << 7;
+const CHANGED_TEXT: u32 = 1 << 8;
+const CHANGED_PHYSICAL: u32 = 1 << 9;
+const CHANGED_MODIFIERS: u32 = 1 << 10;
+const CHANGED_CLICKS: u32 = 1 << 11;
+const CHANGED_TOUCH: u32 = 1 << 12;
+const CHANGED_NAV: u32 = 1 << 13;
+const CHANGED_ALL: u32 = (1 << 14) - 1;
+
+/// Input state of one recorded frame.
+///
+/// The keyboard text lives in `text`; the text buffer of `input` is left
+/// empty since it belongs to the context it was taken from. `nav_actions`
+/// are the `Navigation::actions` requested for the frame.
+#[derive(Clone)]
+pub struct InputFrame {
+    pub input: Input,
+    pub text: Vec<u8>,
+    pub nav_actions: NavActions,
+    pub delta_time_seconds: f32,
+}
+
+impl InputFrame {
+    pub fn from_context(ctx: &Context) -> Self {
+        let mut input = ctx.input;
+        let keyboard = &mut input.keyboard;
+        let len = keyboard.text_len.max(0) as usize;
+        let text = if len == 0 || keyboard.text.memory.ptr.is_null() {
+            Vec::new()
+        } else {
+            unsafe { slice::from_raw_parts(keyboard.text.memory.ptr as *const u8, len).to_vec() }
+        };
+        keyboard.text = unsafe { mem::zeroed() };
+        keyboard.text_memory = [0; 16];
+        InputFrame {
+            input,
+            text,
+            nav_actions: ctx.nav.actions,
+            delta_time_seconds: ctx.delta_time_seconds,
+        }
+    }
+
+    fn zeroed() -> Self {
+        InputFrame {
+            input: unsafe { mem::zeroed() },
+            text: Vec::new(),
+            nav_actions: 0,
+            delta_time_seconds: 0.0,
+        }
+    }
+
+    fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    fn changes(&self, prev: &InputFrame) -> u32 {
+        let (a, b) = (&self.input.mouse, &prev.input.mouse);
+        let mut changed = 0;
+        if bits(self.delta_time_seconds) != bits(prev.delta_time_seconds) {
+            changed |= CHANGED_DELTA_TIME;
+        }
+        if !same_point(a.pos, b.pos) {
+            changed |= CHANGED_POS;
+        }
+        if !same_point(a.prev, b.prev) {
+            changed |= CHANGED_PREV;
+        }
+        if !same_point(a.delta, b.delta) {
+            changed |= CHANGED_DELTA;
+        }
+        if !same_point(a.scroll_delta, b.scroll_delta) {
+            changed |= CHANGED_SCROLL;
+        }
+        if grab_bits(a) != grab_bits(b) {
+            changed |= CHANGED_GRAB;
+        }
+        if a.buttons.iter().zip(b.buttons.iter()).any(|(x, y)| {
+            x.down != y.down || x.clicked != y.clicked || !same_point(x.clicked_pos, y.clicked_pos)
+        }) {
+            changed |= CHANGED_BUTTONS;
+        }
+        let (ka, kb) = (&self.input.keyboard.keys, &prev.input.keyboard.keys);
+        if ka
+            .iter()
+            .zip(kb.iter())
+            .any(|(x, y)| x.down != y.down || x.clicked != y.clicked)
+        {
+            changed |= CHANGED_KEYS;
+        }
+        if self.text() != prev.text() {
+            changed |= CHANGED_TEXT;
+        }
+        let (pa, pb) = (&self.input.keyboard.physical, &prev.input.keyboard.physical);
+        if pa
+            .iter()
+            .zip(pb.iter())
+            .any(|(x, y)| x.down != y.down || x.clicked != y.clicked)
+        {
+            changed |= CHANGED_PHYSICAL;
+        }
+        if self.input.keyboard.modifiers != prev.input.keyboard.modifiers {
+            changed |= CHANGED_MODIFIERS;
+        }
+        if self.input.mouse.click_count != prev.input.mouse.click_count {
+            changed |= CHANGED_CLICKS;
+        }
+        let (mut ta, mut tb) = (Vec::new(), Vec::new());
+        let _ = write_touch(&mut ta, &self.input.touch);
+        let _ = write_touch(&mut tb, &prev.input.touch);
+        if ta != tb {
+            changed |= CHANGED_TOUCH;
+        }
+        if self.nav_actions != prev.nav_actions {
+            changed |= CHANGED_NAV;
+        }
+        changed
+    }
+
+    /// Restores the recorded state into `ctx`; call between
+    /// `nk_input_begin` and `nk_input_end_recorded`.
+    pub fn apply(&self, ctx: &mut Context) {
+        let keyboard = ctx.input.keyboard;
+        ctx.input = self.input;
+        ctx.input.keyboard.text = keyboard.text;
+        ctx.input.keyboard.text_memory = keyboard.text_memory;
+        ctx.input.keyboard.text_len = 0;
+        ctx.nav.actions = self.nav_actions;
+        ctx.delta_time_seconds = self.delta_time_seconds;
+        let text = &mut ctx.input.keyboard.text;
+        let len = self.text.len() as Size;
+        unsafe {
+            nk_buffer_clear(text);
+            nk_buffer_push(text, NK_BUFFER_FRONT, self.text.as_ptr() as _, len, 1);
+        }
+        if text.allocated == len {
+            ctx.input.keyboard.text_len = len as Int;
+        }
+    }
+}
+
+#[inline]
+fn bits(v: f32) -> u32 {
+    v.to_bits()
+}
+
+#[inline]
+fn same_point(a: Point, b: Point) -> bool {
+    bits(a.x) == bits(b.x) && bits(a.y) == bits(b.y)
+}
+
+#[inline]
+fn grab_bits(m: &Mouse) -> u8 {
+    (m.grab != 0) as u8 | ((m.grabbed != 0) as u8) << 1 | ((m.ungrab != 0) as u8) << 2
+}
+
+pub(crate) fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(crate) fn write_varint<W: Write>(out: &mut W, mut v: u32) -> io::Result<()> {
+    loop {
+        let byte = (v & 0x7f) as u8;
+        v >>= 7;
+        if v == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+pub(crate) fn write_f32<W: Write>(out: &mut W, v: f32) -> io::Result<()> {
+    let b = bits(v);
+    out.write_all(&[b as u8, (b >> 8) as u8, (b >> 16) as u8, (b >> 24) as u8])
+}
+
+pub(crate) fn write_point<W: Write>(out: &mut W, p: Point) -> io::Result<()> {
+    write_f32(out, p.x)?;
+    write_f32(out, p.y)
+}
+
+fn write_touch<W: Write>(out: &mut W, touch: &Touch) -> io::Result<()> {
+    for p in &touch.points {
+        write_varint(out, p.phase)?;
+        if p.phase == NK_TOUCH_NONE {
+            continue;
+        }
+        write_varint(out, p.id as u32)?;
+        write_varint(out, (p.id >> 32) as u32)?;
+        write_point(out, p.pos)?;
+        write_point(out, p.prev)?;
+        write_point(out, p.start)?;
+        write_f32(out, p.held)?;
+        write_varint(out, p.tap as u32)?;
+    }
+    write_varint(out, touch.gestures)?;
+    write_varint(out, (touch.primary + 1) as u32)?;
+    write_point(out, touch.tap_pos)?;
+    write_point(out, touch.pan)?;
+    write_f32(out, touch.pinch_scale)?;
+    write_f32(out, touch.pinch_total)?;
+    write_point(out, touch.pinch_center)
+}
+
+pub(crate) fn read_u8<R: Read>(input: &mut R) -> io::Result<u8> {
+    let mut b = [0u8; 1];
+    input.read_exact(&mut b)?;
+    Ok(b[0])
+}
+
+pub(crate) fn read_varint<R: Read>(input: &mut R) -> io::Result<u32> {
+    let mut v = 0u32;
+    let mut shift = 0;
+    loop {
+        let byte = read_u8(input)?;
+        if shift > 28 {
+            return Err(invalid("varint overflow"));
+        }
+        v |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(v);
+        }
+        shift += 7;
+    }
+}
+
+pub(crate) fn read_f32<R: Read>(input: &mut R) -> io::Result<f32> {
+    let mut b = [0u8; 4];
+    input.read_exact(&mut b)?;
+    Ok(f32::from_bits(
+        b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24,
+    ))
+}
+
+pub(crate) fn read_point<R: Read>(input: &mut R) -> io::Result<Point> {
+    let x = read_f32(input)?;
+    let y = read_f32(input)?;
+    Ok(Point::new(x, y))
+}
+
+fn read_flag<R: Read>(input: &mut R) -> io::Result<Int> {
+    match read_varint(input)? {
+        0 => Ok(0),
+        1 => Ok(1),
+        _ => Err(invalid("input recording flag out of range")),
+    }
+}
+
+fn read_touch<R: Read>(input: &mut R, touch: &mut Touch) -> io::Result<()> {
+    for p in touch.points.iter_mut() {
+        *p = unsafe { mem::zeroed() };
+        p.phase = read_varint(input)?;
+        if p.phase > NK_TOUCH_CANCELLED {
+            return Err(invalid("touch phase out of range"));
+        }
+        if p.phase == NK_TOUCH_NONE {
+            continue;
+        }
+        p.id = read_varint(input)? as u64 | (read_varint(input)? as u64) << 32;
+        p.pos = read_point(input)?;
+        p.prev = read_point(input)?;
+        p.start = read_point(input)?;
+        p.held = read_f32(input)?;
+        p.tap = read_flag(input)?;
+    }
+    touch.gestures = read_varint(input)?;
+    touch.primary = match (read_varint(input)? as libc::c_int).checked_sub(1) {
+        Some(primary) if (-1..NK_TOUCH_MAX as libc::c_int).contains(&primary) => primary,
+        _ => return Err(invalid("primary touch slot out of range")),
+    };
+    touch.tap_pos = read_point(input)?;
+    touch.pan = read_point(input)?;
+    touch.pinch_scale = read_f32(input)?;
+    touch.pinch_total = read_f32(input)?;
+    touch.pinch_center = read_point(input)?;
+    Ok(())
+}
+
+/* key states as 32 bit down and clicked masks plus the nonzero counts */
+fn write_keys<W: Write>(out: &mut W, keys: &[Key]) -> io::Result<()> {
+    for chunk in keys.chunks(32) {
+        let (mut down, mut clicked) = (0u32, 0u32);
+        for (i, k) in chunk.iter().enumerate() {
+            down |= ((k.down != 0) as u32) << i;
+            clicked |= ((k.clicked != 0) as u32) << i;
+        }
+        write_varint(out, down)?;
+        write_varint(out, clicked)?;
+    }
+    for k in keys.iter().filter(|k| k.clicked != 0) {
+        write_varint(out, k.clicked)?;
+    }
+    Ok(())
+}
+
+fn read_keys<R: Read>(input: &mut R, keys: &mut [Key]) -> io::Result<()> {
+    let mut masks = [(0u32, 0u32); 4];
+    for m in masks.iter_mut().take(keys.len().div_ceil(32)) {
+        m.0 = read_varint(input)?;
+        m.1 = read_varint(input)?;
+    }
+    for (i, k) in keys.iter_mut().enumerate() {
+        let (down, clicked) = masks[i / 32];
+        k.down = (down >> (i % 32) & 1) as Int;
+        k.clicked = if clicked >> (i % 32) & 1 != 0 {
+            read_varint(input)?
+        } else {
+            0
+        };
+    }
+    Ok(())
+}
+
+/// Writes one `InputFrame` per call of `record`.
+pub struct InputRecorder<W: Write> {
+    out: W,
+    prev: InputFrame,
+}
+
+impl<W: Write> InputRecorder<W> {
+    pub fn new(mut out: W) -> io::Result<Self> {
+        out.write_all(&INPUT_RECORDING_MAGIC)?;
+        out.write_all(&[INPUT_RECORDING_VERSION])?;
+        Ok(InputRecorder {
+            out,
+            prev: InputFrame::zeroed(),
+        })
+    }
+
+    /// Records the input of `ctx`; call right after `nk_input_end`.
+    pub fn record(&mut self, ctx: &Context) -> io::Result<()> {
+        self.write_frame(&InputFrame::from_context(ctx))
+    }
+
+    pub fn write_frame(&mut self, frame: &InputFrame) -> io::Result<()> {
+        let changed = frame.changes(&self.prev);
+        let out = &mut self.out;
+        let mouse = &frame.input.mouse;
+        out.write_all(&[TAG_FRAME])?;
+        write_varint(out, changed)?;
+        if changed & CHANGED_DELTA_TIME != 0 {
+            write_f32(out, frame.delta_time_seconds)?;
+        }
+        if changed & CHANGED_POS != 0 {
+            write_point(out, mouse.pos)?;
+        }
+        if changed & CHANGED_PREV != 0 {
+            write_point(out, mouse.prev)?;
+        }
+        if changed & CHANGED_DELTA != 0 {
+            write_point(out, mouse.delta)?;
+        }
+        if changed & CHANGED_SCROLL != 0 {
+            write_point(out, mouse.scroll_delta)?;
+        }
+        if changed & CHANGED_GRAB != 0 {
+            out.write_all(&[grab_bits(mouse)])?;
+        }
+        if changed & CHANGED_BUTTONS != 0 {
+            for b in &mouse.buttons {
+                write_varint(out, b.down as u32)?;
+                write_varint(out, b.clicked)?;
+                write_point(out, b.clicked_pos)?;
+            }
+        }
+        if changed & CHANGED_KEYS != 0 {
+            write_keys(out, &frame.input.keyboard.keys)?;
+        }
+        if changed & CHANGED_TEXT != 0 {
+            let text = frame.text();
+            write_varint(out, text.len() as u32)?;
+            out.write_all(text)?;
+        }
+        if changed & CHANGED_PHYSICAL != 0 {
+            write_keys(out, &frame.input.keyboard.physical)?;
+        }
+        if changed & CHANGED_MODIFIERS != 0 {
+            write_varint(out, frame.input.keyboard.modifiers)?;
+        }
+        if changed & CHANGED_CLICKS != 0 {
+            for &count in &frame.input.mouse.click_count {
+                write_varint(out, count)?;
+            }
+        }
+        if changed & CHANGED_TOUCH != 0 {
+            write_touch(out, &frame.input.touch)?;
+        }
+        if changed & CHANGED_NAV != 0 {
+            write_varint(out, frame.nav_actions)?;
+        }
+        self.prev = frame.clone();
+        Ok(())
+    }
+
+    /// Writes the end marker and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&[TAG_END])?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Reads frames written by `InputRecorder`.
+pub struct InputReplayer<R: Read> {
+    input: R,
+    frame: InputFrame,
+    done: bool,
+}
+
+impl<R: Read> InputReplayer<R> {
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut header = [0u8; 5];
+        input.read_exact(&mut header)?;
+        if header[..4] != INPUT_RECORDING_MAGIC {
+            return Err(invalid("not an input recording"));
+        }
+        if header[4] != INPUT_RECORDING_VERSION {
+            return Err(invalid("unsupported input recording version"));
+        }
+        Ok(InputReplayer {
+            input,
+            frame: InputFrame::zeroed(),
+            done: false,
+        })
+    }
+
+    /// Returns the next frame or `None` at the end of the recording.
+    pub fn read_frame(&mut self) -> io::Result<Option<InputFrame>> {
+        if self.done {
+            return Ok(None);
+        }
+        let mut tag = [0u8; 1];
+        if self.input.read(&mut tag)? == 0 || tag[0] == TAG_END {
+            self.done = true;
+            return Ok(None);
+        }
+        if tag[0] != TAG_FRAME {
+            return Err(invalid("unknown input recording tag"));
+        }
+        let input = &mut self.input;
+        let frame = &mut self.frame;
+        let changed = read_varint(input)?;
+        if changed & !CHANGED_ALL != 0 {
+            return Err(invalid("unknown input recording field"));
+        }
+        if changed & CHANGED_DELTA_TIME != 0 {
+            frame.delta_time_seconds = read_f32(input)?;
+        }
+        if changed & CHANGED_POS != 0 {
+            frame.input.mouse.pos = read_point(input)?;
+        }
+        if changed & CHANGED_PREV != 0 {
+            frame.input.mouse.prev = read_point(input)?;
+        }
+        if changed & CHANGED_DELTA != 0 {
+            frame.input.mouse.delta = read_point(input)?;
+        }
+        if changed & CHANGED_SCROLL != 0 {
+            frame.input.mouse.scroll_delta = read_point(input)?;
+        }
+        if changed & CHANGED_GRAB != 0 {
+            let grab = read_u8(input)?;
+            let mouse = &mut frame.input.mouse;
+            mouse.grab = grab & 1;
+            mouse.grabbed = grab >> 1 & 1;
+            mouse.ungrab = grab >> 2 & 1;
+        }
+        if changed & CHANGED_BUTTONS != 0 {
+            for b in frame.input.mouse.buttons.iter_mut() {
+                b.down = read_flag(input)?;
+                b.clicked = read_varint(input)?;
+                b.clicked_pos = read_point(input)?;
+            }
+        }
+        if changed & CHANGED_KEYS != 0 {
+            read_keys(input, &mut frame.input.keyboard.keys)?;
+        }
+        if changed & CHANGED_TEXT != 0 {
+            let len = read_varint(input)? as usize;
+            frame.text.clear();
+            input.by_ref().take(len as u64).read_to_end(&mut frame.text)?;
+            if frame.text.len() != len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            frame.input.keyboard.text_len = len as Int;
+        }
+        if changed & CHANGED_PHYSICAL != 0 {
+            read_keys(input, &mut frame.input.keyboard.physical)?;
+        }
+        if changed & CHANGED_MODIFIERS != 0 {
+            frame.input.keyboard.modifiers = read_varint(input)?;
+        }
+        if changed & CHANGED_CLICKS != 0 {
+            for count in frame.input.mouse.click_count.iter_mut() {
+                *count = read_varint(input)?;
+            }
+        }
+        if changed & CHANGED_TOUCH != 0 {
+            read_touch(input, &mut frame.input.touch)?;
+        }
+        if changed & CHANGED_NAV != 0 {
+            frame.nav_actions = read_varint(input)?;
+        }
+        Ok(Some(frame.clone()))
+    }
+
+    /// Feeds the next recorded frame into `ctx` in place of live input.
+    /// Returns `false` once the recording is exhausted.
+    pub fn replay(&mut self, ctx: &mut Context) -> io::Result<bool> {
+        match self.read_frame()? {
+            Some(frame) => unsafe {
+                nk_input_begin(ctx);
+                frame.apply(ctx);
+                nk_input_end_recorded(ctx);
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use allocator::OwnedContext;
+
+    type Step = Box<dyn Fn(*mut Context)>;
+
+    fn script() -> Vec<Step> {
+        unsafe {
+            vec![
+                Box::new(|ctx| nk_input_motion(ctx, 10, 20)),
+                Box::new(|ctx| nk_input_button(ctx, NK_BUTTON_LEFT, 10, 20, 1)),
+                Box::new(|ctx| {
+                    nk_input_motion(ctx, 14, 26);
+                    nk_input_button(ctx, NK_BUTTON_LEFT, 14, 26, 0);
+                }),
+                Box::new(|ctx| {
+                    nk_input_key(ctx, NK_KEY_SHIFT, 1);
+                    nk_input_unicode(ctx, 'x' as Rune);
+                    nk_input_unicode(ctx, '\u{e9}' as Rune);
+                }),
+                Box::new(|ctx| {
+                    for c in "text longer than sixteen bytes, \u{263a}".chars() {
+                        nk_input_unicode(ctx, c as Rune);
+                    }
+                }),
+                Box::new(|ctx| nk_input_scroll(ctx, Point::new(0.0, -2.5))),
+                Box::new(|ctx| nk_input_key(ctx, NK_KEY_SHIFT, 0)),
+                Box::new(|ctx| nk_input_nav(ctx, NK_NAV_NEXT | NK_NAV_ACTIVATE)),
+                Box::new(|ctx| nk_input_key(ctx, NK_KEY_TAB, 1)),
+                Box::new(|ctx| {
+                    nk_input_touch(ctx, 1, 100, 100, 1);
+                    nk_input_touch(ctx, 2, 200, 100, 1);
+                }),
+                Box::new(|ctx| {
+                    nk_input_touch(ctx, 1, 80, 110, 1);
+                    nk_input_touch(ctx, 2, 240, 110, 1);
+                }),
+                Box::new(|ctx| nk_input_touch(ctx, 2, 260, 120, 1)),
+                Box::new(|ctx| {
+                    nk_input_touch(ctx, 1, 80, 110, 0);
+                    nk_input_touch(ctx, 2, 260, 120, 0);
+                }),
+                Box::new(|_| {}),
+            ]
+        }
+    }
+
+    /* runs `steps` as frames of a fresh context and records each of them */
+    fn record(steps: &[Step]) -> (Vec<u8>, Vec<InputFrame>) {
+        let mut ctx = OwnedContext::new();
+        let mut recorder = InputRecorder::new(Vec::new()).unwrap();
+        let mut frames = Vec::new();
+        for step in steps {
+            unsafe {
+                nk_input_begin(&mut *ctx);
+                ctx.delta_time_seconds = 0.016;
+                step(&mut *ctx);
+                nk_input_end(&mut *ctx);
+            }
+            recorder.record(&ctx).unwrap();
+            frames.push(InputFrame::from_context(&ctx));
+            unsafe { nk_clear(&mut *ctx) };
+        }
+        (recorder.finish().unwrap(), frames)
+    }
+
+    fn assert_replays(steps: &[Step]) {
+        let (bytes, frames) = record(steps);
+        let mut ctx = OwnedContext::new();
+        let mut replayer = InputReplayer::new(&bytes[..]).unwrap();
+        for (i, frame) in frames.iter().enumerate() {
+            assert!(replayer.replay(&mut ctx).unwrap());
+            let replayed = InputFrame::from_context(&ctx);
+            assert_eq!(replayed.changes(frame), 0, "frame {} differs", i);
+            unsafe { nk_clear(&mut *ctx) };
+        }
+        assert!(!replayer.replay(&mut ctx).unwrap());
+    }
+
+    #[test]
+    fn replay_is_bit_identical() {
+        assert_replays(&script());
+    }
+
+    #[test]
+    fn replays_nav_actions() {
+        let steps: Vec<Step> = vec![Box::new(|ctx| unsafe { nk_input_nav(ctx, NK_NAV_CANCEL) })];
+        let (bytes, _) = record(&steps);
+        let mut ctx = OwnedContext::new();
+        let mut replayer = InputReplayer::new(&bytes[..]).unwrap();
+        assert!(replayer.replay(&mut ctx).unwrap());
+        assert_eq!(ctx.nav.actions, NK_NAV_CANCEL);
+    }
+
+    #[test]
+    fn unchanged_frames_are_small() {
+        let idle = |n| {
+            let steps: Vec<Step> = (0..n).map(|_| Box::new(|_: *mut Context| {}) as Step).collect();
+            record(&steps).0.len()
+        };
+        /* a tag and an empty change set */
+        assert_eq!(idle(20) - idle(10), 10 * 2);
+    }
+
+    #[test]
+    fn rejects_foreign_and_truncated_input() {
+        assert!(InputReplayer::new(&b"NKIX\x01"[..]).is_err());
+        assert!(InputReplayer::new(&[b'N', b'K', b'I', b'R', 0xff][..]).is_err());
+        let (bytes, _) = record(&script());
+        let mut replayer = InputReplayer::new(&bytes[..bytes.len() / 2]).unwrap();
+        let mut ctx = OwnedContext::new();
+        let mut result = Ok(true);
+        while let Ok(true) = result {
+            result = replayer.replay(&mut ctx);
+        }
+        assert!(result.is_err());
+    }
+
+    /* reads a frame whose `changed` set and fields are `body` */
+    fn damaged(body: &[u8]) -> String {
+        let mut bytes = INPUT_RECORDING_MAGIC.to_vec();
+        bytes.push(INPUT_RECORDING_VERSION);
+        bytes.push(TAG_FRAME);
+        bytes.extend_from_slice(body);
+        let mut replayer = InputReplayer::new(&bytes[..]).unwrap();
+        let e = replayer.read_frame().err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        e.to_string()
+    }
+
+    /* touch group with free slots, no gestures and `primary` + 1 */
+    fn touch_body(primary: u32) -> Vec<u8> {
+        let mut body = Vec::new();
+        write_varint(&mut body, CHANGED_TOUCH).unwrap();
+        body.extend_from_slice(&[0; NK_TOUCH_MAX]);
+        write_varint(&mut body, 0).unwrap();
+        write_varint(&mut body, primary).unwrap();
+        body.extend_from_slice(&[0; 4 * 8]);
+        body
+    }
+
+    #[test]
+    fn rejects_out_of_range_touch_and_flags() {
+        /* sanity: the fixture itself decodes */
+        let mut bytes = INPUT_RECORDING_MAGIC.to_vec();
+        bytes.push(INPUT_RECORDING_VERSION);
+        bytes.push(TAG_FRAME);
+        bytes.extend_from_slice(&touch_body(NK_TOUCH_MAX as u32));
+        let frame = InputReplayer::new(&bytes[..]).unwrap().read_frame().unwrap();
+        assert_eq!(frame.unwrap().input.touch.primary, NK_TOUCH_MAX as libc::c_int - 1);
+
+        let slot_error = "primary touch slot out of range";
+        assert_eq!(damaged(&touch_body(NK_TOUCH_MAX as u32 + 1)), slot_error);
+        /* wraps to i32::MIN, whose predecessor does not exist */
+        assert_eq!(damaged(&touch_body(1 << 31)), slot_error);
+        assert_eq!(damaged(&touch_body(u32::MAX)), slot_error);
+
+        let flag_error = "input recording flag out of range";
+        let mut body = Vec::new();
+        write_varint(&mut body, CHANGED_TOUCH).unwrap();
+        write_varint(&mut body, NK_TOUCH_BEGAN).unwrap();
+        body.extend_from_slice(&[1, 0]);
+        body.extend_from_slice(&[0; 3 * 8 + 4]);
+        body.push(2);
+        assert_eq!(damaged(&body), flag_error);
+
+        let mut body = Vec::new();
+        write_varint(&mut body, CHANGED_BUTTONS).unwrap();
+        body.push(2);
+        assert_eq!(damaged(&body), flag_error);
+    }
+}
@@ -0,0 +1,1228 @@
+//! Versioned binary serialization of the command queue.
+//!
+//! `CommandWriter` encodes the commands of a frame so that another process
+//! or machine can read them back with `CommandReader` and draw them with
+//! its own backend. Frames are self-contained: every frame carries the
+//! fonts its text commands use, identified by the name given to
+//! `CommandWriter::register_font` and the font height, which the receiver
+//! resolves against its own fonts.
+//!
+//! Stream format (integers unsigned LEB128 varints, `i` zigzag varints,
+//! floats little-endian IEEE 754 bits, colors 4 bytes r, g, b, a):
+//!
+//! ```text
+//! header  "NKCQ" version:u8
+//! frame   0x01 length:varint command...
+//! end     0x00 (optional, EOF at a frame boundary is accepted)
+//! ```
+//!
+//! Every command starts with its `NK_COMMAND_*` type as one byte, font
+//! definitions with 0xfe; fonts are numbered in the order they are defined.
+//!
+//! ```text
+//! font            name_len name f32 height
+//! scissor         rect
+//! line            begin end f32 thickness color
+//! curve           begin ctrl0 ctrl1 end f32 thickness color
+//! line_styled     begin end f32 thickness color style
+//! curve_styled    begin ctrl0 ctrl1 end f32 thickness color style
+//! rect            rect f32 rounding f32 thickness color
+//! rect_filled     rect f32 rounding color
+//! rect_shadow     rect f32 rounding f32 feather color
+//! rect_multi      rect left top right bottom
+//! rect_corners    rect radii f32 thickness color
+//! rect_corners_filled rect radii color
+//! rect_gradient   rect radii gradient
+//! circle          rect f32 thickness color
+//! circle_filled   rect color
+//! circle_gradient rect gradient
+//! arc             center f32 radius f32 a0 f32 a1 f32 thickness color
+//! arc_filled      center f32 radius f32 a0 f32 a1 color
+//! triangle        a b c f32 thickness color
+//! triangle_filled a b c color
+//! polygon         f32 thickness color count (i x, i y)...
+//! polygon_filled  color count (i x, i y)...
+//! polygon_gradient gradient count (i x, i y)...
+//! polyline        f32 thickness color count (i x, i y)...
+//! polygon_styled  f32 thickness color style count (i x, i y)...
+//! polyline_styled f32 thickness color style count (i x, i y)...
+//! text            font f32 height rect background foreground len bytes
+//! image           rect i handle_id w h region[4] color
+//! ```
+//!
+//! Rectangles are four floats x, y, w, h and points two floats. Corner
+//! radii are four floats and gradients a `NK_GRADIENT_*` type byte, start
+//! and end point, start and end color. Stroke styles are a `NK_LINE_CAP_*`
+//! byte, a `NK_LINE_JOIN_*` byte, f32 miter limit, f32 dash offset and
+//! the dash count followed by that many f32 dash lengths. Images
+//! travel by `Handle::id`; custom commands are process local and skipped.
+
+use command::Command;
+use math::Rect;
+use nuklear::*;
+use replay::{invalid, read_f32, read_point, read_u8, read_varint, write_f32, write_point,
+             write_varint};
+use std::io::{self, Read, Write};
+use std::str;
+
+pub const COMMAND_STREAM_MAGIC: [u8; 4] = *b"NKCQ";
+pub const COMMAND_STREAM_VERSION: u8 = 2;
+
+const TAG_END: u8 = 0;
+const TAG_FRAME: u8 = 1;
+const TAG_FONT: u8 = 0xfe;
+
+/* refuse frames that cannot come from a sane UI before allocating them */
+const MAX_FRAME_SIZE: usize = 1 << 26;
+
+/// Font identity on the wire.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FontKey {
+    /// Name registered by the sender, empty for unregistered fonts.
+    pub name: String,
+    pub height: f32,
+}
+
+/// Names the sender gives to its fonts. Like the receiver, it tells fonts
+/// apart by height only; registering a second font of the same height
+/// renames the first.
+#[derive(Default)]
+pub struct FontNames {
+    fonts: Vec<FontKey>,
+}
+
+impl FontNames {
+    pub fn new() -> Self {
+        FontNames { fonts: Vec::new() }
+    }
+
+    pub fn insert(&mut self, font: &UserFont, name: &str) {
+        self.fonts.retain(|k| k.height != font.height);
+        self.fonts.push(FontKey {
+            name: name.to_owned(),
+            height: font.height,
+        });
+    }
+
+    fn key(&self, font: &UserFont) -> FontKey {
+        match self.fonts.iter().find(|k| k.height == font.height) {
+            Some(key) => key.clone(),
+            None => FontKey {
+                name: String::new(),
+                height: font.height,
+            },
+        }
+    }
+}
+
+/// Font of `atlas` closest in height to `key`, for receivers that do not
+/// care about names.
+pub fn closest_font<'a>(atlas: &'a FontAtlas, key: &FontKey) -> Option<&'a UserFont> {
+    let mut best: Option<&'a UserFont> = None;
+    let mut font = atlas.fonts;
+    while !font.is_null() {
+        let handle = unsafe { &(*font).handle };
+        let closer = match best {
+            Some(b) => (handle.height - key.height).abs() < (b.height - key.height).abs(),
+            None => true,
+        };
+        if closer {
+            best = Some(handle);
+        }
+        font = unsafe { (*font).next };
+    }
+    best
+}
+
+#[inline]
+pub(crate) fn zigzag(v: i32) -> u32 {
+    ((v << 1) ^ (v >> 31)) as u32
+}
+
+#[inline]
+pub(crate) fn unzigzag(v: u32) -> i32 {
+    (v >> 1) as i32 ^ -((v & 1) as i32)
+}
+
+fn write_rect<W: Write>(out: &mut W, r: Rect) -> io::Result<()> {
+    write_f32(out, r.x)?;
+    write_f32(out, r.y)?;
+    write_f32(out, r.w)?;
+    write_f32(out, r.h)
+}
+
+fn write_color<W: Write>(out: &mut W, c: Color) -> io::Result<()> {
+    out.write_all(&[c.r, c.g, c.b, c.a])
+}
+
+fn write_radii<W: Write>(out: &mut W, r: [f32; 4]) -> io::Result<()> {
+    for &v in &r {
+        write_f32(out, v)?;
+    }
+    Ok(())
+}
+
+fn write_gradient<W: Write>(out: &mut W, g: &Gradient) -> io::Result<()> {
+    out.write_all(&[g.type_0 as u8])?;
+    write_point(out, g.start)?;
+    write_point(out, g.end)?;
+    write_color(out, g.start_color)?;
+    write_color(out, g.end_color)
+}
+
+fn write_stroke_style<W: Write>(out: &mut W, s: &StrokeStyle) -> io::Result<()> {
+    let count = s.dash_count.min(NK_STROKE_MAX_DASHES);
+    out.write_all(&[s.cap as u8, s.join as u8])?;
+    write_f32(out, s.miter_limit)?;
+    write_f32(out, s.dash_offset)?;
+    write_varint(out, count)?;
+    for &d in &s.dash[..count as usize] {
+        write_f32(out, d)?;
+    }
+    Ok(())
+}
+
+fn write_points<W: Write>(out: &mut W, points: &[PointI]) -> io::Result<()> {
+    write_varint(out, points.len() as u32)?;
+    for p in points {
+        write_varint(out, zigzag(p.x as i32))?;
+        write_varint(out, zigzag(p.y as i32))?;
+    }
+    Ok(())
+}
+
+fn read_rect<R: Read>(input: &mut R) -> io::Result<Rect> {
+    let x = read_f32(input)?;
+    let y = read_f32(input)?;
+    let w = read_f32(input)?;
+    let h = read_f32(input)?;
+    Ok(Rect::new(x, y, w, h))
+}
+
+fn read_color<R: Read>(input: &mut R) -> io::Result<Color> {
+    let mut c = [0u8; 4];
+    input.read_exact(&mut c)?;
+    Ok(Color {
+        r: c[0],
+        g: c[1],
+        b: c[2],
+        a: c[3],
+    })
+}
+
+fn read_radii<R: Read>(input: &mut R) -> io::Result<[f32; 4]> {
+    Ok([
+        read_f32(input)?,
+        read_f32(input)?,
+        read_f32(input)?,
+        read_f32(input)?,
+    ])
+}
+
+fn read_gradient<R: Read>(input: &mut R) -> io::Result<Gradient> {
+    let type_0 = read_u8(input)? as GradientType;
+    if type_0 != NK_GRADIENT_LINEAR && type_0 != NK_GRADIENT_RADIAL {
+        return Err(invalid("unknown command stream gradient type"));
+    }
+    Ok(Gradient {
+        type_0,
+        start: read_point(input)?,
+        end: read_point(input)?,
+        start_color: read_color(input)?,
+        end_color: read_color(input)?,
+    })
+}
+
+fn read_stroke_style<R: Read>(input: &mut R) -> io::Result<StrokeStyle> {
+    let cap = read_u8(input)? as LineCap;
+    let join = read_u8(input)? as LineJoin;
+    if cap > NK_LINE_CAP_SQUARE || join > NK_LINE_JOIN_BEVEL {
+        return Err(invalid("unknown command stream line cap or join"));
+    }
+    let mut style = StrokeStyle {
+        cap,
+        join,
+        miter_limit: read_f32(input)?,
+        dash_offset: read_f32(input)?,
+        dash_count: read_varint(input)?,
+        dash: [0.0; 8],
+    };
+    if style.dash_count > NK_STROKE_MAX_DASHES {
+        return Err(invalid("command stream dash pattern too long"));
+    }
+    for d in style.dash[..style.dash_count as usize].iter_mut() {
+        *d = read_f32(input)?;
+    }
+    Ok(style)
+}
+
+fn read_short<R: Read>(input: &mut R) -> io::Result<Short> {
+    let v = unzigzag(read_varint(input)?);
+    if v < Short::MIN as i32 || v > Short::MAX as i32 {
+        return Err(invalid("command stream coordinate out of range"));
+    }
+    Ok(v as Short)
+}
+
+fn read_ushort<R: Read>(input: &mut R) -> io::Result<Ushort> {
+    let v = read_varint(input)?;
+    if v > Ushort::MAX as u32 {
+        return Err(invalid("command stream size out of range"));
+    }
+    Ok(v as Ushort)
+}
+
+/// Encodes the commands of one frame into the body of a frame record.
+pub fn encode_frame<'a, I>(commands: I, names: &FontNames) -> Vec<u8>
+where
+    I: IntoIterator<Item = Command<'a>>,
+{
+    let mut out = Vec::new();
+    let mut fonts: Vec<FontKey> = Vec::new();
+    for cmd in commands {
+        /* writing into a Vec cannot fail */
+        let _ = encode_command(&mut out, &cmd, &mut fonts, names);
+    }
+    out
+}
+
+fn encode_command(
+    out: &mut Vec<u8>,
+    cmd: &Command,
+    fonts: &mut Vec<FontKey>,
+    names: &FontNames,
+) -> io::Result<()> {
+    match *cmd {
+        Command::Nop | Command::Custom { .. } | Command::CustomDraw { .. } => {}
+        Command::Scissor { rect } => {
+            out.push(NK_COMMAND_SCISSOR as u8);
+            write_rect(out, rect)?;
+        }
+        Command::Line {
+            begin,
+            end,
+            thickness,
+            color,
+        } => {
+            out.push(NK_COMMAND_LINE as u8);
+            write_point(out, begin)?;
+            write_point(out, end)?;
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+        }
+        Command::Curve {
+            begin,
+            ctrl,
+            end,
+            thickness,
+            color,
+        } => {
+            out.push(NK_COMMAND_CURVE as u8);
+            write_point(out, begin)?;
+            write_point(out, ctrl[0])?;
+            write_point(out, ctrl[1])?;
+            write_point(out, end)?;
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+        }
+        Command::LineStyled {
+            begin,
+            end,
+            thickness,
+            color,
+            ref style,
+        } => {
+            out.push(NK_COMMAND_LINE_STYLED as u8);
+            write_point(out, begin)?;
+            write_point(out, end)?;
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+            write_stroke_style(out, style)?;
+        }
+        Command::CurveStyled {
+            begin,
+            ctrl,
+            end,
+            thickness,
+            color,
+            ref style,
+        } => {
+            out.push(NK_COMMAND_CURVE_STYLED as u8);
+            write_point(out, begin)?;
+            write_point(out, ctrl[0])?;
+            write_point(out, ctrl[1])?;
+            write_point(out, end)?;
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+            write_stroke_style(out, style)?;
+        }
+        Command::Rect {
+            rect,
+            rounding,
+            thickness,
+            color,
+        } => {
+            out.push(NK_COMMAND_RECT as u8);
+            write_rect(out, rect)?;
+            write_f32(out, rounding)?;
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+        }
+        Command::RectFilled {
+            rect,
+            rounding,
+            color,
+        } => {
+            out.push(NK_COMMAND_RECT_FILLED as u8);
+            write_rect(out, rect)?;
+            write_f32(out, rounding)?;
+            write_color(out, color)?;
+        }
+        Command::RectShadow {
+            rect,
+            rounding,
+            feather,
+            color,
+        } => {
+            out.push(NK_COMMAND_RECT_SHADOW as u8);
+            write_rect(out, rect)?;
+            write_f32(out, rounding)?;
+            write_f32(out, feather)?;
+            write_color(out, color)?;
+        }
+        Command::RectMultiColor {
+            rect,
+            left,
+            top,
+            right,
+            bottom,
+        } => {
+            out.push(NK_COMMAND_RECT_MULTI_COLOR as u8);
+            write_rect(out, rect)?;
+            for &c in &[left, top, right, bottom] {
+                write_color(out, c)?;
+            }
+        }
+        Command::RectCorners {
+            rect,
+            rounding,
+            thickness,
+            color,
+        } => {
+            out.push(NK_COMMAND_RECT_CORNERS as u8);
+            write_rect(out, rect)?;
+            write_radii(out, rounding)?;
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+        }
+        Command::RectCornersFilled {
+            rect,
+            rounding,
+            color,
+        } => {
+            out.push(NK_COMMAND_RECT_CORNERS_FILLED as u8);
+            write_rect(out, rect)?;
+            write_radii(out, rounding)?;
+            write_color(out, color)?;
+        }
+        Command::RectGradient {
+            rect,
+            rounding,
+            ref gradient,
+        } => {
+            out.push(NK_COMMAND_RECT_GRADIENT as u8);
+            write_rect(out, rect)?;
+            write_radii(out, rounding)?;
+            write_gradient(out, gradient)?;
+        }
+        Command::Circle {
+            rect,
+            thickness,
+            color,
+        } => {
+            out.push(NK_COMMAND_CIRCLE as u8);
+            write_rect(out, rect)?;
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+        }
+        Command::CircleFilled { rect, color } => {
+            out.push(NK_COMMAND_CIRCLE_FILLED as u8);
+            write_rect(out, rect)?;
+            write_color(out, color)?;
+        }
+        Command::CircleGradient { rect, ref gradient } => {
+            out.push(NK_COMMAND_CIRCLE_GRADIENT as u8);
+            write_rect(out, rect)?;
+            write_gradient(out, gradient)?;
+        }
+        Command::Arc {
+            center,
+            radius,
+            angles,
+            thickness,
+            color,
+        } => {
+            out.push(NK_COMMAND_ARC as u8);
+            write_point(out, center)?;
+            write_f32(out, radius)?;
+            write_f32(out, angles[0])?;
+            write_f32(out, angles[1])?;
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+        }
+        Command::ArcFilled {
+            center,
+            radius,
+            angles,
+            color,
+        } => {
+            out.push(NK_COMMAND_ARC_FILLED as u8);
+            write_point(out, center)?;
+            write_f32(out, radius)?;
+            write_f32(out, angles[0])?;
+            write_f32(out, angles[1])?;
+            write_color(out, color)?;
+        }
+        Command::Triangle {
+            points,
+            thickness,
+            color,
+        } => {
+            out.push(NK_COMMAND_TRIANGLE as u8);
+            for &p in &points {
+                write_point(out, p)?;
+            }
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+        }
+        Command::TriangleFilled { points, color } => {
+            out.push(NK_COMMAND_TRIANGLE_FILLED as u8);
+            for &p in &points {
+                write_point(out, p)?;
+            }
+            write_color(out, color)?;
+        }
+        Command::Polygon {
+            points,
+            thickness,
+            color,
+        } => {
+            out.push(NK_COMMAND_POLYGON as u8);
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+            write_points(out, points)?;
+        }
+        Command::PolygonFilled { points, color } => {
+            out.push(NK_COMMAND_POLYGON_FILLED as u8);
+            write_color(out, color)?;
+            write_points(out, points)?;
+        }
+        Command::PolygonGradient {
+            points,
+            ref gradient,
+        } => {
+            out.push(NK_COMMAND_POLYGON_GRADIENT as u8);
+            write_gradient(out, gradient)?;
+            write_points(out, points)?;
+        }
+        Command::Polyline {
+            points,
+            thickness,
+            color,
+        } => {
+            out.push(NK_COMMAND_POLYLINE as u8);
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+            write_points(out, points)?;
+        }
+        Command::PolygonStyled {
+            points,
+            thickness,
+            color,
+            ref style,
+        }
+        | Command::PolylineStyled {
+            points,
+            thickness,
+            color,
+            ref style,
+        } => {
+            out.push(match *cmd {
+                Command::PolygonStyled { .. } => NK_COMMAND_POLYGON_STYLED as u8,
+                _ => NK_COMMAND_POLYLINE_STYLED as u8,
+            });
+            write_f32(out, thickness)?;
+            write_color(out, color)?;
+            write_stroke_style(out, style)?;
+            write_points(out, points)?;
+        }
+        Command::Text {
+            rect,
+            font,
+            height,
+            string,
+            background,
+            foreground,
+        } => {
+            let key = names.key(font);
+            let index = match fonts.iter().position(|f| *f == key) {
+                Some(index) => index,
+                None => {
+                    out.push(TAG_FONT);
+                    write_varint(out, key.name.len() as u32)?;
+                    out.extend_from_slice(key.name.as_bytes());
+                    write_f32(out, key.height)?;
+                    fonts.push(key);
+                    fonts.len() - 1
+                }
+            };
+            out.push(NK_COMMAND_TEXT as u8);
+            write_varint(out, index as u32)?;
+            write_f32(out, height)?;
+            write_rect(out, rect)?;
+            write_color(out, background)?;
+            write_color(out, foreground)?;
+            write_varint(out, string.len() as u32)?;
+            out.extend_from_slice(string.as_bytes());
+        }
+        Command::Image { rect, image, color } => {
+            out.push(NK_COMMAND_IMAGE as u8);
+            write_rect(out, rect)?;
+            write_varint(out, zigzag(unsafe { image.handle.id }))?;
+            write_varint(out, image.w as u32)?;
+            write_varint(out, image.h as u32)?;
+            for &r in &image.region {
+                write_varint(out, r as u32)?;
+            }
+            write_color(out, color)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Copy, Clone)]
+enum Entry {
+    /* commands without borrowed data */
+    Plain(Command<'static>),
+    Points {
+        type_0: CommandType,
+        start: usize,
+        len: usize,
+        thickness: f32,
+        color: Color,
+    },
+    GradientPoints {
+        start: usize,
+        len: usize,
+        gradient: Gradient,
+    },
+    StyledPoints {
+        type_0: CommandType,
+        start: usize,
+        len: usize,
+        thickness: f32,
+        color: Color,
+        style: StrokeStyle,
+    },
+    Text {
+        rect: Rect,
+        font: usize,
+        height: f32,
+        start: usize,
+        len: usize,
+        background: Color,
+        foreground: Color,
+    },
+}
+
+/// The commands of one decoded frame.
+pub struct CommandFrame {
+    fonts: Vec<FontKey>,
+    entries: Vec<Entry>,
+    points: Vec<PointI>,
+    text: String,
+}
+
+impl CommandFrame {
+    /// Decodes the body of a frame record, as produced by `encode_frame`.
+    pub fn decode(body: &[u8]) -> io::Result<CommandFrame> {
+        let mut frame = CommandFrame {
+            fonts: Vec::new(),
+            entries: Vec::new(),
+            points: Vec::new(),
+            text: String::new(),
+        };
+        let mut input = body;
+        while !input.is_empty() {
+            let tag = read_u8(&mut input)?;
+            if tag == TAG_FONT {
+                let name = read_string(&mut input).map_err(truncated)?;
+                let height = read_f32(&mut input).map_err(truncated)?;
+                frame.fonts.push(FontKey {
+                    name: name.to_owned(),
+                    height,
+                });
+            } else {
+                let entry = frame
+                    .decode_command(tag as CommandType, &mut input)
+                    .map_err(truncated)?;
+                frame.entries.push(entry);
+            }
+        }
+        Ok(frame)
+    }
+
+    fn decode_command(&mut self, type_0: CommandType, input: &mut &[u8]) -> io::Result<Entry> {
+        let cmd = match type_0 {
+            NK_COMMAND_SCISSOR => Command::Scissor {
+                rect: read_rect(input)?,
+            },
+            NK_COMMAND_LINE => Command::Line {
+                begin: read_point(input)?,
+                end: read_point(input)?,
+                thickness: read_f32(input)?,
+                color: read_color(input)?,
+            },
+            NK_COMMAND_CURVE => Command::Curve {
+                begin: read_point(input)?,
+                ctrl: [read_point(input)?, read_point(input)?],
+                end: read_point(input)?,
+                thickness: read_f32(input)?,
+                color: read_color(input)?,
+            },
+            NK_COMMAND_LINE_STYLED => Command::LineStyled {
+                begin: read_point(input)?,
+                end: read_point(input)?,
+                thickness: read_f32(input)?,
+                color: read_color(input)?,
+                style: read_stroke_style(input)?,
+            },
+            NK_COMMAND_CURVE_STYLED => Command::CurveStyled {
+                begin: read_point(input)?,
+                ctrl: [read_point(input)?, read_point(input)?],
+                end: read_point(input)?,
+                thickness: read_f32(input)?,
+                color: read_color(input)?,
+                style: read_stroke_style(input)?,
+            },
+            NK_COMMAND_RECT => Command::Rect {
+                rect: read_rect(input)?,
+                rounding: read_f32(input)?,
+                thickness: read_f32(input)?,
+                color: read_color(input)?,
+            },
+            NK_COMMAND_RECT_FILLED => Command::RectFilled {
+                rect: read_rect(input)?,
+                rounding: read_f32(input)?,
+                color: read_color(input)?,
+            },
+            NK_COMMAND_RECT_SHADOW => Command::RectShadow {
+                rect: read_rect(input)?,
+                rounding: read_f32(input)?,
+                feather: read_f32(input)?,
+                color: read_color(input)?,
+            },
+            NK_COMMAND_RECT_MULTI_COLOR => Command::RectMultiColor {
+                rect: read_rect(input)?,
+                left: read_color(input)?,
+                top: read_color(input)?,
+                right: read_color(input)?,
+                bottom: read_color(input)?,
+            },
+            NK_COMMAND_RECT_CORNERS => Command::RectCorners {
+                rect: read_rect(input)?,
+                rounding: read_radii(input)?,
+                thickness: read_f32(input)?,
+                color: read_color(input)?,
+            },
+            NK_COMMAND_RECT_CORNERS_FILLED => Command::RectCornersFilled {
+                rect: read_rect(input)?,
+                rounding: read_radii(input)?,
+                color: read_color(input)?,
+            },
+            NK_COMMAND_RECT_GRADIENT => Command::RectGradient {
+                rect: read_rect(input)?,
+                rounding: read_radii(input)?,
+                gradient: read_gradient(input)?,
+            },
+            NK_COMMAND_CIRCLE => Command::Circle {
+                rect: read_rect(input)?,
+                thickness: read_f32(input)?,
+                color: read_color(input)?,
+            },
+            NK_COMMAND_CIRCLE_FILLED => Command::CircleFilled {
+                rect: read_rect(input)?,
+                color: read_color(input)?,
+            },
+            NK_COMMAND_CIRCLE_GRADIENT => Command::CircleGradient {
+                rect: read_rect(input)?,
+                gradient: read_gradient(input)?,
+            },
+            NK_COMMAND_ARC => Command::Arc {
+                center: read_point(input)?,
+                radius: read_f32(input)?,
+                angles: [read_f32(input)?, read_f32(input)?],
+                thickness: read_f32(input)?,
+                color: read_color(input)?,
+            },
+            NK_COMMAND_ARC_FILLED => Command::ArcFilled {
+                center: read_point(input)?,
+                radius: read_f32(input)?,
+                angles: [read_f32(input)?, read_f32(input)?],
+                color: read_color(input)?,
+            },
+            NK_COMMAND_TRIANGLE => Command::Triangle {
+                points: [read_point(input)?, read_point(input)?, read_point(input)?],
+                thickness: read_f32(input)?,
+                color: read_color(input)?,
+            },
+            NK_COMMAND_TRIANGLE_FILLED => Command::TriangleFilled {
+                points: [read_point(input)?, read_point(input)?, read_point(input)?],
+                color: read_color(input)?,
+            },
+            NK_COMMAND_POLYGON | NK_COMMAND_POLYGON_FILLED | NK_COMMAND_POLYLINE => {
+                let thickness = if type_0 == NK_COMMAND_POLYGON_FILLED {
+                    0.0
+                } else {
+                    read_f32(input)?
+                };
+                let color = read_color(input)?;
+                let (start, len) = self.read_points(input)?;
+                return Ok(Entry::Points {
+                    type_0,
+                    start,
+                    len,
+                    thickness,
+                    color,
+                });
+            }
+            NK_COMMAND_POLYGON_GRADIENT => {
+                let gradient = read_gradient(input)?;
+                let (start, len) = self.read_points(input)?;
+                return Ok(Entry::GradientPoints {
+                    start,
+                    len,
+                    gradient,
+                });
+            }
+            NK_COMMAND_POLYGON_STYLED | NK_COMMAND_POLYLINE_STYLED => {
+                let thickness = read_f32(input)?;
+                let color = read_color(input)?;
+                let style = read_stroke_style(input)?;
+                let (start, len) = self.read_points(input)?;
+                return Ok(Entry::StyledPoints {
+                    type_0,
+                    start,
+                    len,
+                    thickness,
+                    color,
+                    style,
+                });
+            }
+            NK_COMMAND_TEXT => {
+                let font = read_varint(input)? as usize;
+                if font >= self.fonts.len() {
+                    return Err(invalid("command stream text uses an undefined font"));
+                }
+                let height = read_f32(input)?;
+                let rect = read_rect(input)?;
+                let background = read_color(input)?;
+                let foreground = read_color(input)?;
+                let string = read_string(input)?;
+                let start = self.text.len();
+                self.text.push_str(string);
+                return Ok(Entry::Text {
+                    rect,
+                    font,
+                    height,
+                    start,
+                    len: string.len(),
+                    background,
+                    foreground,
+                });
+            }
+            NK_COMMAND_IMAGE => {
+                let rect = read_rect(input)?;
+                let id = unzigzag(read_varint(input)?);
+                let w = read_ushort(input)?;
+                let h = read_ushort(input)?;
+                let mut region = [0; 4];
+                for r in region.iter_mut() {
+                    *r = read_ushort(input)?;
+                }
+                Command::Image {
+                    rect,
+                    image: Image {
+                        handle: Handle { id },
+                        w,
+                        h,
+                        region,
+                    },
+                    color: read_color(input)?,
+                }
+            }
+            _ => return Err(invalid("unknown command stream command")),
+        };
+        Ok(Entry::Plain(cmd))
+    }
+
+    fn read_points(&mut self, input: &mut &[u8]) -> io::Result<(usize, usize)> {
+        let len = read_varint(input)? as usize;
+        if len > input.len() / 2 {
+            return Err(invalid("command stream point count too large"));
+        }
+        let start = self.points.len();
+        for _ in 0..len {
+            let x = read_short(input)?;
+            let y = read_short(input)?;
+            self.points.push(PointI { x, y });
+        }
+        Ok((start, len))
+    }
+
+    /// Fonts used by the frame, indexed by its text commands.
+    pub fn fonts(&self) -> &[FontKey] {
+        &self.fonts
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the commands, drawing text with the fonts `resolve`
+    /// picks for each of `fonts()`.
+    pub fn commands<'a, F>(&'a self, resolve: F) -> FrameCommands<'a>
+    where
+        F: FnMut(&FontKey) -> &'a UserFont,
+    {
+        FrameCommands {
+            frame: self,
+            fonts: self.fonts.iter().map(resolve).collect(),
+            index: 0,
+        }
+    }
+}
+
+/* the body length is known, so running out of it is damage, not EOF */
+fn truncated(e: io::Error) -> io::Error {
+    match e.kind() {
+        io::ErrorKind::UnexpectedEof => invalid("command stream frame ends inside a command"),
+        _ => e,
+    }
+}
+
+fn read_string<'a>(input: &mut &'a [u8]) -> io::Result<&'a str> {
+    let len = read_varint(input)? as usize;
+    if len > input.len() {
+        return Err(invalid("command stream string truncated"));
+    }
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    str::from_utf8(bytes).map_err(|_| invalid("command stream string is not UTF-8"))
+}
+
+/// Iterator over the commands of a `CommandFrame`.
+pub struct FrameCommands<'a> {
+    frame: &'a CommandFrame,
+    fonts: Vec<&'a UserFont>,
+    index: usize,
+}
+
+impl<'a> Iterator for FrameCommands<'a> {
+    type Item = Command<'a>;
+
+    fn next(&mut self) -> Option<Command<'a>> {
+        let entry = *self.frame.entries.get(self.index)?;
+        self.index += 1;
+        Some(match entry {
+            Entry::Plain(cmd) => cmd,
+            Entry::Points {
+                type_0,
+                start,
+                len,
+                thickness,
+                color,
+            } => {
+                let points = &self.frame.points[start..start + len];
+                match type_0 {
+                    NK_COMMAND_POLYGON => Command::Polygon {
+                        points,
+                        thickness,
+                        color,
+                    },
+                    NK_COMMAND_POLYGON_FILLED => Command::PolygonFilled { points, color },
+                    _ => Command::Polyline {
+                        points,
+                        thickness,
+                        color,
+                    },
+                }
+            }
+            Entry::GradientPoints {
+                start,
+                len,
+                gradient,
+            } => Command::PolygonGradient {
+                points: &self.frame.points[start..start + len],
+                gradient,
+            },
+            Entry::StyledPoints {
+                type_0,
+                start,
+                len,
+                thickness,
+                color,
+                style,
+            } => {
+                let points = &self.frame.points[start..start + len];
+                match type_0 {
+                    NK_COMMAND_POLYGON_STYLED => Command::PolygonStyled {
+                        points,
+                        thickness,
+                        color,
+                        style,
+                    },
+                    _ => Command::PolylineStyled {
+                        points,
+                        thickness,
+                        color,
+                        style,
+                    },
+                }
+            }
+            Entry::Text {
+                rect,
+                font,
+                height,
+                start,
+                len,
+                background,
+                foreground,
+            } => Command::Text {
+                rect,
+                font: self.fonts[font],
+                height,
+                string: &self.frame.text[start..start + len],
+                background,
+                foreground,
+            },
+        })
+    }
+}
+
+/// Writes one frame of commands per call of `write_frame`.
+pub struct CommandWriter<W: Write> {
+    out: W,
+    fonts: FontNames,
+}
+
+impl<W: Write> CommandWriter<W> {
+    pub fn new(mut out: W) -> io::Result<Self> {
+        out.write_all(&COMMAND_STREAM_MAGIC)?;
+        out.write_all(&[COMMAND_STREAM_VERSION])?;
+        Ok(CommandWriter {
+            out,
+            fonts: FontNames::new(),
+        })
+    }
+
+    /// Names `font` for the receiver; unregistered fonts go out unnamed.
+    pub fn register_font(&mut self, font: &UserFont, name: &str) {
+        self.fonts.insert(font, name);
+    }
+
+    pub fn font_names(&self) -> &FontNames {
+        &self.fonts
+    }
+
+    /// Writes the commands of a frame, e.g. `ctx.commands()` before
+    /// `nk_clear`.
+    pub fn write_frame<'a, I>(&mut self, commands: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = Command<'a>>,
+    {
+        let body = encode_frame(commands, &self.fonts);
+        self.write_encoded(&body)
+    }
+
+    /// Writes a frame body produced by `encode_frame`.
+    pub fn write_encoded(&mut self, body: &[u8]) -> io::Result<()> {
+        self.out.write_all(&[TAG_FRAME])?;
+        write_varint(&mut self.out, body.len() as u32)?;
+        self.out.write_all(body)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Writes the end marker and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&[TAG_END])?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Reads frames written by `CommandWriter`.
+pub struct CommandReader<R: Read> {
+    input: R,
+    done: bool,
+}
+
+impl<R: Read> CommandReader<R> {
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut header = [0u8; 5];
+        input.read_exact(&mut header)?;
+        if header[..4] != COMMAND_STREAM_MAGIC {
+            return Err(invalid("not a command stream"));
+        }
+        if header[4] != COMMAND_STREAM_VERSION {
+            return Err(invalid("unsupported command stream version"));
+        }
+        Ok(CommandReader { input, done: false })
+    }
+
+    /// Returns the body of the next frame or `None` at the end of the
+    /// stream.
+    pub fn read_encoded(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.done {
+            return Ok(None);
+        }
+        let mut tag = [0u8; 1];
+        if self.input.read(&mut tag)? == 0 || tag[0] == TAG_END {
+            self.done = true;
+            return Ok(None);
+        }
+        if tag[0] != TAG_FRAME {
+            return Err(invalid("unknown command stream tag"));
+        }
+        let len = read_varint(&mut self.input)? as usize;
+        if len > MAX_FRAME_SIZE {
+            return Err(invalid("command stream frame too large"));
+        }
+        let mut body = vec![0u8; len];
+        self.input.read_exact(&mut body)?;
+        Ok(Some(body))
+    }
+
+    /// Returns the next frame or `None` at the end of the stream.
+    pub fn read_frame(&mut self) -> io::Result<Option<CommandFrame>> {
+        match self.read_encoded()? {
+            Some(body) => CommandFrame::decode(&body).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harness::{describe_command, draw_every_command, Harness};
+    use math::Point;
+
+    fn describe<'a, I: IntoIterator<Item = Command<'a>>>(commands: I) -> Vec<String> {
+        commands.into_iter().map(|c| describe_command(&c)).collect()
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let mut harness = Harness::new(320, 320);
+        let ctx = harness.context_mut();
+        unsafe { draw_every_command(ctx) };
+        let font = unsafe { &*ctx.style.font };
+        let sent = describe(ctx.commands());
+
+        let mut writer = CommandWriter::new(Vec::new()).unwrap();
+        writer.register_font(font, "proggy");
+        writer.write_frame(ctx.commands()).unwrap();
+        writer.write_frame(None).unwrap();
+        let bytes = writer.finish().unwrap();
+
+        let mut reader = CommandReader::new(&bytes[..]).unwrap();
+        let frame = reader.read_frame().unwrap().unwrap();
+        let key = FontKey {
+            name: "proggy".to_owned(),
+            height: font.height,
+        };
+        assert_eq!(frame.fonts(), &[key][..]);
+        assert_eq!(describe(frame.commands(|_| font)), sent);
+        assert!(reader.read_frame().unwrap().unwrap().is_empty());
+        assert!(reader.read_frame().unwrap().is_none());
+        unsafe { nk_clear(ctx) };
+    }
+
+    #[test]
+    fn rejects_foreign_and_damaged_streams() {
+        assert!(CommandReader::new(&b"NKCX\x01"[..]).is_err());
+        let mut header = COMMAND_STREAM_MAGIC.to_vec();
+        header.push(COMMAND_STREAM_VERSION.wrapping_add(1));
+        assert!(CommandReader::new(&header[..]).is_err());
+
+        let mut harness = Harness::new(320, 320);
+        let ctx = harness.context_mut();
+        unsafe { draw_every_command(ctx) };
+        let body = encode_frame(ctx.commands(), &FontNames::new());
+        unsafe { nk_clear(ctx) };
+        for len in 0..body.len() {
+            /* a cut through a command never decodes to a shorter frame */
+            match CommandFrame::decode(&body[..len]) {
+                Ok(frame) => assert!(CommandFrame::decode(&body).unwrap().len() > frame.len()),
+                Err(e) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+            }
+        }
+
+        let damage = |body: &[u8]| {
+            let e = CommandFrame::decode(body).err().unwrap();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+            e.to_string()
+        };
+        let line = Command::Line {
+            begin: Point::new(1.0, 2.0),
+            end: Point::new(3.0, 4.0),
+            thickness: 1.0,
+            color: Color {
+                r: 1,
+                g: 2,
+                b: 3,
+                a: 4,
+            },
+        };
+        let body = encode_frame(Some(line), &FontNames::new());
+        assert_eq!(
+            damage(&body[..body.len() - 1]),
+            "command stream frame ends inside a command"
+        );
+        assert_eq!(damage(&[0x7f]), "unknown command stream command");
+        let gradient = Command::RectGradient {
+            rect: Rect::new(0.0, 0.0, 10.0, 10.0),
+            rounding: [0.0; 4],
+            gradient: Gradient {
+                type_0: NK_GRADIENT_RADIAL,
+                start: Point::new(5.0, 5.0),
+                end: Point::new(10.0, 5.0),
+                start_color: Color {
+                    r: 0,
+                    g: 0,
+                    b: 0,
+                    a: 255,
+                },
+                end_color: Color {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                    a: 255,
+                },
+            },
+        };
+        let mut body = encode_frame(Some(gradient), &FontNames::new());
+        /* type byte, rect and radii precede the gradient type */
+        assert_eq!(body[33], NK_GRADIENT_RADIAL as u8);
+        body[33] = NK_GRADIENT_RADIAL as u8 + 1;
+        assert_eq!(damage(&body), "unknown command stream gradient type");
+    }
+}
@@ -0,0 +1,937 @@
+//! SVG export of the command queue.
+//!
+//! `to_svg` turns the commands of a frame into a standalone SVG document
+//! that scales without the blur of a screenshot. Every command maps to the
+//! closest SVG primitive:
+//!
+//! * rectangles, circles, triangles, polygons and polylines to `<rect>`,
+//!   `<ellipse>`, `<polygon>` and `<polyline>`, keeping rounding and
+//!   stroke width;
+//! * arcs and curves to `<path>` arcs and cubic Béziers;
+//! * stroke styles to the matching `stroke-linecap`, `stroke-linejoin`,
+//!   `stroke-miterlimit` and `stroke-dasharray` attributes;
+//! * scissors to clip paths around the commands that follow them;
+//! * text to `<text>` with the command's font height as font size;
+//! * images to `<image>`, referenced or embedded as PNG as decided by the
+//!   caller, cropped to `Image::region`.
+//!
+//! Multi-color rectangles become a linear gradient, which is exact when
+//! the colors only change along one axis; gradient fills map to SVG
+//! gradients one to one. Rectangles with differing corner radii become
+//! paths, shadows blurred rectangles. Custom commands have nothing to
+//! export and are left out.
+
+use command::Command;
+use math::{Point, Rect};
+use nuklear::*;
+use raster::Pixmap;
+use std::f32::consts::PI;
+use std::fmt::Write;
+
+/// How an image shows up in the document.
+pub enum SvgImage {
+    /// Reference to an external file or URL.
+    Href(String),
+    /// Pixels embedded as a PNG data URI.
+    Embed(Pixmap),
+}
+
+/// Document settings for `to_svg`.
+#[derive(Clone)]
+pub struct SvgOptions {
+    pub width: f32,
+    pub height: f32,
+    /// Painted behind all commands if given.
+    pub background: Option<Color>,
+    pub font_family: String,
+}
+
+impl SvgOptions {
+    pub fn new(width: f32, height: f32) -> Self {
+        SvgOptions {
+            width,
+            height,
+            background: None,
+            font_family: "monospace".to_owned(),
+        }
+    }
+}
+
+/* shortest decimal form, so documents stay small and stable */
+struct Num(f32);
+
+impl ::std::fmt::Display for Num {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        let v = (self.0 * 100.0).round() / 100.0;
+        if v == 0.0 || !v.is_finite() {
+            f.write_str("0")
+        } else {
+            write!(f, "{}", v)
+        }
+    }
+}
+
+fn hex(c: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b)
+}
+
+fn paint(kind: &str, c: Color) -> String {
+    if c.a == 255 {
+        format!("{}=\"{}\"", kind, hex(c))
+    } else {
+        format!(
+            "{}=\"{}\" {}-opacity=\"{}\"",
+            kind,
+            hex(c),
+            kind,
+            Num(c.a as f32 / 255.0)
+        )
+    }
+}
+
+fn fill(c: Color) -> String {
+    paint("fill", c)
+}
+
+fn stroke(c: Color, thickness: f32) -> String {
+    format!(
+        "fill=\"none\" {} stroke-width=\"{}\"",
+        paint("stroke", c),
+        Num(thickness)
+    )
+}
+
+/* SVG defaults to butt caps, miter joins and a miter limit of 4 as well */
+fn stroke_styled(c: Color, thickness: f32, style: &StrokeStyle) -> String {
+    let mut out = stroke(c, thickness);
+    match style.cap {
+        NK_LINE_CAP_ROUND => out.push_str(" stroke-linecap=\"round\""),
+        NK_LINE_CAP_SQUARE => out.push_str(" stroke-linecap=\"square\""),
+        _ => {}
+    }
+    match style.join {
+        NK_LINE_JOIN_ROUND => out.push_str(" stroke-linejoin=\"round\""),
+        NK_LINE_JOIN_BEVEL => out.push_str(" stroke-linejoin=\"bevel\""),
+        _ if style.miter_limit != 4.0 => {
+            let _ = write!(out, " stroke-miterlimit=\"{}\"", Num(style.miter_limit.max(1.0)));
+        }
+        _ => {}
+    }
+    let dash = &style.dash[..style.dash_count.min(NK_STROKE_MAX_DASHES) as usize];
+    if dash.iter().any(|&d| d > 0.0) {
+        let list: Vec<String> = dash.iter().map(|&d| Num(d.max(0.0)).to_string()).collect();
+        let _ = write!(out, " stroke-dasharray=\"{}\"", list.join(" "));
+        if style.dash_offset != 0.0 {
+            let _ = write!(out, " stroke-dashoffset=\"{}\"", Num(style.dash_offset));
+        }
+    }
+    out
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            /* XML 1.0 allows no other C0 control, not even as a reference */
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c < ' ' => out.push('\u{fffd}'),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn point_list<I: Iterator<Item = Point>>(points: I) -> String {
+    let mut out = String::new();
+    for (i, p) in points.enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let _ = write!(out, "{},{}", Num(p.x), Num(p.y));
+    }
+    out
+}
+
+fn point_i(p: &PointI) -> Point {
+    Point::new(p.x as f32, p.y as f32)
+}
+
+/* path data of the arc from angle `a0` to `a1`, clockwise on screen */
+fn arc_path(center: Point, radius: f32, a0: f32, a1: f32, pie: bool) -> String {
+    let at = |a: f32| Point::new(center.x + a.cos() * radius, center.y + a.sin() * radius);
+    let sweep = if a1 >= a0 { 1 } else { 0 };
+    let delta = (a1 - a0).abs();
+    let start = at(a0);
+    let mut d = String::new();
+    if pie {
+        let _ = write!(d, "M{},{} L{},{}", Num(center.x), Num(center.y), Num(start.x), Num(start.y));
+    } else {
+        let _ = write!(d, "M{},{}", Num(start.x), Num(start.y));
+    }
+    /* SVG cannot draw a full circle with one arc */
+    let steps = if delta >= 2.0 * PI - 1e-4 { 2 } else { 1 };
+    for i in 1..steps + 1 {
+        let a = a0 + (a1 - a0) * i as f32 / steps as f32;
+        let end = at(a);
+        let large = (delta / steps as f32 > PI) as i32;
+        let _ = write!(
+            d,
+            " A{},{} 0 {} {} {},{}",
+            Num(radius),
+            Num(radius),
+            large,
+            sweep,
+            Num(end.x),
+            Num(end.y)
+        );
+    }
+    if pie {
+        d.push_str(" Z");
+    }
+    d
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const TABLE: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+impl Pixmap {
+    /// Encodes the pixmap as an uncompressed RGBA PNG.
+    pub fn to_png(&self) -> Vec<u8> {
+        /* `pixels` exists, so its rows plus a filter byte each fit usize */
+        let stride = self.width as usize * 4;
+        let mut raw = Vec::with_capacity((stride + 1) * self.height as usize);
+        for row in self.pixels.chunks(stride.max(1)) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+        /* zlib stream of stored deflate blocks */
+        let mut z = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(0xffff).peekable();
+        if blocks.peek().is_none() {
+            z.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+        }
+        while let Some(block) = blocks.next() {
+            let last = blocks.peek().is_none() as u8;
+            let len = block.len() as u16;
+            z.push(last);
+            z.extend_from_slice(&len.to_le_bytes());
+            z.extend_from_slice(&(!len).to_le_bytes());
+            z.extend_from_slice(block);
+        }
+        let (mut a, mut b) = (1u32, 0u32);
+        for &byte in &raw {
+            a = (a + byte as u32) % 65521;
+            b = (b + a) % 65521;
+        }
+        z.extend_from_slice(&(b << 16 | a).to_be_bytes());
+
+        let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+        png_chunk(&mut out, b"IHDR", &header);
+        png_chunk(&mut out, b"IDAT", &z);
+        png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+}
+
+fn image_href(image: SvgImage) -> String {
+    match image {
+        SvgImage::Href(href) => escape(&href),
+        SvgImage::Embed(pixmap) => format!("data:image/png;base64,{}", base64(&pixmap.to_png())),
+    }
+}
+
+/// Writes `commands` as an SVG document. `image` decides how each image
+/// command is written; images it returns `None` for are left out.
+pub fn to_svg<'a, I, F>(commands: I, options: &SvgOptions, mut image: F) -> String
+where
+    I: IntoIterator<Item = Command<'a>>,
+    F: FnMut(&Image) -> Option<SvgImage>,
+{
+    let mut out = String::new();
+    let mut defs = String::new();
+    let mut body = String::new();
+    let mut clips = 0;
+    let mut gradients = 0;
+    let mut shadows = 0;
+    let mut clipped = false;
+    for cmd in commands {
+        let b = &mut body;
+        match cmd {
+            Command::Nop | Command::Custom { .. } | Command::CustomDraw { .. } => {}
+            Command::Scissor { rect } => {
+                if clipped {
+                    b.push_str("</g>\n");
+                }
+                clips += 1;
+                let _ = writeln!(
+                    defs,
+                    "<clipPath id=\"clip{}\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/></clipPath>",
+                    clips,
+                    Num(rect.x),
+                    Num(rect.y),
+                    Num(rect.w.max(0.0)),
+                    Num(rect.h.max(0.0))
+                );
+                let _ = writeln!(b, "<g clip-path=\"url(#clip{})\">", clips);
+                clipped = true;
+            }
+            Command::Line {
+                begin,
+                end,
+                thickness,
+                color,
+            } => {
+                let _ = writeln!(
+                    b,
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>",
+                    Num(begin.x),
+                    Num(begin.y),
+                    Num(end.x),
+                    Num(end.y),
+                    stroke(color, thickness)
+                );
+            }
+            Command::Curve {
+                begin,
+                ctrl,
+                end,
+                thickness,
+                color,
+            } => {
+                let _ = writeln!(
+                    b,
+                    "<path d=\"M{},{} C{},{} {},{} {},{}\" {}/>",
+                    Num(begin.x),
+                    Num(begin.y),
+                    Num(ctrl[0].x),
+                    Num(ctrl[0].y),
+                    Num(ctrl[1].x),
+                    Num(ctrl[1].y),
+                    Num(end.x),
+                    Num(end.y),
+                    stroke(color, thickness)
+                );
+            }
+            Command::LineStyled {
+                begin,
+                end,
+                thickness,
+                color,
+                ref style,
+            } => {
+                let _ = writeln!(
+                    b,
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>",
+                    Num(begin.x),
+                    Num(begin.y),
+                    Num(end.x),
+                    Num(end.y),
+                    stroke_styled(color, thickness, style)
+                );
+            }
+            Command::CurveStyled {
+                begin,
+                ctrl,
+                end,
+                thickness,
+                color,
+                ref style,
+            } => {
+                let _ = writeln!(
+                    b,
+                    "<path d=\"M{},{} C{},{} {},{} {},{}\" {}/>",
+                    Num(begin.x),
+                    Num(begin.y),
+                    Num(ctrl[0].x),
+                    Num(ctrl[0].y),
+                    Num(ctrl[1].x),
+                    Num(ctrl[1].y),
+                    Num(end.x),
+                    Num(end.y),
+                    stroke_styled(color, thickness, style)
+                );
+            }
+            Command::Rect {
+                rect,
+                rounding,
+                thickness,
+                color,
+            } => write_rect(b, rect, rounding, &stroke(color, thickness)),
+            Command::RectFilled {
+                rect,
+                rounding,
+                color,
+            } => write_rect(b, rect, rounding, &fill(color)),
+            Command::RectCorners {
+                rect,
+                rounding,
+                thickness,
+                color,
+            } => write_rect_corners(b, rect, rounding, &stroke(color, thickness)),
+            Command::RectCornersFilled {
+                rect,
+                rounding,
+                color,
+            } => write_rect_corners(b, rect, rounding, &fill(color)),
+            Command::RectGradient {
+                rect,
+                rounding,
+                ref gradient,
+            } => {
+                gradients += 1;
+                let paint = write_gradient(&mut defs, gradients, gradient);
+                write_rect_corners(b, rect, rounding, &paint);
+            }
+            Command::RectShadow {
+                rect,
+                rounding,
+                feather,
+                color,
+            } => {
+                /* a gaussian with a deviation of a quarter of the band is
+                 * close to the linear fade of the rasterizer */
+                shadows += 1;
+                let _ = writeln!(
+                    defs,
+                    "<filter id=\"shadow{}\" filterUnits=\"userSpaceOnUse\" x=\"{}\" y=\"{}\" \
+                     width=\"{}\" height=\"{}\"><feGaussianBlur stdDeviation=\"{}\"/></filter>",
+                    shadows,
+                    Num(rect.x - feather),
+                    Num(rect.y - feather),
+                    Num(rect.w.max(0.0) + feather * 2.0),
+                    Num(rect.h.max(0.0) + feather * 2.0),
+                    Num(feather / 4.0)
+                );
+                let paint = format!("{} filter=\"url(#shadow{})\"", fill(color), shadows);
+                write_rect(b, rect, rounding, &paint);
+            }
+            Command::RectMultiColor {
+                rect,
+                left,
+                top,
+                right,
+                bottom,
+            } => {
+                /* corners: left top-left, top top-right, right bottom-right,
+                 * bottom bottom-left */
+                let same = |a: Color, b: Color| (a.r, a.g, a.b, a.a) == (b.r, b.g, b.b, b.a);
+                let (x2, y2, from, to) = if same(left, bottom) && same(top, right) {
+                    (1, 0, left, top)
+                } else if same(left, top) && same(bottom, right) {
+                    (0, 1, left, bottom)
+                } else {
+                    (1, 1, left, right)
+                };
+                gradients += 1;
+                let _ = writeln!(
+                    defs,
+                    "<linearGradient id=\"grad{}\" x1=\"0\" y1=\"0\" x2=\"{}\" y2=\"{}\">\
+                     <stop offset=\"0\" stop-color=\"{}\" stop-opacity=\"{}\"/>\
+                     <stop offset=\"1\" stop-color=\"{}\" stop-opacity=\"{}\"/></linearGradient>",
+                    gradients,
+                    x2,
+                    y2,
+                    hex(from),
+                    Num(from.a as f32 / 255.0),
+                    hex(to),
+                    Num(to.a as f32 / 255.0)
+                );
+                write_rect(b, rect, 0.0, &format!("fill=\"url(#grad{})\"", gradients));
+            }
+            Command::Circle {
+                rect,
+                thickness,
+                color,
+            } => write_ellipse(b, rect, &stroke(color, thickness)),
+            Command::CircleFilled { rect, color } => write_ellipse(b, rect, &fill(color)),
+            Command::CircleGradient { rect, ref gradient } => {
+                gradients += 1;
+                let paint = write_gradient(&mut defs, gradients, gradient);
+                write_ellipse(b, rect, &paint);
+            }
+            Command::Arc {
+                center,
+                radius,
+                angles,
+                thickness,
+                color,
+            } => {
+                let _ = writeln!(
+                    b,
+                    "<path d=\"{}\" {}/>",
+                    arc_path(center, radius, angles[0], angles[1], false),
+                    stroke(color, thickness)
+                );
+            }
+            Command::ArcFilled {
+                center,
+                radius,
+                angles,
+                color,
+            } => {
+                let _ = writeln!(
+                    b,
+                    "<path d=\"{}\" {}/>",
+                    arc_path(center, radius, angles[0], angles[1], true),
+                    fill(color)
+                );
+            }
+            Command::Triangle {
+                points,
+                thickness,
+                color,
+            } => {
+                let _ = writeln!(
+                    b,
+                    "<polygon points=\"{}\" {}/>",
+                    point_list(points.iter().cloned()),
+                    stroke(color, thickness)
+                );
+            }
+            Command::TriangleFilled { points, color } => {
+                let _ = writeln!(
+                    b,
+                    "<polygon points=\"{}\" {}/>",
+                    point_list(points.iter().cloned()),
+                    fill(color)
+                );
+            }
+            Command::Polygon {
+                points,
+                thickness,
+                color,
+            } => {
+                let _ = writeln!(
+                    b,
+                    "<polygon points=\"{}\" {}/>",
+                    point_list(points.iter().map(point_i)),
+                    stroke(color, thickness)
+                );
+            }
+            Command::PolygonStyled {
+                points,
+                thickness,
+                color,
+                ref style,
+            } => {
+                let _ = writeln!(
+                    b,
+                    "<polygon points=\"{}\" {}/>",
+                    point_list(points.iter().map(point_i)),
+                    stroke_styled(color, thickness, style)
+                );
+            }
+            Command::PolylineStyled {
+                points,
+                thickness,
+                color,
+                ref style,
+            } => {
+                let _ = writeln!(
+                    b,
+                    "<polyline points=\"{}\" {}/>",
+                    point_list(points.iter().map(point_i)),
+                    stroke_styled(color, thickness, style)
+                );
+            }
+            Command::PolygonFilled { points, color } => {
+                let _ = writeln!(
+                    b,
+                    "<polygon points=\"{}\" {}/>",
+                    point_list(points.iter().map(point_i)),
+                    fill(color)
+                );
+            }
+            Command::PolygonGradient {
+                points,
+                ref gradient,
+            } => {
+                gradients += 1;
+                let paint = write_gradient(&mut defs, gradients, gradient);
+                let _ = writeln!(
+                    b,
+                    "<polygon points=\"{}\" {}/>",
+                    point_list(points.iter().map(point_i)),
+                    paint
+                );
+            }
+            Command::Polyline {
+                points,
+                thickness,
+                color,
+            } => {
+                let _ = writeln!(
+                    b,
+                    "<polyline points=\"{}\" {}/>",
+                    point_list(points.iter().map(point_i)),
+                    stroke(color, thickness)
+                );
+            }
+            Command::Text {
+                rect,
+                height,
+                string,
+                foreground,
+                ..
+            } => {
+                /* commands give the top of the line, SVG the baseline */
+                let _ = writeln!(
+                    b,
+                    "<text x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\" {} \
+                     xml:space=\"preserve\">{}</text>",
+                    Num(rect.x),
+                    Num(rect.y + height * 0.8),
+                    escape(&options.font_family),
+                    Num(height),
+                    fill(foreground),
+                    escape(string)
+                );
+            }
+            Command::Image { rect, image: img, color } => {
+                let href = match image(&img) {
+                    Some(i) => image_href(i),
+                    None => continue,
+                };
+                let opacity = if color.a == 255 {
+                    String::new()
+                } else {
+                    format!(" opacity=\"{}\"", Num(color.a as f32 / 255.0))
+                };
+                if img.region[2] != 0 && img.region[3] != 0 {
+                    /* show the region of a sub-image, stretched over `rect` */
+                    let _ = writeln!(
+                        b,
+                        "<svg x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\" \
+                         preserveAspectRatio=\"none\"{}><image width=\"{}\" height=\"{}\" xlink:href=\"{}\"/></svg>",
+                        Num(rect.x),
+                        Num(rect.y),
+                        Num(rect.w),
+                        Num(rect.h),
+                        img.region[0],
+                        img.region[1],
+                        img.region[2],
+                        img.region[3],
+                        opacity,
+                        img.w,
+                        img.h,
+                        href
+                    );
+                } else {
+                    let _ = writeln!(
+                        b,
+                        "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" \
+                         preserveAspectRatio=\"none\"{} xlink:href=\"{}\"/>",
+                        Num(rect.x),
+                        Num(rect.y),
+                        Num(rect.w),
+                        Num(rect.h),
+                        opacity,
+                        href
+                    );
+                }
+            }
+        }
+    }
+    if clipped {
+        body.push_str("</g>\n");
+    }
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+         width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        Num(options.width),
+        Num(options.height),
+        Num(options.width),
+        Num(options.height)
+    );
+    if !defs.is_empty() {
+        let _ = write!(out, "<defs>\n{}</defs>\n", defs);
+    }
+    if let Some(bg) = options.background {
+        let _ = writeln!(out, "<rect width=\"100%\" height=\"100%\" {}/>", fill(bg));
+    }
+    out.push_str(&body);
+    out.push_str("</svg>\n");
+    out
+}
+
+/* defines gradient `id` in user space and returns the paint using it */
+fn write_gradient(defs: &mut String, id: usize, g: &Gradient) -> String {
+    let stops = format!(
+        "<stop offset=\"0\" stop-color=\"{}\" stop-opacity=\"{}\"/>\
+         <stop offset=\"1\" stop-color=\"{}\" stop-opacity=\"{}\"/>",
+        hex(g.start_color),
+        Num(g.start_color.a as f32 / 255.0),
+        hex(g.end_color),
+        Num(g.end_color.a as f32 / 255.0)
+    );
+    if g.type_0 == NK_GRADIENT_RADIAL {
+        let (dx, dy) = (g.end.x - g.start.x, g.end.y - g.start.y);
+        let _ = writeln!(
+            defs,
+            "<radialGradient id=\"grad{}\" gradientUnits=\"userSpaceOnUse\" \
+             cx=\"{}\" cy=\"{}\" r=\"{}\">{}</radialGradient>",
+            id,
+            Num(g.start.x),
+            Num(g.start.y),
+            Num((dx * dx + dy * dy).sqrt()),
+            stops
+        );
+    } else {
+        let _ = writeln!(
+            defs,
+            "<linearGradient id=\"grad{}\" gradientUnits=\"userSpaceOnUse\" \
+             x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">{}</linearGradient>",
+            id,
+            Num(g.start.x),
+            Num(g.start.y),
+            Num(g.end.x),
+            Num(g.end.y),
+            stops
+        );
+    }
+    format!("fill=\"url(#grad{})\"", id)
+}
+
+/* radii top-left, top-right, bottom-right, bottom-left */
+fn write_rect_corners(out: &mut String, rect: Rect, rounding: [f32; 4], paint: &str) {
+    if rounding.iter().all(|&r| r == rounding[0]) {
+        return write_rect(out, rect, rounding[0], paint);
+    }
+    let max = (rect.w.min(rect.h) / 2.0).max(0.0);
+    let r: Vec<f32> = rounding.iter().map(|&r| r.min(max).max(0.0)).collect();
+    let (x0, y0, x1, y1) = (rect.x, rect.y, rect.x + rect.w, rect.y + rect.h);
+    let arc =
+        |r: f32, x: f32, y: f32| format!("A{},{} 0 0 1 {},{}", Num(r), Num(r), Num(x), Num(y));
+    let _ = writeln!(
+        out,
+        "<path d=\"M{},{} H{} {} V{} {} H{} {} V{} {} Z\" {}/>",
+        Num(x0 + r[0]),
+        Num(y0),
+        Num(x1 - r[1]),
+        arc(r[1], x1, y0 + r[1]),
+        Num(y1 - r[2]),
+        arc(r[2], x1 - r[2], y1),
+        Num(x0 + r[3]),
+        arc(r[3], x0, y1 - r[3]),
+        Num(y0 + r[0]),
+        arc(r[0], x0 + r[0], y0),
+        paint
+    );
+}
+
+fn write_rect(out: &mut String, rect: Rect, rounding: f32, paint: &str) {
+    let rounding = rounding.min(rect.w / 2.0).min(rect.h / 2.0).max(0.0);
+    let _ = write!(
+        out,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"",
+        Num(rect.x),
+        Num(rect.y),
+        Num(rect.w.max(0.0)),
+        Num(rect.h.max(0.0))
+    );
+    if rounding > 0.0 {
+        let _ = write!(out, " rx=\"{}\"", Num(rounding));
+    }
+    let _ = writeln!(out, " {}/>", paint);
+}
+
+fn write_ellipse(out: &mut String, rect: Rect, paint: &str) {
+    let _ = writeln!(
+        out,
+        "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\" {}/>",
+        Num(rect.x + rect.w / 2.0),
+        Num(rect.y + rect.h / 2.0),
+        Num(rect.w.max(0.0) / 2.0),
+        Num(rect.h.max(0.0) / 2.0),
+        paint
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harness::{draw_every_command, Harness};
+
+    /* element names in document order; panics on unbalanced tags */
+    fn elements(doc: &str) -> Vec<String> {
+        let mut open: Vec<&str> = Vec::new();
+        let mut names = Vec::new();
+        let mut rest = doc;
+        while let Some(start) = rest.find('<') {
+            let end = start + rest[start..].find('>').expect("unterminated tag");
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop(), Some(name), "unbalanced </{}>", name);
+                continue;
+            }
+            let name = tag.split(|c: char| c.is_whitespace() || c == '/').next().unwrap();
+            names.push(name.to_owned());
+            if !tag.ends_with('/') {
+                open.push(name);
+            }
+        }
+        assert!(open.is_empty(), "unclosed {:?}", open);
+        names
+    }
+
+    #[test]
+    fn every_command_exports_to_a_well_formed_document() {
+        let mut harness = Harness::new(320, 320);
+        let ctx = harness.context_mut();
+        unsafe { draw_every_command(ctx) };
+        let mut options = SvgOptions::new(320.0, 320.0);
+        options.background = Some(Color {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 255,
+        });
+        let doc = to_svg(ctx.commands(), &options, |_| Some(SvgImage::Embed(Pixmap::new(2, 2))));
+        unsafe { nk_clear(ctx) };
+        let names = elements(&doc);
+        assert_eq!(names[0], "svg");
+        for name in &[
+            "clipPath", "line", "path", "rect", "ellipse", "polygon", "polyline", "text",
+            "image", "linearGradient", "radialGradient",
+        ] {
+            assert!(names.iter().any(|n| n == name), "no <{}> in\n{}", name, doc);
+        }
+        assert!(doc.contains("stroke-dasharray=\"4 2\""), "{}", doc);
+        assert!(doc.contains("xmlns:xlink=\"http://www.w3.org/1999/xlink\""));
+        assert!(doc.contains("xlink:href=\"data:image/png;base64,iVBORw0KGgo"));
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        assert_eq!(escape("a<b & \"c\">"), "a&lt;b &amp; &quot;c&quot;&gt;");
+        assert_eq!(escape("a\tb\u{1}c\u{1f}"), "a\tb\u{fffd}c\u{fffd}");
+    }
+
+    #[test]
+    fn control_characters_leave_text_well_formed() {
+        let harness = Harness::new(320, 320);
+        let font = unsafe { &*harness.context().style.font };
+        let white = Color {
+            r: 255,
+            g: 255,
+            b: 255,
+            a: 255,
+        };
+        let text = Command::Text {
+            rect: Rect::new(0.0, 0.0, 100.0, 20.0),
+            font,
+            height: font.height,
+            string: "bell\u{1}",
+            background: white,
+            foreground: white,
+        };
+        let doc = to_svg(Some(text), &SvgOptions::new(320.0, 320.0), |_| None);
+        assert!(!doc.contains('\u{1}'), "{}", doc);
+        assert!(doc.contains(">bell\u{fffd}</text>"), "{}", doc);
+        assert!(elements(&doc).iter().any(|n| n == "text"));
+    }
+
+    #[test]
+    fn base64_matches_rfc_4648() {
+        let cases = ["", "f", "fo", "foo", "foob", "fooba", "foobar"];
+        let expected = ["", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy"];
+        for (input, out) in cases.iter().zip(expected.iter()) {
+            assert_eq!(base64(input.as_bytes()), *out);
+        }
+    }
+
+    #[test]
+    fn png_stores_the_pixels() {
+        let (w, h) = (3u32, 2u32);
+        let pixels: Vec<u8> = (0..w * h * 4).map(|i| i as u8).collect();
+        let png = unsafe { Pixmap::from_raw_rgba(w, h, pixels.as_ptr() as _) }.to_png();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            let body = &rest[4..8 + len];
+            let crc = &rest[8 + len..12 + len];
+            assert_eq!(crc32(body).to_be_bytes(), crc);
+            chunks.push((body[..4].to_vec(), body[4..].to_vec()));
+            rest = &rest[12 + len..];
+        }
+        let kinds: Vec<&[u8]> = chunks.iter().map(|c| &c.0[..]).collect();
+        assert_eq!(kinds, [&b"IHDR"[..], b"IDAT", b"IEND"]);
+        assert_eq!(&chunks[0].1[..8], &[0, 0, 0, 3, 0, 0, 0, 2]);
+
+        /* a single stored deflate block behind the zlib header */
+        let z = &chunks[1].1;
+        assert_eq!(&z[..3], &[0x78, 0x01, 1]);
+        let len = u16::from_le_bytes([z[3], z[4]]) as usize;
+        let raw = &z[7..7 + len];
+        let mut expected = Vec::new();
+        for row in pixels.chunks(w as usize * 4) {
+            expected.push(0);
+            expected.extend_from_slice(row);
+        }
+        assert_eq!(raw, &expected[..]);
+        let (mut a, mut b) = (1u32, 0u32);
+        for &byte in raw {
+            a = (a + byte as u32) % 65521;
+            b = (b + a) % 65521;
+        }
+        assert_eq!(&z[7 + len..], &(b << 16 | a).to_be_bytes());
+    }
+}
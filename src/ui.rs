@@ -0,0 +1,785 @@
+use allocator::OwnedContext;
+use custom::{CustomArea, CustomDraws};
+use input::*;
+use math::*;
+use nuklear::*;
+use std::ffi::CString;
+use std::ops::{Deref, DerefMut};
+use std::panic::Location;
+use std::{mem, ptr};
+
+/// Safe front-end over an initialized `Context`.
+///
+/// Every scope (window, group, tree, menu, popup, combo, contextual,
+/// tooltip) is either a closure or a `Scope` guard which calls the matching
+/// `*_end`/`*_pop` function when it goes out of scope.
+pub struct Ui<'a> {
+    ctx: &'a mut Context,
+    records: Option<&'a mut Vec<WidgetRecord>>,
+}
+
+/// Label and screen bounds of a widget built through `Ui`.
+#[derive(Clone)]
+pub struct WidgetRecord {
+    pub label: String,
+    pub bounds: Rect,
+}
+
+/// Guard returned by the `begin_*` methods of `Ui`.
+///
+/// Dereferences to the `Ui` it was created from and calls the matching
+/// `*_end`/`*_pop` function on drop if the scope was opened.
+pub struct Scope<'s, 'a: 's> {
+    ui: &'s mut Ui<'a>,
+    open: bool,
+    end: Option<unsafe fn(*mut Context)>,
+}
+
+impl<'s, 'a> Scope<'s, 'a> {
+    fn new(ui: &'s mut Ui<'a>, open: bool, end: unsafe fn(*mut Context), always: bool) -> Self {
+        let end = if open || always { Some(end) } else { None };
+        Scope { ui, open, end }
+    }
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+}
+
+impl<'s, 'a> Deref for Scope<'s, 'a> {
+    type Target = Ui<'a>;
+    fn deref(&self) -> &Ui<'a> {
+        self.ui
+    }
+}
+
+impl<'s, 'a> DerefMut for Scope<'s, 'a> {
+    fn deref_mut(&mut self) -> &mut Ui<'a> {
+        self.ui
+    }
+}
+
+impl<'s, 'a> Drop for Scope<'s, 'a> {
+    fn drop(&mut self) {
+        if let Some(end) = self.end {
+            unsafe { end(self.ui.ctx) }
+        }
+    }
+}
+
+/// Column description for `Ui::layout_row_template`.
+#[derive(Copy, Clone)]
+pub enum RowTemplate {
+    Dynamic,
+    Variable(f32),
+    Static(f32),
+}
+
+/// Converts `s` into a C string, cutting it at the first interior NUL.
+pub(crate) fn c_string(s: &str) -> CString {
+    let bytes = match s.bytes().position(|b| b == 0) {
+        Some(n) => &s.as_bytes()[..n],
+        None => s.as_bytes(),
+    };
+    CString::new(bytes).unwrap()
+}
+
+impl<'a> Ui<'a> {
+    /// Wraps `ctx` for the current frame.
+    pub fn new(ctx: &'a mut OwnedContext) -> Self {
+        Ui { ctx, records: None }
+    }
+
+    /// Wraps a context that is not an `OwnedContext`, e.g. one set up with
+    /// `nk_init_fixed`.
+    ///
+    /// # Safety
+    ///
+    /// `ctx` must have been initialized with one of the `nk_init*`
+    /// functions and not freed since, and its font and memory must outlive
+    /// the returned `Ui`.
+    pub unsafe fn from_context(ctx: &'a mut Context) -> Self {
+        Ui { ctx, records: None }
+    }
+
+    /// Like `new`, but appends the label and bounds of every labelled
+    /// widget to `records` while the frame is built.
+    pub fn with_records(ctx: &'a mut OwnedContext, records: &'a mut Vec<WidgetRecord>) -> Self {
+        Ui {
+            records: Some(records),
+            ..Ui::new(ctx)
+        }
+    }
+
+    /* runs `f` and records the bounds of the widget it allocated, if any */
+    fn record<R, F>(&mut self, label: &str, f: F) -> R
+    where
+        F: FnOnce(&mut Context) -> R,
+    {
+        if self.records.is_none() {
+            return f(self.ctx);
+        }
+        self.ctx.last_widget_bounds = Rect::new(0.0, 0.0, 0.0, 0.0);
+        let res = f(self.ctx);
+        let bounds = self.ctx.last_widget_bounds;
+        if bounds.w > 0.0 || bounds.h > 0.0 {
+            if let Some(ref mut records) = self.records {
+                records.push(WidgetRecord {
+                    label: label.to_owned(),
+                    bounds,
+                });
+            }
+        }
+        res
+    }
+
+    #[inline]
+    pub fn context(&self) -> &Context {
+        self.ctx
+    }
+
+    /// Raw access for calling `nk_*` functions that have no safe wrapper.
+    #[inline]
+    pub fn context_mut(&mut self) -> &mut Context {
+        self.ctx
+    }
+
+    #[inline]
+    pub fn input(&self) -> &Input {
+        &self.ctx.input
+    }
+
+    /* ---------------------------------------------------------------
+     *                          SCOPES
+     * --------------------------------------------------------------- */
+
+    pub fn begin_window<'s>(
+        &'s mut self,
+        name: &str,
+        title: &str,
+        bounds: Rect,
+        flags: Flags,
+    ) -> Scope<'s, 'a> {
+        let (name, title) = (c_string(name), c_string(title));
+        let open =
+            unsafe { 0 != nk_begin_titled(self.ctx, name.as_ptr(), title.as_ptr(), bounds, flags) };
+        /* nk_end has to be called even for collapsed or closed windows */
+        Scope::new(self, open, nk_end, true)
+    }
+
+    /// Runs `f` inside the window `title` if it is visible.
+    pub fn window<R, F>(&mut self, title: &str, bounds: Rect, flags: Flags, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Ui) -> R,
+    {
+        let mut win = self.begin_window(title, title, bounds, flags);
+        if win.is_open() {
+            Some(f(&mut win))
+        } else {
+            None
+        }
+    }
+
+    pub fn begin_group<'s>(&'s mut self, name: &str, title: &str, flags: Flags) -> Scope<'s, 'a> {
+        let (name, title) = (c_string(name), c_string(title));
+        let open =
+            unsafe { 0 != nk_group_begin_titled(self.ctx, name.as_ptr(), title.as_ptr(), flags) };
+        Scope::new(self, open, nk_group_end, false)
+    }
+
+    pub fn group<R, F>(&mut self, title: &str, flags: Flags, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Ui) -> R,
+    {
+        let mut group = self.begin_group(title, title, flags);
+        if group.is_open() {
+            Some(f(&mut group))
+        } else {
+            None
+        }
+    }
+
+    /// Runs `f` in a small window following the cursor while something is
+    /// dragged. Call it outside of other windows.
+    pub fn drag_preview<R, F>(&mut self, size: Point, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Ui) -> R,
+    {
+        let open = unsafe { 0 != nk_drag_preview_begin(self.ctx, size) };
+        let mut preview = Scope::new(self, open, nk_drag_preview_end, false);
+        if preview.is_open() {
+            Some(f(&mut preview))
+        } else {
+            None
+        }
+    }
+
+    /// Opens a tree node or tab. The node state is keyed by `title` and the
+    /// caller's source line, the same way the C `nk_tree_push` macro does.
+    #[track_caller]
+    pub fn begin_tree<'s>(
+        &'s mut self,
+        kind: TreeType,
+        title: &str,
+        initial: CollapseStates,
+    ) -> Scope<'s, 'a> {
+        let line = Location::caller().line() as Int;
+        let label = title;
+        let title = c_string(title);
+        let open = self.record(label, |ctx| unsafe {
+            0 != nk_tree_push_hashed(ctx, kind, title.as_ptr(), initial, ptr::null(), 0, line)
+        });
+        Scope::new(self, open, nk_tree_pop, false)
+    }
+
+    #[track_caller]
+    pub fn tree_node<R, F>(&mut self, title: &str, initial: CollapseStates, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Ui) -> R,
+    {
+        let mut tree = self.begin_tree(NK_TREE_NODE, title, initial);
+        if tree.is_open() {
+            Some(f(&mut tree))
+        } else {
+            None
+        }
+    }
+
+    #[track_caller]
+    pub fn tree_tab<R, F>(&mut self, title: &str, initial: CollapseStates, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Ui) -> R,
+    {
+        let mut tree = self.begin_tree(NK_TREE_TAB, title, initial);
+        if tree.is_open() {
+            Some(f(&mut tree))
+        } else {
+            None
+        }
+    }
+
+    pub fn begin_menubar<'s>(&'s mut self) -> Scope<'s, 'a> {
+        unsafe { nk_menubar_begin(self.ctx) };
+        Scope::new(self, true, nk_menubar_end, true)
+    }
+
+    pub fn menubar<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Ui) -> R,
+    {
+        let mut bar = self.begin_menubar();
+        f(&mut bar)
+    }
+
+    pub fn begin_menu<'s>(&'s mut self, title: &str, align: Flags, size: Point) -> Scope<'s, 'a> {
+        let open = self.record(title, |ctx| unsafe {
+            0 != nk_menu_begin_text(ctx, title.as_ptr() as _, title.len() as Int, align, size)
+        });
+        Scope::new(self, open, nk_menu_end, false)
+    }
+
+    pub fn menu<R, F>(&mut self, title: &str, align: Flags, size: Point, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Ui) -> R,
+    {
+        let mut menu = self.begin_menu(title, align, size);
+        if menu.is_open() {
+            Some(f(&mut menu))
+        } else {
+            None
+        }
+    }
+
+    pub fn menu_item(&mut self, title: &str, align: Flags) -> bool {
+        self.record(title, |ctx| unsafe {
+            0 != nk_menu_item_text(ctx, title.as_ptr() as _, title.len() as Int, align)
+        })
+    }
+
+    pub fn menu_close(&mut self) {
+        unsafe { nk_menu_close(self.ctx) }
+    }
+
+    pub fn begin_popup<'s>(
+        &'s mut self,
+        kind: PopupType,
+        title: &str,
+        flags: Flags,
+        bounds: Rect,
+    ) -> Scope<'s, 'a> {
+        let title = c_string(title);
+        let open = unsafe { 0 != nk_popup_begin(self.ctx, kind, title.as_ptr(), flags, bounds) };
+        Scope::new(self, open, nk_popup_end, false)
+    }
+
+    pub fn popup<R, F>(
+        &mut self,
+        kind: PopupType,
+        title: &str,
+        flags: Flags,
+        bounds: Rect,
+        f: F,
+    ) -> Option<R>
+    where
+        F: FnOnce(&mut Ui) -> R,
+    {
+        let mut popup = self.begin_popup(kind, title, flags, bounds);
+        if popup.is_open() {
+            Some(f(&mut popup))
+        } else {
+            None
+        }
+    }
+
+    pub fn popup_close(&mut self) {
+        unsafe { nk_popup_close(self.ctx) }
+    }
+
+    pub fn begin_combo<'s>(&'s mut self, selected: &str, size: Point) -> Scope<'s, 'a> {
+        let open = self.record(selected, |ctx| unsafe {
+            0 != nk_combo_begin_text(ctx, selected.as_ptr() as _, selected.len() as Int, size)
+        });
+        Scope::new(self, open, nk_combo_end, false)
+    }
+
+    pub fn begin_combo_color<'s>(&'s mut self, color: Color, size: Point) -> Scope<'s, 'a> {
+        let open = unsafe { 0 != nk_combo_begin_color(self.ctx, color, size) };
+        Scope::new(self, open, nk_combo_end, false)
+    }
+
+    pub fn combo_item(&mut self, text: &str, align: Flags) -> bool {
+        self.record(text, |ctx| unsafe {
+            0 != nk_combo_item_text(ctx, text.as_ptr() as _, text.len() as Int, align)
+        })
+    }
+
+    pub fn combo_close(&mut self) {
+        unsafe { nk_combo_close(self.ctx) }
+    }
+
+    /// Combo box over `items`. Returns the newly selected index, if any.
+    pub fn combo(
+        &mut self,
+        items: &[&str],
+        selected: usize,
+        item_height: f32,
+        size: Point,
+    ) -> Option<usize> {
+        let current = match items.get(selected) {
+            Some(item) => *item,
+            None => "",
+        };
+        let mut picked = None;
+        let mut combo = self.begin_combo(current, size);
+        if combo.is_open() {
+            combo.layout_row_dynamic(item_height, 1);
+            for (i, item) in items.iter().enumerate() {
+                if combo.combo_item(item, NK_TEXT_LEFT) && i != selected {
+                    picked = Some(i);
+                }
+            }
+        }
+        picked
+    }
+
+    pub fn begin_contextual<'s>(
+        &'s mut self,
+        flags: Flags,
+        size: Point,
+        trigger: Rect,
+    ) -> Scope<'s, 'a> {
+        let open = unsafe { 0 != nk_contextual_begin(self.ctx, flags, size, trigger) };
+        Scope::new(self, open, nk_contextual_end, false)
+    }
+
+    pub fn contextual<R, F>(&mut self, flags: Flags, size: Point, trigger: Rect, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Ui) -> R,
+    {
+        let mut menu = self.begin_contextual(flags, size, trigger);
+        if menu.is_open() {
+            Some(f(&mut menu))
+        } else {
+            None
+        }
+    }
+
+    pub fn contextual_item(&mut self, text: &str, align: Flags) -> bool {
+        self.record(text, |ctx| unsafe {
+            0 != nk_contextual_item_text(ctx, text.as_ptr() as _, text.len() as Int, align)
+        })
+    }
+
+    pub fn contextual_close(&mut self) {
+        unsafe { nk_contextual_close(self.ctx) }
+    }
+
+    pub fn begin_tooltip<'s>(&'s mut self, width: f32) -> Scope<'s, 'a> {
+        let open = unsafe { 0 != nk_tooltip_begin(self.ctx, width) };
+        Scope::new(self, open, nk_tooltip_end, false)
+    }
+
+    pub fn tooltip(&mut self, text: &str) {
+        let text = c_string(text);
+        unsafe { nk_tooltip(self.ctx, text.as_ptr()) }
+    }
+
+    /* ---------------------------------------------------------------
+     *                          LAYOUT
+     * --------------------------------------------------------------- */
+
+    pub fn layout_row_dynamic(&mut self, height: f32, cols: usize) {
+        unsafe { nk_layout_row_dynamic(self.ctx, height, cols as Int) }
+    }
+
+    pub fn layout_row_static(&mut self, height: f32, item_width: i32, cols: usize) {
+        unsafe { nk_layout_row_static(self.ctx, height, item_width, cols as Int) }
+    }
+
+    pub fn layout_row_template(&mut self, height: f32, columns: &[RowTemplate]) {
+        unsafe {
+            nk_layout_row_template_begin(self.ctx, height);
+            for column in columns {
+                match *column {
+                    RowTemplate::Dynamic => nk_layout_row_template_push_dynamic(self.ctx),
+                    RowTemplate::Variable(min) => {
+                        nk_layout_row_template_push_variable(self.ctx, min)
+                    }
+                    RowTemplate::Static(width) => {
+                        nk_layout_row_template_push_static(self.ctx, width)
+                    }
+                }
+            }
+            nk_layout_row_template_end(self.ctx);
+        }
+    }
+
+    pub fn spacing(&mut self, cols: usize) {
+        unsafe { nk_spacing(self.ctx, cols as Int) }
+    }
+
+    pub fn widget_bounds(&mut self) -> Rect {
+        unsafe { nk_widget_bounds(self.ctx) }
+    }
+
+    pub fn window_bounds(&self) -> Rect {
+        unsafe { nk_window_get_bounds(self.ctx) }
+    }
+
+    pub fn window_is_closed(&mut self, name: &str) -> bool {
+        let name = c_string(name);
+        unsafe { 0 != nk_window_is_closed(self.ctx, name.as_ptr()) }
+    }
+
+    pub fn window_close(&mut self, name: &str) {
+        let name = c_string(name);
+        unsafe { nk_window_close(self.ctx, name.as_ptr()) }
+    }
+
+    /* ---------------------------------------------------------------
+     *                          WIDGETS
+     * --------------------------------------------------------------- */
+
+    pub fn label(&mut self, text: &str, align: Flags) {
+        let color = self.ctx.style.text.color;
+        self.label_colored(text, align, color)
+    }
+
+    pub fn label_colored(&mut self, text: &str, align: Flags, color: Color) {
+        self.record(text, |ctx| unsafe {
+            nk_text_colored(ctx, text.as_ptr() as _, text.len() as Int, align, color)
+        })
+    }
+
+    pub fn label_wrap(&mut self, text: &str) {
+        self.record(text, |ctx| unsafe {
+            nk_text_wrap(ctx, text.as_ptr() as _, text.len() as Int)
+        })
+    }
+
+    pub fn image(&mut self, img: Image, color: Color) {
+        unsafe { nk_image_color(self.ctx, img, color) }
+    }
+
+    /// Takes the next widget slot and queues `draw` to paint it, see
+    /// `CustomDraws`. Returns `false` if the slot is not visible.
+    ///
+    /// Frame hashes only see the slot, so closures that animate should
+    /// also call `request_redraw`.
+    pub fn custom<'d, T: ?Sized, F>(&mut self, draws: &mut CustomDraws<'d, T>, draw: F) -> bool
+    where
+        F: FnMut(&mut T, CustomArea) + 'd,
+    {
+        let mut bounds: Rect = unsafe { mem::zeroed() };
+        if NK_WIDGET_INVALID == unsafe { nk_widget_nav(&mut bounds, self.ctx) } {
+            return false;
+        }
+        draws.push(self.ctx, bounds, draw).is_some()
+    }
+
+    pub fn button(&mut self, title: &str) -> bool {
+        self.record(title, |ctx| unsafe {
+            0 != nk_button_text(ctx, title.as_ptr() as _, title.len() as Int)
+        })
+    }
+
+    pub fn button_symbol(&mut self, symbol: SymbolType) -> bool {
+        unsafe { 0 != nk_button_symbol(self.ctx, symbol) }
+    }
+
+    pub fn button_symbol_text(&mut self, symbol: SymbolType, text: &str, align: Flags) -> bool {
+        self.record(text, |ctx| unsafe {
+            0 != nk_button_symbol_text(ctx, symbol, text.as_ptr() as _, text.len() as Int, align)
+        })
+    }
+
+    pub fn button_color(&mut self, color: Color) -> bool {
+        unsafe { 0 != nk_button_color(self.ctx, color) }
+    }
+
+    pub fn button_image(&mut self, img: Image) -> bool {
+        unsafe { 0 != nk_button_image(self.ctx, img) }
+    }
+
+    /// Returns `true` if `active` changed.
+    pub fn checkbox(&mut self, text: &str, active: &mut bool) -> bool {
+        let mut value = *active as Int;
+        let changed = self.record(text, |ctx| unsafe {
+            0 != nk_checkbox_text(ctx, text.as_ptr() as _, text.len() as Int, &mut value)
+        });
+        *active = value != 0;
+        changed
+    }
+
+    /// Radio button. Returns the new activation state.
+    pub fn option(&mut self, text: &str, active: bool) -> bool {
+        self.record(text, |ctx| unsafe {
+            0 != nk_option_text(ctx, text.as_ptr() as _, text.len() as Int, active as Int)
+        })
+    }
+
+    /// Returns `true` if `selected` changed.
+    pub fn selectable(&mut self, text: &str, align: Flags, selected: &mut bool) -> bool {
+        let mut value = *selected as Int;
+        let changed = self.record(text, |ctx| unsafe {
+            0 != nk_selectable_text(
+                ctx,
+                text.as_ptr() as _,
+                text.len() as Int,
+                align,
+                &mut value,
+            )
+        });
+        *selected = value != 0;
+        changed
+    }
+
+    /// Returns `true` if `value` changed.
+    pub fn slider_f32(&mut self, min: f32, value: &mut f32, max: f32, step: f32) -> bool {
+        unsafe { 0 != nk_slider_float(self.ctx, min, value, max, step) }
+    }
+
+    /// Returns `true` if `value` changed.
+    pub fn slider_i32(&mut self, min: i32, value: &mut i32, max: i32, step: i32) -> bool {
+        unsafe { 0 != nk_slider_int(self.ctx, min, value, max, step) }
+    }
+
+    /// Returns `true` if `current` changed.
+    pub fn progress(&mut self, current: &mut usize, max: usize, modifiable: bool) -> bool {
+        let mut value = *current as Size;
+        let changed =
+            unsafe { 0 != nk_progress(self.ctx, &mut value, max as Size, modifiable as Int) };
+        *current = value as usize;
+        changed
+    }
+
+    /// Property widget; a `name` starting with `#` hides the label.
+    pub fn property_i32(
+        &mut self,
+        name: &str,
+        min: i32,
+        value: &mut i32,
+        max: i32,
+        step: i32,
+        inc_per_pixel: f32,
+    ) {
+        let label = name;
+        let name = c_string(name);
+        self.record(label, |ctx| unsafe {
+            nk_property_int(ctx, name.as_ptr(), min, value, max, step, inc_per_pixel)
+        })
+    }
+
+    pub fn property_f32(
+        &mut self,
+        name: &str,
+        min: f32,
+        value: &mut f32,
+        max: f32,
+        step: f32,
+        inc_per_pixel: f32,
+    ) {
+        let label = name;
+        let name = c_string(name);
+        self.record(label, |ctx| unsafe {
+            nk_property_float(ctx, name.as_ptr(), min, value, max, step, inc_per_pixel)
+        })
+    }
+
+    pub fn property_f64(
+        &mut self,
+        name: &str,
+        min: f64,
+        value: &mut f64,
+        max: f64,
+        step: f64,
+        inc_per_pixel: f32,
+    ) {
+        let label = name;
+        let name = c_string(name);
+        self.record(label, |ctx| unsafe {
+            nk_property_double(ctx, name.as_ptr(), min, value, max, step, inc_per_pixel)
+        })
+    }
+
+    /// Edit field over `text` holding at most `max` bytes. Returns the
+    /// `NK_EDIT_*` event flags.
+    pub fn edit_string(&mut self, flags: Flags, text: &mut String, max: usize) -> Flags {
+        let mut memory = text.clone().into_bytes();
+        memory.resize(max.max(memory.len()), 0);
+        let mut len = text.len() as Int;
+        let events = unsafe {
+            nk_edit_string(
+                self.ctx,
+                flags,
+                memory.as_mut_ptr() as _,
+                &mut len,
+                max as Int,
+                None,
+            )
+        };
+        memory.truncate(len as usize);
+        if memory != text.as_bytes() {
+            *text = String::from_utf8_lossy(&memory).into_owned();
+        }
+        events
+    }
+
+    pub fn color_picker(&mut self, color: ColorF, format: ColorFormat) -> ColorF {
+        unsafe { nk_color_picker(self.ctx, color, format) }
+    }
+
+    pub fn is_widget_hovered(&mut self) -> bool {
+        unsafe { 0 != nk_widget_is_hovered(self.ctx) }
+    }
+
+    /// Whether the last widget has the keyboard/gamepad focus.
+    pub fn is_widget_focused(&self) -> bool {
+        0 != self.ctx.last_widget_state & NK_WIDGET_STATE_FOCUSED as Flags
+    }
+
+    /// Consecutive clicks with which `button` was pressed on the last widget
+    /// this frame, 0 if it was not pressed there. Needs the backend to feed
+    /// buttons through `nk_input_button_timed`.
+    pub fn widget_clicks(&mut self, button: Buttons) -> u32 {
+        unsafe { nk_widget_clicks(self.ctx, button) as u32 }
+    }
+
+    /// Whether the last widget, e.g. a `selectable` or the header of
+    /// `begin_tree`, was just double-clicked with the left button.
+    pub fn is_widget_double_clicked(&mut self) -> bool {
+        unsafe { 0 != nk_widget_is_double_clicked(self.ctx) }
+    }
+
+    pub fn is_widget_triple_clicked(&mut self) -> bool {
+        unsafe { 0 != nk_widget_is_triple_clicked(self.ctx) }
+    }
+
+    /// Lets the last widget start a drag carrying `data` of type `tag`.
+    /// Returns true on the frame the drag starts.
+    pub fn drag_source(&mut self, tag: &str, data: &[u8]) -> bool {
+        let tag = c_string(tag);
+        unsafe {
+            0 != nk_drag_source(
+                self.ctx,
+                tag.as_ptr(),
+                data.as_ptr() as *const Void,
+                data.len() as Int,
+            )
+        }
+    }
+
+    /// Whether the last widget is where the current drag started.
+    pub fn is_drag_source(&mut self) -> bool {
+        unsafe { 0 != nk_drag_is_source(self.ctx) }
+    }
+
+    /// Payload being dragged, if it has type `tag`.
+    pub fn drag_payload(&self, tag: &str) -> Option<&[u8]> {
+        let tag = c_string(tag);
+        let mut size = 0;
+        unsafe {
+            let data = nk_drag_payload(self.ctx, tag.as_ptr(), &mut size);
+            if data.is_null() {
+                None
+            } else {
+                Some(std::slice::from_raw_parts(data as *const u8, size as usize))
+            }
+        }
+    }
+
+    /// Makes the last widget accept payloads of type `tag` and returns the
+    /// payload on the frame it is dropped there.
+    pub fn drop_target(&mut self, tag: &str) -> Option<&[u8]> {
+        let state = unsafe { nk_drop_target(self.ctx, c_string(tag).as_ptr()) };
+        self.delivered(tag, state)
+    }
+
+    /// `drop_target` for the content of the current window or group; call
+    /// it after their widgets.
+    pub fn window_drop_target(&mut self, tag: &str) -> Option<&[u8]> {
+        let state = unsafe { nk_window_drop_target(self.ctx, c_string(tag).as_ptr()) };
+        self.delivered(tag, state)
+    }
+
+    fn delivered(&self, tag: &str, state: DropStates) -> Option<&[u8]> {
+        if state == NK_DROP_DELIVERED {
+            self.drag_payload(tag)
+        } else {
+            None
+        }
+    }
+
+    /// Caret of the focused edit widget, where the host should open the
+    /// IME candidate window.
+    pub fn edit_caret(&self) -> Option<Rect> {
+        let mut caret = Rect::new(0.0, 0.0, 0.0, 0.0);
+        if unsafe { 0 != nk_edit_caret(self.ctx, &mut caret) } {
+            Some(caret)
+        } else {
+            None
+        }
+    }
+
+    /// Memory usage of the last frame and the peaks so far, including the
+    /// allocations of `atlas` if given.
+    pub fn memory_report(&mut self, atlas: Option<&FontAtlas>) -> MemoryReport {
+        unsafe {
+            let mut report: MemoryReport = mem::zeroed();
+            let atlas = atlas.map_or(ptr::null(), |a| a as *const FontAtlas);
+            nk_memory_report(self.ctx, atlas, &mut report);
+            report
+        }
+    }
+
+    /// Asks the host for another frame within `seconds`, for animations
+    /// and timers that change the UI without input.
+    pub fn request_redraw(&mut self, seconds: f32) {
+        unsafe { nk_request_redraw(self.ctx, seconds) }
+    }
+
+    pub fn is_mouse_hovering(&self, rect: Rect) -> bool {
+        unsafe { 0 != nk_input_is_mouse_hovering_rect(&self.ctx.input, rect) }
+    }
+}